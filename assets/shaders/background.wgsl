@@ -0,0 +1,68 @@
+#import bevy_sprite::mesh2d_view_bind_group
+#import bevy_sprite::mesh2d_struct
+
+struct Background {
+    base: vec4<f32>;
+    accent: vec4<f32>;
+    // x: seconds elapsed, y: intensity from 0 to 1, zw: screen size in pixels
+    params: vec4<f32>;
+};
+
+[[group(1), binding(0)]]
+var<uniform> background: Background;
+
+struct FragmentInput {
+    [[builtin(front_facing)]] is_front: bool;
+    [[location(0)]] world_position: vec4<f32>;
+    [[location(1)]] world_normal: vec3<f32>;
+    [[location(2)]] uv: vec2<f32>;
+#ifdef VERTEX_TANGENTS
+    [[location(3)]] world_tangent: vec4<f32>;
+#endif
+};
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+fn noise(p: vec2<f32>) -> f32 {
+    let cell = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    let a = hash(cell);
+    let b = hash(cell + vec2<f32>(1.0, 0.0));
+    let c = hash(cell + vec2<f32>(0.0, 1.0));
+    let d = hash(cell + vec2<f32>(1.0, 1.0));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+fn fbm(p: vec2<f32>) -> f32 {
+    var value = 0.0;
+    var amplitude = 0.5;
+    var point = p;
+    for (var i = 0; i < 5; i = i + 1) {
+        value = value + amplitude * noise(point);
+        point = point * 2.0;
+        amplitude = amplitude * 0.5;
+    }
+    return value;
+}
+
+[[stage(fragment)]]
+fn fragment(in: FragmentInput) -> [[location(0)]] vec4<f32> {
+    let time = background.params.x;
+    let intensity = background.params.y;
+    let pixel = in.uv * background.params.zw;
+
+    // slowly drifting nebula, churning faster as the game heats up
+    let drift = time * (0.02 + 0.08 * intensity);
+    let nebula = fbm(pixel / 300.0 + vec2<f32>(drift, drift * 0.6));
+    var color = mix(background.base.rgb, background.accent.rgb, nebula * (0.25 + 0.5 * intensity));
+
+    // grid scrolling towards the viewer, its lines brightening with intensity
+    let grid = abs(fract((pixel + vec2<f32>(0.0, time * (10.0 + 60.0 * intensity))) / 64.0) - 0.5);
+    let line = 1.0 - smoothstep(0.0, 0.03, min(grid.x, grid.y));
+    color = color + background.accent.rgb * line * (0.05 + 0.2 * intensity);
+
+    return vec4<f32>(color, 1.0);
+}
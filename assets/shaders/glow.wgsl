@@ -0,0 +1,27 @@
+#import bevy_sprite::mesh2d_view_bind_group
+#import bevy_sprite::mesh2d_struct
+
+struct GlowMaterial {
+    color: vec4<f32>;
+};
+
+[[group(1), binding(0)]]
+var<uniform> material: GlowMaterial;
+
+struct FragmentInput {
+    [[builtin(front_facing)]] is_front: bool;
+    [[location(0)]] world_position: vec4<f32>;
+    [[location(1)]] world_normal: vec3<f32>;
+    [[location(2)]] uv: vec2<f32>;
+#ifdef VERTEX_TANGENTS
+    [[location(3)]] world_tangent: vec4<f32>;
+#endif
+};
+
+// soft radial falloff from the center of the quad, blended additively
+[[stage(fragment)]]
+fn fragment(in: FragmentInput) -> [[location(0)]] vec4<f32> {
+    let distance = length(in.uv - vec2<f32>(0.5, 0.5)) * 2.0;
+    let falloff = clamp(1.0 - distance, 0.0, 1.0);
+    return vec4<f32>(material.color.rgb, material.color.a * falloff * falloff);
+}
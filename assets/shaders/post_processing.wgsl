@@ -0,0 +1,52 @@
+#import bevy_sprite::mesh2d_view_bind_group
+#import bevy_sprite::mesh2d_struct
+
+struct PostProcessing {
+    // x: vignette strength, y: aberration strength, z: scanlines on, w: seconds elapsed
+    params: vec4<f32>;
+    // screen size in pixels
+    resolution: vec4<f32>;
+};
+
+[[group(1), binding(0)]]
+var<uniform> post: PostProcessing;
+
+struct FragmentInput {
+    [[builtin(front_facing)]] is_front: bool;
+    [[location(0)]] world_position: vec4<f32>;
+    [[location(1)]] world_normal: vec3<f32>;
+    [[location(2)]] uv: vec2<f32>;
+#ifdef VERTEX_TANGENTS
+    [[location(3)]] world_tangent: vec4<f32>;
+#endif
+};
+
+// layers a color over what's been composed so far
+fn over(below: vec4<f32>, color: vec3<f32>, alpha: f32) -> vec4<f32> {
+    let a = clamp(alpha, 0.0, 1.0);
+    return vec4<f32>(mix(below.rgb, color, a), below.a + a * (1.0 - below.a));
+}
+
+[[stage(fragment)]]
+fn fragment(in: FragmentInput) -> [[location(0)]] vec4<f32> {
+    let centered = in.uv - vec2<f32>(0.5, 0.5);
+    let edge = length(centered) * 1.414;
+    var color = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+
+    // red closing in from the edges
+    let vignette = smoothstep(0.35, 1.0, edge) * post.params.x;
+    color = over(color, vec3<f32>(0.45, 0.0, 0.0), vignette * 0.85);
+
+    // red and cyan fringes on opposite sides, split along the direction from the center
+    let fringe = smoothstep(0.5, 1.0, edge) * post.params.y;
+    let side = step(0.0, centered.x + centered.y);
+    color = over(color, mix(vec3<f32>(1.0, 0.0, 0.2), vec3<f32>(0.0, 0.9, 1.0), side), fringe * 0.4);
+
+    // scanlines every other pixel row, flickering slightly, and darkened corners
+    let row = in.uv.y * post.resolution.y;
+    let scanline = 0.5 + 0.5 * sin(row * 3.14159);
+    let flicker = 0.9 + 0.1 * sin(post.params.w * 60.0);
+    color = over(color, vec3<f32>(0.0, 0.0, 0.0), post.params.z * (scanline * 0.22 * flicker + smoothstep(0.8, 1.2, edge) * 0.5));
+
+    return color;
+}
@@ -0,0 +1,64 @@
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use madge::bench::{self, Scenario};
+
+/// Crowds each system is measured with, from a quiet early wave to far beyond the worst one.
+const COUNTS: [usize; 4] = [100, 1_000, 5_000, 10_000];
+/// Steps timed on a scenario before it's built anew, a quarter of a second of play: few enough
+/// that the bullets haven't flown off and the dying enemies and their shards haven't faded yet.
+const STEPS_PER_SCENARIO: u64 = 15;
+
+/// The scenario built by `setup`, stepped once so the systems are set up and the queries have
+/// seen every archetype before anything is timed.
+fn warmed(setup: fn(usize) -> Scenario, count: usize) -> Scenario {
+    let mut scenario = setup(count);
+    scenario.step();
+    scenario
+}
+
+/// Measures one step of the scenario built by `setup` for every crowd size. Only the steps are
+/// timed, the scenario is built and warmed up outside of them, and built again every
+/// `STEPS_PER_SCENARIO` steps so the crowd doesn't thin out over the iterations.
+fn bench_scenario(c: &mut Criterion, name: &str, setup: fn(usize) -> Scenario) {
+    let mut group = c.benchmark_group(name);
+    // the largest crowds take long enough per step that fewer samples still settle
+    group.sample_size(20);
+    for count in COUNTS {
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            b.iter_custom(|iterations| {
+                let mut scenario = warmed(setup, count);
+                let mut elapsed = Duration::ZERO;
+                for iteration in 0..iterations {
+                    if iteration > 0 && iteration % STEPS_PER_SCENARIO == 0 {
+                        scenario = warmed(setup, count);
+                    }
+                    let start = Instant::now();
+                    scenario.step();
+                    elapsed += start.elapsed();
+                }
+                elapsed
+            })
+        });
+    }
+    group.finish();
+}
+
+fn collision(c: &mut Criterion) {
+    bench_scenario(c, "collision", bench::collision);
+}
+
+fn steering(c: &mut Criterion) {
+    bench_scenario(c, "steering", bench::steering);
+}
+
+fn bullets(c: &mut Criterion) {
+    bench_scenario(c, "bullets", bench::bullets);
+}
+
+fn particles(c: &mut Criterion) {
+    bench_scenario(c, "particles", bench::particles);
+}
+
+criterion_group!(benches, collision, steering, bullets, particles);
+criterion_main!(benches);
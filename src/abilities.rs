@@ -0,0 +1,336 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    bosses::Boss,
+    confine_player,
+    deaths::Dying,
+    elites::Elite,
+    events::{BombDetonated, EnemyKilled},
+    hives::Hive,
+    input_buffer::{BufferedAction, InputBuffer, StepInput},
+    kill_score,
+    loadout::{Loadout, Passive},
+    settings::Settings,
+    splitters::Splitter,
+    AddSimulationSystem, Enemy, Game, InsertIfAlive, Player, TimeScale,
+};
+
+/// Seconds a dash lasts.
+const DASH_DURATION: f32 = 0.15;
+/// Seconds between dashes.
+const DASH_COOLDOWN: f32 = 1.0;
+/// linear speed while dashing in meters per second
+const DASH_VELOCITY: f32 = 1800.0;
+const STARTING_BOMBS: usize = 1;
+/// Seconds of focus a full meter holds.
+const FOCUS_CAPACITY: f32 = 3.0;
+/// Seconds an empty focus meter takes to refill.
+const FOCUS_RECHARGE: f32 = 8.0;
+/// Rate enemies and their bullets move at while the player is focusing.
+const FOCUS_TIME_SCALE: f32 = 0.3;
+/// Fixed steps of transforms kept to rewind through, about three seconds.
+const HISTORY_STEPS: usize = 180;
+/// Seconds of rewinding a full meter holds, as long as the history goes back.
+const REWIND_CAPACITY: f32 = 3.0;
+/// Seconds an empty rewind meter takes to refill.
+const REWIND_RECHARGE: f32 = 20.0;
+
+/// Short burst of speed in the direction the player is moving.
+#[derive(Component)]
+pub struct Dash {
+    cooldown: Timer,
+    active: Timer,
+    direction: Vec3,
+}
+
+impl Dash {
+    fn new(cooldown: f32) -> Self {
+        let mut cooldown = Timer::from_seconds(cooldown, false);
+        cooldown.tick(cooldown.duration());
+        let mut active = Timer::from_seconds(DASH_DURATION, false);
+        active.tick(active.duration());
+        Self {
+            cooldown,
+            active,
+            direction: Vec3::ZERO,
+        }
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        !self.active.finished()
+    }
+}
+
+/// Screen-clearing bombs the player has left.
+#[derive(Component)]
+pub struct Bombs(pub usize);
+
+/// Meter that slows enemies down while the player holds Shift.
+#[derive(Component)]
+pub struct Focus {
+    /// fraction of the meter left
+    meter: f32,
+    active: bool,
+}
+
+impl Focus {
+    pub fn meter(&self) -> f32 {
+        self.meter
+    }
+
+    /// Tops the meter up by `amount`, a fraction of a full one.
+    pub fn refill(&mut self, amount: f32) {
+        self.meter = (self.meter + amount).min(1.0);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+impl Default for Focus {
+    fn default() -> Self {
+        Self {
+            meter: 1.0,
+            active: false,
+        }
+    }
+}
+
+/// Transforms an entity had over the last fixed steps, the newest at the back.
+#[derive(Component, Default)]
+pub struct History(VecDeque<Transform>);
+
+impl History {
+    fn record(&mut self, transform: &Transform) {
+        if self.0.len() == HISTORY_STEPS {
+            self.0.pop_front();
+        }
+        self.0.push_back(*transform);
+    }
+}
+
+/// Meter that winds the player back along their history while they hold R, and the enemies
+/// along theirs if the whole world rewinds.
+#[derive(Component)]
+pub struct Rewind {
+    /// fraction of the meter left
+    meter: f32,
+    active: bool,
+}
+
+impl Rewind {
+    pub fn meter(&self) -> f32 {
+        self.meter
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+impl Default for Rewind {
+    fn default() -> Self {
+        Self {
+            meter: 1.0,
+            active: false,
+        }
+    }
+}
+
+pub struct AbilitiesPlugin;
+
+impl Plugin for AbilitiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(enemy_history_system)
+            .add_simulation_system(dash_system)
+            // focus goes by whether the player rewinds this step
+            .add_simulation_system(rewind_system)
+            .add_simulation_system(focus_system)
+            // in the tutorial as well, its last step is setting a bomb off
+            .add_simulation_system(bomb_system);
+    }
+}
+
+/// Ability components for a freshly spawned player flying the given loadout.
+pub fn player_abilities(loadout: &Loadout) -> (Dash, Bombs, Focus, Rewind, History) {
+    let passive = loadout.ship.passive();
+    let cooldown = if passive == Passive::Afterburner {
+        DASH_COOLDOWN / 2.0
+    } else {
+        DASH_COOLDOWN
+    };
+    let bombs = if passive == Passive::Reserves {
+        STARTING_BOMBS + 1
+    } else {
+        STARTING_BOMBS
+    };
+    (
+        Dash::new(cooldown),
+        Bombs(bombs),
+        Focus::default(),
+        Rewind::default(),
+        History::default(),
+    )
+}
+
+fn dash_system(
+    time_scale: Res<TimeScale>,
+    settings: Res<Settings>,
+    input: Res<StepInput>,
+    mut buffer: ResMut<InputBuffer>,
+    mut query: Query<(&mut Dash, &mut Transform), With<Player>>,
+) {
+    let (mut dash, mut transform) = match query.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    let delta = time_scale.step_duration();
+    dash.cooldown.tick(delta);
+    dash.active.tick(delta);
+
+    if dash.cooldown.finished() && (buffer.take(BufferedAction::Dash) || input.pressed(KeyCode::X))
+    {
+        // dash forward when not steering in any direction
+        dash.direction = buffer
+            .steering(&input)
+            .try_normalize()
+            .unwrap_or(transform.rotation * Vec3::Y);
+        dash.cooldown.reset();
+        dash.active.reset();
+    }
+
+    if dash.is_dashing() {
+        transform.translation += dash.direction * DASH_VELOCITY * time_scale.step();
+        transform.translation = confine_player(transform.translation, &settings);
+    }
+}
+
+/// Enemies are the only ones besides the player to rewind, bullets carry on regardless.
+fn enemy_history_system(mut commands: Commands, enemies: Query<Entity, Added<Enemy>>) {
+    for entity in enemies.iter() {
+        commands.add(InsertIfAlive(entity, History::default()));
+    }
+}
+
+/// Records where the player and enemies are every step, or steps them back through it while R
+/// is held. Enemies are only recorded when the whole world rewinds.
+fn rewind_system(
+    input: Res<StepInput>,
+    time_scale: Res<TimeScale>,
+    settings: Res<Settings>,
+    mut player: Query<(&mut Rewind, &mut History, &mut Transform), With<Player>>,
+    mut enemies: Query<
+        (&mut History, &mut Transform),
+        (With<Enemy>, Without<Dying>, Without<Player>),
+    >,
+) {
+    let (mut rewind, mut history, mut transform) = match player.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    let step = time_scale.step();
+    rewind.active = input.pressed(KeyCode::R) && rewind.meter > 0.0;
+    if rewind.active {
+        rewind.meter -= step / REWIND_CAPACITY;
+        if let Some(previous) = history.0.pop_back() {
+            *transform = previous;
+        }
+        if settings.rewind_world {
+            for (mut history, mut transform) in enemies.iter_mut() {
+                if let Some(previous) = history.0.pop_back() {
+                    *transform = previous;
+                }
+            }
+        }
+    } else {
+        rewind.meter += step / REWIND_RECHARGE;
+        history.record(&transform);
+        if settings.rewind_world {
+            for (mut history, transform) in enemies.iter_mut() {
+                history.record(&transform);
+            }
+        }
+    }
+    rewind.meter = rewind.meter.clamp(0.0, 1.0);
+}
+
+fn focus_system(
+    input: Res<StepInput>,
+    settings: Res<Settings>,
+    mut time_scale: ResMut<TimeScale>,
+    mut query: Query<(&mut Focus, &Rewind), With<Player>>,
+) {
+    let (mut focus, rewind) = match query.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => {
+            time_scale.enemies = 1.0;
+            return;
+        }
+    };
+
+    let step = time_scale.step();
+    focus.active = input.any_pressed([KeyCode::LShift, KeyCode::RShift]) && focus.meter > 0.0;
+    focus.meter = if focus.active {
+        focus.meter - step / FOCUS_CAPACITY
+    } else {
+        focus.meter + step / FOCUS_RECHARGE
+    }
+    .clamp(0.0, 1.0);
+    // enemies hold still apart from being wound back, and their bullets with them
+    time_scale.enemies = if rewind.active && settings.rewind_world {
+        0.0
+    } else if focus.active {
+        FOCUS_TIME_SCALE
+    } else {
+        1.0
+    };
+}
+
+fn bomb_system(
+    mut commands: Commands,
+    mut buffer: ResMut<InputBuffer>,
+    mut game: ResMut<Game>,
+    mut bombs: Query<&mut Bombs, With<Player>>,
+    enemies: Query<
+        (
+            Entity,
+            &Enemy,
+            Option<&Hive>,
+            Option<&Elite>,
+            Option<&Splitter>,
+        ),
+        (Without<Dying>, Without<Boss>),
+    >,
+    mut enemy_killed: EventWriter<EnemyKilled>,
+    mut bomb_detonated: EventWriter<BombDetonated>,
+) {
+    if !buffer.take(BufferedAction::Bomb) {
+        return;
+    }
+    let mut bombs = match bombs.get_single_mut() {
+        Ok(bombs) => bombs,
+        Err(_) => return,
+    };
+    if bombs.0 == 0 {
+        return;
+    }
+    bombs.0 -= 1;
+
+    // bombs vaporize elites outright, splitting ones included, but bosses shrug them off
+    for (entity, enemy, hive, elite, splitter) in enemies.iter() {
+        commands.entity(entity).insert(Dying::default());
+        game.score += kill_score(hive, elite, splitter);
+        enemy_killed.send(EnemyKilled {
+            kind: enemy.kind,
+            modifiers: elite
+                .map(|elite| elite.modifiers.clone())
+                .unwrap_or_default(),
+        });
+    }
+    bomb_detonated.send(BombDetonated);
+}
@@ -0,0 +1,245 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    despawn_with,
+    events::AchievementUnlocked,
+    locale::Locale,
+    menu::{screen_node, spawn_button, spawn_title, MenuButton},
+    save,
+    stats::{LifetimeStats, RunStats, StatsUpdate},
+    GameState, UiFont,
+};
+
+const ACHIEVEMENTS_FILE: &str = "achievements";
+/// Seconds an unlock notification stays on screen.
+const TOAST_DURATION: f32 = 4.0;
+
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Serialize, Deserialize)]
+pub enum Achievement {
+    Centurion,
+    Pacifist,
+    Untouchable,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 3] = [
+        Achievement::Centurion,
+        Achievement::Pacifist,
+        Achievement::Untouchable,
+    ];
+
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            Achievement::Centurion => "achievement.centurion",
+            Achievement::Pacifist => "achievement.pacifist",
+            Achievement::Untouchable => "achievement.untouchable",
+        }
+    }
+
+    pub fn description_key(&self) -> &'static str {
+        match self {
+            Achievement::Centurion => "achievement.centurion.description",
+            Achievement::Pacifist => "achievement.pacifist.description",
+            Achievement::Untouchable => "achievement.untouchable.description",
+        }
+    }
+
+    /// Whether the achievement's condition holds at this point of the current run.
+    fn is_met(&self, run: &RunStats, lifetime: &LifetimeStats) -> bool {
+        match self {
+            Achievement::Centurion => lifetime.total_kills() + run.total_kills() >= 100,
+            Achievement::Pacifist => run.total_kills() == 0 && run.time_survived >= 5.0 * 60.0,
+            Achievement::Untouchable => run.damage_taken == 0.0 && run.wave_reached >= 10,
+        }
+    }
+}
+
+/// Achievements the player has unlocked, persisted between sessions.
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Achievements {
+    pub unlocked: BTreeSet<Achievement>,
+}
+
+impl save::Versioned for Achievements {
+    const VERSION: u32 = 1;
+}
+
+impl Achievements {
+    pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+        self.unlocked.contains(&achievement)
+    }
+}
+
+/// Unlock notification, shown in its slot down the corner of the screen until the timer runs out.
+#[derive(Component)]
+struct Toast {
+    timer: Timer,
+    slot: usize,
+}
+
+#[derive(Component)]
+struct AchievementsScreen;
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<Achievements>(ACHIEVEMENTS_FILE))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(unlock_system.after(StatsUpdate)),
+            )
+            .add_system(spawn_toast_system)
+            .add_system(toast_system)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Achievements).with_system(spawn_achievements_screen),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Achievements)
+                    .with_system(despawn_with::<AchievementsScreen>),
+            );
+    }
+}
+
+fn unlock_system(
+    mut achievements: ResMut<Achievements>,
+    run: Res<RunStats>,
+    lifetime: Res<LifetimeStats>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    if run.assisted {
+        return;
+    }
+    let mut changed = false;
+    for achievement in Achievement::ALL {
+        if !achievements.is_unlocked(achievement) && achievement.is_met(&run, &lifetime) {
+            achievements.unlocked.insert(achievement);
+            unlocked.send(AchievementUnlocked(achievement));
+            changed = true;
+        }
+    }
+    if changed {
+        save::store(ACHIEVEMENTS_FILE, &*achievements);
+    }
+}
+
+fn spawn_toast_system(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    locale: Res<Locale>,
+    toasts: Query<&Toast>,
+    mut unlocked: EventReader<AchievementUnlocked>,
+) {
+    // the first slot no toast on screen is in, so a new toast never lands on one still showing
+    let mut taken: Vec<usize> = toasts.iter().map(|toast| toast.slot).collect();
+    for AchievementUnlocked(achievement) in unlocked.iter() {
+        let index = (0..).find(|slot| !taken.contains(slot)).unwrap();
+        taken.push(index);
+        commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        right: Val::Px(10.0),
+                        top: Val::Px(10.0 + 70.0 * index as f32),
+                        ..default()
+                    },
+                    padding: Rect::all(Val::Px(10.0)),
+                    flex_direction: FlexDirection::ColumnReverse,
+                    ..default()
+                },
+                color: Color::rgba(0.1, 0.1, 0.1, 0.85).into(),
+                ..default()
+            })
+            .insert(Toast {
+                timer: Timer::from_seconds(TOAST_DURATION, false),
+                slot: index,
+            })
+            .with_children(|parent| {
+                parent.spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        locale.format(
+                            "achievements.toast",
+                            &[("name", &locale.get(achievement.name_key()))],
+                        ),
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: 22.0,
+                            color: Color::GOLD,
+                        },
+                        default(),
+                    ),
+                    ..default()
+                });
+                parent.spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        locale.get(achievement.description_key()),
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: 18.0,
+                            color: Color::rgb(0.85, 0.85, 0.85),
+                        },
+                        default(),
+                    ),
+                    ..default()
+                });
+            });
+    }
+}
+
+fn toast_system(mut commands: Commands, time: Res<Time>, mut toasts: Query<(Entity, &mut Toast)>) {
+    for (entity, mut toast) in toasts.iter_mut() {
+        if toast.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn spawn_achievements_screen(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    locale: Res<Locale>,
+    achievements: Res<Achievements>,
+) {
+    commands
+        .spawn_bundle(screen_node())
+        .insert(AchievementsScreen)
+        .with_children(|parent| {
+            spawn_title(parent, &font, &locale, "achievements.title");
+            for achievement in Achievement::ALL {
+                let (status, color) = if achievements.is_unlocked(achievement) {
+                    ("achievements.unlocked", Color::GOLD)
+                } else {
+                    ("achievements.locked", Color::GRAY)
+                };
+                parent.spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    text: Text::with_section(
+                        locale.format(
+                            "achievements.entry",
+                            &[
+                                ("name", &locale.get(achievement.name_key())),
+                                ("description", &locale.get(achievement.description_key())),
+                                ("status", &locale.get(status)),
+                            ],
+                        ),
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: 26.0,
+                            color,
+                        },
+                        default(),
+                    ),
+                    ..default()
+                });
+            }
+            spawn_button(parent, &font, &locale, "menu.back", MenuButton::MainMenu);
+        });
+}
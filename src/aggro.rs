@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    deaths::Dying, events::Provoked, AddSimulationSystem, Enemy, GameState, InsertIfAlive, Player,
+    TimeScale,
+};
+
+/// Seconds between an enemy's looks around for something better to chase.
+const RETARGET_INTERVAL: f32 = 0.5;
+/// Distance within which enemies notice anything besides the player, who they always know of.
+const AGGRO_RANGE: f32 = 400.0;
+/// Distance over which a target's draw halves.
+const DISTANCE_FALLOFF: f32 = 150.0;
+/// How much more a taunting target draws enemies.
+const TAUNT_FACTOR: f32 = 4.0;
+/// Grudge an enemy holds for each hit it takes from the same attacker.
+const GRUDGE_PER_HIT: f32 = 1.0;
+/// Grudge lost per second as an enemy forgets who hit it.
+const GRUDGE_DECAY: f32 = 0.5;
+
+/// Something enemies go after, drawing them the more the higher the weight.
+#[derive(Component)]
+pub struct Threat {
+    pub weight: f32,
+}
+
+/// Draws enemies to the entity it's on far more than its threat would, until the timer finishes.
+#[derive(Component)]
+pub struct Taunt(pub Timer);
+
+/// What an enemy is chasing and what it holds against whom.
+#[derive(Component)]
+pub struct Aggro {
+    /// `None` until the enemy first looks around, it goes for the player meanwhile
+    pub target: Option<Entity>,
+    /// the last attacker to hit the enemy, and how much that still counts
+    grudge: Option<(Entity, f32)>,
+    retarget: Timer,
+}
+
+impl Aggro {
+    /// The aggro of the `index`th enemy to join the run.
+    fn new(index: u32) -> Self {
+        let mut retarget = Timer::from_seconds(RETARGET_INTERVAL, true);
+        // spread the crowd's lookups over the interval instead of all on the same step
+        let offset = (index % 32) as f32 / 32.0;
+        retarget.set_elapsed(Duration::from_secs_f32(RETARGET_INTERVAL * offset));
+        Self {
+            target: None,
+            grudge: None,
+            retarget,
+        }
+    }
+
+    fn provoke(&mut self, by: Entity) {
+        self.grudge = match self.grudge {
+            Some((attacker, grudge)) if attacker == by => Some((by, grudge + GRUDGE_PER_HIT)),
+            _ => Some((by, GRUDGE_PER_HIT)),
+        };
+    }
+
+    fn grudge(&self, against: Entity) -> f32 {
+        match self.grudge {
+            Some((attacker, grudge)) if attacker == against => grudge,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Enemies that joined the current run so far.
+#[derive(Default)]
+struct Joined(u32);
+
+type ThreatItem<'a> = (Entity, &'a Threat, &'a Transform, Option<&'a Taunt>);
+
+pub struct AggroPlugin;
+
+impl Plugin for AggroPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Joined>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_joined))
+            .add_system_set(SystemSet::on_enter(GameState::Tutorial).with_system(reset_joined))
+            .add_simulation_system(start_aggro_system)
+            .add_simulation_system(aggro_system)
+            .add_simulation_system(taunt_system);
+    }
+}
+
+fn reset_joined(mut joined: ResMut<Joined>) {
+    joined.0 = 0;
+}
+
+fn start_aggro_system(
+    mut commands: Commands,
+    mut joined: ResMut<Joined>,
+    enemies: Query<Entity, Added<Enemy>>,
+) {
+    for entity in enemies.iter() {
+        commands.add(InsertIfAlive(entity, Aggro::new(joined.0)));
+        joined.0 = joined.0.wrapping_add(1);
+    }
+}
+
+/// Settles grudges and, on each enemy's own throttled timer, picks the target drawing it the
+/// most: weighed by distance, grudge and taunts.
+fn aggro_system(
+    time_scale: Res<TimeScale>,
+    mut enemies: Query<(&mut Aggro, &Transform), (With<Enemy>, Without<Dying>)>,
+    threats: Query<(Entity, &Threat, &Transform, Option<&Taunt>), Without<Enemy>>,
+    player: Query<(), With<Player>>,
+    mut provoked: EventReader<Provoked>,
+) {
+    for event in provoked.iter() {
+        if let Ok((mut aggro, _)) = enemies.get_mut(event.enemy) {
+            aggro.provoke(event.by);
+        }
+    }
+
+    let step = time_scale.enemy_step();
+    let delta = time_scale.enemy_step_duration();
+    for (mut aggro, transform) in enemies.iter_mut() {
+        if let Some((attacker, grudge)) = aggro.grudge {
+            let grudge = grudge - GRUDGE_DECAY * step;
+            aggro.grudge = (grudge > 0.0).then_some((attacker, grudge));
+        }
+        let target_gone = aggro
+            .target
+            .is_some_and(|target| threats.get(target).is_err());
+        if !aggro.retarget.tick(delta).just_finished() && aggro.target.is_some() && !target_gone {
+            continue;
+        }
+
+        let position = transform.translation;
+        let draw = |(entity, threat, target, taunt): ThreatItem| {
+            let distance = target.translation.distance(position);
+            let taunt = if taunt.is_some() { TAUNT_FACTOR } else { 1.0 };
+            let draw = threat.weight * taunt * (1.0 + aggro.grudge(entity))
+                / (1.0 + distance / DISTANCE_FALLOFF);
+            (entity, distance, draw)
+        };
+        let noticed =
+            |entity: Entity, distance: f32| distance <= AGGRO_RANGE || player.get(entity).is_ok();
+        let target = threats
+            .iter()
+            .map(draw)
+            .filter(|(entity, distance, _)| noticed(*entity, *distance))
+            .max_by(|(.., a), (.., b)| a.total_cmp(b))
+            .map(|(entity, ..)| entity);
+        aggro.target = target;
+    }
+}
+
+fn taunt_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut taunts: Query<(Entity, &mut Taunt)>,
+) {
+    for (entity, mut taunt) in taunts.iter_mut() {
+        if taunt.0.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).remove::<Taunt>();
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    path::Path,
+};
+
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::{
+    events::{BossArrived, ComboMilestone, PlayerDamaged, WaveStarted},
+    locale::Locale,
+    settings::Settings,
+    GameState, Health, Player, UiFont,
+};
+
+/// Directory, relative to the asset root, the announcer's voice clips are loaded from.
+const VOICE_DIR: &str = "announcer";
+/// Seconds an announcement holds the announcer, whether it's spoken or shown as a banner.
+const ANNOUNCEMENT_DURATION: f32 = 2.0;
+/// Seconds at the end of a banner's lifetime over which it fades out.
+const FADE_DURATION: f32 = 0.5;
+/// Seconds an announcement waits its turn before it's too late to be worth making.
+const MAX_WAIT: f32 = 3.0;
+/// Player health at or below which they're one hit from death.
+const LOW_HEALTH: f32 = 1.0;
+
+/// Kind of thing the announcer calls out, each with its own voice clip and cooldown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Callout {
+    WaveStarted,
+    BossArrived,
+    ComboMilestone,
+    LowHealth,
+}
+
+impl Callout {
+    const ALL: [Callout; 4] = [
+        Callout::WaveStarted,
+        Callout::BossArrived,
+        Callout::ComboMilestone,
+        Callout::LowHealth,
+    ];
+
+    /// Announcements with a higher priority cut in line ahead of the rest.
+    fn priority(&self) -> u8 {
+        match self {
+            Callout::BossArrived => 3,
+            Callout::LowHealth => 2,
+            Callout::WaveStarted => 1,
+            Callout::ComboMilestone => 0,
+        }
+    }
+
+    /// Seconds after an announcement before another one of the same kind is made.
+    fn cooldown(&self) -> f32 {
+        match self {
+            Callout::WaveStarted | Callout::BossArrived => 0.0,
+            Callout::ComboMilestone => 8.0,
+            Callout::LowHealth => 15.0,
+        }
+    }
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Callout::WaveStarted => "wave.ogg",
+            Callout::BossArrived => "boss.ogg",
+            Callout::ComboMilestone => "combo.ogg",
+            Callout::LowHealth => "low_health.ogg",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Callout::WaveStarted => Color::rgb(0.85, 0.85, 0.85),
+            Callout::BossArrived | Callout::LowHealth => Color::ORANGE_RED,
+            Callout::ComboMilestone => Color::rgb(0.4, 0.8, 1.0),
+        }
+    }
+}
+
+/// Callout waiting its turn, with the banner text shown when there's no clip to play.
+struct Announcement {
+    callout: Callout,
+    text: String,
+    /// seconds since the game started when it was queued
+    queued_at: f64,
+}
+
+impl Announcement {
+    fn rank(&self) -> (u8, f64) {
+        // older ones first among the same priority, the heap pops its greatest
+        (self.callout.priority(), -self.queued_at)
+    }
+}
+
+impl PartialEq for Announcement {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Announcement {}
+
+impl PartialOrd for Announcement {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Announcement {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (priority, age) = self.rank();
+        let (other_priority, other_age) = other.rank();
+        priority
+            .cmp(&other_priority)
+            .then(age.total_cmp(&other_age))
+    }
+}
+
+/// Voice clips, the announcements queued up and the one being made.
+#[derive(Default)]
+struct Announcer {
+    clips: HashMap<Callout, Handle<AudioSource>>,
+    queue: BinaryHeap<Announcement>,
+    /// running until the current announcement is over
+    speaking: Option<Timer>,
+    cooldowns: HashMap<Callout, Timer>,
+}
+
+impl Announcer {
+    fn announce(&mut self, callout: Callout, text: String, now: f64) {
+        if self.cooldowns.contains_key(&callout) {
+            return;
+        }
+        self.queue.push(Announcement {
+            callout,
+            text,
+            queued_at: now,
+        });
+    }
+}
+
+/// Banner shown in place of a voice clip.
+#[derive(Component)]
+struct Banner(Timer);
+
+pub struct AnnouncerPlugin;
+
+impl Plugin for AnnouncerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Announcer>()
+            .add_startup_system(load_clips)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_announcer))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(callout_system)
+                    .with_system(announce_system.after(callout_system))
+                    .with_system(banner_system),
+            );
+    }
+}
+
+/// Loads whichever voice clips are in the announcer's directory, banners stand in for the rest.
+fn load_clips(asset_server: Res<AssetServer>, mut announcer: ResMut<Announcer>) {
+    let files: Vec<_> = match asset_server.asset_io().read_directory(Path::new(VOICE_DIR)) {
+        Ok(files) => files.collect(),
+        Err(_) => return,
+    };
+    for callout in Callout::ALL {
+        let path = Path::new(VOICE_DIR).join(callout.file_name());
+        if files.contains(&path) {
+            announcer.clips.insert(callout, asset_server.load(path));
+        }
+    }
+}
+
+/// Clears what was left over from the last run, the clips stay loaded.
+fn reset_announcer(mut announcer: ResMut<Announcer>) {
+    announcer.queue.clear();
+    announcer.speaking = None;
+    announcer.cooldowns.clear();
+}
+
+/// Queues an announcement for everything worth calling out this frame.
+fn callout_system(
+    time: Res<Time>,
+    locale: Res<Locale>,
+    mut announcer: ResMut<Announcer>,
+    player: Query<&Health, With<Player>>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut boss_arrived: EventReader<BossArrived>,
+    mut combo_milestone: EventReader<ComboMilestone>,
+    mut player_damaged: EventReader<PlayerDamaged>,
+) {
+    let now = time.seconds_since_startup();
+    for started in wave_started.iter() {
+        let text = locale.format("announcer.wave_started", &[("wave", &started.number)]);
+        announcer.announce(Callout::WaveStarted, text, now);
+    }
+    for arrived in boss_arrived.iter() {
+        let boss = locale.get_or(&format!("boss.{}", arrived.name), &arrived.display_name);
+        let text = locale.format("announcer.boss_arrived", &[("boss", &boss)]);
+        announcer.announce(Callout::BossArrived, text, now);
+    }
+    for milestone in combo_milestone.iter() {
+        let text = locale.format("announcer.combo", &[("combo", &milestone.count)]);
+        announcer.announce(Callout::ComboMilestone, text, now);
+    }
+    let low_health = player
+        .get_single()
+        .is_ok_and(|health| health.current > 0.0 && health.current <= LOW_HEALTH);
+    if player_damaged.iter().count() > 0 && low_health {
+        let text = locale.get("announcer.low_health").to_string();
+        announcer.announce(Callout::LowHealth, text, now);
+    }
+}
+
+/// Makes the most important announcement waiting once the last one is over, as a voice clip if
+/// there is one and a banner otherwise.
+fn announce_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    font: Res<UiFont>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    mut announcer: ResMut<Announcer>,
+) {
+    let announcer = &mut *announcer;
+    announcer
+        .cooldowns
+        .retain(|_, cooldown| !cooldown.tick(time.delta()).finished());
+    if let Some(speaking) = &mut announcer.speaking {
+        if !speaking.tick(time.delta()).finished() {
+            return;
+        }
+        announcer.speaking = None;
+    }
+
+    let now = time.seconds_since_startup();
+    let announcement = loop {
+        match announcer.queue.pop() {
+            Some(announcement) if now - announcement.queued_at > MAX_WAIT as f64 => continue,
+            // another one of its kind was made while it waited
+            Some(announcement) if announcer.cooldowns.contains_key(&announcement.callout) => {
+                continue
+            }
+            Some(announcement) => break announcement,
+            None => return,
+        }
+    };
+    let callout = announcement.callout;
+    announcer.speaking = Some(Timer::from_seconds(ANNOUNCEMENT_DURATION, false));
+    if callout.cooldown() > 0.0 {
+        announcer
+            .cooldowns
+            .insert(callout, Timer::from_seconds(callout.cooldown(), false));
+    }
+
+    let clip = announcer
+        .clips
+        .get(&callout)
+        .filter(|clip| asset_server.get_load_state(*clip) == LoadState::Loaded);
+    if let Some(clip) = clip {
+        audio.play_with_settings(
+            clip.clone(),
+            PlaybackSettings::ONCE.with_volume(settings.announcer_volume),
+        );
+        return;
+    }
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Percent(20.0),
+                    ..default()
+                },
+                size: Size::new(Val::Percent(100.0), Val::Auto),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(Banner(Timer::from_seconds(ANNOUNCEMENT_DURATION, false)))
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    announcement.text,
+                    TextStyle {
+                        font: font.0.clone(),
+                        font_size: 48.0,
+                        color: callout.color(),
+                    },
+                    default(),
+                ),
+                ..default()
+            });
+        });
+}
+
+fn banner_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut banners: Query<(Entity, &mut Banner, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (entity, mut banner, children) in banners.iter_mut() {
+        if banner.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let remaining = ANNOUNCEMENT_DURATION - banner.0.elapsed_secs();
+        let alpha = (remaining / FADE_DURATION).min(1.0);
+        for &child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                for section in text.sections.iter_mut() {
+                    section.style.color.set_a(alpha);
+                }
+            }
+        }
+    }
+}
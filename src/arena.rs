@@ -0,0 +1,397 @@
+use bevy::{math::const_vec2, prelude::*};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    damage_player,
+    enemy_bullets::EnemyBullet,
+    events::{DamageSource, PlayerDamaged, WaveStarted},
+    layers::RenderLayer,
+    loadout::{GameMode, Loadout},
+    packs::ContentPacks,
+    palette::Palette,
+    perks::Bounce,
+    save,
+    settings::Settings,
+    AddSimulationSystem, Bullet, GameState, Health, Player, TimeScale, BOUNDS,
+};
+
+const LAYOUT_FILE: &str = "arena";
+/// File in a content pack holding the placements it adds to the arena.
+pub const ARENA_FILE: &str = "arena.ron";
+
+/// Depths on the floor decorations and obstacles are drawn at, above zones and scorch marks.
+const DECORATION_DEPTH: f32 = 0.2;
+const OBSTACLE_DEPTH: f32 = 0.3;
+/// Factor the safe zone shrinks by with every new wave.
+const SHRINK_FACTOR: f32 = 0.85;
+/// Smallest half extents the safe zone shrinks down to.
+const MIN_HALF_EXTENTS: Vec2 = const_vec2!([150.0, 100.0]);
+/// Speed in pixels per second at which the zone's edges close in.
+const SHRINK_SPEED: f32 = 40.0;
+/// Seconds between hits taken while outside the safe zone.
+const HAZARD_INTERVAL: f32 = 1.0;
+/// Factor enemies outside the safe zone move faster by as they rush back in.
+const RUSH_FACTOR: f32 = 2.0;
+/// Distance off an obstacle's side a bullet bouncing off it is put at, for it not to count as
+/// inside again.
+const BOUNCE_CLEARANCE: f32 = 1.0;
+
+/// Part of the arena that is safe to stand in, contracting after each wave in the shrinking
+/// arena mode.
+pub struct SafeZone {
+    enabled: bool,
+    half_extents: Vec2,
+    /// half extents the zone is currently shrinking towards
+    target: Vec2,
+    hazard_timer: Timer,
+}
+
+impl Default for SafeZone {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            half_extents: BOUNDS / 2.0,
+            target: BOUNDS / 2.0,
+            hazard_timer: Timer::from_seconds(HAZARD_INTERVAL, true),
+        }
+    }
+}
+
+impl SafeZone {
+    pub fn contains(&self, position: Vec3) -> bool {
+        !self.enabled
+            || (position.x.abs() <= self.half_extents.x && position.y.abs() <= self.half_extents.y)
+    }
+
+    /// Factor an enemy at the given position moves faster by.
+    pub fn enemy_speed_factor(&self, position: Vec3) -> f32 {
+        if self.contains(position) {
+            1.0
+        } else {
+            RUSH_FACTOR
+        }
+    }
+}
+
+/// Square placed in the arena, centered on `position` with sides `size` long.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Placement {
+    pub position: (f32, f32),
+    pub size: f32,
+}
+
+impl Placement {
+    pub fn center(&self) -> Vec2 {
+        Vec2::new(self.position.0, self.position.1)
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        let offset = (point - self.center()).abs();
+        offset.x <= self.size / 2.0 && offset.y <= self.size / 2.0
+    }
+}
+
+/// What's placed in the arena besides the hazards, laid out in the editor and persisted between
+/// sessions, with what the enabled content packs place added in play.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArenaLayout {
+    /// walls the player and every bullet are stopped by, enemies fly over them
+    pub obstacles: Vec<Placement>,
+    /// areas enemies spawn in instead of anywhere around the player, when there are any
+    pub spawn_zones: Vec<Placement>,
+    /// drawn behind everything without getting in anyone's way
+    pub decorations: Vec<Placement>,
+}
+
+impl save::Versioned for ArenaLayout {
+    const VERSION: u32 = 1;
+}
+
+impl ArenaLayout {
+    pub fn load() -> Self {
+        save::load(LAYOUT_FILE)
+    }
+
+    pub fn store(&self) {
+        save::store(LAYOUT_FILE, self);
+    }
+
+    /// The layout with the placements of every enabled pack's layout added to it.
+    pub fn with_packs(mut self, packs: &ContentPacks) -> Self {
+        for pack in packs.load_each::<ArenaLayout>(ARENA_FILE) {
+            self.obstacles.extend(pack.obstacles);
+            self.spawn_zones.extend(pack.spawn_zones);
+            self.decorations.extend(pack.decorations);
+        }
+        self
+    }
+
+    /// Random point in one of the spawn zones, if there are any.
+    pub fn spawn_point(&self, rng: &mut impl Rng) -> Option<Vec3> {
+        let zone = self.spawn_zones.choose(rng)?;
+        let half = zone.size / 2.0;
+        let offset = Vec2::new(rng.gen_range(-half..=half), rng.gen_range(-half..=half));
+        Some((zone.center() + offset).extend(0.0))
+    }
+}
+
+#[derive(Component)]
+pub struct Obstacle(pub Placement);
+
+/// Translucent sprite covering one side of the hazard outside the safe zone.
+#[derive(Component, Clone, Copy)]
+enum HazardOverlay {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl HazardOverlay {
+    const ALL: [HazardOverlay; 4] = [
+        HazardOverlay::Left,
+        HazardOverlay::Right,
+        HazardOverlay::Top,
+        HazardOverlay::Bottom,
+    ];
+
+    /// Center and size of the overlay for a zone with the given half extents.
+    fn rect(&self, half_extents: Vec2) -> (Vec2, Vec2) {
+        let bounds = BOUNDS / 2.0;
+        // the sides span the whole height, the top and bottom only the width of the zone
+        let side = Vec2::new(bounds.x - half_extents.x, BOUNDS.y);
+        let cap = Vec2::new(half_extents.x * 2.0, bounds.y - half_extents.y);
+        match self {
+            HazardOverlay::Left => (Vec2::new(-(bounds.x + half_extents.x) / 2.0, 0.0), side),
+            HazardOverlay::Right => (Vec2::new((bounds.x + half_extents.x) / 2.0, 0.0), side),
+            HazardOverlay::Top => (Vec2::new(0.0, (bounds.y + half_extents.y) / 2.0), cap),
+            HazardOverlay::Bottom => (Vec2::new(0.0, -(bounds.y + half_extents.y) / 2.0), cap),
+        }
+    }
+}
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        let layout = ArenaLayout::load().with_packs(app.world.resource::<ContentPacks>());
+        app.init_resource::<SafeZone>()
+            .insert_resource(layout)
+            .add_system(reload_layout_system)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(start_safe_zone)
+                    .with_system(spawn_layout),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Tutorial).with_system(start_safe_zone))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(hazard_overlay_system),
+            )
+            .add_simulation_system(shrink_on_wave_system)
+            .add_simulation_system(safe_zone_system)
+            .add_simulation_system(hazard_damage_system)
+            .add_simulation_system(obstacle_system);
+    }
+}
+
+/// Takes the placements of the packs turned on or off in.
+fn reload_layout_system(packs: Res<ContentPacks>, mut layout: ResMut<ArenaLayout>) {
+    if packs.is_changed() && !packs.is_added() {
+        *layout = ArenaLayout::load().with_packs(&packs);
+    }
+}
+
+fn start_safe_zone(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    loadout: Res<Loadout>,
+    palette: Res<Palette>,
+    mut safe_zone: ResMut<SafeZone>,
+) {
+    *safe_zone = SafeZone {
+        // the tutorial always plays in the full arena
+        enabled: *state.current() == GameState::Playing && loadout.mode == GameMode::ShrinkingArena,
+        ..default()
+    };
+    if !safe_zone.enabled {
+        return;
+    }
+    for overlay in HazardOverlay::ALL {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: palette.hazard,
+                    custom_size: Some(Vec2::ZERO),
+                    ..default()
+                },
+                ..default()
+            })
+            // drawn over the objects in the hazard
+            .insert(RenderLayer::Fx)
+            .insert(overlay);
+    }
+}
+
+/// Places the layout's obstacles and decorations, the tutorial always plays in the empty arena.
+fn spawn_layout(mut commands: Commands, layout: Res<ArenaLayout>, palette: Res<Palette>) {
+    for obstacle in layout.obstacles.iter() {
+        palette
+            .spawn_sprite(
+                &mut commands,
+                RenderLayer::Decals,
+                palette.obstacle,
+                obstacle.size,
+                Transform::from_translation(obstacle.center().extend(OBSTACLE_DEPTH)),
+            )
+            .insert(Obstacle(*obstacle));
+    }
+    for decoration in layout.decorations.iter() {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: palette.decoration,
+                    custom_size: Some(Vec2::splat(decoration.size)),
+                    ..default()
+                },
+                transform: Transform::from_translation(
+                    decoration.center().extend(DECORATION_DEPTH),
+                ),
+                ..default()
+            })
+            .insert(RenderLayer::Decals);
+    }
+}
+
+/// Pushes the player out of obstacles and bounces the bullets that ran into one off it, removing
+/// the ones without a bounce left.
+fn obstacle_system(
+    mut commands: Commands,
+    obstacles: Query<&Obstacle>,
+    mut player: Query<(&Player, &mut Transform)>,
+    mut bullets: Query<(Entity, &mut Bullet, &mut Transform, Option<&mut Bounce>), Without<Player>>,
+    enemy_bullets: Query<
+        (Entity, &Transform),
+        (With<EnemyBullet>, Without<Player>, Without<Bullet>),
+    >,
+) {
+    for Obstacle(obstacle) in obstacles.iter() {
+        for (entity, mut bullet, mut transform, bounce) in bullets.iter_mut() {
+            let position = transform.translation.truncate();
+            if !obstacle.contains(position) {
+                continue;
+            }
+            // out the nearest side, which is the one it bounces off
+            let pushed = push_out(obstacle, position, BOUNCE_CLEARANCE);
+            if let Some(mut bounce) = bounce {
+                if bounce.reflect(&mut bullet.direction, (pushed - position).extend(0.0)) {
+                    transform.translation = pushed.extend(transform.translation.z);
+                    continue;
+                }
+            }
+            commands.entity(entity).despawn_recursive();
+        }
+        for (entity, transform) in enemy_bullets.iter() {
+            if obstacle.contains(transform.translation.truncate()) {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        for (player, mut transform) in player.iter_mut() {
+            let position = transform.translation.truncate();
+            let pushed = push_out(obstacle, position, player.hit_radius);
+            transform.translation = pushed.extend(transform.translation.z);
+        }
+    }
+}
+
+/// Position of a circle of the given radius at `position` moved just far enough to no longer
+/// overlap the square.
+fn push_out(square: &Placement, position: Vec2, radius: f32) -> Vec2 {
+    let half = Vec2::splat(square.size / 2.0);
+    let center = square.center();
+    let closest = position.clamp(center - half, center + half);
+    let offset = position - closest;
+    if offset != Vec2::ZERO {
+        if offset.length() >= radius {
+            return position;
+        }
+        return closest + offset.normalize() * radius;
+    }
+    // the center is inside the square, leave along the axis with the least way to go
+    let inside = position - center;
+    let depth = half - inside.abs();
+    if depth.x < depth.y {
+        Vec2::new(center.x + inside.x.signum() * (half.x + radius), position.y)
+    } else {
+        Vec2::new(position.x, center.y + inside.y.signum() * (half.y + radius))
+    }
+}
+
+fn shrink_on_wave_system(
+    mut safe_zone: ResMut<SafeZone>,
+    mut wave_started: EventReader<WaveStarted>,
+) {
+    // the first wave starts with the full arena
+    for _ in wave_started.iter().filter(|started| started.number > 1) {
+        safe_zone.target = (safe_zone.target * SHRINK_FACTOR).max(MIN_HALF_EXTENTS);
+    }
+}
+
+fn safe_zone_system(time_scale: Res<TimeScale>, mut safe_zone: ResMut<SafeZone>) {
+    if !safe_zone.enabled {
+        return;
+    }
+    let step = SHRINK_SPEED * time_scale.step();
+    let target = safe_zone.target;
+    safe_zone.half_extents = (safe_zone.half_extents - Vec2::splat(step)).max(target);
+}
+
+fn hazard_damage_system(
+    mut commands: Commands,
+    mut state: ResMut<State<GameState>>,
+    time_scale: Res<TimeScale>,
+    settings: Res<Settings>,
+    mut safe_zone: ResMut<SafeZone>,
+    mut player: Query<(Entity, &Transform, &mut Health), With<Player>>,
+    mut player_damaged: EventWriter<PlayerDamaged>,
+) {
+    let (entity, transform, mut health) = match player.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    if safe_zone.contains(transform.translation) {
+        safe_zone.hazard_timer.reset();
+        return;
+    }
+    if safe_zone
+        .hazard_timer
+        .tick(time_scale.step_duration())
+        .just_finished()
+    {
+        damage_player(
+            &mut commands,
+            &mut state,
+            &settings,
+            entity,
+            &mut health,
+            1.0,
+            DamageSource::ArenaEdge,
+            &mut player_damaged,
+        );
+    }
+}
+
+fn hazard_overlay_system(
+    safe_zone: Res<SafeZone>,
+    mut overlays: Query<(&HazardOverlay, &mut Sprite, &mut Transform)>,
+) {
+    if !safe_zone.is_changed() {
+        return;
+    }
+    for (overlay, mut sprite, mut transform) in overlays.iter_mut() {
+        let (center, size) = overlay.rect(safe_zone.half_extents);
+        sprite.custom_size = Some(size);
+        transform.translation = center.extend(transform.translation.z);
+    }
+}
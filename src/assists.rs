@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Largest angle in radians aim assist bends a shot by, at full strength.
+const MAX_AIM_ASSIST_ANGLE: f32 = 0.3;
+
+/// Optional help for players who find the game too hard. Runs played with any assist enabled
+/// don't count towards records or achievements.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Assists {
+    /// fraction of normal speed the game runs at
+    pub game_speed: f32,
+    /// fraction of incoming damage ignored
+    pub damage_reduction: f32,
+    /// how strongly shots are pulled towards nearby enemies, from 0 to 1
+    pub aim_assist: f32,
+}
+
+impl Assists {
+    pub const GAME_SPEEDS: [f32; 4] = [1.0, 0.9, 0.8, 0.7];
+    pub const DAMAGE_REDUCTIONS: [f32; 3] = [0.0, 0.25, 0.5];
+    pub const AIM_ASSISTS: [f32; 3] = [0.0, 0.5, 1.0];
+
+    pub fn is_active(&self) -> bool {
+        *self != Assists::default()
+    }
+
+    /// Damage the player takes from a hit of the given strength.
+    pub fn damage(&self, amount: f32) -> f32 {
+        amount * (1.0 - self.damage_reduction)
+    }
+
+    /// Turns a shot fired from `origin` along `direction` towards the nearest target within
+    /// reach of the assist.
+    pub fn aim(&self, origin: Vec3, direction: Vec3, targets: impl Iterator<Item = Vec3>) -> Vec3 {
+        let max_angle = MAX_AIM_ASSIST_ANGLE * self.aim_assist;
+        if max_angle <= 0.0 {
+            return direction;
+        }
+        let target = targets
+            .map(|target| target - origin)
+            .filter(|offset| offset.angle_between(direction) <= max_angle)
+            .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+        match target {
+            Some(offset) => offset.normalize(),
+            None => direction,
+        }
+    }
+}
+
+impl Default for Assists {
+    fn default() -> Self {
+        Self {
+            game_speed: 1.0,
+            damage_reduction: 0.0,
+            aim_assist: 0.0,
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use bevy::{
+    ecs::system::SystemParam,
+    input::{
+        gamepad::{GamepadEvent, GamepadEventType},
+        keyboard::KeyboardInput,
+        mouse::{MouseButtonInput, MouseMotion},
+        touch::TouchInput,
+    },
+    prelude::*,
+};
+
+use crate::{
+    bot::BotController,
+    locale::{Locale, Localized},
+    menu::{screen_node, spawn_title},
+    GameState, Health, Player, UiFont,
+};
+
+/// Seconds the main menu sits untouched before a demo run starts behind it.
+const IDLE_DURATION: f32 = 30.0;
+/// Seconds a demo run lasts before it goes back to the menu, for the demo to start over.
+const DEMO_DURATION: f32 = 60.0;
+
+/// Counts how long the main menu has been left alone, and times the demo run it leads to.
+pub struct Attract {
+    idle: Timer,
+    /// counting down the demo run in progress, if there is one
+    demo: Option<Timer>,
+}
+
+impl Default for Attract {
+    fn default() -> Self {
+        Self {
+            idle: Timer::from_seconds(IDLE_DURATION, false),
+            demo: None,
+        }
+    }
+}
+
+impl Attract {
+    /// Whether the run in progress is a demo the bot plays.
+    pub fn is_running(&self) -> bool {
+        self.demo.is_some()
+    }
+}
+
+/// Title shown over a demo run.
+#[derive(Component)]
+struct DemoScreen;
+
+/// Anything the player does with the keyboard, mouse, touchscreen or a gamepad.
+///
+/// The bot's keys don't count, it presses them without any events to go with them.
+#[derive(SystemParam)]
+struct PlayerInput<'w, 's> {
+    keyboard: EventReader<'w, 's, KeyboardInput>,
+    mouse_buttons: EventReader<'w, 's, MouseButtonInput>,
+    mouse_motion: EventReader<'w, 's, MouseMotion>,
+    touches: EventReader<'w, 's, TouchInput>,
+    gamepads: EventReader<'w, 's, GamepadEvent>,
+}
+
+impl PlayerInput<'_, '_> {
+    /// Whether there was any input since the last check.
+    fn any(&mut self) -> bool {
+        // every reader is read to the end, for older input not to count the next time
+        let buttons = self.gamepads.iter().any(|GamepadEvent(_, event)| {
+            matches!(event, GamepadEventType::ButtonChanged(_, value) if *value > 0.5)
+        });
+        let counts = [
+            self.keyboard.iter().count(),
+            self.mouse_buttons.iter().count(),
+            self.mouse_motion.iter().count(),
+            self.touches.iter().count(),
+        ];
+        buttons || counts.iter().any(|&count| count > 0)
+    }
+}
+
+/// Arcade-style attract mode: the bot plays a demo run behind the title while the main menu is
+/// left alone, which goes back to it on any input.
+pub struct AttractPlugin;
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Attract>()
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(reset_idle))
+            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(idle_system))
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(start_demo))
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(demo_system))
+            .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(stop_demo));
+    }
+}
+
+fn reset_idle(mut attract: ResMut<Attract>) {
+    attract.idle.reset();
+}
+
+/// Starts a demo run once the menu has been idle long enough.
+fn idle_system(
+    time: Res<Time>,
+    mut attract: ResMut<Attract>,
+    mut state: ResMut<State<GameState>>,
+    mut bot: ResMut<BotController>,
+    mut input: PlayerInput,
+) {
+    if input.any() {
+        attract.idle.reset();
+        return;
+    }
+    if attract.idle.tick(time.delta()).just_finished() {
+        attract.demo = Some(Timer::from_seconds(DEMO_DURATION, false));
+        // ahead of the run, for it to start out as the bot's
+        bot.enabled = true;
+        let _ = state.set(GameState::Playing);
+    }
+}
+
+/// Puts the title over a demo run.
+fn start_demo(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    locale: Res<Locale>,
+    attract: Res<Attract>,
+) {
+    if !attract.is_running() {
+        return;
+    }
+    commands
+        .spawn_bundle(screen_node())
+        .insert(DemoScreen)
+        .with_children(|parent| {
+            spawn_title(parent, &font, &locale, "menu.title");
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        locale.get("attract.press_any_key"),
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: 24.0,
+                            color: Color::rgb(0.85, 0.85, 0.85),
+                        },
+                        default(),
+                    ),
+                    ..default()
+                })
+                .insert(Localized("attract.press_any_key"));
+        });
+}
+
+/// Keeps the bot alive and goes back to the menu on any input or once the demo is over.
+fn demo_system(
+    time: Res<Time>,
+    mut attract: ResMut<Attract>,
+    mut state: ResMut<State<GameState>>,
+    mut input: PlayerInput,
+    mut player: Query<&mut Health, Added<Player>>,
+) {
+    let input = input.any();
+    let demo = match &mut attract.demo {
+        Some(demo) => demo,
+        None => return,
+    };
+    // a demo doesn't end at game over, that would count as a finished run
+    for mut health in player.iter_mut() {
+        health.current = f32::MAX;
+    }
+    if input || demo.tick(time.delta()).finished() {
+        let _ = state.set(GameState::Menu);
+    }
+}
+
+/// Gives the player the controls back, the title goes along with the rest of the run.
+fn stop_demo(mut attract: ResMut<Attract>, mut bot: ResMut<BotController>) {
+    if attract.demo.take().is_some() {
+        bot.enabled = false;
+    }
+}
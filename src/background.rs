@@ -0,0 +1,183 @@
+use bevy::{
+    ecs::system::{lifetimeless::SRes, SystemParamItem},
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        render_asset::{PrepareAssetError, RenderAsset},
+        render_resource::{
+            std140::{AsStd140, Std140},
+            BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+            BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, Buffer,
+            BufferBindingType, BufferInitDescriptor, BufferSize, BufferUsages, ShaderStages,
+        },
+        renderer::RenderDevice,
+    },
+    sprite::{Material2d, Material2dPipeline, Material2dPlugin, MaterialMesh2dBundle},
+};
+
+use crate::{deaths::Dying, layers::RenderLayer, palette::Palette, waves::Wave, Enemy, GameState};
+
+/// Rate per second the intensity eases towards its target.
+const INTENSITY_EASING: f32 = 1.5;
+
+/// Animated nebula and grid drawn in place of the flat clear color during a run.
+#[derive(Debug, Clone, TypeUuid)]
+#[uuid = "8c4f1f2a-3b7e-4d0c-9e55-2a61b7d0f4e3"]
+pub struct BackgroundMaterial {
+    pub base: Color,
+    pub accent: Color,
+    pub time: f32,
+    /// how hectic the run currently is, from 0 to 1
+    pub intensity: f32,
+    pub resolution: Vec2,
+}
+
+#[derive(Clone, Default, AsStd140)]
+struct BackgroundUniformData {
+    base: Vec4,
+    accent: Vec4,
+    params: Vec4,
+}
+
+pub struct GpuBackgroundMaterial {
+    _buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+impl RenderAsset for BackgroundMaterial {
+    type ExtractedAsset = BackgroundMaterial;
+    type PreparedAsset = GpuBackgroundMaterial;
+    type Param = (
+        SRes<RenderDevice>,
+        SRes<Material2dPipeline<BackgroundMaterial>>,
+    );
+
+    fn extract_asset(&self) -> Self::ExtractedAsset {
+        self.clone()
+    }
+
+    fn prepare_asset(
+        material: Self::ExtractedAsset,
+        (render_device, pipeline): &mut SystemParamItem<Self::Param>,
+    ) -> Result<Self::PreparedAsset, PrepareAssetError<Self::ExtractedAsset>> {
+        let value = BackgroundUniformData {
+            base: material.base.as_linear_rgba_f32().into(),
+            accent: material.accent.as_linear_rgba_f32().into(),
+            params: Vec4::new(
+                material.time,
+                material.intensity,
+                material.resolution.x,
+                material.resolution.y,
+            ),
+        };
+        let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("background_uniform_buffer"),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            contents: value.as_std140().as_bytes(),
+        });
+        let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("background_bind_group"),
+            layout: &pipeline.material2d_layout,
+        });
+        Ok(GpuBackgroundMaterial {
+            _buffer: buffer,
+            bind_group,
+        })
+    }
+}
+
+impl Material2d for BackgroundMaterial {
+    fn fragment_shader(asset_server: &AssetServer) -> Option<Handle<Shader>> {
+        Some(asset_server.load("shaders/background.wgsl"))
+    }
+
+    fn bind_group(material: &GpuBackgroundMaterial) -> &BindGroup {
+        &material.bind_group
+    }
+
+    fn bind_group_layout(render_device: &RenderDevice) -> BindGroupLayout {
+        render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: BufferSize::new(
+                        BackgroundUniformData::std140_size_static() as u64
+                    ),
+                },
+                count: None,
+            }],
+            label: Some("background_layout"),
+        })
+    }
+}
+
+#[derive(Component)]
+struct BackgroundQuad;
+
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(Material2dPlugin::<BackgroundMaterial>::default())
+            .add_system(background_system);
+        for state in [GameState::Playing, GameState::Tutorial] {
+            app.add_system_set(SystemSet::on_enter(state).with_system(spawn_background));
+        }
+    }
+}
+
+fn spawn_background(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<BackgroundMaterial>>,
+) {
+    commands
+        .spawn_bundle(MaterialMesh2dBundle {
+            mesh: meshes.add(Mesh::from(shape::Quad::default())).into(),
+            material: materials.add(BackgroundMaterial {
+                base: palette.background,
+                accent: palette.enemy,
+                time: 0.0,
+                intensity: 0.0,
+                resolution: Vec2::ONE,
+            }),
+            ..default()
+        })
+        .insert(RenderLayer::Background)
+        .insert(BackgroundQuad);
+}
+
+fn background_system(
+    time: Res<Time>,
+    windows: Res<Windows>,
+    palette: Res<Palette>,
+    wave: Res<Wave>,
+    enemies: Query<(), (With<Enemy>, Without<Dying>)>,
+    mut quads: Query<(&Handle<BackgroundMaterial>, &mut Transform), With<BackgroundQuad>>,
+    mut materials: ResMut<Assets<BackgroundMaterial>>,
+) {
+    let resolution = windows.get_primary().map_or(Vec2::ONE, |window| {
+        Vec2::new(window.width(), window.height())
+    });
+    let target = wave.intensity(enemies.iter().count());
+    let easing = (INTENSITY_EASING * time.delta_seconds()).min(1.0);
+
+    for (handle, mut transform) in quads.iter_mut() {
+        transform.scale = resolution.extend(1.0);
+        if let Some(material) = materials.get_mut(handle) {
+            material.base = palette.background;
+            material.accent = palette.enemy;
+            material.time = time.seconds_since_startup() as f32;
+            material.intensity += (target - material.intensity) * easing;
+            material.resolution = resolution;
+        }
+    }
+}
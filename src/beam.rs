@@ -0,0 +1,350 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::{
+    audio::{play_queued_audio_system, AudioOutput, AudioSink, Decodable},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use rodio::buffer::SamplesBuffer;
+
+use crate::{
+    events::{BulletHit, ShotFired},
+    loadout::Weapon,
+    munitions::WeaponTunings,
+    palette::Palette,
+    settings::Settings,
+    AddSimulationSystem, EnemyDamage, GameState, Hit, Player, Targets, TimeScale,
+    WithChildrenIfAlive,
+};
+
+/// Distance the beam reaches from the ship, enough to cross the whole arena.
+const BEAM_RANGE: f32 = 1400.0;
+const BEAM_WIDTH: f32 = 6.0;
+/// Seconds of continuous fire a full energy meter lasts.
+const ENERGY_CAPACITY: f32 = 2.5;
+/// Seconds an empty energy meter takes to fill up again.
+const ENERGY_RECHARGE: f32 = 4.0;
+/// Energy an emptied meter has to recharge to before the beam fires again.
+const RESTART_ENERGY: f32 = 0.3;
+/// Samples per second the beam's sounds are synthesized at.
+const SAMPLE_RATE: u32 = 44_100;
+
+/// Continuous hitscan beam fired along the ship's facing, draining an energy meter as it fires and
+/// cutting out while the emptied meter recharges.
+#[derive(Component)]
+pub struct Beam {
+    /// fraction of the energy meter left
+    energy: f32,
+    firing: bool,
+    /// set once the meter runs dry until it has recharged enough
+    overheated: bool,
+    /// interval at which the beam damages what it touches
+    tick: Timer,
+}
+
+impl Beam {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            energy: 1.0,
+            firing: false,
+            overheated: false,
+            tick: Timer::new(interval, true),
+        }
+    }
+
+    pub fn energy(&self) -> f32 {
+        self.energy
+    }
+
+    pub fn is_overheated(&self) -> bool {
+        self.overheated
+    }
+}
+
+/// Sprite drawing the beam, a child of the player.
+#[derive(Component)]
+struct BeamSprite {
+    /// whether it's the bright core rather than the wider glow around it
+    core: bool,
+}
+
+/// Sound of the beam, synthesized when the game starts rather than read from a file.
+#[derive(TypeUuid)]
+#[uuid = "6f1c2a8e-3b7d-4e59-9a41-d2c8b5e07f13"]
+pub struct BeamSound(Vec<f32>);
+
+impl BeamSound {
+    /// Tone gliding from one frequency to another over `duration` seconds, as loud as `envelope`
+    /// has it at each point of the way from 0 to 1.
+    fn sweep(duration: f32, from: f32, to: f32, envelope: impl Fn(f32) -> f32) -> Self {
+        let count = (duration * SAMPLE_RATE as f32) as usize;
+        let mut phase = 0.0_f32;
+        let samples = (0..count)
+            .map(|i| {
+                let progress = i as f32 / count as f32;
+                let frequency = from + (to - from) * progress;
+                phase = (phase + TAU * frequency / SAMPLE_RATE as f32) % TAU;
+                phase.sin() * envelope(progress)
+            })
+            .collect();
+        Self(samples)
+    }
+
+    /// Half a second of the hum played while the beam fires, every partial fitting into it a
+    /// whole number of times for it to loop without a click.
+    fn hum() -> Self {
+        let samples = (0..SAMPLE_RATE / 2)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let partial = |frequency: f32| (TAU * frequency * t).sin();
+                // the crackle swells about as fast as the beam's glow flickers
+                0.3 * partial(110.0)
+                    + 0.15 * partial(220.0)
+                    + 0.08 * partial(330.0)
+                    + 0.05 * partial(1760.0) * (0.5 + 0.5 * partial(8.0))
+            })
+            .collect();
+        Self(samples)
+    }
+}
+
+impl Decodable for BeamSound {
+    type Decoder = SamplesBuffer<f32>;
+    type DecoderItem = f32;
+
+    fn decoder(&self) -> Self::Decoder {
+        SamplesBuffer::new(1, SAMPLE_RATE, self.0.clone())
+    }
+}
+
+/// The beam's hum and the cues it starts and cuts out with.
+struct BeamSounds {
+    start: Handle<BeamSound>,
+    hum: Handle<BeamSound>,
+    stop: Handle<BeamSound>,
+    /// the hum, while the beam is firing
+    playing: Option<Handle<AudioSink>>,
+}
+
+pub struct BeamPlugin;
+
+impl Plugin for BeamPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<BeamSound>()
+            .init_non_send_resource::<AudioOutput<BeamSound>>()
+            .init_resource::<Audio<BeamSound>>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                play_queued_audio_system::<BeamSound>.exclusive_system(),
+            );
+        let mut assets = app.world.resource_mut::<Assets<BeamSound>>();
+        let sounds = BeamSounds {
+            start: assets.add(BeamSound::sweep(0.15, 220.0, 880.0, |progress| {
+                0.4 * (1.0 - progress)
+            })),
+            hum: assets.add(BeamSound::hum()),
+            stop: assets.add(BeamSound::sweep(0.2, 660.0, 110.0, |progress| {
+                0.4 * (1.0 - progress).powi(2)
+            })),
+            playing: None,
+        };
+        app.insert_resource(sounds)
+            .add_simulation_system(spawn_beam_sprites)
+            .add_simulation_system(beam_system)
+            .add_system(beam_sprite_system)
+            .add_system(beam_sound_system);
+    }
+}
+
+/// Fraction of the way from `start` to `end` at which the segment enters the axis aligned box
+/// with the given center and half extents, if it passes through it at all, using the slab method.
+pub fn segment_entry(start: Vec2, end: Vec2, center: Vec2, half_extents: Vec2) -> Option<f32> {
+    let delta = end - start;
+    let (mut near, mut far) = (0.0_f32, 1.0_f32);
+    for axis in 0..2 {
+        let min = center[axis] - half_extents[axis];
+        let max = center[axis] + half_extents[axis];
+        if delta[axis].abs() < f32::EPSILON {
+            // parallel to the slab, so it has to start inside it
+            if start[axis] < min || start[axis] > max {
+                return None;
+            }
+            continue;
+        }
+        let (mut t0, mut t1) = (
+            (min - start[axis]) / delta[axis],
+            (max - start[axis]) / delta[axis],
+        );
+        if t0 > t1 {
+            std::mem::swap(&mut t0, &mut t1);
+        }
+        near = near.max(t0);
+        far = far.min(t1);
+        if near > far {
+            return None;
+        }
+    }
+    Some(near)
+}
+
+fn beam_system(
+    time_scale: Res<TimeScale>,
+    tunings: Res<WeaponTunings>,
+    mut player: Query<(&Transform, &mut Beam), With<Player>>,
+    mut enemies: Targets<Without<Player>>,
+    mut damage: EnemyDamage,
+    mut shot_fired: EventWriter<ShotFired>,
+    mut bullet_hit: EventWriter<BulletHit>,
+) {
+    let (transform, mut beam) = match player.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let step = time_scale.step();
+
+    if beam.overheated && beam.energy >= RESTART_ENERGY {
+        beam.overheated = false;
+    }
+    beam.firing = !beam.overheated;
+    if !beam.firing {
+        beam.energy = (beam.energy + step / ENERGY_RECHARGE).min(1.0);
+        return;
+    }
+    beam.energy -= step / ENERGY_CAPACITY;
+    if beam.energy <= 0.0 {
+        beam.energy = 0.0;
+        beam.overheated = true;
+    }
+
+    if !beam.tick.tick(time_scale.step_duration()).just_finished() {
+        return;
+    }
+    shot_fired.send(ShotFired);
+    let direction = transform.rotation * Vec3::Y;
+    let start = transform.translation.truncate();
+    let end = start + direction.truncate() * BEAM_RANGE;
+    let mut landed = false;
+    for (entity, enemy, enemy_transform, mut elite, splitter) in enemies.iter_mut() {
+        let center = enemy_transform.translation;
+        if segment_entry(start, end, center.truncate(), Vec2::splat(enemy.size / 2.0)).is_none() {
+            continue;
+        }
+        let hit = damage.hit(
+            entity,
+            enemy,
+            center,
+            elite.as_deref_mut(),
+            splitter,
+            direction,
+            tunings.element(Weapon::Beam),
+        );
+        landed |= hit != Hit::Blocked;
+    }
+    // a tick counts as a single shot, however many enemies it passes through
+    if landed {
+        bullet_hit.send(BulletHit);
+    }
+}
+
+fn spawn_beam_sprites(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    player: Query<Entity, Added<Beam>>,
+) {
+    for player in player.iter() {
+        let mut glow = palette.bullet;
+        glow.set_a(0.4);
+        commands.add(WithChildrenIfAlive(
+            player,
+            move |parent: &mut WorldChildBuilder| {
+                for (core, color, width) in [
+                    (false, glow, BEAM_WIDTH * 2.5),
+                    (true, Color::WHITE, BEAM_WIDTH),
+                ] {
+                    parent
+                        .spawn_bundle(SpriteBundle {
+                            sprite: Sprite {
+                                color,
+                                custom_size: Some(Vec2::new(width, BEAM_RANGE)),
+                                ..default()
+                            },
+                            // starts at the ship's nose, the core drawn over the glow
+                            transform: Transform::from_xyz(
+                                0.0,
+                                BEAM_RANGE / 2.0,
+                                if core { 0.02 } else { 0.01 },
+                            ),
+                            visibility: Visibility { is_visible: false },
+                            ..default()
+                        })
+                        .insert(BeamSprite { core });
+                }
+            },
+        ));
+    }
+}
+
+fn beam_sprite_system(
+    time: Res<Time>,
+    player: Query<&Beam, With<Player>>,
+    mut sprites: Query<(&BeamSprite, &mut Sprite, &mut Visibility)>,
+) {
+    let beam = match player.get_single() {
+        Ok(beam) => beam,
+        Err(_) => return,
+    };
+    // the beam crackles by rapidly flickering its width
+    let flicker = 1.0 + 0.25 * (time.seconds_since_startup() as f32 * 50.0).sin();
+    for (beam_sprite, mut sprite, mut visibility) in sprites.iter_mut() {
+        visibility.is_visible = beam.firing;
+        let width = if beam_sprite.core {
+            BEAM_WIDTH
+        } else {
+            BEAM_WIDTH * 2.5 * flicker
+        };
+        sprite.custom_size = Some(Vec2::new(width, BEAM_RANGE));
+    }
+}
+
+/// Hums while the beam fires, with a rising cue as it starts and a falling one as it cuts out,
+/// and falls silent with it when the run is paused or over.
+fn beam_sound_system(
+    state: Res<State<GameState>>,
+    settings: Res<Settings>,
+    audio: Res<Audio<BeamSound>>,
+    sinks: Res<Assets<AudioSink>>,
+    mut sounds: ResMut<BeamSounds>,
+    player: Query<&Beam, With<Player>>,
+) {
+    let firing = state.current().is_simulated() && player.iter().any(|beam| beam.firing);
+    let volume = settings.effects_volume;
+    match sounds.playing.take() {
+        Some(playing) if firing => {
+            if let Some(sink) = sinks.get(&playing) {
+                sink.set_volume(volume);
+            }
+            sounds.playing = Some(playing);
+        }
+        Some(playing) => {
+            if let Some(sink) = sinks.get(&playing) {
+                sink.stop();
+            }
+            audio.play_with_settings(
+                sounds.stop.clone(),
+                PlaybackSettings::ONCE.with_volume(volume),
+            );
+        }
+        None if firing => {
+            audio.play_with_settings(
+                sounds.start.clone(),
+                PlaybackSettings::ONCE.with_volume(volume),
+            );
+            let hum = audio.play_with_settings(
+                sounds.hum.clone(),
+                PlaybackSettings::LOOP.with_volume(volume),
+            );
+            sounds.playing = Some(sinks.get_handle(hum));
+        }
+        None => {}
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::{ecs::event::Events, prelude::*};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    arena::SafeZone,
+    bullet_enemy_collision_system,
+    culling::Culled,
+    deaths,
+    deaths::Dying,
+    elements::{Affinities, Element, Wounds},
+    events::{BulletHit, EnemyKilled, Provoked},
+    move_bullets_system, move_enemy_system,
+    palette::{Palette, PaletteMode},
+    rng::GameRng,
+    scripting::ScriptSteering,
+    settings::Settings,
+    Bullet, Enemy, EnemyKind, Game, Player, TimeScale, BOUNDS, ENEMY_SIZE, FAST_BULLET_VELOCITY,
+};
+
+/// Systems under test and the world they run in.
+pub struct Scenario {
+    world: World,
+    stage: SystemStage,
+}
+
+impl Scenario {
+    fn new(world: World, stage: SystemStage) -> Self {
+        Self { world, stage }
+    }
+
+    /// Runs the systems for one fixed step.
+    pub fn step(&mut self) {
+        self.stage.run(&mut self.world);
+    }
+}
+
+/// A world with the resources every scenario's systems read, the same for every run.
+fn base_world() -> World {
+    let mut world = World::new();
+    world.insert_resource(Palette::new(PaletteMode::Standard));
+    world.insert_resource(GameRng(StdRng::seed_from_u64(0)));
+    world.init_resource::<TimeScale>();
+    world.init_resource::<SafeZone>();
+    world.init_resource::<Game>();
+    // chasers have no affinities to look up
+    world.init_resource::<Affinities>();
+    world.init_resource::<Settings>();
+    // nothing's out of view, every particle is animated
+    world.init_resource::<Culled>();
+    world.init_resource::<ScriptSteering>();
+    world.init_resource::<Events<EnemyKilled>>();
+    world.init_resource::<Events<BulletHit>>();
+    world.init_resource::<Events<Provoked>>();
+    world
+}
+
+/// The same scattering of positions between `min` and `max` for every run.
+fn positions(count: usize, seed: u64, min: Vec2, max: Vec2) -> Vec<Vec3> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            Vec3::new(
+                rng.gen_range(min.x..max.x),
+                rng.gen_range(min.y..max.y),
+                0.0,
+            )
+        })
+        .collect()
+}
+
+/// Positions scattered across the whole arena.
+fn arena_positions(count: usize, seed: u64) -> Vec<Vec3> {
+    positions(count, seed, -BOUNDS / 2.0, BOUNDS / 2.0)
+}
+
+fn spawn_enemies(world: &mut World, positions: Vec<Vec3>) {
+    let enemies = positions.into_iter().map(|position| {
+        (
+            Enemy {
+                kind: EnemyKind::Chaser,
+                velocity: 250.0,
+                size: ENEMY_SIZE,
+            },
+            Wounds::default(),
+            Transform::from_translation(position),
+            GlobalTransform::default(),
+        )
+    });
+    world.spawn_batch(enemies.collect::<Vec<_>>());
+}
+
+fn spawn_bullets(world: &mut World, positions: Vec<Vec3>) {
+    let bullets = positions.into_iter().map(|position| {
+        (
+            Bullet {
+                velocity: FAST_BULLET_VELOCITY,
+                direction: position.normalize_or_zero(),
+                wrapped: false,
+                element: Element::Kinetic,
+            },
+            Transform::from_translation(position),
+            GlobalTransform::default(),
+        )
+    });
+    world.spawn_batch(bullets.collect::<Vec<_>>());
+}
+
+/// `count` enemies and as many bullets, each checked against all the others.
+///
+/// Enemies fill the left half of the arena and bullets the right, so nothing is hit and the step
+/// measures the pairs being checked rather than how many of them happen to hit.
+pub fn collision(count: usize) -> Scenario {
+    let mut world = base_world();
+    let extents = BOUNDS / 2.0;
+    let left = positions(count, 1, -extents, Vec2::new(-20.0, extents.y));
+    spawn_enemies(&mut world, left);
+    let right = positions(count, 2, Vec2::new(20.0, -extents.y), extents);
+    spawn_bullets(&mut world, right);
+    Scenario::new(
+        world,
+        SystemStage::single_threaded().with_system(bullet_enemy_collision_system),
+    )
+}
+
+/// `count` enemies scattered across the arena, all closing in on the player.
+pub fn steering(count: usize) -> Scenario {
+    let mut world = base_world();
+    spawn_enemies(&mut world, arena_positions(count, 1));
+    world
+        .spawn()
+        .insert(Player {
+            velocity: 500.0,
+            rotation_speed: f32::to_radians(360.0),
+            hit_radius: 10.0,
+        })
+        .insert(Transform::default());
+    Scenario::new(
+        world,
+        SystemStage::single_threaded().with_system(move_enemy_system),
+    )
+}
+
+/// `count` bullets scattered across the arena, flying away from its center.
+pub fn bullets(count: usize) -> Scenario {
+    let mut world = base_world();
+    spawn_bullets(&mut world, arena_positions(count, 4));
+    Scenario::new(
+        world,
+        SystemStage::single_threaded().with_system(move_bullets_system),
+    )
+}
+
+/// `count` enemies that just died, bursting into shards and leaving scorch marks.
+pub fn particles(count: usize) -> Scenario {
+    let mut world = base_world();
+    let dying = arena_positions(count, 3).into_iter().map(|position| {
+        (
+            Dying::default(),
+            Sprite {
+                custom_size: Some(Vec2::splat(ENEMY_SIZE)),
+                ..default()
+            },
+            Transform::from_translation(position),
+            GlobalTransform::default(),
+        )
+    });
+    world.spawn_batch(dying.collect::<Vec<_>>());
+    // the burst itself happens once, it's the shards flying apart that cost every step
+    SystemStage::single_threaded()
+        .with_system(deaths::start_dying_system)
+        .run(&mut world);
+    Scenario::new(
+        world,
+        SystemStage::single_threaded()
+            .with_system(deaths::dying_system)
+            .with_system(deaths::shard_system)
+            .with_system(deaths::scorch_system),
+    )
+}
@@ -0,0 +1,430 @@
+use std::{sync::Arc, time::Duration};
+
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use crate::{
+    damage_player,
+    deaths::Dying,
+    elements::{Element, Wounds},
+    enemy_bullets::{Attack, Emitter},
+    events::{BossArrived, BossPhaseChanged, DamageSource, PlayerDamaged, WaveStarted},
+    layers::RenderLayer,
+    loadout::{GameMode, Loadout},
+    packs::ContentPacks,
+    palette::Palette,
+    rng::GameRng,
+    settings::Settings,
+    AddSimulationSystem, Enemy, EnemyKind, GameState, Health, Player, TimeScale, BOUNDS,
+};
+
+/// File bosses are read from, in the game's assets and in content packs.
+pub const BOSSES_FILE: &str = "bosses.ron";
+/// Every this many waves a boss joins the fight, in a boss rush every wave.
+const WAVES_PER_BOSS: usize = 5;
+/// Seconds a move-to node chases its target before giving up on reaching it.
+const MOVE_TIMEOUT: f32 = 3.0;
+/// Seconds after ramming the player before a boss can hurt them by contact again.
+const CONTACT_COOLDOWN: f32 = 1.0;
+const CONTACT_DAMAGE: f32 = 1.0;
+/// Phases a boss goes through, splitting its health evenly.
+const PHASES: u32 = 3;
+/// Score for defeating a boss.
+pub const BOSS_SCORE: usize = 25;
+
+/// Boss as described in `BOSSES_FILE`.
+#[derive(Deserialize)]
+pub struct BossDefinition {
+    /// identifies the boss, its name is looked up under `boss.<name>`
+    pub name: String,
+    /// what the boss is called in languages that don't name it, for bosses from content packs,
+    /// `name` itself if missing
+    #[serde(default)]
+    pub display_name: Option<String>,
+    pub health: u32,
+    pub size: f32,
+    pub velocity: f32,
+    /// factors the boss takes hits of the elements it resists or is vulnerable to by
+    #[serde(default)]
+    pub affinities: Vec<(Element, f32)>,
+    pub behavior: Node,
+}
+
+impl BossDefinition {
+    fn display_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Node of a boss's behavior tree, ticked every fixed step until it succeeds.
+#[derive(Deserialize)]
+pub enum Node {
+    /// runs its children one after another, succeeding once the last one does
+    Sequence(Vec<Node>),
+    /// starts its child over every time it succeeds, never succeeding itself
+    Repeat(Box<Node>),
+    /// flies to the target, giving up after `MOVE_TIMEOUT`
+    MoveTo(Target),
+    /// holds still for this many seconds, flashing a warning of the attack that follows
+    Telegraph(f32),
+    /// carries out a bullet attack, succeeding after its last volley
+    Fire(Attack),
+    /// holds still for this many seconds
+    Cooldown(f32),
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub enum Target {
+    /// where the player is at the moment
+    Player,
+    Point(f32, f32),
+}
+
+#[derive(PartialEq, Eq)]
+enum Status {
+    Running,
+    Success,
+}
+
+/// Progress through a node, shaped like the node it belongs to.
+enum NodeState {
+    Sequence {
+        index: usize,
+        child: Box<NodeState>,
+    },
+    Repeat(Box<NodeState>),
+    Timed(Timer),
+    /// the attack is only started on the node's first tick
+    Firing {
+        started: bool,
+        timer: Timer,
+    },
+    Instant,
+}
+
+impl NodeState {
+    fn start(node: &Node) -> Self {
+        match node {
+            Node::Sequence(children) => NodeState::Sequence {
+                index: 0,
+                child: Box::new(
+                    children
+                        .first()
+                        .map_or(NodeState::Instant, NodeState::start),
+                ),
+            },
+            Node::Repeat(child) => NodeState::Repeat(Box::new(NodeState::start(child))),
+            Node::MoveTo(_) => NodeState::Timed(Timer::from_seconds(MOVE_TIMEOUT, false)),
+            Node::Telegraph(duration) | Node::Cooldown(duration) => {
+                NodeState::Timed(Timer::from_seconds(*duration, false))
+            }
+            Node::Fire(attack) => NodeState::Firing {
+                started: false,
+                timer: Timer::from_seconds(attack.duration(), false),
+            },
+        }
+    }
+}
+
+/// Everything a node can act on while it's ticked.
+struct Context<'a, 'w, 's> {
+    commands: &'a mut Commands<'w, 's>,
+    entity: Entity,
+    transform: &'a mut Transform,
+    player: Vec3,
+    velocity: f32,
+    step: f32,
+    telegraphing: bool,
+}
+
+impl Context<'_, '_, '_> {
+    fn step_duration(&self) -> Duration {
+        Duration::from_secs_f32(self.step)
+    }
+}
+
+fn tick(node: &Node, state: &mut NodeState, context: &mut Context) -> Status {
+    match (node, state) {
+        (Node::Sequence(children), NodeState::Sequence { index, child }) => {
+            // instant children don't hold up the ones after them
+            while let Some(current) = children.get(*index) {
+                if tick(current, child, context) == Status::Running {
+                    return Status::Running;
+                }
+                *index += 1;
+                if let Some(next) = children.get(*index) {
+                    **child = NodeState::start(next);
+                }
+            }
+            Status::Success
+        }
+        (Node::Repeat(child), NodeState::Repeat(state)) => {
+            // starts over on the next tick, so a child that succeeds instantly can't spin forever
+            if tick(child, state, context) == Status::Success {
+                **state = NodeState::start(child);
+            }
+            Status::Running
+        }
+        (Node::MoveTo(target), NodeState::Timed(timeout)) => {
+            let target = match target {
+                Target::Player => context.player,
+                Target::Point(x, y) => Vec3::new(*x, *y, 0.0),
+            };
+            let offset = (target - context.transform.translation).truncate();
+            let distance = context.velocity * context.step;
+            if offset.length() <= distance {
+                context.transform.translation =
+                    target.truncate().extend(context.transform.translation.z);
+                return Status::Success;
+            }
+            context.transform.translation += (offset.normalize() * distance).extend(0.0);
+            if timeout.tick(context.step_duration()).finished() {
+                Status::Success
+            } else {
+                Status::Running
+            }
+        }
+        (Node::Telegraph(_), NodeState::Timed(timer)) => {
+            context.telegraphing = true;
+            wait(timer, context)
+        }
+        (Node::Cooldown(_), NodeState::Timed(timer)) => wait(timer, context),
+        (Node::Fire(attack), NodeState::Firing { started, timer }) => {
+            if !*started {
+                *started = true;
+                context
+                    .commands
+                    .entity(context.entity)
+                    .insert(Emitter::new(*attack));
+            }
+            wait(timer, context)
+        }
+        // the state doesn't match the node, which can only happen if the tree changed
+        (node, state) => {
+            *state = NodeState::start(node);
+            Status::Running
+        }
+    }
+}
+
+fn wait(timer: &mut Timer, context: &Context) -> Status {
+    if timer.tick(context.step_duration()).finished() {
+        Status::Success
+    } else {
+        Status::Running
+    }
+}
+
+/// Every boss that can be spawned.
+struct Bosses(Vec<Arc<BossDefinition>>);
+
+impl Bosses {
+    fn load(packs: &ContentPacks) -> Self {
+        let bosses: Vec<BossDefinition> = packs.load_all(BOSSES_FILE);
+        Self(bosses.into_iter().map(Arc::new).collect())
+    }
+}
+
+/// Enemy that takes many hits to bring down, acting out its definition's behavior tree instead
+/// of chasing the player.
+#[derive(Component)]
+pub struct Boss {
+    definition: Arc<BossDefinition>,
+    health: u32,
+    behavior: NodeState,
+    contact: Timer,
+    /// phase last announced, counted from zero
+    phase: u32,
+}
+
+impl Boss {
+    pub fn affinities(&self) -> &[(Element, f32)] {
+        &self.definition.affinities
+    }
+
+    /// Deals hits to the boss, returning whether they were the last it could take.
+    pub fn take_hits(&mut self, hits: u32) -> bool {
+        self.health = self.health.saturating_sub(hits);
+        self.health == 0
+    }
+
+    /// Phase the boss's health has it in, counted from zero.
+    fn current_phase(&self) -> u32 {
+        let lost = self.definition.health - self.health;
+        (lost * PHASES / self.definition.health.max(1)).min(PHASES - 1)
+    }
+}
+
+/// Warning flashed around a boss while it's telegraphing an attack.
+#[derive(Component)]
+struct TelegraphMarker;
+
+pub struct BossesPlugin;
+
+impl Plugin for BossesPlugin {
+    fn build(&self, app: &mut App) {
+        let bosses = Bosses::load(app.world.resource::<ContentPacks>());
+        app.insert_resource(bosses)
+            .add_system(reload_system)
+            .add_simulation_system(spawn_boss_system)
+            .add_simulation_system(behavior_system)
+            .add_simulation_system(boss_contact_system)
+            .add_simulation_system(phase_system);
+    }
+}
+
+fn reload_system(mut commands: Commands, packs: Res<ContentPacks>) {
+    if packs.is_changed() && !packs.is_added() {
+        commands.insert_resource(Bosses::load(&packs));
+    }
+}
+
+fn spawn_boss_system(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    bosses: Res<Bosses>,
+    loadout: Res<Loadout>,
+    mut rng: ResMut<GameRng>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut boss_arrived: EventWriter<BossArrived>,
+) {
+    for started in wave_started.iter() {
+        if loadout.mode != GameMode::BossRush && started.number % WAVES_PER_BOSS != 0 {
+            continue;
+        }
+        let definition = match bosses.0.choose(&mut rng.0) {
+            Some(definition) => definition.clone(),
+            None => return,
+        };
+        // enters from above the arena
+        let position = Vec3::new(0.0, BOUNDS.y / 2.0 + definition.size, 0.0);
+        let mut marker = palette.enemy_bullet;
+        marker.set_a(0.6);
+        palette
+            .spawn_sprite(
+                &mut commands,
+                RenderLayer::Enemies,
+                palette.enemy,
+                definition.size,
+                Transform::from_translation(position),
+            )
+            .insert(Enemy {
+                kind: EnemyKind::Boss,
+                velocity: definition.velocity,
+                size: definition.size,
+            })
+            .insert(Wounds::default())
+            .insert(Boss {
+                health: definition.health,
+                behavior: NodeState::start(&definition.behavior),
+                contact: Timer::from_seconds(CONTACT_COOLDOWN, false),
+                phase: 0,
+                definition: definition.clone(),
+            })
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            color: marker,
+                            custom_size: Some(Vec2::splat(definition.size * 1.4)),
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(0.0, 0.0, -0.02),
+                        visibility: Visibility { is_visible: false },
+                        ..default()
+                    })
+                    .insert(TelegraphMarker);
+            });
+        boss_arrived.send(BossArrived {
+            name: definition.name.clone(),
+            display_name: definition.display_name().to_string(),
+        });
+    }
+}
+
+fn behavior_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    player: Query<&Transform, (With<Player>, Without<Boss>)>,
+    mut bosses: Query<(Entity, &mut Boss, &mut Transform, &Children), Without<Dying>>,
+    mut markers: Query<&mut Visibility, With<TelegraphMarker>>,
+) {
+    let player = match player.get_single() {
+        Ok(player) => player.translation,
+        Err(_) => return,
+    };
+    for (entity, mut boss, mut transform, children) in bosses.iter_mut() {
+        let boss = &mut *boss;
+        let mut context = Context {
+            commands: &mut commands,
+            entity,
+            transform: &mut transform,
+            player,
+            velocity: boss.definition.velocity,
+            step: time_scale.enemy_step(),
+            telegraphing: false,
+        };
+        tick(&boss.definition.behavior, &mut boss.behavior, &mut context);
+        let telegraphing = context.telegraphing;
+        for &child in children.iter() {
+            if let Ok(mut visibility) = markers.get_mut(child) {
+                visibility.is_visible = telegraphing;
+            }
+        }
+    }
+}
+
+/// Announces the bosses worn down into their next phase by the hits they took this step.
+fn phase_system(
+    mut bosses: Query<&mut Boss, Without<Dying>>,
+    mut phase_changed: EventWriter<BossPhaseChanged>,
+) {
+    for mut boss in bosses.iter_mut() {
+        let phase = boss.current_phase();
+        if phase > boss.phase {
+            boss.phase = phase;
+            phase_changed.send(BossPhaseChanged {
+                name: boss.definition.name.clone(),
+                display_name: boss.definition.display_name().to_string(),
+                phase: phase as usize + 1,
+            });
+        }
+    }
+}
+
+fn boss_contact_system(
+    mut commands: Commands,
+    mut state: ResMut<State<GameState>>,
+    time_scale: Res<TimeScale>,
+    settings: Res<Settings>,
+    mut bosses: Query<(&mut Boss, &Enemy, &Transform), Without<Dying>>,
+    mut player: Query<(Entity, &Player, &Transform, &mut Health), Without<Boss>>,
+    mut player_damaged: EventWriter<PlayerDamaged>,
+) {
+    let (player_entity, player, player_transform, mut health) = match player.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    for (mut boss, enemy, transform) in bosses.iter_mut() {
+        boss.contact.tick(time_scale.step_duration());
+        let touching = transform.translation.distance(player_transform.translation)
+            <= enemy.size / 2.0 + player.hit_radius;
+        if !touching || !boss.contact.finished() {
+            continue;
+        }
+        boss.contact.reset();
+        if damage_player(
+            &mut commands,
+            &mut state,
+            &settings,
+            player_entity,
+            &mut health,
+            CONTACT_DAMAGE,
+            DamageSource::Enemy(EnemyKind::Boss),
+            &mut player_damaged,
+        ) {
+            return;
+        }
+    }
+}
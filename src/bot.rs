@@ -0,0 +1,251 @@
+use std::fs;
+
+use bevy::{app::AppExit, input::InputSystem, prelude::*};
+
+use crate::{
+    cli::Cli, deaths::Dying, enemy_bullets::EnemyBullet, input_buffer::InputBuffering, save,
+    stats::RunStats, Enemy, Game, GameState, Player,
+};
+
+/// What the scratch directory the saves of the bot's runs are kept in is named after.
+pub const SAVE_PURPOSE: &str = "bot";
+/// Angle off the nearest enemy, in radians, within which the bot stops turning.
+const AIM_TOLERANCE: f32 = 0.08;
+/// Distance from the player within which the bot backs away from enemies.
+const KEEP_AWAY: f32 = 220.0;
+/// How far off an axis the way the bot wants to go has to be for it to hold that axis' key.
+const AXIS_THRESHOLD: f32 = 0.38;
+/// Seconds ahead the bot looks for bullets that are going to hit it.
+const DODGE_HORIZON: f32 = 0.75;
+/// Distance a bullet has to pass the player by for the bot not to dodge it.
+const DODGE_RADIUS: f32 = 40.0;
+/// Seconds before a hit within which the bot dashes out of the way rather than steps aside.
+const DASH_WARNING: f32 = 0.2;
+/// Enemies within `CROWD_RADIUS` of the player it takes for the bot to drop a bomb.
+const CROWD_SIZE: usize = 6;
+const CROWD_RADIUS: f32 = 150.0;
+
+/// Something the bot does, the same as what a key of the keyboard map does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    TurnLeft,
+    TurnRight,
+    Up,
+    Down,
+    Left,
+    Right,
+    Dash,
+    Bomb,
+}
+
+impl Action {
+    /// Key the keyboard map does the action with.
+    fn key(&self) -> KeyCode {
+        match self {
+            Action::TurnLeft => KeyCode::Q,
+            Action::TurnRight => KeyCode::E,
+            Action::Up => KeyCode::Up,
+            Action::Down => KeyCode::Down,
+            Action::Left => KeyCode::Left,
+            Action::Right => KeyCode::Right,
+            Action::Dash => KeyCode::X,
+            Action::Bomb => KeyCode::B,
+        }
+    }
+}
+
+/// Plays the run in the player's place by holding down the keys they would.
+///
+/// It turns to face the nearest enemy and fires once it's lined up, steps out of the way of
+/// bullets about to hit it, dashing if they're close, and bombs enemies crowding it. Otherwise it
+/// backs away from enemies that come too close and drifts back toward the middle of the arena.
+#[derive(Default)]
+pub struct BotController {
+    pub enabled: bool,
+    /// actions the bot is taking, their keys let go of once it no longer wants them or is
+    /// disabled
+    held: Vec<Action>,
+}
+
+/// Runs played and how many to play, while the bot plays every run.
+struct BotRuns {
+    played: usize,
+    limit: Option<usize>,
+}
+
+/// The bot, for the attract mode to hand demo runs to, and the hidden `--bot` mode it plays every
+/// run in, a run after another, to see how the balance holds up without anyone playing.
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BotController>()
+            // right after the keyboard is read, for everything reading it to see the bot's keys
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                bot_system.after(InputSystem).before(InputBuffering),
+            );
+
+        let cli = app.world.resource::<Cli>();
+        if !cli.bot {
+            return;
+        }
+        let runs = BotRuns {
+            played: 0,
+            limit: cli.bot_runs,
+        };
+        app.world.resource_mut::<BotController>().enabled = true;
+        app.insert_resource(runs)
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(run_over))
+            .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(next_run));
+    }
+}
+
+fn bot_system(
+    mut bot: ResMut<BotController>,
+    mut keys: ResMut<Input<KeyCode>>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<&Transform, (With<Enemy>, Without<Dying>, Without<Player>)>,
+    bullets: Query<(&EnemyBullet, &Transform), Without<Player>>,
+) {
+    let wanted = match player.get_single() {
+        Ok(player) if bot.enabled => choose_actions(
+            player,
+            enemies.iter(),
+            bullets
+                .iter()
+                .map(|(bullet, transform)| (transform.translation, bullet.velocity())),
+        ),
+        _ => Vec::new(),
+    };
+    for action in bot.held.iter().filter(|action| !wanted.contains(action)) {
+        keys.release(action.key());
+    }
+    for action in &wanted {
+        keys.press(action.key());
+    }
+    bot.held = wanted;
+}
+
+/// Actions the bot takes this frame to deal with the enemies and the bullets coming at it.
+fn choose_actions<'a>(
+    player: &Transform,
+    enemies: impl Iterator<Item = &'a Transform>,
+    bullets: impl Iterator<Item = (Vec3, Vec3)>,
+) -> Vec<Action> {
+    let position = player.translation.truncate();
+    let offsets: Vec<Vec2> = enemies
+        .map(|enemy| enemy.translation.truncate() - position)
+        .collect();
+    let nearest = offsets
+        .iter()
+        .copied()
+        .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+    let mut actions = Vec::new();
+
+    let (dodge, soonest) = dodge(position, bullets);
+    // the closest of enemies getting close push the hardest
+    let retreat = offsets
+        .iter()
+        .filter(|offset| offset.length() < KEEP_AWAY)
+        .fold(Vec2::ZERO, |retreat, offset| {
+            retreat - *offset / offset.length_squared().max(1.0)
+        });
+    // bullets about to hit matter more than enemies getting close
+    let heading = if dodge != Vec2::ZERO {
+        dodge
+    } else if retreat != Vec2::ZERO {
+        retreat
+    } else {
+        -position
+    };
+    let heading = heading.normalize_or_zero();
+    for (component, negative, positive) in [
+        (heading.x, Action::Left, Action::Right),
+        (heading.y, Action::Down, Action::Up),
+    ] {
+        if component < -AXIS_THRESHOLD {
+            actions.push(negative);
+        } else if component > AXIS_THRESHOLD {
+            actions.push(positive);
+        }
+    }
+    if soonest < DASH_WARNING {
+        actions.push(Action::Dash);
+    }
+    let crowd = offsets
+        .iter()
+        .filter(|offset| offset.length() < CROWD_RADIUS)
+        .count();
+    if crowd >= CROWD_SIZE {
+        actions.push(Action::Bomb);
+    }
+
+    if let Some(offset) = nearest {
+        let facing = (player.rotation * Vec3::Y).truncate();
+        let angle = facing.angle_between(offset);
+        if angle > AIM_TOLERANCE {
+            actions.push(Action::TurnLeft);
+        } else if angle < -AIM_TOLERANCE {
+            actions.push(Action::TurnRight);
+        }
+    }
+    actions
+}
+
+/// Way out of the path of the bullets, given by position and velocity, that are going to pass
+/// within `DODGE_RADIUS` of `position` in the next `DODGE_HORIZON` seconds, the soonest ones
+/// counting the most, and the seconds until the soonest of them comes closest.
+fn dodge(position: Vec2, bullets: impl Iterator<Item = (Vec3, Vec3)>) -> (Vec2, f32) {
+    let mut away = Vec2::ZERO;
+    let mut soonest = f32::INFINITY;
+    for (bullet, velocity) in bullets {
+        let (offset, velocity) = (bullet.truncate() - position, velocity.truncate());
+        let speed = velocity.length_squared();
+        if speed <= f32::EPSILON {
+            continue;
+        }
+        let time = -offset.dot(velocity) / speed;
+        let closest = offset + velocity * time;
+        if !(0.0..DODGE_HORIZON).contains(&time) || closest.length() > DODGE_RADIUS {
+            continue;
+        }
+        // a bullet coming straight on is dodged to its side
+        let side = (-closest)
+            .try_normalize()
+            .unwrap_or_else(|| velocity.perp().normalize());
+        away += side / time.max(0.05);
+        soonest = soonest.min(time);
+    }
+    (away, soonest)
+}
+
+/// Prints how the bot's run went.
+fn run_over(mut runs: ResMut<BotRuns>, stats: Res<RunStats>, game: Res<Game>) {
+    runs.played += 1;
+    let cause = match stats.last_damage {
+        Some(source) => format!("{:?}", source),
+        None => "nothing".to_string(),
+    };
+    println!(
+        "run {}: wave {}, score {}, {:.1} s, killed by {}",
+        runs.played, stats.wave_reached, game.score, stats.time_survived, cause
+    );
+}
+
+/// Starts the bot's next run, or exits once it has played all of them.
+fn next_run(
+    runs: Res<BotRuns>,
+    mut state: ResMut<State<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if runs.limit.is_some_and(|limit| runs.played >= limit) {
+        let save_dir = save::scratch_dir(SAVE_PURPOSE);
+        if let Err(err) = fs::remove_dir_all(&save_dir) {
+            warn!("failed to remove {}: {}", save_dir.display(), err);
+        }
+        exit.send(AppExit);
+        return;
+    }
+    let _ = state.set(GameState::Playing);
+}
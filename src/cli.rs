@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::{loadout::GameMode, mutators::Mutator};
+
+/// Options the game was launched with, parsed before the app is built and available to every
+/// plugin as a resource.
+#[derive(Debug, Parser)]
+#[command(version, about = "Survive wave after wave of enemies in the arena")]
+pub struct Cli {
+    /// Start in exclusive fullscreen, whatever the display settings say
+    #[arg(long)]
+    pub fullscreen: bool,
+    /// Seed the randomness of every run with N, so each plays out the same
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+    /// Play runs in this mode instead of the one last picked
+    #[arg(long)]
+    pub mode: Option<GameMode>,
+    /// Play runs with this mutator instead of the ones last picked, given again for every one
+    #[arg(long = "mutator", value_name = "MUTATOR")]
+    pub mutators: Vec<Mutator>,
+    /// Play back a run from its leaderboard submission
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+    /// Check a leaderboard submission for tampering by playing it back, and exit with whether its
+    /// score holds up
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub verify: Option<PathBuf>,
+    /// Render a clip of the final minute of a run from its leaderboard submission, without a
+    /// window, and exit
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["replay", "verify", "headless"]
+    )]
+    pub highlight: Option<PathBuf>,
+    /// Write the clip to FILE, as an MP4 if it ends in .mp4 and as a GIF otherwise
+    #[arg(long, value_name = "FILE", requires = "highlight")]
+    pub clip: Option<PathBuf>,
+    /// Run without a window or a renderer
+    #[arg(long)]
+    pub headless: bool,
+    /// Read and write the settings at this path instead of the save directory
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// Start a run straight away instead of showing the main menu
+    #[arg(long)]
+    pub skip_menu: bool,
+    /// Fill the arena with enemies and bullets, append frame time percentiles to a CSV file and
+    /// exit, to compare performance across builds and machines
+    #[arg(long, hide = true)]
+    pub stress: bool,
+    /// Enemies kept in the arena during a stress run
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 500,
+        requires = "stress",
+        hide = true
+    )]
+    pub stress_enemies: usize,
+    /// Bullets kept in the air during a stress run
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 500,
+        requires = "stress",
+        hide = true
+    )]
+    pub stress_bullets: usize,
+    /// Seconds a stress run measures for
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 30.0,
+        requires = "stress",
+        hide = true
+    )]
+    pub stress_duration: f32,
+    /// CSV file a stress run appends its results to
+    #[arg(
+        long,
+        value_name = "FILE",
+        default_value = "stress.csv",
+        requires = "stress",
+        hide = true
+    )]
+    pub stress_output: PathBuf,
+    /// Render a fixed set of scenes, compare them against the golden images in DIR and exit,
+    /// failing if any of them changed
+    #[arg(long, value_name = "DIR", conflicts_with = "headless", hide = true)]
+    pub visual_test: Option<PathBuf>,
+    /// Write the scenes of a visual test as its new golden images instead of comparing them
+    #[arg(long, requires = "visual_test", hide = true)]
+    pub update_golden: bool,
+    /// Fraction of a scene's pixels that may differ from its golden image before it fails
+    #[arg(
+        long,
+        value_name = "FRACTION",
+        default_value_t = 0.01,
+        requires = "visual_test",
+        hide = true
+    )]
+    pub visual_tolerance: f32,
+    /// Let the bot play every run, starting the next one as soon as one ends and printing how
+    /// each went, to see how changes to the balance play out
+    #[arg(long, conflicts_with_all = ["stress", "visual_test"], hide = true)]
+    pub bot: bool,
+    /// Exit once the bot has played this many runs
+    #[arg(long, value_name = "N", requires = "bot", hide = true)]
+    pub bot_runs: Option<usize>,
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+use crate::{
+    events::{ComboMilestone, EnemyKilled},
+    AddSimulationSystem, GameState, TimeScale,
+};
+
+/// Seconds the player has to land the next kill before the combo drops.
+const COMBO_WINDOW: f32 = 2.0;
+/// Combo counts at every multiple of which a milestone is announced.
+const MILESTONE_STEP: usize = 10;
+
+/// Chain of kills landed in quick succession.
+pub struct Combo {
+    pub count: usize,
+    timer: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            timer: Timer::from_seconds(COMBO_WINDOW, false),
+        }
+    }
+}
+
+pub struct ComboPlugin;
+
+impl Plugin for ComboPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Combo>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_combo))
+            .add_simulation_system(combo_system);
+    }
+}
+
+fn reset_combo(mut combo: ResMut<Combo>) {
+    *combo = Combo::default();
+}
+
+fn combo_system(
+    time_scale: Res<TimeScale>,
+    mut combo: ResMut<Combo>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+    mut combo_milestone: EventWriter<ComboMilestone>,
+) {
+    let kills = enemy_killed.iter().count();
+    if kills > 0 {
+        let previous = combo.count;
+        combo.count += kills;
+        combo.timer.reset();
+        // a bomb can jump several kills at once, so look for a crossed step rather than a hit one
+        if combo.count / MILESTONE_STEP > previous / MILESTONE_STEP {
+            combo_milestone.send(ComboMilestone {
+                count: combo.count / MILESTONE_STEP * MILESTONE_STEP,
+            });
+        }
+    } else if combo.timer.tick(time_scale.step_duration()).just_finished() {
+        combo.count = 0;
+    }
+}
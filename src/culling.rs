@@ -0,0 +1,131 @@
+use bevy::{
+    prelude::*,
+    render::{camera::Camera2d, view::VisibilitySystems},
+    transform::TransformSystem,
+    utils::HashMap,
+};
+
+use crate::layers::RenderLayer;
+
+/// Distance past the edges of the view an entity has to be entirely beyond to be culled, for
+/// nothing to pop in at the edges as the camera moves.
+const CULL_MARGIN: f32 = 64.0;
+
+/// The entities well outside the camera's view, hidden along with their children until they come
+/// back into view.
+///
+/// Only how an entity is drawn changes: it's simulated the same as anything on screen, and
+/// systems that only animate it can skip it while it's culled. It's kept aside rather than as a
+/// component for culling not to move entities between archetypes, which would change the order
+/// the simulation goes through them in with where the camera looks.
+#[derive(Default)]
+pub struct Culled {
+    /// each culled entity, with those of itself and its descendants that were visible when it was
+    /// culled, shown again once it's back in view
+    hidden: HashMap<Entity, Vec<Entity>>,
+}
+
+impl Culled {
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.hidden.contains_key(&entity)
+    }
+}
+
+pub struct CullingPlugin;
+
+impl Plugin for CullingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Culled>().add_system_to_stage(
+            CoreStage::PostUpdate,
+            // sprites are drawn whatever the frustum, so it's their visibility that's set
+            culling_system
+                .after(TransformSystem::TransformPropagate)
+                .before(VisibilitySystems::CheckVisibility),
+        );
+    }
+}
+
+/// Part of the arena a camera centered on `center` sees, or none before it knows the size of what
+/// it renders to.
+pub fn view_rect(center: Vec2, projection: &OrthographicProjection) -> Option<Rect<f32>> {
+    let width = (projection.right - projection.left) * projection.scale;
+    let height = (projection.top - projection.bottom) * projection.scale;
+    // the projection's default no-op extents, left until a window or image sizes it
+    if width <= 2.0 * projection.scale || height <= 2.0 * projection.scale {
+        return None;
+    }
+    Some(Rect {
+        left: center.x - width / 2.0,
+        right: center.x + width / 2.0,
+        bottom: center.y - height / 2.0,
+        top: center.y + height / 2.0,
+    })
+}
+
+/// Part of the arena the camera sees, widened by `CULL_MARGIN`.
+fn view_bounds(camera: &GlobalTransform, projection: &OrthographicProjection) -> Option<Rect<f32>> {
+    view_rect(camera.translation.truncate(), projection).map(|view| Rect {
+        left: view.left - CULL_MARGIN,
+        right: view.right + CULL_MARGIN,
+        bottom: view.bottom - CULL_MARGIN,
+        top: view.top + CULL_MARGIN,
+    })
+}
+
+/// Hides the arena's sprites that are well out of view and shows the ones that came back.
+fn culling_system(
+    mut culled: ResMut<Culled>,
+    cameras: Query<(&GlobalTransform, &OrthographicProjection), With<Camera2d>>,
+    sprites: Query<(Entity, &Sprite, &GlobalTransform), (With<RenderLayer>, Without<Parent>)>,
+    children: Query<&Children>,
+    mut visibilities: Query<&mut Visibility>,
+) {
+    let view = cameras
+        .iter()
+        .find_map(|(camera, projection)| view_bounds(camera, projection));
+    // forgets the entities that are gone
+    culled.hidden.retain(|&entity, _| sprites.contains(entity));
+    for (entity, sprite, transform) in sprites.iter() {
+        let position = transform.translation.truncate();
+        // far enough out for the sprite's corners not to reach into view, however it's turned
+        let reach = sprite.custom_size.unwrap_or(Vec2::ZERO) * transform.scale.truncate();
+        let reach = reach.length() / 2.0;
+        let out_of_view = view.as_ref().is_some_and(|view| {
+            position.x + reach < view.left
+                || position.x - reach > view.right
+                || position.y + reach < view.bottom
+                || position.y - reach > view.top
+        });
+        if out_of_view && !culled.contains(entity) {
+            let mut hidden = Vec::new();
+            hide(entity, &children, &mut visibilities, &mut hidden);
+            culled.hidden.insert(entity, hidden);
+        } else if !out_of_view {
+            for hidden in culled.hidden.remove(&entity).into_iter().flatten() {
+                if let Ok(mut visibility) = visibilities.get_mut(hidden) {
+                    visibility.is_visible = true;
+                }
+            }
+        }
+    }
+}
+
+/// Hides `entity` and its descendants, recording the ones that were visible.
+fn hide(
+    entity: Entity,
+    children: &Query<&Children>,
+    visibilities: &mut Query<&mut Visibility>,
+    hidden: &mut Vec<Entity>,
+) {
+    if let Ok(mut visibility) = visibilities.get_mut(entity) {
+        if visibility.is_visible {
+            visibility.is_visible = false;
+            hidden.push(entity);
+        }
+    }
+    if let Ok(entity_children) = children.get(entity) {
+        for &child in entity_children.iter() {
+            hide(child, children, visibilities, hidden);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+
+use crate::{
+    culling::Culled, hit_flash::HitFlash, layers::RenderLayer, palette::Palette,
+    AddSimulationSystem, TimeScale,
+};
+
+/// Seconds a killed enemy takes to burst before it's removed.
+const DEATH_DURATION: f32 = 0.3;
+/// Factor a dying enemy swells to as it bursts.
+const DEATH_SCALE: f32 = 1.8;
+const SHARDS: usize = 8;
+const SHARD_SIZE: f32 = 4.0;
+const SHARD_SPEED: f32 = 220.0;
+const SHARD_LIFETIME: f32 = 0.5;
+/// Seconds a scorch mark lingers before it's faded out completely.
+const SCORCH_LIFETIME: f32 = 2.0;
+
+/// Marks an enemy that was killed and is playing its death animation. Everything that collides
+/// with or targets enemies skips dying ones, and the entity is despawned once the timer finishes.
+#[derive(Component)]
+pub struct Dying(Timer);
+
+impl Default for Dying {
+    fn default() -> Self {
+        Self(Timer::from_seconds(DEATH_DURATION, false))
+    }
+}
+
+/// Particle flung out of a dying enemy.
+#[derive(Component)]
+pub struct Shard {
+    velocity: Vec3,
+    lifetime: Timer,
+}
+
+/// Decal left on the floor where an enemy died.
+#[derive(Component)]
+pub struct Scorch(Timer);
+
+pub struct DeathsPlugin;
+
+impl Plugin for DeathsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(start_dying_system)
+            .add_simulation_system(dying_system)
+            .add_simulation_system(shard_system)
+            .add_simulation_system(scorch_system);
+    }
+}
+
+pub fn start_dying_system(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    dying: Query<(Entity, &Sprite, &Transform), Added<Dying>>,
+) {
+    let mut rng = thread_rng();
+    for (entity, sprite, transform) in dying.iter() {
+        // outlines and shields go right away, only the body bursts
+        commands
+            .entity(entity)
+            .remove::<HitFlash>()
+            .despawn_descendants();
+
+        let position = transform.translation;
+        let size = sprite.custom_size.map_or(SHARD_SIZE, |size| size.x);
+        for i in 0..SHARDS {
+            let angle = (i as f32 + rng.gen::<f32>()) / SHARDS as f32 * std::f32::consts::TAU;
+            let speed = SHARD_SPEED * rng.gen_range(0.5..1.0);
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: sprite.color,
+                        custom_size: Some(Vec2::splat(SHARD_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(position.truncate().extend(0.0)),
+                    ..default()
+                })
+                .insert(RenderLayer::Fx)
+                .insert(Shard {
+                    velocity: Quat::from_rotation_z(angle) * Vec3::X * speed,
+                    lifetime: Timer::from_seconds(SHARD_LIFETIME, false),
+                });
+        }
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: palette.scorch,
+                    custom_size: Some(Vec2::splat(size * 1.5)),
+                    ..default()
+                },
+                // beneath everything else on the floor
+                transform: Transform::from_xyz(position.x, position.y, 0.0).with_rotation(
+                    Quat::from_rotation_z(rng.gen::<f32>() * std::f32::consts::TAU),
+                ),
+                ..default()
+            })
+            .insert(RenderLayer::Decals)
+            .insert(Scorch(Timer::from_seconds(SCORCH_LIFETIME, false)));
+    }
+}
+
+pub fn dying_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    culled: Res<Culled>,
+    mut dying: Query<(Entity, &mut Dying, &mut Sprite, &mut Transform)>,
+) {
+    for (entity, mut dying, mut sprite, mut transform) in dying.iter_mut() {
+        if dying.0.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        // out of view it only has to be removed on time
+        if culled.contains(entity) {
+            continue;
+        }
+        let progress = dying.0.percent();
+        transform.scale = Vec3::splat(1.0 + (DEATH_SCALE - 1.0) * progress);
+        sprite.color.set_a(1.0 - progress);
+    }
+}
+
+pub fn shard_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    culled: Res<Culled>,
+    mut shards: Query<(Entity, &mut Shard, &mut Sprite, &mut Transform)>,
+) {
+    let _span = info_span!("shards", shards = shards.iter().count()).entered();
+    for (entity, mut shard, mut sprite, mut transform) in shards.iter_mut() {
+        if shard.lifetime.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        // still moved out of view, for one flying back into it to be where it should
+        transform.translation += shard.velocity * time_scale.step();
+        if !culled.contains(entity) {
+            sprite.color.set_a(shard.lifetime.percent_left());
+        }
+    }
+}
+
+pub fn scorch_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    palette: Res<Palette>,
+    mut scorches: Query<(Entity, &mut Scorch, &mut Sprite)>,
+) {
+    for (entity, mut scorch, mut sprite) in scorches.iter_mut() {
+        if scorch.0.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        sprite
+            .color
+            .set_a(palette.scorch.a() * scorch.0.percent_left());
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use discord_rich_presence::{
+    activity::{Activity as DiscordActivity, Timestamps},
+    DiscordIpc, DiscordIpcClient,
+};
+
+use crate::{locale::Locale, stats::RunStats, waves::Wave, Game, GameState};
+
+/// Id of the Discord application the activity is published as, set when building with
+/// `MADGE_DISCORD_APP_ID`.
+const APPLICATION_ID: Option<&str> = option_env!("MADGE_DISCORD_APP_ID");
+/// Seconds between updates, Discord drops updates sent more often than every few seconds.
+const UPDATE_INTERVAL: f32 = 5.0;
+/// Time between attempts to reach Discord when it isn't running.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What the player is doing, as shown on their Discord profile.
+#[derive(Clone)]
+struct Activity {
+    details: String,
+    state: Option<String>,
+    /// unix time in milliseconds the current run started at
+    started: Option<i64>,
+}
+
+/// Sends the player's activity to the thread talking to Discord.
+struct Presence {
+    sender: Sender<Activity>,
+    timer: Timer,
+    last: Option<Activity>,
+}
+
+pub struct DiscordPlugin;
+
+impl Plugin for DiscordPlugin {
+    fn build(&self, app: &mut App) {
+        let application_id = match APPLICATION_ID {
+            Some(application_id) => application_id,
+            None => {
+                warn!("built without MADGE_DISCORD_APP_ID, Discord Rich Presence is disabled");
+                return;
+            }
+        };
+        let (sender, receiver) = mpsc::channel();
+        // talking to Discord blocks on its replies, the game shouldn't wait for them
+        thread::spawn(move || publish(application_id, receiver));
+        app.insert_resource(Presence {
+            sender,
+            // the first update goes out right away
+            timer: Timer::from_seconds(UPDATE_INTERVAL, true),
+            last: None,
+        })
+        .add_system(presence_system);
+    }
+}
+
+fn presence_system(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    locale: Res<Locale>,
+    game: Res<Game>,
+    wave: Res<Wave>,
+    stats: Res<RunStats>,
+    mut presence: ResMut<Presence>,
+) {
+    if presence.last.is_some() && !presence.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let details = match state.current() {
+        GameState::Playing => "presence.playing",
+        GameState::Paused => "presence.paused",
+        GameState::Tutorial => "presence.tutorial",
+        GameState::GameOver => "presence.game_over",
+        GameState::Editor => "presence.editor",
+        GameState::Menu
+        | GameState::Achievements
+        | GameState::Settings
+        | GameState::Packs
+        | GameState::ShipSelect
+        | GameState::Loadout => "presence.menu",
+    };
+    let in_run = matches!(
+        state.current(),
+        GameState::Playing | GameState::Paused | GameState::GameOver
+    );
+    let run = in_run.then(|| {
+        locale.format(
+            "presence.run",
+            &[("wave", &wave.number), ("score", &game.score)],
+        )
+    });
+    let started = in_run.then(|| {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as i64);
+        now - (stats.time_survived * 1000.0) as i64
+    });
+    let activity = Activity {
+        details: locale.get(details).to_string(),
+        state: run,
+        started,
+    };
+    // the elapsed time shown on Discord drifts from the run's while photo mode freezes it, but
+    // resending it on every update would only be noise
+    let unchanged = presence
+        .last
+        .as_ref()
+        .is_some_and(|last| last.details == activity.details && last.state == activity.state);
+    if unchanged {
+        return;
+    }
+    presence.last = Some(activity.clone());
+    // the thread only stops if the channel does
+    let _ = presence.sender.send(activity);
+}
+
+/// Publishes the latest activity received until the game quits, reconnecting whenever Discord
+/// goes away.
+fn publish(application_id: &str, receiver: Receiver<Activity>) {
+    let mut client = DiscordIpcClient::new(application_id);
+    let mut connected = false;
+    let mut current = None;
+    let mut sent = false;
+    loop {
+        match receiver.recv_timeout(RECONNECT_INTERVAL) {
+            Ok(activity) => {
+                current = Some(activity);
+                sent = false;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if !connected {
+            connected = client.connect().is_ok();
+            if !connected {
+                continue;
+            }
+            info!("connected to Discord");
+            // whatever was shown before belonged to the old connection
+            sent = false;
+        }
+        let activity = match &current {
+            Some(activity) if !sent => activity,
+            _ => continue,
+        };
+        let mut payload = DiscordActivity::new().details(activity.details.as_str());
+        if let Some(state) = &activity.state {
+            payload = payload.state(state.as_str());
+        }
+        if let Some(started) = activity.started {
+            payload = payload.timestamps(Timestamps::new().start(started));
+        }
+        sent = true;
+        if let Err(err) = client.set_activity(payload) {
+            warn!("lost the connection to Discord: {}", err);
+            let _ = client.close();
+            connected = false;
+            sent = false;
+        }
+    }
+    if connected {
+        let _ = client.close();
+    }
+}
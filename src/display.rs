@@ -0,0 +1,218 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    prelude::*,
+    window::{PresentMode, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{cli::Cli, mobile::MOBILE, platform::Platform, settings::Settings};
+
+/// How frames are handed to the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Vsync {
+    /// waits for the screen to refresh, never tearing
+    On,
+    /// presents frames as soon as they're done, as many as the FPS cap allows
+    Off,
+    /// runs uncapped but only shows the newest frame on each refresh
+    Mailbox,
+}
+
+impl Vsync {
+    const ALL: [Vsync; 3] = [Vsync::On, Vsync::Off, Vsync::Mailbox];
+
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            Vsync::On => "vsync.on",
+            Vsync::Off => "vsync.off",
+            Vsync::Mailbox => "vsync.mailbox",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let index = Vsync::ALL.iter().position(|m| m == self).unwrap_or(0);
+        Vsync::ALL[(index + 1) % Vsync::ALL.len()]
+    }
+
+    fn present_mode(&self) -> PresentMode {
+        match self {
+            Vsync::On => PresentMode::Fifo,
+            Vsync::Off => PresentMode::Immediate,
+            Vsync::Mailbox => PresentMode::Mailbox,
+        }
+    }
+}
+
+/// How much of the screen the window takes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Windowing {
+    Windowed,
+    /// a window without decorations covering the whole screen at its own resolution
+    Borderless,
+    /// exclusive fullscreen at the selected resolution
+    Fullscreen,
+}
+
+impl Windowing {
+    const ALL: [Windowing; 3] = [
+        Windowing::Windowed,
+        Windowing::Borderless,
+        Windowing::Fullscreen,
+    ];
+
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            Windowing::Windowed => "windowing.windowed",
+            Windowing::Borderless => "windowing.borderless",
+            Windowing::Fullscreen => "windowing.fullscreen",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let index = Windowing::ALL.iter().position(|m| m == self).unwrap_or(0);
+        Windowing::ALL[(index + 1) % Windowing::ALL.len()]
+    }
+
+    fn window_mode(&self) -> WindowMode {
+        match self {
+            Windowing::Windowed => WindowMode::Windowed,
+            Windowing::Borderless => WindowMode::BorderlessFullscreen,
+            // picks the screen's video mode closest to the resolution
+            Windowing::Fullscreen => WindowMode::SizedFullscreen,
+        }
+    }
+}
+
+/// How the game is shown on screen.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Display {
+    pub vsync: Vsync,
+    /// frames per second at most with vsync off, `None` for as many as the machine manages
+    pub fps_cap: Option<u32>,
+    pub windowing: Windowing,
+    /// size of the window in logical pixels, and of the screen in exclusive fullscreen
+    pub resolution: (u32, u32),
+}
+
+impl Display {
+    pub const FPS_CAPS: [Option<u32>; 6] =
+        [None, Some(30), Some(60), Some(120), Some(144), Some(240)];
+    pub const RESOLUTIONS: [(u32, u32); 5] = [
+        (1280, 720),
+        (1366, 768),
+        (1600, 900),
+        (1920, 1080),
+        (2560, 1440),
+    ];
+
+    /// The window as these settings describe it, before the platform had its say.
+    fn window(&self) -> WindowDescriptor {
+        let (width, height) = self.resolution;
+        WindowDescriptor {
+            width: width as f32,
+            height: height as f32,
+            present_mode: self.vsync.present_mode(),
+            mode: self.windowing.window_mode(),
+            ..default()
+        }
+    }
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Self {
+            vsync: Vsync::On,
+            fps_cap: Some(144),
+            windowing: Windowing::Windowed,
+            resolution: Display::RESOLUTIONS[0],
+        }
+    }
+}
+
+/// Start of the frame the limiter last let through, and how long frames have to take at least.
+#[derive(Default)]
+struct FrameLimiter {
+    last_frame: Option<Instant>,
+    frame_time: Option<Duration>,
+}
+
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FrameLimiter>()
+            .add_system(apply_display_system)
+            .add_system_to_stage(CoreStage::Last, frame_limiter_system);
+    }
+}
+
+/// Applies the display settings to the window whenever they change, the window is created
+/// before the settings are loaded so this also sets it up on the first frame. The window is only
+/// resized when the mode or the resolution it was last given changed, so a window the player
+/// resized keeps its size through changes to other settings.
+fn apply_display_system(
+    cli: Res<Cli>,
+    settings: Res<Settings>,
+    platform: Res<Platform>,
+    mut windows: ResMut<Windows>,
+    mut limiter: ResMut<FrameLimiter>,
+    mut applied: Local<Option<(WindowMode, f32, f32)>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let window = match windows.get_primary_mut() {
+        Some(window) => window,
+        None => return,
+    };
+    let display = &settings.display;
+    let mut descriptor = display.window();
+    // only for this session, the settings stay as they are
+    if cli.fullscreen {
+        descriptor.mode = Windowing::Fullscreen.window_mode();
+    }
+    // the platform may insist on a present mode, the same as when the window was created
+    platform.adjust_window(&mut descriptor);
+
+    // every call sends the window a command, even when nothing would change
+    if window.present_mode() != descriptor.present_mode {
+        window.set_present_mode(descriptor.present_mode);
+    }
+    limiter.frame_time = match (descriptor.present_mode, display.fps_cap) {
+        (PresentMode::Immediate, Some(fps)) => {
+            Some(Duration::from_secs_f64(1.0 / fps.max(1) as f64))
+        }
+        // the screen's refresh already holds frames back, and mailbox runs uncapped
+        _ => None,
+    };
+
+    // a phone's screen is always the whole window
+    if MOBILE {
+        return;
+    }
+    let wanted = (descriptor.mode, descriptor.width, descriptor.height);
+    if *applied == Some(wanted) {
+        return;
+    }
+    *applied = Some(wanted);
+    if window.mode() != descriptor.mode {
+        window.set_mode(descriptor.mode);
+    }
+    window.set_resolution(descriptor.width, descriptor.height);
+}
+
+/// Sleeps out the rest of the frame when it came in faster than the FPS cap allows.
+fn frame_limiter_system(mut limiter: ResMut<FrameLimiter>) {
+    if let (Some(last_frame), Some(frame_time)) = (limiter.last_frame, limiter.frame_time) {
+        let elapsed = last_frame.elapsed();
+        if elapsed < frame_time {
+            thread::sleep(frame_time - elapsed);
+        }
+    }
+    limiter.last_frame = Some(Instant::now());
+}
@@ -0,0 +1,152 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{
+    aggro::Threat,
+    deaths::Dying,
+    layers::RenderLayer,
+    nearest_enemy,
+    palette::Palette,
+    perks::{Perk, Perks},
+    spawn_companion_shot, AddSimulationSystem, Enemy, GameState, Player, TimeScale,
+};
+
+/// Most drones orbiting at once, further drone perks make them fire faster instead.
+const MAX_DRONES: u32 = 4;
+const DRONE_SIZE: f32 = 7.0;
+const ORBIT_RADIUS: f32 = 40.0;
+/// Radians per second the drones circle the player at.
+const ORBIT_SPEED: f32 = 2.5;
+/// Furthest an enemy can be from a drone for it to open fire.
+const TARGET_RANGE: f32 = 350.0;
+const BASE_FIRE_INTERVAL: f32 = 0.8;
+/// Factor the fire interval shrinks by with every drone perk past the last drone.
+const FIRE_INTERVAL_DECAY: f32 = 0.75;
+const SHOT_VELOCITY: f32 = 550.0;
+const SHOT_SIZE: f32 = 3.0;
+/// How strongly a drone draws enemies, it's never far from the player anyway.
+const DRONE_THREAT: f32 = 0.3;
+
+/// Companion circling the player, taking shots at the nearest enemy.
+#[derive(Component)]
+struct Drone {
+    /// place among the drones, which spreads them evenly around the orbit
+    index: u32,
+    fire: Timer,
+}
+
+/// Angle the first drone is at on its orbit.
+#[derive(Default)]
+struct Orbit(f32);
+
+pub struct DronesPlugin;
+
+impl Plugin for DronesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Orbit>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_orbit))
+            .add_simulation_system(spawn_drones)
+            .add_simulation_system(orbit_system)
+            .add_simulation_system(drone_fire_system);
+    }
+}
+
+/// Drones orbiting with the given number of drone perks.
+fn drone_count(perks: &Perks) -> u32 {
+    perks.stacks(Perk::Drone).min(MAX_DRONES)
+}
+
+fn fire_interval(perks: &Perks) -> Duration {
+    let upgrades = perks.stacks(Perk::Drone).saturating_sub(MAX_DRONES);
+    Duration::from_secs_f32(BASE_FIRE_INTERVAL * FIRE_INTERVAL_DECAY.powi(upgrades as i32))
+}
+
+fn reset_orbit(mut orbit: ResMut<Orbit>) {
+    *orbit = Orbit::default();
+}
+
+/// Keeps the drones in line with the drone perks gained so far.
+fn spawn_drones(
+    mut commands: Commands,
+    perks: Res<Perks>,
+    palette: Res<Palette>,
+    mut drones: Query<&mut Drone>,
+) {
+    if !perks.is_changed() {
+        return;
+    }
+    let interval = fire_interval(&perks);
+    let mut count = 0;
+    for mut drone in drones.iter_mut() {
+        drone.fire.set_duration(interval);
+        count += 1;
+    }
+    for index in count..drone_count(&perks) {
+        palette
+            .spawn_sprite(
+                &mut commands,
+                RenderLayer::Player,
+                palette.drone,
+                DRONE_SIZE,
+                Transform::from_xyz(0.0, 0.0, 0.1),
+            )
+            .insert(Drone {
+                index,
+                fire: Timer::new(interval, false),
+            })
+            .insert(Threat {
+                weight: DRONE_THREAT,
+            });
+    }
+}
+
+fn orbit_system(
+    time_scale: Res<TimeScale>,
+    perks: Res<Perks>,
+    mut orbit: ResMut<Orbit>,
+    player: Query<&Transform, (With<Player>, Without<Drone>)>,
+    mut drones: Query<(&Drone, &mut Transform)>,
+) {
+    let player = match player.get_single() {
+        Ok(player) => player.translation,
+        Err(_) => return,
+    };
+    orbit.0 = (orbit.0 + ORBIT_SPEED * time_scale.step()).rem_euclid(TAU);
+    let count = drone_count(&perks).max(1) as f32;
+    for (drone, mut transform) in drones.iter_mut() {
+        let angle = orbit.0 + drone.index as f32 * TAU / count;
+        let offset = Vec2::new(angle.cos(), angle.sin()) * ORBIT_RADIUS;
+        transform.translation = (player.truncate() + offset).extend(transform.translation.z);
+    }
+}
+
+fn drone_fire_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    palette: Res<Palette>,
+    mut drones: Query<(Entity, &mut Drone, &Transform)>,
+    enemies: Query<&Transform, (With<Enemy>, Without<Dying>, Without<Drone>)>,
+) {
+    for (entity, mut drone, transform) in drones.iter_mut() {
+        if !drone.fire.tick(time_scale.step_duration()).finished() {
+            continue;
+        }
+        let position = transform.translation;
+        // hold fire, the timer stays finished so the drone shoots as soon as a target shows up
+        let offset = match nearest_enemy(position, TARGET_RANGE, enemies.iter()) {
+            Some(offset) => offset,
+            None => continue,
+        };
+        drone.fire.reset();
+        spawn_companion_shot(
+            &mut commands,
+            &palette,
+            entity,
+            position,
+            offset,
+            SHOT_VELOCITY,
+            SHOT_SIZE,
+        );
+    }
+}
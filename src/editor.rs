@@ -0,0 +1,319 @@
+use bevy::{prelude::*, render::camera::Camera2d};
+
+use crate::{
+    arena::{ArenaLayout, Placement},
+    despawn_with,
+    layers::RenderLayer,
+    locale::Locale,
+    packs::ContentPacks,
+    palette::Palette,
+    window_to_world, GameState, UiFont, BOUNDS,
+};
+
+const MIN_SIZE: f32 = 20.0;
+const MAX_SIZE: f32 = 300.0;
+/// Change in size of the next placement per press of a size key.
+const SIZE_STEP: f32 = 10.0;
+
+/// Kind of placement the editor puts down on a click.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tool {
+    Obstacle,
+    SpawnZone,
+    Decoration,
+}
+
+impl Tool {
+    const ALL: [Tool; 3] = [Tool::Obstacle, Tool::SpawnZone, Tool::Decoration];
+
+    fn name_key(&self) -> &'static str {
+        match self {
+            Tool::Obstacle => "editor.obstacle",
+            Tool::SpawnZone => "editor.spawn_zone",
+            Tool::Decoration => "editor.decoration",
+        }
+    }
+
+    fn placements<'a>(&self, layout: &'a ArenaLayout) -> &'a [Placement] {
+        match self {
+            Tool::Obstacle => &layout.obstacles,
+            Tool::SpawnZone => &layout.spawn_zones,
+            Tool::Decoration => &layout.decorations,
+        }
+    }
+
+    fn placements_mut<'a>(&self, layout: &'a mut ArenaLayout) -> &'a mut Vec<Placement> {
+        match self {
+            Tool::Obstacle => &mut layout.obstacles,
+            Tool::SpawnZone => &mut layout.spawn_zones,
+            Tool::Decoration => &mut layout.decorations,
+        }
+    }
+
+    fn color(&self, palette: &Palette) -> Color {
+        match self {
+            Tool::Obstacle => palette.obstacle,
+            // spawn zones are invisible in a run
+            Tool::SpawnZone => palette.hazard,
+            Tool::Decoration => palette.decoration,
+        }
+    }
+}
+
+/// Tool and size the next placement is made with.
+struct Brush {
+    tool: Tool,
+    size: f32,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            tool: Tool::Obstacle,
+            size: 60.0,
+        }
+    }
+}
+
+/// Everything shown while editing, removed when leaving the editor.
+#[derive(Component)]
+struct EditorScreen;
+
+/// Sprite of a placement in the layout, rebuilt whenever the layout changes.
+#[derive(Component)]
+struct PlacementSprite;
+
+/// Preview of the next placement following the mouse.
+#[derive(Component)]
+struct BrushPreview;
+
+#[derive(Component)]
+struct HelpText;
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Brush>()
+            .add_system_set(SystemSet::on_enter(GameState::Editor).with_system(spawn_editor))
+            .add_system_set(
+                SystemSet::on_update(GameState::Editor)
+                    .with_system(editor_input_system)
+                    .with_system(placement_sprites_system)
+                    .with_system(brush_preview_system)
+                    .with_system(help_text_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Editor)
+                    .with_system(despawn_with::<EditorScreen>)
+                    .with_system(discard_changes),
+            );
+    }
+}
+
+fn spawn_editor(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    mut brush: ResMut<Brush>,
+    mut layout: ResMut<ArenaLayout>,
+) {
+    *brush = Brush::default();
+    // only the player's own layout is edited and saved, without the packs' placements, and
+    // taking it in rebuilds the placement sprites
+    *layout = ArenaLayout::load();
+    // the arena's extent, placements outside it can't be reached
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, 0.04),
+                custom_size: Some(BOUNDS),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, 0.5),
+            ..default()
+        })
+        .insert(RenderLayer::Background)
+        .insert(EditorScreen);
+    commands
+        .spawn_bundle(SpriteBundle::default())
+        .insert(RenderLayer::Ui)
+        .insert(BrushPreview)
+        .insert(EditorScreen);
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 20.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+                default(),
+            ),
+            ..default()
+        })
+        .insert(HelpText)
+        .insert(EditorScreen);
+}
+
+/// Unsaved changes are dropped when leaving the editor, play-testing saves them first.
+fn discard_changes(packs: Res<ContentPacks>, mut layout: ResMut<ArenaLayout>) {
+    *layout = ArenaLayout::load().with_packs(&packs);
+}
+
+/// The arena's camera, for finding what's under the mouse.
+type ArenaCamera<'w, 's> =
+    Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<Camera2d>>;
+
+/// Position of the mouse in arena coordinates, if it's over the window.
+fn cursor_position(windows: &Windows, camera: &ArenaCamera) -> Option<Vec2> {
+    let window = windows.get_primary()?;
+    let cursor = window.cursor_position()?;
+    let (camera, transform) = camera.get_single().ok()?;
+    Some(window_to_world(window, cursor, camera, transform))
+}
+
+fn editor_input_system(
+    windows: Res<Windows>,
+    camera: ArenaCamera,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    mut state: ResMut<State<GameState>>,
+    mut brush: ResMut<Brush>,
+    mut layout: ResMut<ArenaLayout>,
+) {
+    for (key, tool) in [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3]
+        .into_iter()
+        .zip(Tool::ALL)
+    {
+        if keys.just_pressed(key) {
+            brush.tool = tool;
+        }
+    }
+    if keys.just_pressed(KeyCode::LBracket) {
+        brush.size = (brush.size - SIZE_STEP).max(MIN_SIZE);
+    }
+    if keys.just_pressed(KeyCode::RBracket) {
+        brush.size = (brush.size + SIZE_STEP).min(MAX_SIZE);
+    }
+    if keys.just_pressed(KeyCode::S) {
+        layout.store();
+    }
+    if keys.just_pressed(KeyCode::P) {
+        layout.store();
+        let _ = state.set(GameState::Playing);
+        return;
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        let _ = state.set(GameState::Menu);
+        return;
+    }
+
+    let cursor = match cursor_position(&windows, &camera) {
+        Some(cursor) if cursor.abs().cmple(BOUNDS / 2.0).all() => cursor,
+        _ => return,
+    };
+    if buttons.just_pressed(MouseButton::Left) {
+        let placement = Placement {
+            position: (cursor.x, cursor.y),
+            size: brush.size,
+        };
+        brush.tool.placements_mut(&mut layout).push(placement);
+    }
+    if buttons.just_pressed(MouseButton::Right) {
+        // placements of the brush's kind go first, the most recently placed of each kind first
+        let tools = std::iter::once(brush.tool).chain(Tool::ALL);
+        for tool in tools {
+            let index = tool
+                .placements(&layout)
+                .iter()
+                .rposition(|placement| placement.contains(cursor));
+            if let Some(index) = index {
+                tool.placements_mut(&mut layout).remove(index);
+                break;
+            }
+        }
+    }
+}
+
+fn placement_sprites_system(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    layout: Res<ArenaLayout>,
+    sprites: Query<Entity, With<PlacementSprite>>,
+) {
+    if !layout.is_changed() {
+        return;
+    }
+    for entity in sprites.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for (depth, tool) in Tool::ALL.into_iter().enumerate() {
+        for placement in tool.placements(&layout).iter() {
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: tool.color(&palette),
+                        custom_size: Some(Vec2::splat(placement.size)),
+                        ..default()
+                    },
+                    // the first tool's on top
+                    transform: Transform::from_translation(
+                        placement.center().extend(0.5 - 0.1 * depth as f32),
+                    ),
+                    ..default()
+                })
+                .insert(RenderLayer::Decals)
+                .insert(PlacementSprite)
+                .insert(EditorScreen);
+        }
+    }
+}
+
+fn brush_preview_system(
+    windows: Res<Windows>,
+    camera: ArenaCamera,
+    palette: Res<Palette>,
+    brush: Res<Brush>,
+    mut previews: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<BrushPreview>>,
+) {
+    let cursor = cursor_position(&windows, &camera);
+    for (mut sprite, mut transform, mut visibility) in previews.iter_mut() {
+        visibility.is_visible = cursor.is_some();
+        if let Some(cursor) = cursor {
+            transform.translation = cursor.extend(transform.translation.z);
+        }
+        let mut color = brush.tool.color(&palette);
+        color.set_a(color.a() * 0.6);
+        sprite.color = color;
+        sprite.custom_size = Some(Vec2::splat(brush.size));
+    }
+}
+
+fn help_text_system(
+    brush: Res<Brush>,
+    locale: Res<Locale>,
+    mut texts: Query<&mut Text, With<HelpText>>,
+) {
+    if !brush.is_changed() && !locale.is_changed() {
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = locale.format(
+            "editor.help",
+            &[
+                ("tool", &locale.get(brush.tool.name_key())),
+                ("size", &brush.size),
+            ],
+        );
+    }
+}
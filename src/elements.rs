@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{packs::ContentPacks, EnemyKind};
+
+/// File the affinities of every kind of enemy but bosses are read from, in the game's assets and
+/// in content packs.
+pub const ENEMIES_FILE: &str = "enemies.ron";
+
+/// What a hit is made of, deciding how hard it lands on enemies resisting or vulnerable to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+#[reflect_value(PartialEq, Serialize, Deserialize)]
+pub enum Element {
+    /// bullets and blades
+    #[default]
+    Kinetic,
+    Fire,
+    Ice,
+    Shock,
+}
+
+impl Element {
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            Element::Kinetic => "element.kinetic",
+            Element::Fire => "element.fire",
+            Element::Ice => "element.ice",
+            Element::Shock => "element.shock",
+        }
+    }
+}
+
+/// Factor an enemy with the given affinities takes hits of `element` by, 1 for elements it has
+/// none for.
+pub fn factor(affinities: &[(Element, f32)], element: Element) -> f32 {
+    affinities
+        .iter()
+        .find(|(affinity, _)| *affinity == element)
+        .map_or(1.0, |(_, factor)| *factor)
+}
+
+/// Factors a kind of enemy takes hits of the elements it resists or is vulnerable to by, as
+/// described in `ENEMIES_FILE`.
+#[derive(Deserialize)]
+pub struct EnemyAffinities {
+    kind: EnemyKind,
+    affinities: Vec<(Element, f32)>,
+}
+
+/// Affinities of every kind of enemy listed, the ones listed last taking precedence.
+#[derive(Default)]
+pub struct Affinities(Vec<EnemyAffinities>);
+
+impl Affinities {
+    fn load(packs: &ContentPacks) -> Self {
+        Self(packs.load_all(ENEMIES_FILE))
+    }
+
+    /// Affinities of the kind, none if it isn't listed, like bosses bringing their own.
+    pub fn of(&self, kind: EnemyKind) -> &[(Element, f32)] {
+        self.0
+            .iter()
+            .rev()
+            .find(|enemy| enemy.kind == kind)
+            .map_or(&[], |enemy| &enemy.affinities)
+    }
+}
+
+/// Fraction of a hit an enemy has taken from resisted hits that didn't add up to a whole one yet,
+/// carried over to the next hit it takes. Every enemy has it from when it spawns, for a second
+/// hit in the same step to carry on from the first.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Wounds(pub f32);
+
+pub struct ElementsPlugin;
+
+impl Plugin for ElementsPlugin {
+    fn build(&self, app: &mut App) {
+        let affinities = Affinities::load(app.world.resource::<ContentPacks>());
+        app.insert_resource(affinities).add_system(reload_system);
+    }
+}
+
+fn reload_system(mut commands: Commands, packs: Res<ContentPacks>) {
+    if packs.is_changed() && !packs.is_added() {
+        commands.insert_resource(Affinities::load(&packs));
+    }
+}
@@ -0,0 +1,221 @@
+use bevy::{prelude::*, reflect::FromReflect};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    elements::Wounds, layers::RenderLayer, palette::Palette, AddSimulationSystem, Enemy, EnemyKind,
+    Player, TimeScale, ENEMY_SIZE,
+};
+
+/// Wave from which enemies may spawn as elites.
+const ELITE_WAVE: usize = 3;
+/// Extra chance per wave past `ELITE_WAVE` for an enemy to spawn as an elite.
+const ELITE_CHANCE_PER_WAVE: f32 = 0.04;
+const MAX_ELITE_CHANCE: f32 = 0.3;
+/// Factor each modifier grows an elite by.
+const SIZE_PER_MODIFIER: f32 = 0.3;
+const FAST_FACTOR: f32 = 1.6;
+/// Extra bullets an armored elite takes before it dies.
+const ARMOR: u32 = 2;
+/// Rate in radians per second a shielded elite turns its shield towards the player.
+const SHIELD_TURN_RATE: f32 = 1.2;
+/// Cosine of the half angle of the cone in front of a shielded elite that blocks bullets.
+const SHIELD_COVERAGE: f32 = 0.5;
+const SPLIT_CHILDREN: usize = 2;
+const SPLIT_VELOCITY: f32 = 320.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Reflect, FromReflect)]
+#[reflect_value(PartialEq, Serialize, Deserialize)]
+pub enum Modifier {
+    Fast,
+    Armored,
+    /// breaks up into smaller enemies when shot down
+    Splitting,
+    /// bullets coming at it from the front are blocked
+    Shielded,
+}
+
+impl Modifier {
+    const ALL: [Modifier; 4] = [
+        Modifier::Fast,
+        Modifier::Armored,
+        Modifier::Splitting,
+        Modifier::Shielded,
+    ];
+
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            Modifier::Fast => "modifier.fast",
+            Modifier::Armored => "modifier.armored",
+            Modifier::Splitting => "modifier.splitting",
+            Modifier::Shielded => "modifier.shielded",
+        }
+    }
+}
+
+/// Stronger enemy carrying one or more modifiers.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Elite {
+    pub modifiers: Vec<Modifier>,
+    /// bullets it can still take before the next one kills it
+    pub armor: u32,
+    /// angle in radians the shield faces
+    facing: f32,
+}
+
+impl Elite {
+    pub fn has(&self, modifier: Modifier) -> bool {
+        self.modifiers.contains(&modifier)
+    }
+
+    /// Whether a bullet travelling along `direction` is stopped by the elite's shield.
+    pub fn blocks(&self, direction: Vec3) -> bool {
+        let facing = Quat::from_rotation_z(self.facing) * Vec3::Y;
+        self.has(Modifier::Shielded) && facing.dot(direction) < -SHIELD_COVERAGE
+    }
+
+    /// Score awarded for killing the elite.
+    pub fn score(&self) -> usize {
+        1 + 2 * self.modifiers.len()
+    }
+}
+
+pub struct ElitesPlugin;
+
+impl Plugin for ElitesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(shield_facing_system);
+    }
+}
+
+/// Modifiers for a freshly spawned enemy on the given wave, empty for a regular enemy.
+pub fn roll_modifiers(wave: usize, rng: &mut impl Rng) -> Vec<Modifier> {
+    if wave < ELITE_WAVE {
+        return Vec::new();
+    }
+    let waves_in = (wave - ELITE_WAVE + 1) as f32;
+    if !rng.gen_bool((ELITE_CHANCE_PER_WAVE * waves_in).min(MAX_ELITE_CHANCE) as f64) {
+        return Vec::new();
+    }
+    // later waves stack more modifiers on a single elite
+    let count = rng.gen_range(1..=(1 + wave / 6).min(Modifier::ALL.len()));
+    Modifier::ALL.choose_multiple(rng, count).copied().collect()
+}
+
+pub fn spawn_elite(
+    commands: &mut Commands,
+    palette: &Palette,
+    position: Vec3,
+    kind: EnemyKind,
+    velocity: f32,
+    modifiers: Vec<Modifier>,
+) {
+    let size = ENEMY_SIZE * (1.0 + SIZE_PER_MODIFIER * modifiers.len() as f32);
+    let velocity = if modifiers.contains(&Modifier::Fast) {
+        velocity * FAST_FACTOR
+    } else {
+        velocity
+    };
+    let armor = if modifiers.contains(&Modifier::Armored) {
+        ARMOR
+    } else {
+        0
+    };
+    let shielded = modifiers.contains(&Modifier::Shielded);
+    // tinted further towards the elite color the more modifiers it carries
+    let tint = (0.3 * modifiers.len() as f32).min(1.0);
+    let color = Color::from(Vec4::from(palette.enemy).lerp(Vec4::from(palette.elite), tint));
+
+    palette
+        .spawn_sprite(
+            commands,
+            RenderLayer::Enemies,
+            color,
+            size,
+            Transform::from_translation(position)
+                .with_rotation(Quat::from_rotation_z(palette.enemy_rotation)),
+        )
+        .insert(Enemy {
+            kind,
+            velocity,
+            size,
+        })
+        .insert(Wounds::default())
+        .insert(Elite {
+            modifiers,
+            armor,
+            facing: 0.0,
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: palette.elite,
+                    custom_size: Some(Vec2::splat(size + 4.0)),
+                    ..default()
+                },
+                // behind the palette's own outline, if it draws one
+                transform: Transform::from_xyz(0.0, 0.0, -0.02),
+                ..default()
+            });
+            if shielded {
+                parent.spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: palette.shield,
+                        custom_size: Some(Vec2::new(size * 1.4, 4.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, size / 2.0 + 5.0, 0.01),
+                    ..default()
+                });
+            }
+        });
+}
+
+/// Spawns the smaller enemies a splitting elite breaks up into.
+pub fn split(commands: &mut Commands, palette: &Palette, position: Vec3, kind: EnemyKind) {
+    for i in 0..SPLIT_CHILDREN {
+        let angle = i as f32 / SPLIT_CHILDREN as f32 * std::f32::consts::TAU;
+        let offset = Quat::from_rotation_z(angle) * Vec3::X * ENEMY_SIZE;
+        let size = ENEMY_SIZE * 0.7;
+        palette
+            .spawn_sprite(
+                commands,
+                RenderLayer::Enemies,
+                palette.enemy,
+                size,
+                Transform::from_translation(position + offset),
+            )
+            .insert(Enemy {
+                kind,
+                velocity: SPLIT_VELOCITY,
+                size,
+            })
+            .insert(Wounds::default());
+    }
+}
+
+fn shield_facing_system(
+    time_scale: Res<TimeScale>,
+    player: Query<&Transform, (With<Player>, Without<Elite>)>,
+    mut elites: Query<(&mut Elite, &mut Transform)>,
+) {
+    let player_position = match player.get_single() {
+        Ok(transform) => transform.translation,
+        Err(_) => return,
+    };
+    let max_turn = SHIELD_TURN_RATE * time_scale.enemy_step();
+    for (mut elite, mut transform) in elites.iter_mut() {
+        if !elite.has(Modifier::Shielded) {
+            continue;
+        }
+        let to_player = player_position - transform.translation;
+        let target = (-to_player.x).atan2(to_player.y);
+        // turn the short way round, no faster than the turn rate
+        let difference = (target - elite.facing + std::f32::consts::PI)
+            .rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+        elite.facing += difference.clamp(-max_turn, max_turn);
+        transform.rotation = Quat::from_rotation_z(elite.facing);
+    }
+}
@@ -0,0 +1,267 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    damage_player,
+    deaths::Dying,
+    events::{DamageSource, Grazed, PlayerDamaged},
+    layers::RenderLayer,
+    out_of_bounds,
+    palette::Palette,
+    settings::Settings,
+    AddSimulationSystem, GameState, Health, Player, TimeScale,
+};
+
+const ENEMY_BULLET_SIZE: f32 = 7.0;
+/// Damage an enemy bullet deals to the player.
+const ENEMY_BULLET_DAMAGE: f32 = 1.0;
+/// Distance beyond hitting the player within which a bullet grazes them.
+const GRAZE_RADIUS: f32 = 20.0;
+
+/// Shot fired at the player by an enemy, travelling at a fixed velocity until it hits the player
+/// or leaves the arena.
+#[derive(Component)]
+pub struct EnemyBullet {
+    velocity: Vec3,
+    /// how close the bullet came and where, while it's passing within grazing distance of the
+    /// player, it only counts as a graze once it's gone past without hitting them
+    passing: Option<(f32, Vec3)>,
+    /// each bullet only grazes the player once, however often it comes back around
+    grazed: bool,
+}
+
+impl EnemyBullet {
+    /// Units the bullet moves per second of enemy time.
+    pub fn velocity(&self) -> Vec3 {
+        self.velocity
+    }
+}
+
+/// Spread of bullets an enemy fires in a single volley.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum Pattern {
+    /// evenly spaced all around the shooter, the first one aimed at the player
+    Ring { count: usize, speed: f32 },
+    /// fanned out over `spread` radians, centered on the player
+    Fan {
+        count: usize,
+        spread: f32,
+        speed: f32,
+    },
+    /// `arms` evenly spaced bullets that ignore the player, turning by `turn` radians every volley
+    Spiral { arms: usize, turn: f32, speed: f32 },
+}
+
+/// Pattern fired over and over, `interval` seconds apart.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct Attack {
+    pub pattern: Pattern,
+    pub volleys: usize,
+    pub interval: f32,
+}
+
+impl Attack {
+    /// Seconds from the first volley to the last.
+    pub fn duration(&self) -> f32 {
+        self.volleys.saturating_sub(1) as f32 * self.interval
+    }
+}
+
+/// Carries out an attack from the entity it's on, removing itself after the last volley.
+#[derive(Component)]
+pub struct Emitter {
+    attack: Attack,
+    /// volleys fired so far
+    fired: usize,
+    timer: Timer,
+}
+
+impl Emitter {
+    /// Starts the attack, firing the first volley on the next step.
+    pub fn new(attack: Attack) -> Self {
+        let mut timer = Timer::from_seconds(attack.interval, true);
+        timer.set_elapsed(timer.duration());
+        Self {
+            attack,
+            fired: 0,
+            timer,
+        }
+    }
+}
+
+pub struct EnemyBulletsPlugin;
+
+impl Plugin for EnemyBulletsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(emitter_system)
+            .add_simulation_system(enemy_bullet_movement_system)
+            .add_simulation_system(enemy_bullet_collision_system);
+    }
+}
+
+/// Fires the `volley`th volley of `pattern` from `position` at the player standing at `player`.
+pub fn fire(
+    commands: &mut Commands,
+    palette: &Palette,
+    pattern: Pattern,
+    volley: usize,
+    position: Vec3,
+    player: Vec3,
+) {
+    let aim = (player - position).truncate();
+    let aim = aim.y.atan2(aim.x);
+    let (count, speed, first, step) = match pattern {
+        Pattern::Ring { count, speed } => (count, speed, aim, TAU / count.max(1) as f32),
+        Pattern::Fan {
+            count,
+            spread,
+            speed,
+        } => {
+            let step = if count > 1 {
+                spread / (count - 1) as f32
+            } else {
+                0.0
+            };
+            (count, speed, aim - spread / 2.0, step)
+        }
+        Pattern::Spiral { arms, turn, speed } => (
+            arms,
+            speed,
+            (turn * volley as f32) % TAU,
+            TAU / arms.max(1) as f32,
+        ),
+    };
+    for index in 0..count {
+        let angle = first + step * index as f32;
+        spawn(
+            commands,
+            palette,
+            position,
+            Vec3::new(angle.cos(), angle.sin(), 0.0) * speed,
+        );
+    }
+}
+
+pub fn spawn(commands: &mut Commands, palette: &Palette, position: Vec3, velocity: Vec3) {
+    palette
+        .spawn_sprite(
+            commands,
+            RenderLayer::Bullets,
+            palette.enemy_bullet,
+            ENEMY_BULLET_SIZE,
+            Transform::from_translation(position),
+        )
+        .insert(EnemyBullet {
+            velocity,
+            passing: None,
+            grazed: false,
+        });
+}
+
+fn emitter_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    palette: Res<Palette>,
+    player: Query<&Transform, (With<Player>, Without<Emitter>)>,
+    mut emitters: Query<(Entity, &mut Emitter, &Transform), Without<Dying>>,
+) {
+    let player = match player.get_single() {
+        Ok(player) => player.translation,
+        Err(_) => return,
+    };
+    for (entity, mut emitter, transform) in emitters.iter_mut() {
+        let emitter = &mut *emitter;
+        // without an interval every volley goes out at once
+        let volleys = if emitter.attack.interval > 0.0 {
+            emitter
+                .timer
+                .tick(time_scale.enemy_step_duration())
+                .times_finished() as usize
+        } else {
+            emitter.attack.volleys
+        };
+        for _ in 0..volleys {
+            if emitter.fired >= emitter.attack.volleys {
+                break;
+            }
+            fire(
+                &mut commands,
+                &palette,
+                emitter.attack.pattern,
+                emitter.fired,
+                transform.translation,
+                player,
+            );
+            emitter.fired += 1;
+        }
+        if emitter.fired >= emitter.attack.volleys {
+            commands.entity(entity).remove::<Emitter>();
+        }
+    }
+}
+
+fn enemy_bullet_movement_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut bullets: Query<(Entity, &EnemyBullet, &mut Transform)>,
+) {
+    for (entity, bullet, mut transform) in bullets.iter_mut() {
+        transform.translation += bullet.velocity * time_scale.enemy_step();
+        if out_of_bounds(transform.translation) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn enemy_bullet_collision_system(
+    mut commands: Commands,
+    mut state: ResMut<State<GameState>>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+    mut bullets: Query<(Entity, &mut EnemyBullet, &Transform)>,
+    mut player: Query<(Entity, &Player, &Transform, &mut Health), Without<EnemyBullet>>,
+    mut player_damaged: EventWriter<PlayerDamaged>,
+    mut grazed: EventWriter<Grazed>,
+) {
+    let (player_entity, player, player_transform, mut health) = match player.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    for (entity, mut bullet, transform) in bullets.iter_mut() {
+        let distance = transform.translation.distance(player_transform.translation);
+        let hit_distance = player.hit_radius + ENEMY_BULLET_SIZE / 2.0;
+        if distance > hit_distance {
+            // the pass that checks for hits already knows how close every bullet comes
+            if bullet.grazed {
+                continue;
+            }
+            if distance <= hit_distance + GRAZE_RADIUS {
+                if bullet.passing.is_none_or(|(closest, _)| distance < closest) {
+                    bullet.passing = Some((distance, transform.translation));
+                }
+            } else if let Some((_, position)) = bullet.passing.take() {
+                bullet.grazed = true;
+                grazed.send(Grazed {
+                    position,
+                    color: palette.enemy_bullet,
+                });
+            }
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        if damage_player(
+            &mut commands,
+            &mut state,
+            &settings,
+            player_entity,
+            &mut health,
+            ENEMY_BULLET_DAMAGE,
+            DamageSource::EnemyBullet,
+            &mut player_damaged,
+        ) {
+            return;
+        }
+    }
+}
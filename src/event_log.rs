@@ -0,0 +1,272 @@
+use std::collections::VecDeque;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    events::{
+        BossArrived, BossPhaseChanged, ComboMilestone, EnemyKilled, GhostOutlasted,
+        ObjectiveGraded, ObjectiveSet, PerkGained, PickupCollected, ReportSaved, WaveStarted,
+    },
+    locale::Locale,
+    EnemyKind, GameState, UiFont,
+};
+
+/// Seconds an entry stays in the log.
+const ENTRY_DURATION: f32 = 4.0;
+/// Seconds at the end of an entry's lifetime over which it fades out.
+const FADE_DURATION: f32 = 1.0;
+/// Most entries shown at once, the oldest make room for new ones.
+const MAX_ENTRIES: usize = 6;
+/// Most lines kept in the log's history, long after their entries are gone.
+const HISTORY_LENGTH: usize = 100;
+
+/// Column in the bottom left corner announcing notable events of the run.
+#[derive(Component)]
+struct EventLog;
+
+#[derive(Component)]
+struct LogEntry(Timer);
+
+/// Every line logged in the current run, the oldest first, up to `HISTORY_LENGTH` of them.
+#[derive(Default)]
+pub struct LogHistory(pub VecDeque<String>);
+
+/// What's logged about the bosses: their arrival and the phases they're worn down into.
+#[derive(SystemParam)]
+struct BossEvents<'w, 's> {
+    arrived: EventReader<'w, 's, BossArrived>,
+    phase_changed: EventReader<'w, 's, BossPhaseChanged>,
+}
+
+pub struct EventLogPlugin;
+
+impl Plugin for EventLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LogHistory>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_event_log))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(log_events_system)
+                    .with_system(fade_entries_system),
+            );
+    }
+}
+
+fn spawn_event_log(mut commands: Commands, mut history: ResMut<LogHistory>) {
+    history.0.clear();
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    ..default()
+                },
+                // entries are added at the bottom and push the older ones up
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexStart,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(EventLog);
+}
+
+fn log_events_system(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    locale: Res<Locale>,
+    mut history: ResMut<LogHistory>,
+    log: Query<Entity, With<EventLog>>,
+    entries: Query<(Entity, &LogEntry)>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut combo_milestone: EventReader<ComboMilestone>,
+    mut perk_gained: EventReader<PerkGained>,
+    mut boss_events: BossEvents,
+    mut pickup_collected: EventReader<PickupCollected>,
+    mut ghost_outlasted: EventReader<GhostOutlasted>,
+    mut objective_set: EventReader<ObjectiveSet>,
+    mut objective_graded: EventReader<ObjectiveGraded>,
+    mut report_saved: EventReader<ReportSaved>,
+) {
+    // the log is spawned on entering the run, so events of the first frame wait until it exists
+    let log = match log.get_single() {
+        Ok(log) => log,
+        Err(_) => return,
+    };
+
+    let mut lines = Vec::new();
+    // the objective of the wave that's over is graded before the next one is announced
+    for graded in objective_graded.iter() {
+        let key = format!("objective.{}", graded.name);
+        let objective = locale.get(&key);
+        lines.push(if graded.met {
+            (
+                locale.format(
+                    "log.objective_met",
+                    &[("objective", &objective), ("score", &graded.score)],
+                ),
+                Color::GOLD,
+            )
+        } else {
+            (
+                locale.format("log.objective_failed", &[("objective", &objective)]),
+                Color::GRAY,
+            )
+        });
+    }
+    for started in wave_started.iter() {
+        lines.push((
+            locale.format("log.wave_started", &[("wave", &started.number)]),
+            Color::rgb(0.85, 0.85, 0.85),
+        ));
+    }
+    for set in objective_set.iter() {
+        lines.push((
+            locale.format(
+                "log.objective_set",
+                &[("objective", &locale.get(&format!("objective.{}", set.name)))],
+            ),
+            Color::rgb(1.0, 0.9, 0.6),
+        ));
+    }
+    for arrived in boss_events.arrived.iter() {
+        lines.push((
+            locale.format(
+                "log.boss_arrived",
+                &[(
+                    "boss",
+                    &locale.get_or(&format!("boss.{}", arrived.name), &arrived.display_name),
+                )],
+            ),
+            Color::ORANGE_RED,
+        ));
+    }
+    for changed in boss_events.phase_changed.iter() {
+        lines.push((
+            locale.format(
+                "log.boss_phase",
+                &[
+                    (
+                        "boss",
+                        &locale.get_or(&format!("boss.{}", changed.name), &changed.display_name),
+                    ),
+                    ("phase", &changed.phase),
+                ],
+            ),
+            Color::ORANGE_RED,
+        ));
+    }
+    for killed in enemy_killed.iter() {
+        if killed.kind == EnemyKind::Boss {
+            lines.push((locale.get("log.boss_killed").to_string(), Color::ORANGE_RED));
+            continue;
+        }
+        if killed.modifiers.is_empty() {
+            continue;
+        }
+        let modifiers = killed
+            .modifiers
+            .iter()
+            .map(|modifier| locale.get(modifier.name_key()).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        lines.push((
+            locale.format(
+                "log.elite_killed",
+                &[
+                    ("enemy", &locale.get(killed.kind.name_key())),
+                    ("modifiers", &modifiers),
+                ],
+            ),
+            Color::GOLD,
+        ));
+    }
+    for milestone in combo_milestone.iter() {
+        lines.push((
+            locale.format("log.combo", &[("combo", &milestone.count)]),
+            Color::rgb(0.4, 0.8, 1.0),
+        ));
+    }
+    for PickupCollected in pickup_collected.iter() {
+        lines.push((
+            locale.get("log.pickup").to_string(),
+            Color::rgb(0.9, 0.9, 0.5),
+        ));
+    }
+    for PerkGained(perk) in perk_gained.iter() {
+        lines.push((
+            locale.format("log.perk", &[("perk", &locale.get(perk.name_key()))]),
+            Color::rgb(0.5, 1.0, 0.5),
+        ));
+    }
+    for outlasted in ghost_outlasted.iter() {
+        lines.push((
+            locale.format("log.ghost_outlasted", &[("score", &outlasted.score)]),
+            Color::rgb(0.75, 0.75, 1.0),
+        ));
+    }
+    for saved in report_saved.iter() {
+        lines.push((
+            locale.format("log.report_saved", &[("path", &saved.path)]),
+            Color::GRAY,
+        ));
+    }
+    if lines.is_empty() {
+        return;
+    }
+    for (line, _) in lines.iter() {
+        history.0.push_back(line.clone());
+    }
+    while history.0.len() > HISTORY_LENGTH {
+        history.0.pop_front();
+    }
+
+    // drop the oldest entries to stay within the limit
+    let mut existing = entries.iter().collect::<Vec<_>>();
+    existing.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.0.elapsed()));
+    let excess = (existing.len() + lines.len()).saturating_sub(MAX_ENTRIES);
+    for (entity, _) in existing.into_iter().take(excess) {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.entity(log).with_children(|parent| {
+        for (line, color) in lines.into_iter().rev().take(MAX_ENTRIES).rev() {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        line,
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: 18.0,
+                            color,
+                        },
+                        default(),
+                    ),
+                    ..default()
+                })
+                .insert(LogEntry(Timer::from_seconds(ENTRY_DURATION, false)));
+        }
+    });
+}
+
+fn fade_entries_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut entries: Query<(Entity, &mut LogEntry, &mut Text)>,
+) {
+    for (entity, mut entry, mut text) in entries.iter_mut() {
+        if entry.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let remaining = ENTRY_DURATION - entry.0.elapsed_secs();
+        let alpha = (remaining / FADE_DURATION).min(1.0);
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
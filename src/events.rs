@@ -0,0 +1,182 @@
+use bevy::{
+    ecs::{event::Events, schedule::ShouldRun, system::Resource},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::Achievement, elites::Modifier, in_run, perks::Perk, EnemyKind, GameState,
+    SimulationClock, SimulationSteps,
+};
+
+/// Sent every time the player fires a bullet.
+pub struct ShotFired;
+
+/// Sent when a bash kills an enemy, along with the enemy's own `EnemyKilled`.
+pub struct BashKill;
+
+/// Sent when a bullet connects with an enemy, whether or not it kills it.
+pub struct BulletHit;
+
+/// Sent when an enemy is destroyed.
+pub struct EnemyKilled {
+    pub kind: EnemyKind,
+    /// modifiers the enemy carried if it was an elite
+    pub modifiers: Vec<Modifier>,
+}
+
+/// Sent when something other than the player hits an enemy, which holds it against them.
+pub struct Provoked {
+    pub enemy: Entity,
+    pub by: Entity,
+}
+
+/// Sent when the player takes damage.
+pub struct PlayerDamaged {
+    pub amount: f32,
+    pub source: DamageSource,
+}
+
+/// What dealt damage to the player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DamageSource {
+    /// an enemy of this kind, running into it or caught by its attack
+    Enemy(EnemyKind),
+    /// a shot fired by a boss
+    EnemyBullet,
+    Asteroid,
+    Mine,
+    Laser,
+    /// ground set alight by a mine
+    Burning,
+    /// the hazard outside the safe part of a shrinking arena
+    ArenaEdge,
+    /// a mod script
+    Script,
+}
+
+/// Sent when an enemy bullet passes close by the player without hitting them.
+pub struct Grazed {
+    /// where the bullet was at its closest
+    pub position: Vec3,
+    pub color: Color,
+}
+
+/// Sent when the player sets off a bomb.
+pub struct BombDetonated;
+
+/// Sent when the player collects a pickup.
+pub struct PickupCollected;
+
+/// Sent when a new wave begins, including the first one of a run.
+pub struct WaveStarted {
+    pub number: usize,
+}
+
+/// Sent when the combo reaches another multiple of the milestone step.
+pub struct ComboMilestone {
+    pub count: usize,
+}
+
+/// Sent when a boss joins the fight.
+pub struct BossArrived {
+    /// the boss's name in the boss definitions
+    pub name: String,
+    /// what the boss is called where the locale doesn't name it
+    pub display_name: String,
+}
+
+/// Sent when a boss is worn down into its next phase, every third of its health.
+pub struct BossPhaseChanged {
+    /// the boss's name in the boss definitions
+    pub name: String,
+    /// what the boss is called where the locale doesn't name it
+    pub display_name: String,
+    /// phase the boss entered, the one it arrived in being the first
+    pub phase: usize,
+}
+
+/// Sent when the player outlasts the ghost of their best run on the seed.
+pub struct GhostOutlasted {
+    /// what the ghost's run scored
+    pub score: usize,
+}
+
+/// Sent when a wave starts with a bonus objective set for it.
+pub struct ObjectiveSet {
+    /// the objective's name in the objective definitions
+    pub name: String,
+}
+
+/// Sent when the wave a bonus objective was set for is over.
+pub struct ObjectiveGraded {
+    pub name: String,
+    pub met: bool,
+    /// what meeting the objective scores
+    pub score: usize,
+}
+
+/// Sent when a bug report was saved, for the player to know where to find it.
+pub struct ReportSaved {
+    pub path: String,
+}
+
+/// Sent when the player is granted a perk.
+pub struct PerkGained(pub Perk);
+
+/// Sent the first time the player meets an achievement's condition.
+pub struct AchievementUnlocked(pub Achievement);
+
+pub struct EventsPlugin;
+
+impl Plugin for EventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_event::<ShotFired>()
+            .add_simulation_event::<BashKill>()
+            .add_simulation_event::<BulletHit>()
+            .add_simulation_event::<Provoked>()
+            .add_simulation_event::<EnemyKilled>()
+            .add_simulation_event::<PlayerDamaged>()
+            .add_simulation_event::<Grazed>()
+            .add_simulation_event::<BombDetonated>()
+            .add_simulation_event::<PickupCollected>()
+            .add_simulation_event::<WaveStarted>()
+            .add_event::<ComboMilestone>()
+            .add_simulation_event::<PerkGained>()
+            .add_simulation_event::<BossArrived>()
+            .add_simulation_event::<BossPhaseChanged>()
+            .add_simulation_event::<GhostOutlasted>()
+            .add_simulation_event::<ObjectiveSet>()
+            .add_simulation_event::<ObjectiveGraded>()
+            .add_event::<ReportSaved>()
+            .add_event::<AchievementUnlocked>();
+    }
+}
+
+trait AddSimulationEvent {
+    /// Adds an event the simulation sends or reads, which ages with the simulation's steps while
+    /// a run is on rather than with every frame.
+    fn add_simulation_event<T: Resource>(&mut self) -> &mut Self;
+}
+
+impl AddSimulationEvent for App {
+    fn add_simulation_event<T: Resource>(&mut self) -> &mut Self {
+        self.init_resource::<Events<T>>().add_system_to_stage(
+            CoreStage::First,
+            Events::<T>::update_system
+                .with_run_criteria(run_if_events_age)
+                .after(SimulationSteps),
+        )
+    }
+}
+
+/// Events are dropped after two updates, so a run's events are only updated on frames the
+/// simulation steps on: every step sees what was sent since the one before, however many frames
+/// go by between them, like while the frame rate outpaces the steps or the run is paused.
+fn run_if_events_age(state: Res<State<GameState>>, clock: Res<SimulationClock>) -> ShouldRun {
+    if in_run(&state) && clock.steps == 0 {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
+}
@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
+
+use crate::{
+    deaths::Dying, packs::ContentPacks, palette::Palette, AddSimulationSystem, EnemyKind,
+    TimeScale, BOUNDS,
+};
+
+/// File formations are read from, in the game's assets and in content packs.
+pub const FORMATIONS_FILE: &str = "formations.ron";
+/// Chance each spawn has of being a whole formation rather than a single enemy, once any
+/// formation is available.
+const FORMATION_CHANCE: f64 = 0.05;
+/// Distance outside the arena's edge formations entering from it start at.
+const EDGE_MARGIN: f32 = 30.0;
+/// Speed of enemies spawned in a formation.
+const FORMATION_VELOCITY: f32 = 250.0;
+
+/// Group of enemies spawned together in a fixed arrangement, read from `FORMATIONS_FILE`.
+#[derive(Deserialize)]
+pub struct Formation {
+    /// used when logging problems with the formation
+    pub name: String,
+    /// first wave the formation can show up in
+    pub min_wave: usize,
+    /// how likely it is to be picked, relative to the other formations available
+    pub weight: u32,
+    pub anchor: Anchor,
+    pub slots: Vec<Slot>,
+}
+
+/// Where a formation is placed and which way it's heading.
+#[derive(Deserialize, Clone, Copy)]
+pub enum Anchor {
+    /// centered on the player, heading up
+    Player,
+    /// just outside a random edge of the arena, heading into it
+    Edge,
+}
+
+/// Position of one enemy in a formation and the way it enters the arena.
+#[derive(Deserialize)]
+pub struct Slot {
+    pub offset: (f32, f32),
+    pub path: EntryPath,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub enum EntryPath {
+    /// chases the player right away like any other enemy
+    Chase,
+    /// flies straight along the formation's heading for a while before chasing
+    Sweep { duration: f32 },
+    /// closes in on the formation's anchor for a while before chasing
+    Converge { duration: f32 },
+}
+
+/// Every formation that can be spawned.
+pub struct Formations(Vec<Formation>);
+
+impl Formations {
+    fn load(packs: &ContentPacks) -> Self {
+        let formations: Vec<Formation> = packs.load_all(FORMATIONS_FILE);
+        for formation in formations
+            .iter()
+            .filter(|formation| formation.slots.is_empty())
+        {
+            warn!("formation {} has no slots", formation.name);
+        }
+        Self(formations)
+    }
+
+    /// Picks one of the formations available in the wave, if it's time for one.
+    pub fn roll(&self, wave: usize, rng: &mut impl Rng) -> Option<&Formation> {
+        let available = self
+            .0
+            .iter()
+            .filter(|formation| formation.min_wave <= wave && !formation.slots.is_empty())
+            .collect::<Vec<_>>();
+        if available.is_empty() || !rng.gen_bool(FORMATION_CHANCE) {
+            return None;
+        }
+        available
+            .choose_weighted(rng, |formation| formation.weight)
+            .ok()
+            .copied()
+    }
+}
+
+/// Enemy following its formation's entry path, it only starts chasing once the timer finishes.
+#[derive(Component)]
+pub struct Entering {
+    velocity: Vec3,
+    timer: Timer,
+}
+
+pub struct FormationsPlugin;
+
+impl Plugin for FormationsPlugin {
+    fn build(&self, app: &mut App) {
+        let formations = Formations::load(app.world.resource::<ContentPacks>());
+        app.insert_resource(formations)
+            .add_system(reload_system)
+            .add_simulation_system(entry_system);
+    }
+}
+
+fn reload_system(mut commands: Commands, packs: Res<ContentPacks>) {
+    if packs.is_changed() && !packs.is_added() {
+        commands.insert_resource(Formations::load(&packs));
+    }
+}
+
+/// Spawns every enemy of the formation, anchored relative to the player at `player`.
+pub fn spawn(
+    commands: &mut Commands,
+    palette: &Palette,
+    formation: &Formation,
+    player: Vec3,
+    rng: &mut impl Rng,
+) {
+    let (anchor, heading) = match formation.anchor {
+        Anchor::Player => (player.truncate(), Vec2::Y),
+        Anchor::Edge => edge_anchor(rng),
+    };
+    // x of the offsets runs along the edge, to the right when looking along the heading
+    let right = Vec2::new(heading.y, -heading.x);
+    for slot in formation.slots.iter() {
+        let position = anchor + right * slot.offset.0 + heading * slot.offset.1;
+        let entity = crate::spawn_enemy(
+            commands,
+            palette,
+            position.extend(0.0),
+            EnemyKind::Chaser,
+            FORMATION_VELOCITY,
+        );
+        let (direction, duration) = match slot.path {
+            EntryPath::Chase => continue,
+            EntryPath::Sweep { duration } => (heading, duration),
+            EntryPath::Converge { duration } => ((anchor - position).normalize_or_zero(), duration),
+        };
+        commands.entity(entity).insert(Entering {
+            velocity: (direction * FORMATION_VELOCITY).extend(0.0),
+            timer: Timer::from_seconds(duration, false),
+        });
+    }
+}
+
+/// Random point just outside one of the arena's edges, along with the heading into the arena.
+fn edge_anchor(rng: &mut impl Rng) -> (Vec2, Vec2) {
+    let heading = *[Vec2::X, -Vec2::X, Vec2::Y, -Vec2::Y]
+        .choose(rng)
+        .unwrap_or(&Vec2::X);
+    // somewhere along the middle half of the edge
+    let along = Vec2::new(heading.y, -heading.x) * BOUNDS * rng.gen_range(-0.25..0.25);
+    let edge = -heading * (BOUNDS / 2.0 + EDGE_MARGIN);
+    (edge + along, heading)
+}
+
+fn entry_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut entering: Query<(Entity, &mut Entering, &mut Transform), Without<Dying>>,
+) {
+    for (entity, mut entry, mut transform) in entering.iter_mut() {
+        transform.translation += entry.velocity * time_scale.enemy_step();
+        if entry
+            .timer
+            .tick(time_scale.enemy_step_duration())
+            .finished()
+        {
+            commands.entity(entity).remove::<Entering>();
+        }
+    }
+}
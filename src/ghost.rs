@@ -0,0 +1,234 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::Cli,
+    events::GhostOutlasted,
+    layers::RenderLayer,
+    loadout::{GameMode, Loadout},
+    palette::Palette,
+    rng::{self, Reseed, RunSeed},
+    save,
+    stats::RunStats,
+    AddSimulationSystem, Game, GameState, Player, TimeScale,
+};
+
+const GHOSTS_FILE: &str = "ghosts";
+/// Most seeds a ghost is kept for, the ones raced longest ago make room for new ones.
+const MAX_GHOSTS: usize = 10;
+/// Simulated seconds between the positions recorded for a ghost.
+const SAMPLE_INTERVAL: f32 = 0.1;
+/// Seconds a ghost takes to fade out once its run is over.
+const FADE_DURATION: f32 = 1.0;
+const GHOST_ALPHA: f32 = 0.3;
+/// Above everything else on the floor, and below the enemies it shouldn't hide.
+const GHOST_DEPTH: f32 = 0.9;
+
+/// Best run played on a seed, with the way the player went through it.
+#[derive(Serialize, Deserialize)]
+struct GhostRun {
+    seed: u64,
+    mode: GameMode,
+    score: usize,
+    /// position and facing, in milliradians, every `SAMPLE_INTERVAL`
+    path: Vec<(i16, i16, i16)>,
+}
+
+/// Ghosts of the best runs played on daily seeds and on the seeds given on the command line,
+/// persisted between sessions. Runs on a fresh seed can't be raced again and don't leave one.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Ghosts {
+    /// the ghost raced most recently last
+    runs: Vec<GhostRun>,
+}
+
+impl save::Versioned for Ghosts {
+    const VERSION: u32 = 1;
+}
+
+impl Ghosts {
+    fn position(&self, seed: u64, mode: GameMode) -> Option<usize> {
+        self.runs
+            .iter()
+            .position(|run| run.seed == seed && run.mode == mode)
+    }
+}
+
+/// The way the player went through the current run so far.
+#[derive(Default)]
+struct GhostRecording {
+    path: Vec<(i16, i16, i16)>,
+    /// simulated seconds until the next position is recorded
+    next_sample: f32,
+}
+
+/// Translucent copy of the player going through the best run on the same seed, for them to race.
+#[derive(Component)]
+struct Ghost {
+    path: Vec<(i16, i16, i16)>,
+    score: usize,
+    /// simulated seconds into the run
+    clock: f32,
+}
+
+/// Races the player against a ghost of their best run on the seed they play, in the same mode,
+/// showing where they were at the same point and disappearing where that run ended. Runs on the
+/// day's seed race the best run of the day.
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<Ghosts>(GHOSTS_FILE))
+            .init_resource::<GhostRecording>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(spawn_ghost.after(Reseed)),
+            )
+            .add_simulation_system(record_system)
+            .add_simulation_system(ghost_system)
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(store_ghost));
+    }
+}
+
+fn spawn_ghost(
+    mut commands: Commands,
+    seed: Res<RunSeed>,
+    loadout: Res<Loadout>,
+    palette: Res<Palette>,
+    mut ghosts: ResMut<Ghosts>,
+    mut recording: ResMut<GhostRecording>,
+) {
+    *recording = GhostRecording::default();
+    let index = match ghosts.position(seed.0, loadout.mode) {
+        Some(index) => index,
+        None => return,
+    };
+    // raced again, so it's the last to make room for a new one
+    ghosts.runs[index..].rotate_left(1);
+    let run = ghosts.runs.last().unwrap();
+    let mut color = palette.player.unwrap_or_else(|| loadout.ship.color());
+    color.set_a(GHOST_ALPHA);
+    palette
+        .spawn_sprite(
+            &mut commands,
+            RenderLayer::Decals,
+            color,
+            loadout.ship.size(),
+            Transform::from_xyz(0.0, 0.0, GHOST_DEPTH),
+        )
+        .insert(Ghost {
+            path: run.path.clone(),
+            score: run.score,
+            clock: 0.0,
+        });
+}
+
+fn record_system(
+    state: Res<State<GameState>>,
+    time_scale: Res<TimeScale>,
+    player: Query<&Transform, With<Player>>,
+    mut recording: ResMut<GhostRecording>,
+) {
+    // the tutorial isn't a run to race
+    if *state.current() != GameState::Playing {
+        return;
+    }
+    let transform = match player.get_single() {
+        Ok(transform) => transform,
+        Err(_) => return,
+    };
+    recording.next_sample -= time_scale.step();
+    if recording.next_sample > 0.0 {
+        return;
+    }
+    recording.next_sample += SAMPLE_INTERVAL;
+    let facing = transform.rotation * Vec3::Y;
+    let angle = (-facing.x).atan2(facing.y);
+    recording.path.push((
+        transform.translation.x.round() as i16,
+        transform.translation.y.round() as i16,
+        (angle * 1000.0).round() as i16,
+    ));
+}
+
+/// Moves the ghost along its path in step with the run, and fades it out once its run is over.
+fn ghost_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut ghost: Query<(Entity, &mut Ghost, &mut Transform, &mut Sprite)>,
+    mut outlasted: EventWriter<GhostOutlasted>,
+) {
+    let (entity, mut ghost, mut transform, mut sprite) = match ghost.get_single_mut() {
+        Ok(ghost) => ghost,
+        Err(_) => return,
+    };
+    let was_running = ghost.clock < duration(&ghost.path);
+    ghost.clock += time_scale.step();
+    let over = ghost.clock - duration(&ghost.path);
+    if over >= 0.0 {
+        if was_running {
+            outlasted.send(GhostOutlasted { score: ghost.score });
+        }
+        if over >= FADE_DURATION {
+            commands.entity(entity).despawn_recursive();
+            return;
+        }
+        sprite
+            .color
+            .set_a(GHOST_ALPHA * (1.0 - over / FADE_DURATION));
+    }
+    let sample = (ghost.clock / SAMPLE_INTERVAL).min((ghost.path.len() - 1) as f32);
+    let (from, to) = (sample.floor() as usize, sample.ceil() as usize);
+    let (position, angle) = lerp(ghost.path[from], ghost.path[to], sample.fract());
+    transform.translation = position.extend(transform.translation.z);
+    transform.rotation = Quat::from_rotation_z(angle);
+}
+
+/// Simulated seconds the run the path was recorded on lasted.
+fn duration(path: &[(i16, i16, i16)]) -> f32 {
+    path.len().saturating_sub(1) as f32 * SAMPLE_INTERVAL
+}
+
+/// Position and facing `t` of the way from one recorded sample to the next.
+fn lerp(from: (i16, i16, i16), to: (i16, i16, i16), t: f32) -> (Vec2, f32) {
+    let (from_angle, to_angle) = (from.2 as f32 / 1000.0, to.2 as f32 / 1000.0);
+    // the short way around, rather than spinning through a full turn where the angle wraps
+    let turn = (to_angle - from_angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU)
+        - std::f32::consts::PI;
+    let position =
+        Vec2::new(from.0 as f32, from.1 as f32).lerp(Vec2::new(to.0 as f32, to.1 as f32), t);
+    (position, from_angle + turn * t)
+}
+
+/// Keeps the run that just ended as the seed's ghost if it beat the one before. Assisted runs are
+/// kept out of it, the same as out of the records.
+fn store_ghost(
+    cli: Res<Cli>,
+    seed: Res<RunSeed>,
+    loadout: Res<Loadout>,
+    game: Res<Game>,
+    stats: Res<RunStats>,
+    mut ghosts: ResMut<Ghosts>,
+    mut recording: ResMut<GhostRecording>,
+) {
+    let path = std::mem::take(&mut recording.path);
+    if stats.assisted || !rng::is_repeatable(&cli, &loadout) || path.len() < 2 {
+        return;
+    }
+    let best = ghosts.position(seed.0, loadout.mode);
+    if let Some(best) = best {
+        if ghosts.runs[best].score >= game.score {
+            return;
+        }
+        ghosts.runs.remove(best);
+    }
+    ghosts.runs.push(GhostRun {
+        seed: seed.0,
+        mode: loadout.mode,
+        score: game.score,
+        path,
+    });
+    let excess = ghosts.runs.len().saturating_sub(MAX_GHOSTS);
+    ghosts.runs.drain(..excess);
+    save::store(GHOSTS_FILE, &*ghosts);
+}
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+
+use crate::{
+    abilities::Focus, events::Grazed, layers::RenderLayer, AddSimulationSystem, Game, Player,
+    TimeScale,
+};
+
+/// Points scored for every bullet grazed.
+const GRAZE_SCORE: usize = 1;
+/// Fraction of the focus meter a graze refills.
+const GRAZE_FOCUS: f32 = 0.05;
+const SPARKS: usize = 4;
+const SPARK_SIZE: f32 = 3.0;
+const SPARK_SPEED: f32 = 160.0;
+const SPARK_LIFETIME: f32 = 0.25;
+
+/// Particle thrown off where a bullet grazed the player.
+#[derive(Component)]
+struct Spark {
+    velocity: Vec3,
+    lifetime: Timer,
+}
+
+pub struct GrazePlugin;
+
+impl Plugin for GrazePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(graze_system)
+            .add_simulation_system(spark_system);
+    }
+}
+
+/// Rewards every near miss with points and focus, and sparks flying off the player's side.
+fn graze_system(
+    mut commands: Commands,
+    mut game: ResMut<Game>,
+    mut player: Query<(&mut Focus, &Transform), With<Player>>,
+    mut grazed: EventReader<Grazed>,
+) {
+    let mut rng = thread_rng();
+    for graze in grazed.iter() {
+        game.score += GRAZE_SCORE;
+        let (mut focus, transform) = match player.get_single_mut() {
+            Ok(player) => player,
+            Err(_) => continue,
+        };
+        focus.refill(GRAZE_FOCUS);
+
+        // sparks fly off the side the bullet passed on, away from the player
+        let away = (graze.position - transform.translation)
+            .truncate()
+            .normalize_or_zero();
+        let away = away.y.atan2(away.x);
+        let position = transform.translation.lerp(graze.position, 0.5);
+        for _ in 0..SPARKS {
+            let angle = away + rng.gen_range(-0.6..0.6);
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: graze.color,
+                        custom_size: Some(Vec2::splat(SPARK_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(position.truncate().extend(0.0)),
+                    ..default()
+                })
+                .insert(RenderLayer::Fx)
+                .insert(Spark {
+                    velocity: Quat::from_rotation_z(angle)
+                        * Vec3::X
+                        * SPARK_SPEED
+                        * rng.gen_range(0.5..1.0),
+                    lifetime: Timer::from_seconds(SPARK_LIFETIME, false),
+                });
+        }
+    }
+}
+
+fn spark_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut sparks: Query<(Entity, &mut Spark, &mut Sprite, &mut Transform)>,
+) {
+    for (entity, mut spark, mut sprite, mut transform) in sparks.iter_mut() {
+        if spark.lifetime.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.translation += spark.velocity * time_scale.step();
+        sprite.color.set_a(spark.lifetime.percent_left());
+    }
+}
@@ -0,0 +1,460 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    bosses::Boss,
+    damage_player,
+    deaths::Dying,
+    events::{DamageSource, PlayerDamaged},
+    hit_flash::HitFlash,
+    layers::RenderLayer,
+    out_of_bounds,
+    palette::Palette,
+    perks::Bounce,
+    rng::GameRng,
+    settings::Settings,
+    waves::Wave,
+    zones,
+    zones::ZoneKind,
+    AddSimulationSystem, Bullet, Enemy, GameState, Health, Player, TimeScale, BOUNDS,
+};
+
+/// Wave from which asteroids start drifting through the arena.
+const ASTEROID_WAVE: usize = 2;
+/// Wave from which mines are laid.
+const MINE_WAVE: usize = 3;
+/// Wave from which laser sweeps fire.
+const LASER_WAVE: usize = 4;
+const ASTEROID_SPEED: f32 = 120.0;
+/// Hits an asteroid takes from bullets before breaking up.
+const ASTEROID_HEALTH: u32 = 3;
+/// Distance at which a mine starts its fuse.
+const MINE_TRIGGER_RADIUS: f32 = 60.0;
+/// Distance within which an exploding mine hurts the player and destroys enemies.
+const MINE_BLAST_RADIUS: f32 = 90.0;
+const MINE_FUSE: f32 = 0.5;
+const MAX_MINES: usize = 5;
+/// Seconds a laser is telegraphed before it fires.
+const LASER_WARNING: f32 = 1.5;
+/// Seconds a laser stays lethal once it fires.
+const LASER_DURATION: f32 = 0.4;
+const LASER_WIDTH: f32 = 24.0;
+
+/// Neutral rock drifting across the arena, hurting the player and enemies alike.
+#[derive(Component)]
+struct Asteroid {
+    velocity: Vec3,
+    radius: f32,
+    health: u32,
+}
+
+#[derive(Component)]
+struct Mine {
+    /// counts down once something comes close enough
+    fuse: Option<Timer>,
+}
+
+/// Beam across the whole arena along one axis, warning the player before it fires.
+#[derive(Component)]
+struct Laser {
+    warning: Timer,
+    active: Timer,
+    vertical: bool,
+    /// whether the beam already hurt the player this sweep
+    hit_player: bool,
+}
+
+impl Laser {
+    fn is_firing(&self) -> bool {
+        self.warning.finished()
+    }
+
+    /// Whether a point within `radius` of `position` is covered by the beam at `offset`.
+    fn covers(&self, offset: f32, position: Vec3, radius: f32) -> bool {
+        let coordinate = if self.vertical {
+            position.x
+        } else {
+            position.y
+        };
+        (coordinate - offset).abs() <= LASER_WIDTH / 2.0 + radius
+    }
+}
+
+/// Timers deciding when the next of each hazard appears, scaled by how far the run has come.
+struct HazardSpawner {
+    asteroid: Timer,
+    mine: Timer,
+    laser: Timer,
+}
+
+impl Default for HazardSpawner {
+    fn default() -> Self {
+        Self {
+            asteroid: Timer::from_seconds(6.0, true),
+            mine: Timer::from_seconds(8.0, true),
+            laser: Timer::from_seconds(12.0, true),
+        }
+    }
+}
+
+pub struct HazardsPlugin;
+
+impl Plugin for HazardsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HazardSpawner>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_spawner))
+            .add_simulation_system(hazard_spawn_system)
+            .add_simulation_system(asteroid_movement_system)
+            .add_simulation_system(asteroid_collision_system)
+            .add_simulation_system(mine_system)
+            .add_simulation_system(laser_system);
+    }
+}
+
+fn reset_spawner(mut spawner: ResMut<HazardSpawner>) {
+    *spawner = HazardSpawner::default();
+}
+
+fn hazard_spawn_system(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    time_scale: Res<TimeScale>,
+    wave: Res<Wave>,
+    palette: Res<Palette>,
+    mut spawner: ResMut<HazardSpawner>,
+    mut rng: ResMut<GameRng>,
+    mines: Query<&Mine>,
+) {
+    // the tutorial arena stays free of hazards
+    if *state.current() != GameState::Playing {
+        return;
+    }
+    let delta = time_scale.step_duration();
+    let rng = &mut rng.0;
+    let extents = BOUNDS / 2.0;
+
+    if wave.is_changed() {
+        // hazards come more often the further the run goes, down to a floor
+        let waves_in = wave.number as f32;
+        spawner
+            .asteroid
+            .set_duration(Duration::from_secs_f32((7.0 - 0.4 * waves_in).max(2.0)));
+        spawner
+            .mine
+            .set_duration(Duration::from_secs_f32((10.0 - 0.5 * waves_in).max(4.0)));
+        spawner
+            .laser
+            .set_duration(Duration::from_secs_f32((16.0 - 0.6 * waves_in).max(6.0)));
+    }
+
+    if wave.number >= ASTEROID_WAVE && spawner.asteroid.tick(delta).just_finished() {
+        // enter from a random point on the edge, aimed roughly across the arena
+        let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+        let (y, x) = angle.sin_cos();
+        let start = Vec3::new(x * extents.x, y * extents.y, 0.0);
+        let target = Vec3::new(
+            rng.gen_range(-extents.x..extents.x) / 2.0,
+            rng.gen_range(-extents.y..extents.y) / 2.0,
+            0.0,
+        );
+        let radius = rng.gen_range(14.0..28.0);
+        palette
+            .spawn_sprite(
+                &mut commands,
+                RenderLayer::Enemies,
+                palette.asteroid,
+                radius * 2.0,
+                Transform::from_translation(start),
+            )
+            .insert(Asteroid {
+                velocity: (target - start).normalize_or_zero() * ASTEROID_SPEED,
+                radius,
+                health: ASTEROID_HEALTH,
+            });
+    }
+
+    if wave.number >= MINE_WAVE
+        && spawner.mine.tick(delta).just_finished()
+        && mines.iter().count() < MAX_MINES
+    {
+        // lying on the floor, above everything else there
+        let position = Vec3::new(
+            rng.gen_range(-extents.x..extents.x) * 0.9,
+            rng.gen_range(-extents.y..extents.y) * 0.9,
+            0.5,
+        );
+        palette
+            .spawn_sprite(
+                &mut commands,
+                RenderLayer::Decals,
+                palette.mine,
+                12.0,
+                Transform::from_translation(position),
+            )
+            .insert(Mine { fuse: None });
+    }
+
+    if wave.number >= LASER_WAVE && spawner.laser.tick(delta).just_finished() {
+        let vertical = rng.gen_bool(0.5);
+        let (offset, size) = if vertical {
+            (
+                rng.gen_range(-extents.x..extents.x),
+                Vec2::new(LASER_WIDTH, BOUNDS.y),
+            )
+        } else {
+            (
+                rng.gen_range(-extents.y..extents.y),
+                Vec2::new(BOUNDS.x, LASER_WIDTH),
+            )
+        };
+        let translation = if vertical {
+            Vec3::new(offset, 0.0, 0.4)
+        } else {
+            Vec3::new(0.0, offset, 0.4)
+        };
+        // a faint warning line until the beam fires
+        let mut warning_color = palette.laser;
+        warning_color.set_a(0.2);
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: warning_color,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_translation(translation),
+                ..default()
+            })
+            .insert(RenderLayer::Fx)
+            .insert(Laser {
+                warning: Timer::from_seconds(LASER_WARNING, false),
+                active: Timer::from_seconds(LASER_DURATION, false),
+                vertical,
+                hit_player: false,
+            });
+    }
+}
+
+fn asteroid_movement_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut asteroids: Query<(Entity, &Asteroid, &mut Transform)>,
+) {
+    for (entity, asteroid, mut transform) in asteroids.iter_mut() {
+        transform.translation += asteroid.velocity * time_scale.step();
+        // leave some slack past the edge so asteroids can drift in from outside
+        if out_of_bounds(transform.translation * 0.9) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn asteroid_collision_system(
+    mut commands: Commands,
+    mut state: ResMut<State<GameState>>,
+    settings: Res<Settings>,
+    mut asteroids: Query<(Entity, &mut Asteroid, &Transform)>,
+    mut bullets: Query<
+        (Entity, &mut Bullet, &mut Transform, Option<&mut Bounce>),
+        Without<Asteroid>,
+    >,
+    // bosses weather every hazard
+    enemies: Query<
+        (Entity, &Transform),
+        (With<Enemy>, Without<Dying>, Without<Bullet>, Without<Boss>),
+    >,
+    mut player: Query<(Entity, &Player, &Transform, &mut Health), Without<Bullet>>,
+    mut player_damaged: EventWriter<PlayerDamaged>,
+) {
+    for (asteroid_entity, mut asteroid, asteroid_transform) in asteroids.iter_mut() {
+        let position = asteroid_transform.translation;
+
+        for (bullet_entity, mut bullet, mut bullet_transform, bounce) in bullets.iter_mut() {
+            if asteroid.health > 0
+                && bullet_transform.translation.distance(position) <= asteroid.radius
+            {
+                // bouncing bullets glance off the surface, pushed back out past it
+                let normal = (bullet_transform.translation - position)
+                    .truncate()
+                    .extend(0.0);
+                let bounced =
+                    bounce.is_some_and(|mut bounce| bounce.reflect(&mut bullet.direction, normal));
+                if bounced {
+                    let surface = position + normal.normalize_or_zero() * (asteroid.radius + 1.0);
+                    bullet_transform.translation =
+                        surface.truncate().extend(bullet_transform.translation.z);
+                } else {
+                    commands.entity(bullet_entity).despawn_recursive();
+                }
+                asteroid.health -= 1;
+                if asteroid.health > 0 {
+                    commands.entity(asteroid_entity).insert(HitFlash::default());
+                }
+            }
+        }
+        if asteroid.health == 0 {
+            commands.entity(asteroid_entity).despawn_recursive();
+            continue;
+        }
+
+        // enemies are crushed, but don't count as kills for the player
+        for (enemy, enemy_transform) in enemies.iter() {
+            if enemy_transform.translation.distance(position) <= asteroid.radius {
+                commands.entity(enemy).insert(Dying::default());
+            }
+        }
+
+        if let Ok((player_entity, player, player_transform, mut health)) = player.get_single_mut() {
+            if player_transform.translation.distance(position)
+                <= asteroid.radius + player.hit_radius
+            {
+                commands.entity(asteroid_entity).despawn_recursive();
+                if damage_player(
+                    &mut commands,
+                    &mut state,
+                    &settings,
+                    player_entity,
+                    &mut health,
+                    1.0,
+                    DamageSource::Asteroid,
+                    &mut player_damaged,
+                ) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn mine_system(
+    mut commands: Commands,
+    mut state: ResMut<State<GameState>>,
+    time_scale: Res<TimeScale>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+    mut mines: Query<(Entity, &mut Mine, &Transform, &mut Sprite)>,
+    bullets: Query<(Entity, &Transform), With<Bullet>>,
+    enemies: Query<(Entity, &Transform), (With<Enemy>, Without<Dying>, Without<Boss>)>,
+    mut player: Query<(Entity, &Transform, &mut Health), With<Player>>,
+    mut player_damaged: EventWriter<PlayerDamaged>,
+) {
+    let mut blasts = Vec::new();
+    for (mine_entity, mut mine, mine_transform, mut sprite) in mines.iter_mut() {
+        let position = mine_transform.translation;
+        let near = |translation: Vec3| translation.distance(position) <= MINE_TRIGGER_RADIUS;
+
+        // shooting a mine sets it off straight away
+        let shot = bullets
+            .iter()
+            .find(|(_, transform)| transform.translation.distance(position) <= 10.0);
+        if let Some((bullet, _)) = shot {
+            commands.entity(bullet).despawn_recursive();
+            commands.entity(mine_entity).despawn_recursive();
+            blasts.push(position);
+            continue;
+        }
+
+        if mine.fuse.is_none()
+            && (enemies
+                .iter()
+                .any(|(_, transform)| near(transform.translation))
+                || player
+                    .iter()
+                    .any(|(_, transform, _)| near(transform.translation)))
+        {
+            mine.fuse = Some(Timer::from_seconds(MINE_FUSE, false));
+            sprite.color = Color::WHITE;
+        }
+        if let Some(fuse) = &mut mine.fuse {
+            if fuse.tick(time_scale.step_duration()).finished() {
+                commands.entity(mine_entity).despawn_recursive();
+                blasts.push(position);
+            }
+        }
+    }
+
+    for blast in blasts {
+        // the ground keeps burning after the blast, for enemies and the player alike
+        zones::spawn(&mut commands, &palette, ZoneKind::Burning, blast, true);
+        for (enemy, transform) in enemies.iter() {
+            if transform.translation.distance(blast) <= MINE_BLAST_RADIUS {
+                commands.entity(enemy).insert(Dying::default());
+            }
+        }
+        if let Ok((entity, transform, mut health)) = player.get_single_mut() {
+            if transform.translation.distance(blast) <= MINE_BLAST_RADIUS
+                && damage_player(
+                    &mut commands,
+                    &mut state,
+                    &settings,
+                    entity,
+                    &mut health,
+                    1.0,
+                    DamageSource::Mine,
+                    &mut player_damaged,
+                )
+            {
+                return;
+            }
+        }
+    }
+}
+
+fn laser_system(
+    mut commands: Commands,
+    mut state: ResMut<State<GameState>>,
+    time_scale: Res<TimeScale>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+    mut lasers: Query<(Entity, &mut Laser, &Transform, &mut Sprite)>,
+    enemies: Query<(Entity, &Transform), (With<Enemy>, Without<Dying>, Without<Boss>)>,
+    mut player: Query<(Entity, &Player, &Transform, &mut Health)>,
+    mut player_damaged: EventWriter<PlayerDamaged>,
+) {
+    let delta = time_scale.step_duration();
+    for (laser_entity, mut laser, laser_transform, mut sprite) in lasers.iter_mut() {
+        if !laser.is_firing() {
+            if laser.warning.tick(delta).just_finished() {
+                sprite.color = palette.laser;
+            }
+            continue;
+        }
+        if laser.active.tick(delta).finished() {
+            commands.entity(laser_entity).despawn_recursive();
+            continue;
+        }
+
+        let offset = if laser.vertical {
+            laser_transform.translation.x
+        } else {
+            laser_transform.translation.y
+        };
+        for (enemy, transform) in enemies.iter() {
+            if laser.covers(offset, transform.translation, 0.0) {
+                commands.entity(enemy).insert(Dying::default());
+            }
+        }
+        if laser.hit_player {
+            continue;
+        }
+        if let Ok((entity, player_data, transform, mut health)) = player.get_single_mut() {
+            if laser.covers(offset, transform.translation, player_data.hit_radius) {
+                laser.hit_player = true;
+                if damage_player(
+                    &mut commands,
+                    &mut state,
+                    &settings,
+                    entity,
+                    &mut health,
+                    1.0,
+                    DamageSource::Laser,
+                    &mut player_damaged,
+                ) {
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Sender},
+    thread::{self, JoinHandle},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+        texture::BevyDefault,
+    },
+};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    imageops::{self, FilterType},
+    Delay, Frame, RgbaImage,
+};
+
+use crate::{
+    cli::Cli,
+    photo_mode::{CaptureUpdate, PhotoMode},
+    replay::{self, Playback},
+    submission::LastSubmission,
+    GameState, SimulationClock, BOUNDS, TIME_STEP,
+};
+
+/// Directory highlight clips are saved to.
+const CLIPS_DIR: &str = "clips";
+/// Seconds of a run at its end a clip covers.
+const CLIP_DURATION: f32 = 60.0;
+const FRAMES_PER_SECOND: f32 = 10.0;
+/// Size of a clip's frames relative to the arena.
+const CLIP_SCALE: f32 = 0.4;
+/// Encoder speed, from 1 for the best palettes to 30 for the fastest encoding.
+const ENCODER_SPEED: i32 = 10;
+/// Most fixed steps a run is played ahead by in a frame on its way to the clip's final minute.
+const SKIP_STEPS: u64 = 120;
+
+/// Fixed steps of a run a clip covers.
+fn clip_steps() -> u64 {
+    (CLIP_DURATION / TIME_STEP).round() as u64
+}
+
+/// Fixed steps between two frames of a clip.
+fn frame_steps() -> u32 {
+    (1.0 / (FRAMES_PER_SECOND * TIME_STEP)).round() as u32
+}
+
+/// Size of a clip's frames, even for the video encoder.
+fn clip_size() -> (u32, u32) {
+    let size = BOUNDS * CLIP_SCALE;
+    (size.x as u32 & !1, size.y as u32 & !1)
+}
+
+/// Encoder a clip's frames are written to, picked by the extension of the clip's file.
+enum ClipWriter {
+    Gif(GifEncoder<File>),
+    /// ffmpeg, taking the frames in as raw pixels on its standard input
+    Mp4(Child),
+}
+
+impl ClipWriter {
+    fn create(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        if path.extension().is_some_and(|extension| extension == "mp4") {
+            let (width, height) = clip_size();
+            let ffmpeg = Command::new("ffmpeg")
+                .args([
+                    "-loglevel",
+                    "error",
+                    "-y",
+                    "-f",
+                    "rawvideo",
+                    "-pix_fmt",
+                    "rgba",
+                ])
+                .args(["-s", &format!("{}x{}", width, height)])
+                .args(["-r", &FRAMES_PER_SECOND.to_string(), "-i", "-"])
+                .args(["-pix_fmt", "yuv420p"])
+                .arg(path)
+                .stdin(Stdio::piped())
+                .spawn()
+                .map_err(|err| {
+                    format!(
+                        "MP4 clips are encoded with ffmpeg, which can't be started: {}",
+                        err
+                    )
+                })?;
+            return Ok(ClipWriter::Mp4(ffmpeg));
+        }
+        let file = File::create(path).map_err(|err| err.to_string())?;
+        let mut encoder = GifEncoder::new_with_speed(file, ENCODER_SPEED);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|err| err.to_string())?;
+        Ok(ClipWriter::Gif(encoder))
+    }
+
+    fn write(&mut self, frame: RgbaImage) -> Result<(), String> {
+        match self {
+            ClipWriter::Gif(encoder) => {
+                let delay = Delay::from_numer_denom_ms(1000, FRAMES_PER_SECOND as u32);
+                encoder
+                    .encode_frame(Frame::from_parts(frame, 0, 0, delay))
+                    .map_err(|err| err.to_string())
+            }
+            ClipWriter::Mp4(ffmpeg) => ffmpeg
+                .stdin
+                .as_mut()
+                .ok_or_else(|| "ffmpeg isn't taking frames".to_string())
+                .and_then(|input| input.write_all(&frame).map_err(|err| err.to_string())),
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        match self {
+            // the GIF is closed off as its encoder is dropped
+            ClipWriter::Gif(_) => Ok(()),
+            ClipWriter::Mp4(mut ffmpeg) => {
+                // the end of its input is where the video ends
+                drop(ffmpeg.stdin.take());
+                match ffmpeg.wait() {
+                    Ok(status) if status.success() => Ok(()),
+                    Ok(status) => Err(format!("ffmpeg failed, {}", status)),
+                    Err(err) => Err(err.to_string()),
+                }
+            }
+        }
+    }
+}
+
+/// A clip being rendered from the run played back with `--highlight`.
+struct Export {
+    path: PathBuf,
+    /// image every camera renders into instead of a window
+    target: Handle<Image>,
+    /// frames go off to be encoded on the encoder's thread as they're captured
+    frames: Option<Sender<RgbaImage>>,
+    encoder: Option<JoinHandle<Result<(), String>>>,
+    /// a frame is on its way back from the render world
+    capturing: bool,
+}
+
+/// Another instance of the game rendering a clip of the last run, started from the summary
+/// screen.
+#[derive(Default)]
+struct ClipExport(Option<Child>);
+
+/// Highlight clips of the final minute of a run, rendered by playing its leaderboard submission
+/// back.
+///
+/// Pressing G once a run is over starts the game again with `--highlight`, which plays the run
+/// back without a window, with every camera rendering into an image. It runs ahead to the final
+/// minute and then takes a frame's worth of steps at a time, each frame captured by photo mode
+/// and encoded on a thread of its own before the run goes on. Assisted runs aren't submitted
+/// and so can't be clipped.
+pub struct HighlightsPlugin;
+
+impl Plugin for HighlightsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClipExport>()
+            .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(export_system));
+
+        let cli = app.world.resource::<Cli>();
+        if cli.highlight.is_none() {
+            return;
+        }
+        let path = cli.clip.clone().unwrap_or_else(clip_path);
+        let mut writer = ClipWriter::create(&path).unwrap_or_else(|err| {
+            eprintln!("can't write a clip to {}: {}", path.display(), err);
+            std::process::exit(1);
+        });
+        let (frames, captured) = mpsc::channel::<RgbaImage>();
+        let encoder = thread::spawn(move || {
+            let (width, height) = clip_size();
+            for frame in captured {
+                writer.write(imageops::resize(
+                    &frame,
+                    width,
+                    height,
+                    FilterType::Triangle,
+                ))?;
+            }
+            writer.finish()
+        });
+        let target = app
+            .world
+            .resource_mut::<Assets<Image>>()
+            .add(render_target());
+        app.insert_resource(Export {
+            path,
+            target,
+            frames: Some(frames),
+            encoder: Some(encoder),
+            capturing: false,
+        })
+        // nothing is stepped before it's paced for the clip
+        .insert_resource(SimulationClock {
+            fixed_steps: Some(0),
+            ..default()
+        })
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(record_system.after(CaptureUpdate)),
+        )
+        .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(finish_system));
+    }
+}
+
+/// Image the size of the arena the cameras render the clip's frames into.
+fn render_target() -> Image {
+    let size = Extent3d {
+        width: BOUNDS.x as u32,
+        height: BOUNDS.y as u32,
+        depth_or_array_layers: 1,
+    };
+    // the 2d pipelines only draw into targets of the default format
+    let mut image = Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::bevy_default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_SRC
+        | TextureUsages::COPY_DST
+        | TextureUsages::RENDER_ATTACHMENT;
+    image
+}
+
+/// Paces the run being played back for the clip: ahead to its final minute as fast as it goes,
+/// then a frame's worth of steps once the last frame came back, capturing what they rendered.
+fn record_system(
+    playback: Res<Playback>,
+    mut clock: ResMut<SimulationClock>,
+    mut photo_mode: ResMut<PhotoMode>,
+    mut export: ResMut<Export>,
+    mut cameras: Query<&mut Camera>,
+) {
+    // cameras spawned on the way render into the clip as well
+    for mut camera in cameras.iter_mut() {
+        camera.target = RenderTarget::Image(export.target.clone());
+    }
+    if let Some(frame) = photo_mode.take_capture() {
+        if let Some(frames) = &export.frames {
+            // the encoder only stops taking frames when it failed, which `finish_system` reports
+            let _ = frames.send(frame);
+        }
+        export.capturing = false;
+    }
+
+    let ahead = playback.steps_left().saturating_sub(clip_steps());
+    clock.fixed_steps = Some(if ahead > 0 {
+        ahead.min(SKIP_STEPS) as u32
+    } else if export.capturing {
+        0
+    } else if clock.steps > 0 {
+        // the steps this frame took are rendered in it
+        export.capturing = photo_mode.capture_target(export.target.clone());
+        0
+    } else {
+        frame_steps()
+    });
+}
+
+/// Waits for the clip to be encoded once the run is over and exits with whether it was saved. A
+/// frame still being captured as the run ended is left out.
+fn finish_system(mut export: ResMut<Export>) {
+    // the encoder is done once no more frames can come
+    export.frames = None;
+    let result = match export.encoder.take() {
+        Some(encoder) => encoder
+            .join()
+            .unwrap_or_else(|_| Err("the encoder panicked".to_string())),
+        None => return,
+    };
+    replay::remove_save_dir();
+    match result {
+        Ok(()) => {
+            println!("saved highlight clip to {}", export.path.display());
+            std::process::exit(0);
+        }
+        Err(err) => {
+            eprintln!(
+                "failed to save highlight clip to {}: {}",
+                export.path.display(),
+                err
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Starts rendering a clip of the run that just ended when G is pressed.
+fn export_system(
+    keys: Res<Input<KeyCode>>,
+    last_submission: Res<LastSubmission>,
+    mut export: ResMut<ClipExport>,
+) {
+    if !keys.just_pressed(KeyCode::G) {
+        return;
+    }
+    if let Some(rendering) = &mut export.0 {
+        if let Ok(None) = rendering.try_wait() {
+            info!("a highlight clip is already being saved");
+            return;
+        }
+    }
+    let submission = match &last_submission.0 {
+        Some(submission) => submission,
+        None => {
+            info!("only runs submitted to the leaderboard can be clipped, assisted ones aren't");
+            return;
+        }
+    };
+    let path = clip_path();
+    let rendering = env::current_exe().and_then(|game| {
+        Command::new(game)
+            .arg("--highlight")
+            .arg(submission)
+            .arg("--clip")
+            .arg(&path)
+            .spawn()
+    });
+    match rendering {
+        Ok(rendering) => {
+            info!("saving a highlight clip to {}", path.display());
+            export.0 = Some(rendering);
+        }
+        Err(err) => warn!("failed to start saving a highlight clip: {}", err),
+    }
+}
+
+fn clip_path() -> PathBuf {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let dir = PathBuf::from(CLIPS_DIR);
+    (0..)
+        .map(|index| match index {
+            0 => dir.join(format!("madge-{}.gif", seconds)),
+            index => dir.join(format!("madge-{}-{}.gif", seconds, index)),
+        })
+        .find(|path| !path.exists())
+        .unwrap_or_default()
+}
@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+
+use crate::{culling::Culled, AddSimulationSystem, InsertIfAlive, TimeScale};
+
+/// Seconds a hit flashes for.
+const FLASH_DURATION: f32 = 0.12;
+/// How far the sprite squashes and stretches at the height of the pulse.
+const PULSE_STRENGTH: f32 = 0.3;
+
+/// Flashes a sprite white with a squash and stretch pulse while the timer runs. Inserting it
+/// again during a flash restarts it.
+#[derive(Component)]
+pub struct HitFlash(pub Timer);
+
+impl Default for HitFlash {
+    fn default() -> Self {
+        Self(Timer::from_seconds(FLASH_DURATION, false))
+    }
+}
+
+/// Color and scale the sprite returns to once the flash is over.
+#[derive(Component)]
+struct FlashOrigin {
+    color: Color,
+    scale: Vec3,
+}
+
+pub struct HitFlashPlugin;
+
+impl Plugin for HitFlashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(start_flash_system)
+            .add_simulation_system(hit_flash_system);
+    }
+}
+
+fn start_flash_system(
+    mut commands: Commands,
+    flashed: Query<(Entity, &Sprite, &Transform), (Added<HitFlash>, Without<FlashOrigin>)>,
+) {
+    for (entity, sprite, transform) in flashed.iter() {
+        commands.add(InsertIfAlive(
+            entity,
+            FlashOrigin {
+                color: sprite.color,
+                scale: transform.scale,
+            },
+        ));
+    }
+}
+
+fn hit_flash_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    culled: Res<Culled>,
+    mut flashed: Query<(
+        Entity,
+        &mut HitFlash,
+        &FlashOrigin,
+        &mut Sprite,
+        &mut Transform,
+    )>,
+) {
+    for (entity, mut flash, origin, mut sprite, mut transform) in flashed.iter_mut() {
+        if flash.0.tick(time_scale.step_duration()).finished() {
+            sprite.color = origin.color;
+            transform.scale = origin.scale;
+            commands
+                .entity(entity)
+                .remove::<HitFlash>()
+                .remove::<FlashOrigin>();
+            continue;
+        }
+        // a flash out of view still runs its course, for the sprite to be left as it was
+        if culled.contains(entity) {
+            continue;
+        }
+        sprite.color = Color::WHITE;
+        // widens and flattens, peaking halfway through the flash
+        let pulse = (flash.0.percent() * std::f32::consts::PI).sin() * PULSE_STRENGTH;
+        transform.scale = origin.scale * Vec3::new(1.0 + pulse, 1.0 - pulse, 1.0);
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    deaths::Dying, elements::Wounds, layers::RenderLayer, palette::Palette, rng::GameRng,
+    settings::Settings, AddSimulationSystem, Enemy, EnemyKind, TimeScale, TimerConfig,
+};
+
+/// Wave from which hives join the enemies spawned.
+const HIVE_WAVE: usize = 5;
+/// Chance for an enemy spawned from `HIVE_WAVE` on, that isn't any other special kind, to be a
+/// hive.
+const HIVE_CHANCE: f64 = 0.04;
+const HIVE_SIZE: f32 = 40.0;
+const HIVE_VELOCITY: f32 = 45.0;
+/// Hits a hive takes to go down.
+const HIVE_HEALTH: u32 = 12;
+pub const HIVE_SCORE: usize = 10;
+/// Seconds between the swarms a hive lets out, and how many swarmlings are in each.
+const SWARM_INTERVAL: f32 = 4.0;
+const SWARM_SIZE: usize = 3;
+/// Swarmlings spawned on the spawner's behalf it hasn't skipped yet at which hives hold off.
+const MAX_SPAWN_DEBT: usize = 12;
+const SWARMLING_SIZE: f32 = 10.0;
+const SWARMLING_VELOCITY: f32 = 300.0;
+
+/// Slow, sturdy enemy that keeps letting out swarms of small, fast enemies while it's alive.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Hive {
+    health: u32,
+    swarm: Timer,
+}
+
+impl Hive {
+    /// Deals hits to the hive, returning whether they were the last it could take.
+    pub fn take_hits(&mut self, hits: u32) -> bool {
+        self.health = self.health.saturating_sub(hits);
+        self.health == 0
+    }
+}
+
+pub struct HivesPlugin;
+
+impl Plugin for HivesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(swarm_system);
+    }
+}
+
+/// Whether the next enemy spawned on the given wave should be a hive.
+pub fn roll_hive(wave: usize, rng: &mut impl Rng) -> bool {
+    wave >= HIVE_WAVE && rng.gen_bool(HIVE_CHANCE)
+}
+
+pub fn spawn(commands: &mut Commands, palette: &Palette, position: Vec3) {
+    palette
+        .spawn_sprite(
+            commands,
+            RenderLayer::Enemies,
+            palette.hive,
+            HIVE_SIZE,
+            Transform::from_translation(position)
+                .with_rotation(Quat::from_rotation_z(palette.enemy_rotation)),
+        )
+        .insert(Enemy {
+            kind: EnemyKind::Hive,
+            velocity: HIVE_VELOCITY,
+            size: HIVE_SIZE,
+        })
+        .insert(Wounds::default())
+        .insert(Hive {
+            health: HIVE_HEALTH,
+            swarm: Timer::from_seconds(SWARM_INTERVAL, true),
+        });
+}
+
+pub fn spawn_swarmling(commands: &mut Commands, palette: &Palette, position: Vec3) {
+    palette
+        .spawn_sprite(
+            commands,
+            RenderLayer::Enemies,
+            palette.hive,
+            SWARMLING_SIZE,
+            Transform::from_translation(position)
+                .with_rotation(Quat::from_rotation_z(palette.enemy_rotation)),
+        )
+        .insert(Enemy {
+            kind: EnemyKind::Swarmling,
+            velocity: SWARMLING_VELOCITY,
+            size: SWARMLING_SIZE,
+        })
+        .insert(Wounds::default());
+}
+
+/// Lets a swarm out of every hive whose time has come.
+///
+/// Swarmlings take the place of enemies the spawner would have made, it skips as many of its
+/// next spawns as hives let out. Hives hold off while the spawner is too far behind or the arena
+/// is full, so a wave with a few of them left alive doesn't keep growing.
+fn swarm_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+    mut rng: ResMut<GameRng>,
+    mut config: ResMut<TimerConfig>,
+    mut hives: Query<(&mut Hive, &Transform), Without<Dying>>,
+    enemies: Query<(), (With<Enemy>, Without<Dying>)>,
+) {
+    let mut crowd = enemies.iter().count();
+    for (mut hive, transform) in hives.iter_mut() {
+        if !hive
+            .swarm
+            .tick(time_scale.enemy_step_duration())
+            .just_finished()
+        {
+            continue;
+        }
+        if config.spawn_debt + SWARM_SIZE > MAX_SPAWN_DEBT
+            || crowd + SWARM_SIZE > settings.max_enemies
+        {
+            continue;
+        }
+        for _ in 0..SWARM_SIZE {
+            let angle = rng.0.gen::<f32>() * std::f32::consts::TAU;
+            let offset = Quat::from_rotation_z(angle) * Vec3::X * HIVE_SIZE / 2.0;
+            spawn_swarmling(&mut commands, &palette, transform.translation + offset);
+        }
+        config.spawn_debt += SWARM_SIZE;
+        crowd += SWARM_SIZE;
+    }
+}
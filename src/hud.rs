@@ -0,0 +1,291 @@
+use bevy::prelude::*;
+
+use crate::{
+    abilities::{Focus, Rewind},
+    beam::Beam,
+    loadout::Loadout,
+    locale::{Locale, Localized},
+    munitions::{WeaponSupply, WeaponTunings},
+    settings::Settings,
+    turrets::{Credits, TURRET_COST},
+    GameState, Player, UiFont,
+};
+
+const METER_WIDTH: f32 = 200.0;
+const FOCUS_COLOR: Color = Color::rgb(0.3, 0.6, 0.9);
+const FOCUS_ACTIVE_COLOR: Color = Color::rgb(0.6, 0.9, 1.0);
+const REWIND_COLOR: Color = Color::rgb(0.7, 0.45, 0.9);
+const REWIND_ACTIVE_COLOR: Color = Color::rgb(0.9, 0.75, 1.0);
+const ENERGY_COLOR: Color = Color::rgb(0.95, 0.75, 0.3);
+/// Color of the energy meter while it recharges after running dry, and of the heat or ammo gauge
+/// while the weapon can't fire.
+const OVERHEATED_COLOR: Color = Color::rgb(0.8, 0.25, 0.2);
+const HEAT_COLOR: Color = Color::rgb(0.95, 0.5, 0.2);
+const AMMO_COLOR: Color = Color::rgb(0.55, 0.8, 0.55);
+
+/// Root of the in-run overlay, removed along with everything else when the run ends.
+#[derive(Component)]
+struct Hud;
+
+#[derive(Component)]
+struct FocusMeterFill;
+
+#[derive(Component)]
+struct RewindMeterFill;
+
+#[derive(Component)]
+struct EnergyMeterFill;
+
+#[derive(Component)]
+struct SupplyMeterFill;
+
+#[derive(Component)]
+struct CreditsText;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        for state in [GameState::Playing, GameState::Tutorial] {
+            app.add_system_set(SystemSet::on_enter(state.clone()).with_system(spawn_hud))
+                .add_system_set(
+                    SystemSet::on_update(state)
+                        .with_system(focus_meter_system)
+                        .with_system(rewind_meter_system)
+                        .with_system(energy_meter_system)
+                        .with_system(supply_meter_system),
+                );
+        }
+        // credits are only earned in real runs
+        app.add_system_set(SystemSet::on_update(GameState::Playing).with_system(credits_system));
+    }
+}
+
+fn spawn_hud(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    locale: Res<Locale>,
+    loadout: Res<Loadout>,
+    settings: Res<Settings>,
+    tunings: Res<WeaponTunings>,
+    state: Res<State<GameState>>,
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(20.0),
+                    ..default()
+                },
+                size: Size::new(Val::Percent(100.0), Val::Auto),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(Hud)
+        .with_children(|parent| {
+            spawn_meter(
+                parent,
+                &font,
+                &locale,
+                "hud.focus",
+                FOCUS_COLOR,
+                FocusMeterFill,
+            );
+            spawn_meter(
+                parent,
+                &font,
+                &locale,
+                "hud.rewind",
+                REWIND_COLOR,
+                RewindMeterFill,
+            );
+            if loadout.weapon.is_beam() {
+                spawn_meter(
+                    parent,
+                    &font,
+                    &locale,
+                    "hud.energy",
+                    ENERGY_COLOR,
+                    EnergyMeterFill,
+                );
+            }
+            if let Some(supply) = tunings.supply(settings.munitions, loadout.weapon) {
+                spawn_meter(
+                    parent,
+                    &font,
+                    &locale,
+                    supply.label_key(),
+                    supply_color(&supply),
+                    SupplyMeterFill,
+                );
+            }
+            if *state.current() == GameState::Playing {
+                parent
+                    .spawn_bundle(TextBundle {
+                        style: Style {
+                            margin: Rect::all(Val::Px(8.0)),
+                            ..default()
+                        },
+                        text: Text::with_section(
+                            "",
+                            TextStyle {
+                                font: font.0.clone(),
+                                font_size: 18.0,
+                                color: Color::rgb(0.85, 0.85, 0.85),
+                            },
+                            default(),
+                        ),
+                        ..default()
+                    })
+                    .insert(CreditsText);
+            }
+        });
+}
+
+/// Spawns a labelled bar whose fill is marked with `fill`.
+fn spawn_meter(
+    parent: &mut ChildBuilder,
+    font: &UiFont,
+    locale: &Locale,
+    key: &'static str,
+    color: Color,
+    fill: impl Component,
+) {
+    parent
+        .spawn_bundle(TextBundle {
+            style: Style {
+                margin: Rect::all(Val::Px(8.0)),
+                ..default()
+            },
+            text: Text::with_section(
+                locale.get(key),
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 18.0,
+                    color: Color::rgb(0.85, 0.85, 0.85),
+                },
+                default(),
+            ),
+            ..default()
+        })
+        .insert(Localized(key));
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(METER_WIDTH), Val::Px(12.0)),
+                ..default()
+            },
+            color: Color::rgb(0.2, 0.2, 0.2).into(),
+            ..default()
+        })
+        .with_children(|bar| {
+            bar.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    ..default()
+                },
+                color: color.into(),
+                ..default()
+            })
+            .insert(fill);
+        });
+}
+
+fn focus_meter_system(
+    player: Query<&Focus, With<Player>>,
+    mut fill: Query<(&mut Style, &mut UiColor), With<FocusMeterFill>>,
+) {
+    let focus = match player.get_single() {
+        Ok(focus) => focus,
+        Err(_) => return,
+    };
+    for (mut style, mut color) in fill.iter_mut() {
+        style.size.width = Val::Percent(focus.meter() * 100.0);
+        *color = if focus.is_active() {
+            FOCUS_ACTIVE_COLOR
+        } else {
+            FOCUS_COLOR
+        }
+        .into();
+    }
+}
+
+fn rewind_meter_system(
+    player: Query<&Rewind, With<Player>>,
+    mut fill: Query<(&mut Style, &mut UiColor), With<RewindMeterFill>>,
+) {
+    let rewind = match player.get_single() {
+        Ok(rewind) => rewind,
+        Err(_) => return,
+    };
+    for (mut style, mut color) in fill.iter_mut() {
+        style.size.width = Val::Percent(rewind.meter() * 100.0);
+        *color = if rewind.is_active() {
+            REWIND_ACTIVE_COLOR
+        } else {
+            REWIND_COLOR
+        }
+        .into();
+    }
+}
+
+fn energy_meter_system(
+    player: Query<&Beam, With<Player>>,
+    mut fill: Query<(&mut Style, &mut UiColor), With<EnergyMeterFill>>,
+) {
+    let beam = match player.get_single() {
+        Ok(beam) => beam,
+        Err(_) => return,
+    };
+    for (mut style, mut color) in fill.iter_mut() {
+        style.size.width = Val::Percent(beam.energy() * 100.0);
+        *color = if beam.is_overheated() {
+            OVERHEATED_COLOR
+        } else {
+            ENERGY_COLOR
+        }
+        .into();
+    }
+}
+
+fn supply_color(supply: &WeaponSupply) -> Color {
+    if !supply.can_fire() {
+        OVERHEATED_COLOR
+    } else if supply.uses_ammo() {
+        AMMO_COLOR
+    } else {
+        HEAT_COLOR
+    }
+}
+
+fn supply_meter_system(
+    player: Query<&WeaponSupply, With<Player>>,
+    mut fill: Query<(&mut Style, &mut UiColor), With<SupplyMeterFill>>,
+) {
+    let supply = match player.get_single() {
+        Ok(supply) => supply,
+        Err(_) => return,
+    };
+    for (mut style, mut color) in fill.iter_mut() {
+        style.size.width = Val::Percent(supply.gauge() * 100.0);
+        *color = supply_color(supply).into();
+    }
+}
+
+fn credits_system(
+    locale: Res<Locale>,
+    credits: Res<Credits>,
+    mut text: Query<&mut Text, With<CreditsText>>,
+) {
+    for mut text in text.iter_mut() {
+        text.sections[0].value = locale.format(
+            "hud.credits",
+            &[("credits", &credits.0), ("cost", &TURRET_COST)],
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    collections::HashMap,
+    f32::consts::{FRAC_PI_2, FRAC_PI_4},
+};
+
+use bevy::{prelude::*, render::camera::Camera2d};
+
+use crate::{
+    culling, deaths::Dying, layers::RenderLayer, photo_mode::PhotoMode, Enemy, EnemyKind, GameState,
+};
+
+/// Distance inside the edges of the view indicators are kept at.
+const EDGE_INSET: f32 = 24.0;
+/// Distance past the edges of the view from which enemies are indicated, the indicators growing
+/// to their full size as the enemy closes in to the edge.
+const INDICATOR_RANGE: f32 = 800.0;
+/// Scale of the indicator of an enemy at the far end of `INDICATOR_RANGE`.
+const MIN_SCALE: f32 = 0.5;
+/// Factor the indicators of bosses are drawn larger by.
+const BOSS_SCALE: f32 = 1.6;
+const ARROW_WIDTH: f32 = 3.0;
+const SHAFT_LENGTH: f32 = 14.0;
+const BARB_LENGTH: f32 = 7.0;
+
+/// Arrow at the edge of the screen pointing toward an enemy the camera can't see.
+#[derive(Component)]
+struct Indicator {
+    enemy: Entity,
+}
+
+/// Points out the enemies approaching from out of view with arrows at the edge of the screen,
+/// the closer the enemy the larger the arrow, for the player not to be blindsided by them.
+pub struct IndicatorsPlugin;
+
+impl Plugin for IndicatorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::Playing).with_system(indicator_system));
+    }
+}
+
+fn indicator_system(
+    mut commands: Commands,
+    photo_mode: Res<PhotoMode>,
+    cameras: Query<(&Transform, &OrthographicProjection), (With<Camera2d>, Without<Indicator>)>,
+    enemies: Query<(Entity, &Enemy, &Transform, &Sprite), (Without<Dying>, Without<Indicator>)>,
+    mut indicators: Query<(Entity, &Indicator, &mut Transform), Without<Enemy>>,
+) {
+    let view = cameras.iter().find_map(|(camera, projection)| {
+        culling::view_rect(camera.translation.truncate(), projection)
+    });
+    // nothing to point out in a photo
+    let view = match view {
+        Some(view) if !photo_mode.is_active() => view,
+        _ => {
+            for (entity, _, _) in indicators.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            return;
+        }
+    };
+
+    let mut targets = enemies
+        .iter()
+        .filter_map(|(entity, enemy, transform, sprite)| {
+            let placement = place(&view, transform.translation.truncate(), enemy)?;
+            Some((entity, (placement, sprite.color)))
+        })
+        .collect::<HashMap<_, _>>();
+    for (entity, indicator, mut transform) in indicators.iter_mut() {
+        match targets.remove(&indicator.enemy) {
+            Some((placement, _)) => *transform = placement,
+            None => commands.entity(entity).despawn_recursive(),
+        }
+    }
+    for (enemy, (placement, color)) in targets {
+        spawn_indicator(&mut commands, enemy, placement, color);
+    }
+}
+
+/// Where the indicator of an enemy at `position` goes, rotated to point at it and scaled by how
+/// close it is, if it's out of the view but within `INDICATOR_RANGE` of it.
+fn place(view: &Rect<f32>, position: Vec2, enemy: &Enemy) -> Option<Transform> {
+    let reach = enemy.size / 2.0;
+    let center = Vec2::new(view.left + view.right, view.bottom + view.top) / 2.0;
+    let half_size = Vec2::new(view.right - view.left, view.top - view.bottom) / 2.0;
+    let offset = position - center;
+    if offset.x.abs() - reach <= half_size.x && offset.y.abs() - reach <= half_size.y {
+        return None;
+    }
+    // how far along the offset the edge is, of the view and of where the indicators go
+    let along = |half_size: Vec2| (half_size / offset.abs()).min_element();
+    let edge = center + offset * along(half_size);
+    let distance = position.distance(edge) - reach;
+    if distance > INDICATOR_RANGE {
+        return None;
+    }
+    let inset = (half_size - Vec2::splat(EDGE_INSET)).max(Vec2::ZERO);
+    let mut scale = MIN_SCALE + (1.0 - MIN_SCALE) * (1.0 - distance.max(0.0) / INDICATOR_RANGE);
+    if enemy.kind == EnemyKind::Boss {
+        scale *= BOSS_SCALE;
+    }
+    Some(
+        Transform::from_translation((center + offset * along(inset)).extend(0.0))
+            .with_rotation(Quat::from_rotation_z(offset.y.atan2(offset.x) - FRAC_PI_2))
+            .with_scale(Vec3::new(scale, scale, 1.0)),
+    )
+}
+
+/// Spawns an arrow pointing up in its own frame, the barbs of its head meeting at the tip of the
+/// shaft.
+fn spawn_indicator(commands: &mut Commands, enemy: Entity, transform: Transform, color: Color) {
+    let tip = SHAFT_LENGTH / 2.0;
+    let barb = |side: f32| SpriteBundle {
+        sprite: Sprite {
+            color,
+            custom_size: Some(Vec2::new(ARROW_WIDTH, BARB_LENGTH)),
+            ..default()
+        },
+        transform: Transform::from_translation(
+            (Vec2::new(side, -1.0) * BARB_LENGTH / 2.0 * FRAC_PI_4.sin() + Vec2::Y * tip)
+                .extend(0.0),
+        )
+        .with_rotation(Quat::from_rotation_z(side * FRAC_PI_4)),
+        ..default()
+    };
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::new(ARROW_WIDTH, SHAFT_LENGTH)),
+                ..default()
+            },
+            transform,
+            ..default()
+        })
+        .insert(RenderLayer::Ui)
+        .insert(Indicator { enemy })
+        .with_children(|parent| {
+            parent.spawn_bundle(barb(-1.0));
+            parent.spawn_bundle(barb(1.0));
+        });
+}
@@ -0,0 +1,317 @@
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    music::Music,
+    replay::Playback,
+    touch::{TouchControls, TouchInput},
+    AddSimulationSystem, GameState, TIME_STEP,
+};
+
+/// Seconds a press waits for its action to be possible before it's dropped, a few simulation
+/// steps.
+const BUFFER_WINDOW: f32 = 0.1;
+/// Seconds the directions last steered in still count for once they're let go of, for a dash
+/// taken just as the keys come up to still go that way.
+const COYOTE_WINDOW: f32 = 0.1;
+/// Keys steering the player.
+const DIRECTION_KEYS: [KeyCode; 4] = [KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right];
+/// Keys the simulation reads, each a bit of a step's input in this order.
+pub const RECORDED_KEYS: [KeyCode; 13] = [
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Q,
+    KeyCode::E,
+    KeyCode::X,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::T,
+    KeyCode::R,
+    KeyCode::LShift,
+    KeyCode::RShift,
+];
+/// Bit of a step's input set on the first step after a beat of the soundtrack landed.
+const BEAT: u16 = 1 << RECORDED_KEYS.len();
+
+/// Action whose press is held on to until it can be taken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferedAction {
+    Dash,
+    Bash,
+    Bomb,
+}
+
+impl BufferedAction {
+    const ALL: [BufferedAction; 3] = [
+        BufferedAction::Dash,
+        BufferedAction::Bash,
+        BufferedAction::Bomb,
+    ];
+
+    fn key(&self) -> KeyCode {
+        match self {
+            BufferedAction::Dash => KeyCode::X,
+            BufferedAction::Bash => KeyCode::C,
+            BufferedAction::Bomb => KeyCode::B,
+        }
+    }
+}
+
+/// Actions pressed within the last `BUFFER_WINDOW` that weren't taken yet. A press can come while
+/// its action is still cooling down, and it's taken on the first step the action can be.
+#[derive(Default)]
+pub struct InputBuffer {
+    /// with the seconds since they were pressed
+    pressed: Vec<(BufferedAction, f32)>,
+    /// bits of the direction keys last held, with the seconds since they were let go of
+    steered: (u16, f32),
+}
+
+impl InputBuffer {
+    /// Whether the action was pressed recently, using the press up.
+    pub fn take(&mut self, action: BufferedAction) -> bool {
+        let index = self
+            .pressed
+            .iter()
+            .position(|(pressed, _)| *pressed == action);
+        index.map(|index| self.pressed.remove(index)).is_some()
+    }
+
+    /// Way the player steers this step, not normalized, or the way they steered if they let go of
+    /// the direction keys within the last `COYOTE_WINDOW`.
+    pub fn steering(&self, input: &StepInput) -> Vec3 {
+        let (steered, released) = self.steered;
+        let bits = match input.bits & direction_bits() {
+            0 if released <= COYOTE_WINDOW => steered,
+            held => held,
+        };
+        DIRECTION_KEYS
+            .iter()
+            .zip([Vec3::Y, -Vec3::Y, -Vec3::X, Vec3::X])
+            .filter(|(&key, _)| bits & key_bit(key) != 0)
+            .fold(Vec3::ZERO, |steering, (_, direction)| steering + direction)
+    }
+}
+
+/// Where the touch controls' stick and aim were on a step, rounded for the run to play back from
+/// its recording exactly as it was played.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TouchAxes {
+    /// how far the stick is pushed in each direction, in 127ths
+    stick: [i8; 2],
+    /// point in the arena the ship turns towards, to the nearest unit
+    aim: Option<[i16; 2]>,
+}
+
+impl TouchAxes {
+    fn of(touch: &TouchControls) -> Self {
+        let stick = touch.movement.clamp(Vec2::splat(-1.0), Vec2::ONE) * i8::MAX as f32;
+        Self {
+            stick: [stick.x.round() as i8, stick.y.round() as i8],
+            aim: touch
+                .aim
+                .map(|aim| [aim.x.round() as i16, aim.y.round() as i16]),
+        }
+    }
+
+    /// The axes as bytes, for the recording's hash.
+    pub fn to_bytes(self) -> [u8; 7] {
+        let [x, y] = self.stick;
+        let [aim_x, aim_y] = self.aim.unwrap_or_default().map(i16::to_le_bytes);
+        [
+            x as u8,
+            y as u8,
+            self.aim.is_some() as u8,
+            aim_x[0],
+            aim_x[1],
+            aim_y[0],
+            aim_y[1],
+        ]
+    }
+}
+
+/// The player's input as the simulation takes it in on the current step: every key held at any
+/// point since the step before, so a tap between two steps isn't missed, and the touch controls
+/// as they are at the step. Gameplay reads it rather than the keyboard and the touches for the
+/// run to be recorded exactly as it was played.
+#[derive(Default)]
+pub struct StepInput {
+    bits: u16,
+    previous: u16,
+    touch: TouchAxes,
+}
+
+impl StepInput {
+    /// The step's input as it's recorded, a bit for each of `RECORDED_KEYS` and then the beat.
+    pub fn bits(&self) -> u16 {
+        self.bits
+    }
+
+    pub fn pressed(&self, key: KeyCode) -> bool {
+        self.bits & key_bit(key) != 0
+    }
+
+    pub fn any_pressed(&self, keys: impl IntoIterator<Item = KeyCode>) -> bool {
+        keys.into_iter().any(|key| self.pressed(key))
+    }
+
+    pub fn just_pressed(&self, key: KeyCode) -> bool {
+        self.bits & !self.previous & key_bit(key) != 0
+    }
+
+    pub fn just_released(&self, key: KeyCode) -> bool {
+        self.previous & !self.bits & key_bit(key) != 0
+    }
+
+    /// Whether a beat of the soundtrack landed since the step before.
+    pub fn beat(&self) -> bool {
+        self.bits & BEAT != 0
+    }
+
+    /// The touch controls' stick and aim as they're recorded.
+    pub fn touch(&self) -> TouchAxes {
+        self.touch
+    }
+
+    /// How far the touch controls' stick is pushed in each direction, at most one long.
+    pub fn touch_movement(&self) -> Vec2 {
+        let [x, y] = self.touch.stick;
+        Vec2::new(x as f32, y as f32) / i8::MAX as f32
+    }
+
+    /// Point in the arena a touch is turning the ship towards.
+    pub fn touch_aim(&self) -> Option<Vec2> {
+        self.touch.aim.map(|[x, y]| Vec2::new(x as f32, y as f32))
+    }
+
+    fn advance(&mut self, bits: u16, touch: TouchAxes) {
+        self.previous = self.bits;
+        self.bits = bits;
+        self.touch = touch;
+    }
+}
+
+/// Bit of `key` in a step's input, none for a key the simulation doesn't read.
+fn key_bit(key: KeyCode) -> u16 {
+    RECORDED_KEYS
+        .iter()
+        .position(|&recorded| recorded == key)
+        .map_or(0, |bit| 1 << bit)
+}
+
+/// Bits of the keys steering the player.
+fn direction_bits() -> u16 {
+    DIRECTION_KEYS
+        .iter()
+        .fold(0, |bits, &key| bits | key_bit(key))
+}
+
+/// Names of the bits of a step's input, from the lowest.
+pub fn input_bit_names() -> Vec<String> {
+    RECORDED_KEYS
+        .iter()
+        .map(|key| format!("{:?}", key))
+        .chain(["TouchFiring".to_string(), "Beat".to_string()])
+        .collect()
+}
+
+/// Input of the frames since the last step, for the next one to take in.
+#[derive(Default)]
+struct PendingInput {
+    bits: u16,
+    /// beat of the soundtrack the last beat bit was set for
+    last_beat: u64,
+}
+
+/// Label of the system gathering each frame's input, for whatever presses keys on the player's
+/// behalf to go before.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InputBuffering;
+
+pub struct InputBufferPlugin;
+
+impl Plugin for InputBufferPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputBuffer>()
+            .init_resource::<StepInput>()
+            .init_resource::<PendingInput>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                gather_system
+                    .label(InputBuffering)
+                    .after(InputSystem)
+                    .after(TouchInput),
+            )
+            .add_simulation_system_first(step_input_system);
+    }
+}
+
+/// What's held right now.
+fn held_bits(keyboard_input: &Input<KeyCode>) -> u16 {
+    RECORDED_KEYS
+        .iter()
+        .enumerate()
+        .filter(|(_, &key)| keyboard_input.pressed(key))
+        .fold(0, |keys, (bit, _)| keys | 1 << bit)
+}
+
+/// Adds the frame's input to what the next step takes in.
+fn gather_system(
+    state: Res<State<GameState>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    music: Res<Music>,
+    mut pending: ResMut<PendingInput>,
+) {
+    // what's pressed in the menus isn't for the run
+    if !state.current().is_simulated() {
+        pending.bits = 0;
+        return;
+    }
+    pending.bits |= held_bits(&keyboard_input);
+    if music.beat() != pending.last_beat {
+        pending.last_beat = music.beat();
+        pending.bits |= BEAT;
+    }
+}
+
+/// Takes the step's input in, from the run being played back if there's one, buffers the actions
+/// just pressed and drops the ones that waited too long, and keeps to the last directions steered
+/// in. First in every step, for the rest of it to see the same input.
+fn step_input_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    touch: Res<TouchControls>,
+    playback: Option<ResMut<Playback>>,
+    mut pending: ResMut<PendingInput>,
+    mut input: ResMut<StepInput>,
+    mut buffer: ResMut<InputBuffer>,
+) {
+    match playback {
+        Some(mut playback) => {
+            let (bits, touch) = playback.next_step();
+            input.advance(bits, touch);
+        }
+        None => {
+            input.advance(pending.bits, TouchAxes::of(&touch));
+            // keys still held are held for the steps still due this frame too
+            pending.bits = held_bits(&keyboard_input);
+        }
+    }
+
+    for (_, age) in buffer.pressed.iter_mut() {
+        *age += TIME_STEP;
+    }
+    buffer.pressed.retain(|(_, age)| *age <= BUFFER_WINDOW);
+    for action in BufferedAction::ALL {
+        if input.just_pressed(action.key()) {
+            // pressed again, it waits from the latest press
+            buffer.take(action);
+            buffer.pressed.push((action, 0.0));
+        }
+    }
+    match input.bits & direction_bits() {
+        0 => buffer.steered.1 += TIME_STEP,
+        held => buffer.steered = (held, 0.0),
+    }
+}
@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    damage_player,
+    deaths::Dying,
+    elements::{Element, Wounds},
+    events::{DamageSource, PlayerDamaged},
+    layers::RenderLayer,
+    palette::Palette,
+    settings::Settings,
+    zones::Chilled,
+    AddSimulationSystem, Enemy, EnemyDamage, EnemyKind, GameState, Health, Player, Targets,
+    TimeScale,
+};
+
+/// Wave from which kamikazes join the enemies spawned.
+const KAMIKAZE_WAVE: usize = 3;
+/// Chance for an enemy spawned from `KAMIKAZE_WAVE` on, that isn't a splitter or sniper, to be a
+/// kamikaze.
+const KAMIKAZE_CHANCE: f64 = 0.1;
+const KAMIKAZE_SIZE: f32 = 16.0;
+/// Speed a kamikaze starts out at, speeding up by `ACCELERATION` every second up to
+/// `MAX_VELOCITY`.
+const START_VELOCITY: f32 = 80.0;
+const ACCELERATION: f32 = 120.0;
+const MAX_VELOCITY: f32 = 420.0;
+/// Distance to the player at which a kamikaze arms, going off `FUSE` seconds later.
+const ARM_RADIUS: f32 = 90.0;
+const FUSE: f32 = 0.6;
+const BLAST_RADIUS: f32 = 110.0;
+const BLAST_DAMAGE: f32 = 2.0;
+/// Seconds the flash of a blast takes to fade.
+const FLASH_DURATION: f32 = 0.25;
+
+/// Enemy that speeds up as it homes in on the player and blows up once it's close, or when it's
+/// shot down, hurting everything around it.
+#[derive(Component)]
+pub struct Kamikaze {
+    /// counts down once it's armed
+    fuse: Option<Timer>,
+}
+
+/// Light left by a kamikaze's blast for a moment, covering what it reached.
+#[derive(Component)]
+struct Flash(Timer);
+
+pub struct KamikazesPlugin;
+
+impl Plugin for KamikazesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(kamikaze_system)
+            .add_simulation_system(blast_system)
+            .add_simulation_system(flash_system);
+    }
+}
+
+/// Whether the next enemy spawned on the given wave should be a kamikaze.
+pub fn roll_kamikaze(wave: usize, rng: &mut impl Rng) -> bool {
+    wave >= KAMIKAZE_WAVE && rng.gen_bool(KAMIKAZE_CHANCE)
+}
+
+pub fn spawn(commands: &mut Commands, palette: &Palette, position: Vec3) {
+    palette
+        .spawn_sprite(
+            commands,
+            RenderLayer::Enemies,
+            palette.kamikaze,
+            KAMIKAZE_SIZE,
+            Transform::from_translation(position)
+                .with_rotation(Quat::from_rotation_z(palette.enemy_rotation)),
+        )
+        .insert(Enemy {
+            kind: EnemyKind::Kamikaze,
+            velocity: START_VELOCITY,
+            size: KAMIKAZE_SIZE,
+        })
+        .insert(Wounds::default())
+        .insert(Kamikaze { fuse: None });
+}
+
+/// Speeds kamikazes up toward the player, arms the ones that got close and sets them off once
+/// their fuse runs out, blinking faster the closer they are to going off.
+fn kamikaze_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    palette: Res<Palette>,
+    mut kamikazes: Query<
+        (
+            Entity,
+            &mut Enemy,
+            &mut Kamikaze,
+            &mut Transform,
+            &mut Sprite,
+            Option<&Chilled>,
+        ),
+        Without<Dying>,
+    >,
+    player: Query<&Transform, (With<Player>, Without<Kamikaze>)>,
+) {
+    let target = match player.get_single() {
+        Ok(player) => player.translation,
+        Err(_) => return,
+    };
+    let step = time_scale.enemy_step();
+    for (entity, mut enemy, mut kamikaze, mut transform, mut sprite, chilled) in
+        kamikazes.iter_mut()
+    {
+        enemy.velocity = (enemy.velocity + ACCELERATION * step).min(MAX_VELOCITY);
+        let speed = enemy.velocity * chilled.map_or(1.0, |chilled| chilled.0);
+        let direction = target - transform.translation;
+        transform.translation += direction.normalize_or_zero() * speed * step;
+
+        if kamikaze.fuse.is_none() && transform.translation.distance(target) <= ARM_RADIUS {
+            kamikaze.fuse = Some(Timer::from_seconds(FUSE, false));
+        }
+        if let Some(fuse) = &mut kamikaze.fuse {
+            if fuse.tick(time_scale.enemy_step_duration()).finished() {
+                // goes off by itself, which doesn't count as a kill
+                commands.entity(entity).insert(Dying::default());
+                continue;
+            }
+            let blinks = 2.0 + 10.0 * fuse.percent();
+            sprite.color = if (fuse.elapsed_secs() * blinks).fract() < 0.5 {
+                Color::WHITE
+            } else {
+                palette.kamikaze
+            };
+        }
+    }
+}
+
+/// Blows up every kamikaze that just died, however it happened, hurting the enemies and the
+/// player caught in the blast. Kamikazes caught in it go off in turn.
+fn blast_system(
+    mut state: ResMut<State<GameState>>,
+    settings: Res<Settings>,
+    blasts: Query<&Transform, (With<Kamikaze>, Added<Dying>)>,
+    mut enemies: Targets<Without<Player>>,
+    mut player: Query<(Entity, &Transform, &mut Health), With<Player>>,
+    mut damage: EnemyDamage,
+    mut player_damaged: EventWriter<PlayerDamaged>,
+) {
+    for blast in blasts.iter().map(|transform| transform.translation) {
+        let mut color = damage.palette.kamikaze;
+        color.set_a(0.5);
+        damage
+            .commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::splat(BLAST_RADIUS * 2.0)),
+                    ..default()
+                },
+                transform: Transform::from_translation(blast.truncate().extend(0.2))
+                    .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+                ..default()
+            })
+            .insert(RenderLayer::Fx)
+            .insert(Flash(Timer::from_seconds(FLASH_DURATION, false)));
+
+        for (entity, enemy, transform, mut elite, splitter) in enemies.iter_mut() {
+            let offset = transform.translation - blast;
+            if offset.length() > BLAST_RADIUS + enemy.size / 2.0 {
+                continue;
+            }
+            damage.hit(
+                entity,
+                enemy,
+                transform.translation,
+                elite.as_deref_mut(),
+                splitter,
+                offset.normalize_or_zero(),
+                Element::Fire,
+            );
+        }
+
+        if let Ok((entity, transform, mut health)) = player.get_single_mut() {
+            if transform.translation.distance(blast) <= BLAST_RADIUS
+                && damage_player(
+                    &mut damage.commands,
+                    &mut state,
+                    &settings,
+                    entity,
+                    &mut health,
+                    BLAST_DAMAGE,
+                    DamageSource::Enemy(EnemyKind::Kamikaze),
+                    &mut player_damaged,
+                )
+            {
+                return;
+            }
+        }
+    }
+}
+
+fn flash_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut flashes: Query<(Entity, &mut Flash, &mut Sprite)>,
+) {
+    for (entity, mut flash, mut sprite) in flashes.iter_mut() {
+        if flash.0.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        sprite.color.set_a(0.5 * flash.0.percent_left());
+    }
+}
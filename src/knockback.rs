@@ -0,0 +1,36 @@
+use bevy::prelude::*;
+
+use crate::{deaths::Dying, AddSimulationSystem, TimeScale};
+
+/// Fraction of its speed a knockback loses per second.
+const KNOCKBACK_DRAG: f32 = 6.0;
+/// Speed below which a knockback has worn off.
+const MIN_KNOCKBACK_SPEED: f32 = 10.0;
+
+/// Velocity an enemy was shoved with, on top of its own movement and slowing down until it's
+/// gone.
+#[derive(Component)]
+pub struct Knockback(pub Vec3);
+
+pub struct KnockbackPlugin;
+
+impl Plugin for KnockbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(knockback_system);
+    }
+}
+
+fn knockback_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut knocked: Query<(Entity, &mut Knockback, &mut Transform), Without<Dying>>,
+) {
+    let step = time_scale.enemy_step();
+    for (entity, mut knockback, mut transform) in knocked.iter_mut() {
+        transform.translation += knockback.0 * step;
+        knockback.0 *= (1.0 - KNOCKBACK_DRAG * step).max(0.0);
+        if knockback.0.length() < MIN_KNOCKBACK_SPEED {
+            commands.entity(entity).remove::<Knockback>();
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::{prelude::*, transform::TransformSystem};
+
+/// Range of z values each layer draws within.
+const LAYER_DEPTH: f32 = 1.0;
+
+/// What an entity in the arena is drawn as, deciding what it's drawn above and below.
+///
+/// Every layer has its own range of z values, above all of the layers before it. The z of an
+/// entity's transform only orders it among the rest of its layer, anywhere from 0 up to
+/// `LAYER_DEPTH`, which keeps the transforms the simulation works with flat. Children without a
+/// layer of their own are drawn relative to their parents, and children with one within it,
+/// wherever their parents are drawn.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RenderLayer {
+    /// the backdrop behind the arena
+    Background,
+    /// what lies on the arena's floor: walls, decorations, zones and debris
+    Decals,
+    Enemies,
+    /// the player and whatever fights alongside them
+    Player,
+    /// the player's and the enemies' shots
+    Bullets,
+    /// particles, trails and flashes, and the lighting over all of it
+    Fx,
+    /// text and markers shown in the arena rather than on the HUD
+    Ui,
+}
+
+impl RenderLayer {
+    /// Bottom of the layer's range of z values, every one of them in front of the camera's near
+    /// plane.
+    pub fn z(&self) -> f32 {
+        (*self as usize + 1) as f32 * LAYER_DEPTH
+    }
+}
+
+pub struct LayersPlugin;
+
+impl Plugin for LayersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(
+            CoreStage::PostUpdate,
+            layer_system.after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Lifts every entity with a layer, and the children drawn relative to it, into the layer's
+/// range of z values once their transforms have been propagated.
+fn layer_system(
+    layered: Query<(Entity, &RenderLayer, &Transform)>,
+    children: Query<&Children>,
+    unlayered: Query<&Transform, Without<RenderLayer>>,
+    mut globals: Query<&mut GlobalTransform>,
+) {
+    for (entity, layer, transform) in layered.iter() {
+        raise(
+            entity,
+            layer.z() + transform.translation.z,
+            &children,
+            &unlayered,
+            &mut globals,
+        );
+    }
+}
+
+/// Draws `entity` at `z`, and its children without a layer of their own relative to it.
+fn raise(
+    entity: Entity,
+    z: f32,
+    children: &Query<&Children>,
+    unlayered: &Query<&Transform, Without<RenderLayer>>,
+    globals: &mut Query<&mut GlobalTransform>,
+) {
+    if let Ok(mut global) = globals.get_mut(entity) {
+        global.translation.z = z;
+    }
+    let entity_children = match children.get(entity) {
+        Ok(entity_children) => entity_children,
+        Err(_) => return,
+    };
+    for &child in entity_children.iter() {
+        if let Ok(transform) = unlayered.get(child) {
+            let z = z + transform.translation.z;
+            raise(child, z, children, unlayered, globals);
+        }
+    }
+}
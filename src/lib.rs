@@ -0,0 +1,1539 @@
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+use std::time::Duration;
+
+use bevy::{
+    app::{PluginGroupBuilder, ScheduleRunnerPlugin, ScheduleRunnerSettings},
+    asset::AssetPlugin,
+    ecs::system::{Command, SystemParam},
+    math::const_vec2,
+    prelude::*,
+    render::settings::WgpuSettings,
+    winit::WinitPlugin,
+};
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+mod abilities;
+mod achievements;
+mod aggro;
+mod announcer;
+mod arena;
+mod assists;
+mod attract;
+#[cfg(feature = "fancy-graphics")]
+mod background;
+mod beam;
+/// Worlds crowded with enemies and bullets for the benchmarks in `benches/`, not part of the
+/// game. Each one holds only what its systems read and steps those systems alone, so a
+/// regression shows up in the system that caused it.
+#[doc(hidden)]
+pub mod bench;
+mod bosses;
+mod bot;
+mod cli;
+mod combo;
+mod culling;
+mod deaths;
+#[cfg(feature = "discord")]
+mod discord;
+mod display;
+mod drones;
+mod editor;
+mod elements;
+mod elites;
+mod enemy_bullets;
+mod event_log;
+mod events;
+mod formations;
+mod ghost;
+mod graze;
+mod hazards;
+mod highlights;
+mod hit_flash;
+mod hives;
+mod hud;
+mod indicators;
+mod input_buffer;
+mod kamikazes;
+mod knockback;
+mod layers;
+#[cfg(feature = "fancy-graphics")]
+mod lighting;
+mod loadout;
+mod locale;
+mod melee;
+mod menu;
+mod mobile;
+mod munitions;
+mod music;
+mod mutators;
+mod objectives;
+mod packs;
+mod palette;
+mod pause;
+mod perks;
+mod photo_mode;
+mod platform;
+mod population;
+#[cfg(feature = "fancy-graphics")]
+mod post_processing;
+mod replay;
+mod report;
+mod rng;
+mod rumble;
+mod save;
+mod scripting;
+mod settings;
+mod ship_select;
+mod snipers;
+mod splitters;
+mod stats;
+#[cfg(feature = "steam")]
+mod steam;
+mod stress;
+mod submission;
+#[cfg(feature = "telemetry")]
+mod telemetry;
+mod touch;
+mod trails;
+mod turrets;
+mod tutorial;
+mod visual_test;
+mod waves;
+mod zones;
+
+use abilities::{player_abilities, AbilitiesPlugin};
+use achievements::AchievementsPlugin;
+use aggro::{Aggro, AggroPlugin, Threat};
+use announcer::AnnouncerPlugin;
+use arena::{ArenaLayout, ArenaPlugin, SafeZone};
+use attract::AttractPlugin;
+use beam::{Beam, BeamPlugin};
+use bosses::{Boss, BossesPlugin, BOSS_SCORE};
+use bot::BotPlugin;
+use cli::Cli;
+use combo::ComboPlugin;
+use culling::CullingPlugin;
+use deaths::{DeathsPlugin, Dying};
+use display::DisplayPlugin;
+use drones::DronesPlugin;
+use editor::EditorPlugin;
+use elements::{Affinities, Element, ElementsPlugin, Wounds};
+use elites::{Elite, ElitesPlugin, Modifier};
+use enemy_bullets::EnemyBulletsPlugin;
+use event_log::EventLogPlugin;
+use events::{
+    BulletHit, DamageSource, EnemyKilled, EventsPlugin, PlayerDamaged, Provoked, ShotFired,
+};
+use formations::{Entering, Formations, FormationsPlugin};
+use ghost::GhostPlugin;
+use graze::GrazePlugin;
+use hazards::HazardsPlugin;
+use highlights::HighlightsPlugin;
+use hit_flash::{HitFlash, HitFlashPlugin};
+use hives::{Hive, HivesPlugin, HIVE_SCORE};
+use hud::HudPlugin;
+use indicators::IndicatorsPlugin;
+use input_buffer::{InputBufferPlugin, StepInput};
+use kamikazes::{Kamikaze, KamikazesPlugin};
+use knockback::KnockbackPlugin;
+use layers::{LayersPlugin, RenderLayer};
+use loadout::{GameMode, Loadout, LoadoutPlugin};
+use locale::LocalePlugin;
+use melee::{Melee, MeleePlugin};
+use menu::MenuPlugin;
+use mobile::{MobilePlugin, MOBILE};
+use munitions::{MunitionsPlugin, WeaponSupply, WeaponTunings};
+use music::MusicPlugin;
+use mutators::{Drift, MutatorsPlugin};
+use objectives::ObjectivesPlugin;
+use packs::PacksPlugin;
+use palette::{Palette, PalettePlugin};
+use pause::PausePlugin;
+use perks::{Bounce, Perks, PerksPlugin, Pierce};
+use photo_mode::{PhotoMode, PhotoModePlugin};
+use platform::PlatformPlugin;
+use population::PopulationPlugin;
+use replay::{Playback, ReplayPlugin};
+use report::ReportPlugin;
+use rng::{GameRng, RngPlugin};
+use rumble::RumblePlugin;
+use scripting::{ScriptSteering, ScriptingPlugin};
+use settings::{Settings, SettingsPlugin};
+use ship_select::ShipSelectPlugin;
+use snipers::{Sniper, SnipersPlugin};
+use splitters::Splitter;
+use stats::StatsPlugin;
+use stress::StressPlugin;
+use submission::SubmissionPlugin;
+use touch::TouchPlugin;
+use trails::{Trail, TrailsPlugin};
+use turrets::TurretsPlugin;
+use tutorial::TutorialPlugin;
+use visual_test::VisualTestPlugin;
+use waves::{Wave, WavesPlugin};
+use zones::{Chilled, FrostShot, ZoneKind, ZonesPlugin};
+
+const TIME_STEP: f32 = 1.0 / 60.0;
+/// How strongly the player draws enemies, every companion draws them less.
+const PLAYER_THREAT: f32 = 1.0;
+const BOUNDS: Vec2 = const_vec2!([1200.0, 640.0]);
+/// Bullets at least this fast leave a trail.
+const FAST_BULLET_VELOCITY: f32 = 1000.0;
+/// Side length of a regular enemy.
+const ENEMY_SIZE: f32 = 15.0;
+
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+enum GameState {
+    Menu,
+    Achievements,
+    Settings,
+    Packs,
+    Editor,
+    ShipSelect,
+    Loadout,
+    Tutorial,
+    Playing,
+    /// pushed on top of a run, which carries on where it was once this is popped
+    Paused,
+    GameOver,
+}
+
+impl GameState {
+    /// Whether the game simulation advances in this state.
+    fn is_simulated(&self) -> bool {
+        matches!(self, GameState::Tutorial | GameState::Playing)
+    }
+}
+
+/// Label of the system advancing the game simulation, at the start of every update as an
+/// exclusive system.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct Simulation;
+
+/// Label of the system counting the simulation's steps due in a frame.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct SimulationSteps;
+
+/// The engine's plugins with the content packs set up ahead of the asset server, for assets to be
+/// read through them.
+fn with_packs(group: &mut PluginGroupBuilder) -> &mut PluginGroupBuilder {
+    group.add_before::<AssetPlugin, _>(PacksPlugin)
+}
+
+/// Builds the game from the command line it was launched with and runs it until it's closed.
+pub fn run() {
+    let mut cli = Cli::parse();
+    let mut playback = cli.replay.as_ref().or(cli.highlight.as_ref()).map(|path| {
+        let submission = submission::read(path)
+            .unwrap_or_else(|err| Cli::command().error(ErrorKind::InvalidValue, err).exit());
+        if submission.version != env!("CARGO_PKG_VERSION") {
+            warn!(
+                "the run was played on version {}, it may not play back the same",
+                submission.version
+            );
+        }
+        Playback::new(submission, false)
+    });
+    if let Some(path) = &cli.verify {
+        match submission::verify(path) {
+            Some(submission) => playback = Some(Playback::new(submission, true)),
+            None => std::process::exit(1),
+        }
+        // the run is only played back to be checked
+        cli.headless = true;
+    }
+    if let Some(playback) = &playback {
+        // the run plays out from its own seed, and none of it ends up in the player's saves
+        cli.seed = Some(playback.seed());
+        cli.config = None;
+        save::redirect(&save::scratch_dir(replay::SAVE_PURPOSE));
+    }
+    if cli.visual_test.is_some() {
+        // the scenes only come out the same from the same seed and saves
+        cli.seed.get_or_insert(visual_test::SEED);
+        save::redirect(&save::scratch_dir(visual_test::SAVE_PURPOSE));
+    }
+    if cli.bot {
+        // the bot's runs shouldn't end up in the player's records
+        save::redirect(&save::scratch_dir(bot::SAVE_PURPOSE));
+    }
+    let headless = cli.headless;
+    let highlight = cli.highlight.is_some();
+    let initial_state = if cli.skip_menu
+        || cli.stress
+        || cli.bot
+        || cli.visual_test.is_some()
+        || playback.is_some()
+    {
+        GameState::Playing
+    } else {
+        GameState::Menu
+    };
+
+    let mut app = App::new();
+    app.insert_resource(cli).add_plugin(PlatformPlugin);
+    if let Some(playback) = playback {
+        app.insert_resource(playback);
+    }
+    if headless {
+        // without a renderer nothing needs a window, and without one the loop is driven here
+        app.insert_resource(WgpuSettings {
+            backends: None,
+            ..default()
+        })
+        .add_plugins_with(DefaultPlugins, |group| {
+            with_packs(group).disable::<WinitPlugin>()
+        })
+        .insert_resource(ScheduleRunnerSettings::run_loop(Duration::from_secs_f64(
+            1.0 / 60.0,
+        )))
+        .add_plugin(ScheduleRunnerPlugin);
+    } else if highlight {
+        // the clip's frames are rendered into an image, nothing is shown
+        app.add_plugins_with(DefaultPlugins, |group| {
+            with_packs(group).disable::<WinitPlugin>()
+        })
+        .insert_resource(ScheduleRunnerSettings::run_loop(Duration::from_secs_f64(
+            1.0 / 60.0,
+        )))
+        .add_plugin(ScheduleRunnerPlugin);
+    } else {
+        app.add_plugins_with(DefaultPlugins, with_packs);
+    }
+    app.add_plugin(SettingsPlugin)
+        .add_plugin(RngPlugin)
+        .add_plugin(LocalePlugin)
+        .add_plugin(PalettePlugin)
+        .add_plugin(LayersPlugin)
+        .add_plugin(CullingPlugin)
+        .add_plugin(EventsPlugin)
+        .add_plugin(ComboPlugin)
+        .add_plugin(StatsPlugin)
+        .add_plugin(WavesPlugin)
+        .add_plugin(AchievementsPlugin)
+        .add_plugin(MenuPlugin)
+        .add_plugin(ShipSelectPlugin)
+        .add_plugin(LoadoutPlugin)
+        // ahead of the registries built from the content packs, for it to pick the run's packs
+        .add_plugin(ReplayPlugin)
+        .add_plugin(InputBufferPlugin)
+        .add_plugin(AbilitiesPlugin)
+        .add_plugin(TutorialPlugin)
+        .add_plugin(HudPlugin)
+        .add_plugin(IndicatorsPlugin)
+        .add_plugin(ArenaPlugin)
+        .add_plugin(HazardsPlugin)
+        .add_plugin(ElitesPlugin)
+        .add_plugin(EventLogPlugin)
+        .add_plugin(GhostPlugin)
+        .add_plugin(HitFlashPlugin)
+        .add_plugin(DeathsPlugin)
+        .add_plugin(TrailsPlugin)
+        .add_plugin(BeamPlugin)
+        .add_plugin(MunitionsPlugin)
+        .add_plugin(ElementsPlugin)
+        .add_plugin(PerksPlugin)
+        .add_plugin(DronesPlugin)
+        .add_plugin(KnockbackPlugin)
+        .add_plugin(MeleePlugin)
+        .add_plugin(TurretsPlugin)
+        .add_plugin(ObjectivesPlugin)
+        .add_plugin(MutatorsPlugin)
+        .add_plugin(FormationsPlugin)
+        .add_plugin(EnemyBulletsPlugin)
+        .add_plugin(BossesPlugin)
+        .add_plugin(ScriptingPlugin)
+        .add_plugin(EditorPlugin)
+        .add_plugin(PhotoModePlugin)
+        .add_plugin(HighlightsPlugin)
+        .add_plugin(ReportPlugin)
+        .add_plugin(TouchPlugin)
+        .add_plugin(PausePlugin)
+        .add_plugin(MobilePlugin)
+        .add_plugin(RumblePlugin)
+        .add_plugin(DisplayPlugin)
+        .add_plugin(StressPlugin)
+        .add_plugin(PopulationPlugin)
+        .add_plugin(GrazePlugin)
+        .add_plugin(MusicPlugin)
+        .add_plugin(AnnouncerPlugin)
+        .add_plugin(ZonesPlugin)
+        .add_plugin(AggroPlugin)
+        .add_plugin(SnipersPlugin)
+        .add_plugin(KamikazesPlugin)
+        .add_plugin(HivesPlugin)
+        .add_plugin(SubmissionPlugin)
+        .add_plugin(BotPlugin)
+        .add_plugin(AttractPlugin)
+        .add_plugin(VisualTestPlugin)
+        .init_resource::<Game>()
+        .init_resource::<TimeScale>()
+        .init_resource::<SimulationClock>()
+        .init_resource::<SimulationSchedule>()
+        // the frame's time is taken by an exclusive system, which runs before these
+        .add_system_to_stage(CoreStage::First, clock_system.label(SimulationSteps))
+        .add_system(simulation_system.exclusive_system().label(Simulation))
+        .add_state(initial_state)
+        .add_startup_system(setup)
+        .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(setup_run))
+        .add_system_set(SystemSet::on_enter(GameState::Tutorial).with_system(setup_run))
+        .add_simulation_system(setup_spawn_enemy)
+        .add_simulation_system(player_movement_system)
+        .add_simulation_system(player_shooting_system)
+        .add_simulation_system(move_bullets_system)
+        .add_simulation_system(move_enemy_system)
+        .add_simulation_system(bullet_enemy_collision_system)
+        .add_simulation_system(enemy_player_collions_system)
+        .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(teardown))
+        .add_system_set(SystemSet::on_exit(GameState::Tutorial).with_system(teardown))
+        .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(restart_system));
+    // phones leave apps through the system's navigation
+    if !MOBILE {
+        app.add_system(bevy::input::system::exit_on_esc_system);
+    }
+    #[cfg(feature = "discord")]
+    app.add_plugin(discord::DiscordPlugin);
+    #[cfg(feature = "telemetry")]
+    app.add_plugin(telemetry::TelemetryPlugin);
+    #[cfg(feature = "fancy-graphics")]
+    app.add_plugin(lighting::LightingPlugin)
+        .add_plugin(post_processing::PostProcessingPlugin)
+        .add_plugin(background::BackgroundPlugin);
+    app.run();
+}
+
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+struct Player {
+    /// linear speed in meters per second
+    velocity: f32,
+    /// rotation speed in radians per second
+    rotation_speed: f32,
+    /// distance from the center at which enemies collide with the player
+    hit_radius: f32,
+}
+
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+struct Health {
+    current: f32,
+}
+
+impl Health {
+    fn new(max: f32) -> Self {
+        Self { current: max }
+    }
+}
+
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+struct Bullet {
+    velocity: f32,
+    direction: Vec3,
+    /// whether the bullet already wrapped around the screen once
+    wrapped: bool,
+    element: Element,
+}
+
+/// Shot fired by a drone or turret rather than the player, so it doesn't count towards the
+/// accuracy stat. Enemies it hits hold it against the one that fired it.
+#[derive(Component)]
+struct CompanionShot(Entity);
+
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+struct Enemy {
+    kind: EnemyKind,
+    velocity: f32,
+    size: f32,
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Default,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Debug,
+    Hash,
+    Serialize,
+    Deserialize,
+    Reflect,
+)]
+#[reflect_value(PartialEq, Serialize, Deserialize)]
+enum EnemyKind {
+    #[default]
+    Chaser,
+    Splitter,
+    Sniper,
+    Kamikaze,
+    Hive,
+    Swarmling,
+    Boss,
+}
+
+impl EnemyKind {
+    fn name_key(&self) -> &'static str {
+        match self {
+            EnemyKind::Chaser => "enemy.chaser",
+            EnemyKind::Splitter => "enemy.splitter",
+            EnemyKind::Sniper => "enemy.sniper",
+            EnemyKind::Kamikaze => "enemy.kamikaze",
+            EnemyKind::Hive => "enemy.hive",
+            EnemyKind::Swarmling => "enemy.swarmling",
+            EnemyKind::Boss => "enemy.boss",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Game {
+    score: usize,
+}
+
+struct TimerConfig {
+    enemy_timer: Timer,
+    bullet_timer: Timer,
+    /// spawns the enemy timer called for that haven't happened yet, held back for the beat
+    spawns_due: usize,
+    /// swarmlings hives let out in place of spawns the enemy timer still has to skip
+    spawn_debt: usize,
+}
+
+/// Rate the simulation advances at relative to real time.
+struct TimeScale {
+    global: f32,
+    /// extra factor for enemies and their bullets, lowered while the player is focusing
+    enemies: f32,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self {
+            global: 1.0,
+            enemies: 1.0,
+        }
+    }
+}
+
+impl TimeScale {
+    /// Simulated seconds each fixed step advances by.
+    fn step(&self) -> f32 {
+        TIME_STEP * self.global
+    }
+
+    fn step_duration(&self) -> Duration {
+        Duration::from_secs_f32(self.step())
+    }
+
+    /// Simulated seconds each fixed step advances enemies and enemy bullets by.
+    fn enemy_step(&self) -> f32 {
+        self.step() * self.enemies
+    }
+
+    fn enemy_step_duration(&self) -> Duration {
+        Duration::from_secs_f32(self.enemy_step())
+    }
+}
+
+/// Font used by all UI text.
+struct UiFont(Handle<Font>);
+
+/// Systems advancing the game simulation, every one of them run each fixed step in the order they
+/// were added, and each one's commands applied before the next one runs. Systems in a stage only
+/// run in as much of an order as their labels give them and have their commands wait for the
+/// rest of the stage, which would leave how a run plays out to the order the stage happens to
+/// settle on and to how the steps fall on frames. Here nothing is, and the same input plays a
+/// run out the same every time.
+#[derive(Default)]
+struct SimulationSchedule {
+    systems: Vec<Box<dyn System<In = (), Out = ()>>>,
+    /// systems at the start of `systems` that were set up with the world
+    initialized: usize,
+}
+
+impl SimulationSchedule {
+    fn step(&mut self, world: &mut World) {
+        for system in self.systems.iter_mut() {
+            system.run((), world);
+            system.apply_buffers(world);
+        }
+    }
+}
+
+trait AddSimulationSystem {
+    /// Adds a system to the end of every fixed step of the simulation.
+    fn add_simulation_system<Params>(
+        &mut self,
+        system: impl IntoSystem<(), (), Params>,
+    ) -> &mut Self;
+
+    /// Adds a system to the start of every fixed step, ahead of the ones already added.
+    fn add_simulation_system_first<Params>(
+        &mut self,
+        system: impl IntoSystem<(), (), Params>,
+    ) -> &mut Self;
+}
+
+impl AddSimulationSystem for App {
+    fn add_simulation_system<Params>(
+        &mut self,
+        system: impl IntoSystem<(), (), Params>,
+    ) -> &mut Self {
+        let system = Box::new(IntoSystem::into_system(system));
+        self.world
+            .get_resource_or_insert_with(SimulationSchedule::default)
+            .systems
+            .push(system);
+        self
+    }
+
+    fn add_simulation_system_first<Params>(
+        &mut self,
+        system: impl IntoSystem<(), (), Params>,
+    ) -> &mut Self {
+        let system = Box::new(IntoSystem::into_system(system));
+        self.world
+            .get_resource_or_insert_with(SimulationSchedule::default)
+            .systems
+            .insert(0, system);
+        self
+    }
+}
+
+/// Fixed steps the simulation takes this frame, counted at its start from the time the frame
+/// before took.
+#[derive(Default)]
+struct SimulationClock {
+    /// seconds of the frames so far not made up for by a step yet
+    accumulated: f64,
+    steps: u32,
+    /// steps taken every frame however long it took, for a replay to play out as fast as it can
+    fixed_steps: Option<u32>,
+}
+
+/// Whether a run is on, paused or not.
+fn in_run(state: &State<GameState>) -> bool {
+    state.current().is_simulated() || state.inactives().iter().any(GameState::is_simulated)
+}
+
+fn clock_system(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    photo_mode: Res<PhotoMode>,
+    mut clock: ResMut<SimulationClock>,
+) {
+    // what passes while the game isn't simulated is never made up for
+    if !state.current().is_simulated() || photo_mode.is_active() {
+        clock.accumulated = 0.0;
+        clock.steps = 0;
+        return;
+    }
+    if let Some(steps) = clock.fixed_steps {
+        clock.steps = steps;
+        return;
+    }
+    clock.accumulated += time.delta_seconds_f64();
+    clock.steps = (clock.accumulated / TIME_STEP as f64) as u32;
+    clock.accumulated -= clock.steps as f64 * TIME_STEP as f64;
+}
+
+/// Takes the fixed steps due this frame.
+fn simulation_system(world: &mut World) {
+    let steps = world.resource::<SimulationClock>().steps;
+    world.resource_scope(|world, mut schedule: Mut<SimulationSchedule>| {
+        let initialized = schedule.initialized;
+        for system in schedule.systems[initialized..].iter_mut() {
+            system.initialize(world);
+        }
+        schedule.initialized = schedule.systems.len();
+        let change_tick = world.change_tick();
+        for system in schedule.systems.iter_mut() {
+            system.check_change_tick(change_tick);
+        }
+
+        let mut player = world.query_filtered::<(), With<Player>>();
+        for _ in 0..steps {
+            // nothing happens before the run has set up the player, and once they died the run is
+            // over, the state only changes once the stage loops
+            if player.iter(world).next().is_none() {
+                break;
+            }
+            schedule.step(world);
+        }
+    });
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+    commands.spawn_bundle(UiCameraBundle::default());
+    commands.insert_resource(UiFont(asset_server.load("fonts/FiraSans-Bold.ttf")));
+}
+
+fn setup_run(
+    mut commands: Commands,
+    mut game: ResMut<Game>,
+    loadout: Res<Loadout>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    tunings: Res<WeaponTunings>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    game.score = 0;
+    *time_scale = TimeScale {
+        global: settings.assists.game_speed,
+        ..default()
+    };
+
+    let ship = loadout.ship;
+
+    let text_style = TextStyle {
+        font_size: 60.0,
+        color: Color::BLUE,
+        ..Default::default()
+    };
+
+    let text_alignment_topleft = TextAlignment {
+        vertical: VerticalAlign::Top,
+        horizontal: HorizontalAlign::Left,
+    };
+
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(game.score.to_string(), text_style, text_alignment_topleft),
+            ..Default::default()
+        })
+        .insert(RenderLayer::Ui);
+    palette
+        .spawn_sprite(
+            &mut commands,
+            RenderLayer::Bullets,
+            palette.bullet,
+            5.0,
+            Transform::default(),
+        )
+        .insert(Bullet {
+            velocity: 750.0,
+            direction: 1.0 * Vec3::Y,
+            wrapped: false,
+            element: Element::Kinetic,
+        });
+    let player_color = palette.player.unwrap_or_else(|| ship.color());
+    let mut dash_trail_color = player_color;
+    dash_trail_color.set_a(0.5);
+    let mut player = palette.spawn_sprite(
+        &mut commands,
+        RenderLayer::Player,
+        player_color,
+        ship.size(),
+        Transform::default(),
+    );
+    player
+        .insert(Player {
+            velocity: ship.velocity(),
+            rotation_speed: f32::to_radians(360.0),
+            hit_radius: ship.size() / 2.0 + 2.5,
+        })
+        .insert(Health::new(ship.max_health()))
+        .insert(Threat {
+            weight: PLAYER_THREAT,
+        })
+        .insert(Trail::new(dash_trail_color, 0.25, ship.size() * 0.6))
+        .insert(Melee::default())
+        .insert_bundle(player_abilities(&loadout));
+    if loadout.weapon.is_beam() {
+        player.insert(Beam::new(loadout.fire_interval()));
+    }
+    if let Some(supply) = tunings.supply(settings.munitions, loadout.weapon) {
+        player.insert(supply);
+    }
+    commands.insert_resource(TimerConfig {
+        enemy_timer: Timer::new(Duration::from_millis(500), true),
+        bullet_timer: Timer::new(loadout.fire_interval(), true),
+        spawns_due: 0,
+        spawn_debt: 0,
+    });
+}
+
+/// Outcome of a hit dealt to an enemy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Hit {
+    /// stopped by a shield
+    Blocked,
+    /// taken by armor, the enemy survives
+    Damaged,
+    Killed,
+}
+
+/// Everything needed to deal damage to enemies, shared by every weapon.
+#[derive(SystemParam)]
+struct EnemyDamage<'w, 's> {
+    commands: Commands<'w, 's>,
+    palette: Res<'w, Palette>,
+    game: ResMut<'w, Game>,
+    rng: ResMut<'w, GameRng>,
+    enemy_killed: EventWriter<'w, 's, EnemyKilled>,
+    bosses: Query<'w, 's, &'static mut Boss>,
+    hives: Query<'w, 's, &'static mut Hive>,
+    affinities: Res<'w, Affinities>,
+    wounds: Query<'w, 's, &'static mut Wounds>,
+}
+
+impl<'w, 's> EnemyDamage<'w, 's> {
+    /// Deals a single hit of `element` travelling along `direction` to an enemy, killing it unless
+    /// a shield, armor or a boss's or hive's health takes the hit. A hit the enemy is vulnerable
+    /// to counts as more than one against armor and health, one it resists only kills once enough
+    /// of them add up. Killed enemies start dying and score, splitting ones break up.
+    fn hit(
+        &mut self,
+        entity: Entity,
+        enemy: &Enemy,
+        position: Vec3,
+        mut elite: Option<&mut Elite>,
+        splitter: Option<&Splitter>,
+        direction: Vec3,
+        element: Element,
+    ) -> Hit {
+        if elite.as_ref().is_some_and(|elite| elite.blocks(direction)) {
+            return Hit::Blocked;
+        }
+        let affinities = match self.bosses.get(entity) {
+            Ok(boss) => boss.affinities(),
+            Err(_) => self.affinities.of(enemy.kind),
+        };
+        let hits = self.land(entity, elements::factor(affinities, element));
+        if hits == 0 {
+            self.commands.entity(entity).insert(HitFlash::default());
+            return Hit::Damaged;
+        }
+        let modifiers = if let Ok(mut boss) = self.bosses.get_mut(entity) {
+            if !boss.take_hits(hits) {
+                self.commands.entity(entity).insert(HitFlash::default());
+                return Hit::Damaged;
+            }
+            self.game.score += BOSS_SCORE;
+            Vec::new()
+        } else if let Ok(mut hive) = self.hives.get_mut(entity) {
+            if !hive.take_hits(hits) {
+                self.commands.entity(entity).insert(HitFlash::default());
+                return Hit::Damaged;
+            }
+            self.game.score += kill_score(Some(&*hive), None, None);
+            Vec::new()
+        } else {
+            self.game.score += match elite.as_deref_mut() {
+                Some(elite) => {
+                    // armor takes as many hits as it has left, any more kill
+                    if elite.armor >= hits {
+                        elite.armor -= hits;
+                        self.commands.entity(entity).insert(HitFlash::default());
+                        return Hit::Damaged;
+                    }
+                    if elite.has(Modifier::Splitting) {
+                        elites::split(&mut self.commands, &self.palette, position, enemy.kind);
+                    }
+                    kill_score(None, Some(elite), None)
+                }
+                None => {
+                    if let Some(splitter) = splitter {
+                        splitters::split(
+                            &mut self.commands,
+                            &self.palette,
+                            position,
+                            enemy,
+                            splitter,
+                            &mut self.rng.0,
+                        );
+                    }
+                    kill_score(None, None, splitter)
+                }
+            };
+            elite
+                .map(|elite| elite.modifiers.clone())
+                .unwrap_or_default()
+        };
+        self.commands.entity(entity).insert(Dying::default());
+        self.enemy_killed.send(EnemyKilled {
+            kind: enemy.kind,
+            modifiers,
+        });
+        Hit::Killed
+    }
+
+    /// Whole hits a hit `factor` times as hard as a regular one adds up to with what the enemy
+    /// was wounded by before, keeping what's left over for its next.
+    fn land(&mut self, entity: Entity, factor: f32) -> u32 {
+        let wounds = self.wounds.get(entity).map_or(0.0, |wounds| wounds.0) + factor;
+        let (hits, left) = (wounds.floor(), wounds.fract());
+        if let Ok(mut wounds) = self.wounds.get_mut(entity) {
+            wounds.0 = left;
+        }
+        hits as u32
+    }
+}
+
+/// Score for killing an enemy other than a boss, whether it was worn down or taken out at once.
+fn kill_score(hive: Option<&Hive>, elite: Option<&Elite>, splitter: Option<&Splitter>) -> usize {
+    if hive.is_some() {
+        HIVE_SCORE
+    } else if let Some(elite) = elite {
+        elite.score()
+    } else {
+        splitter.map_or(1, Splitter::score)
+    }
+}
+
+/// Enemies that can still be hit, along with what decides how they take a hit.
+type Targets<'w, 's, 'a, F = ()> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'a Enemy,
+        &'a Transform,
+        Option<&'a mut Elite>,
+        Option<&'a Splitter>,
+    ),
+    (Without<Dying>, F),
+>;
+
+fn bullet_enemy_collision_system(
+    mut bullets: Query<(
+        Entity,
+        &Bullet,
+        &Transform,
+        Option<&mut Pierce>,
+        Option<&CompanionShot>,
+        Option<&FrostShot>,
+    )>,
+    mut enemies: Targets<Without<Bullet>>,
+    mut damage: EnemyDamage,
+    mut bullet_hit: EventWriter<BulletHit>,
+    mut provoked: EventWriter<Provoked>,
+) {
+    // every bullet is checked against every enemy, the counts tell a spike from a crowd in a trace
+    let _span = info_span!(
+        "bullet_enemy_collision",
+        bullets = bullets.iter().count(),
+        enemies = enemies.iter().count(),
+    )
+    .entered();
+    let mut killed = Vec::new();
+    for (bullet_entity, bullet, bullet_transform, mut pierce, companion_shot, frost_shot) in
+        bullets.iter_mut()
+    {
+        for (enemy_entity, enemy, enemy_transform, mut elite, splitter) in enemies.iter_mut() {
+            if killed.contains(&enemy_entity)
+                || pierce
+                    .as_ref()
+                    .is_some_and(|pierce| pierce.has_pierced(enemy_entity))
+                || bullet_transform
+                    .translation
+                    .distance(enemy_transform.translation)
+                    > enemy.size / 2.0 + 2.5
+            {
+                continue;
+            }
+            let hit = damage.hit(
+                enemy_entity,
+                enemy,
+                enemy_transform.translation,
+                elite.as_deref_mut(),
+                splitter,
+                bullet.direction,
+                bullet.element,
+            );
+            match companion_shot {
+                Some(CompanionShot(shooter)) => provoked.send(Provoked {
+                    enemy: enemy_entity,
+                    by: *shooter,
+                }),
+                None if hit != Hit::Blocked => bullet_hit.send(BulletHit),
+                None => {}
+            }
+            if hit != Hit::Blocked && frost_shot.is_some() {
+                zones::spawn(
+                    &mut damage.commands,
+                    &damage.palette,
+                    ZoneKind::Frost,
+                    enemy_transform.translation,
+                    false,
+                );
+            }
+            if hit == Hit::Killed {
+                killed.push(enemy_entity);
+            }
+            // shields stop piercing bullets too
+            let passes = hit != Hit::Blocked
+                && pierce
+                    .as_mut()
+                    .is_some_and(|pierce| pierce.pass_through(enemy_entity));
+            if !passes {
+                damage.commands.entity(bullet_entity).despawn_recursive();
+                break;
+            }
+        }
+    }
+}
+
+fn enemy_player_collions_system(
+    mut commands: Commands,
+    mut state: ResMut<State<GameState>>,
+    mut player_query: Query<(Entity, &Player, &Transform, &mut Health)>,
+    // bosses only hurt on contact every so often and survive it, see the bosses module
+    enemy_entities: Query<(Entity, &Enemy), (Without<Dying>, Without<Boss>)>,
+    query: Query<&Transform>,
+    settings: Res<Settings>,
+    mut player_damaged: EventWriter<PlayerDamaged>,
+) {
+    let (player_entity, player, player_transform, mut health) = match player_query.get_single_mut()
+    {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let player_position = player_transform.translation;
+    let _span = info_span!(
+        "enemy_player_collision",
+        enemies = enemy_entities.iter().count()
+    )
+    .entered();
+    for (entity, enemy) in enemy_entities.iter() {
+        if let Ok(enemy_transform) = query.get(entity) {
+            if enemy_transform.translation.distance(player_position) <= player.hit_radius {
+                commands.entity(entity).insert(Dying::default());
+                if damage_player(
+                    &mut commands,
+                    &mut state,
+                    &settings,
+                    player_entity,
+                    &mut health,
+                    1.0,
+                    DamageSource::Enemy(enemy.kind),
+                    &mut player_damaged,
+                ) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Deals a hit of the given strength to the player and ends the run once their health runs out.
+/// Returns whether the player died.
+fn damage_player(
+    commands: &mut Commands,
+    state: &mut State<GameState>,
+    settings: &Settings,
+    player: Entity,
+    health: &mut Health,
+    amount: f32,
+    source: DamageSource,
+    player_damaged: &mut EventWriter<PlayerDamaged>,
+) -> bool {
+    let damage = settings.assists.damage(amount);
+    health.current -= damage;
+    player_damaged.send(PlayerDamaged {
+        amount: damage,
+        source,
+    });
+    // the tutorial can't be lost
+    if health.current <= 0.0 && *state.current() == GameState::Playing {
+        // the state change only takes effect once the stage loops, so remove the
+        // player right away, which ends the fixed steps due this frame
+        commands.entity(player).despawn_recursive();
+        let _ = state.overwrite_set(GameState::GameOver);
+        return true;
+    }
+    false
+}
+
+fn spawn_enemy(
+    commands: &mut Commands,
+    palette: &Palette,
+    position: Vec3,
+    kind: EnemyKind,
+    velocity: f32,
+) -> Entity {
+    let transform = Transform::from_translation(position)
+        .with_rotation(Quat::from_rotation_z(palette.enemy_rotation));
+    palette
+        .spawn_sprite(
+            commands,
+            RenderLayer::Enemies,
+            palette.enemy,
+            ENEMY_SIZE,
+            transform,
+        )
+        .insert(Enemy {
+            kind,
+            velocity,
+            size: ENEMY_SIZE,
+        })
+        .insert(Wounds::default())
+        .id()
+}
+
+/// Where a single enemy of a wave appears, in one of the arena's spawn zones or else somewhere on
+/// a ring around its center.
+fn enemy_spawn_position(layout: &ArenaLayout, rng: &mut impl Rng) -> Vec3 {
+    layout.spawn_point(rng).unwrap_or_else(|| {
+        let angle = rng.gen::<f32>() * 2.0 * std::f32::consts::PI;
+        let (x, y) = angle.sin_cos();
+        Vec3::new(x * 400.0, y * 400.0, 0.0)
+    })
+}
+
+fn setup_spawn_enemy(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    state: Res<State<GameState>>,
+    wave: Res<Wave>,
+    palette: Res<Palette>,
+    formations: Res<Formations>,
+    layout: Res<ArenaLayout>,
+    loadout: Res<Loadout>,
+    settings: Res<Settings>,
+    input: Res<StepInput>,
+    mut rng: ResMut<GameRng>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<(), (With<Enemy>, Without<Dying>)>,
+    mut config: ResMut<TimerConfig>,
+) {
+    // the tutorial places its own enemies, a boss rush only has its bosses
+    if *state.current() != GameState::Playing || loadout.mode == GameMode::BossRush {
+        return;
+    }
+
+    let rng = &mut rng.0;
+
+    if wave.is_changed() {
+        config.enemy_timer.set_duration(wave.spawn_interval());
+    }
+    config.enemy_timer.tick(time_scale.enemy_step_duration());
+
+    // the screen is full enough, the spawner waits for some of the crowd to be shot down
+    if enemies.iter().count() >= settings.max_enemies {
+        return;
+    }
+    if config.enemy_timer.finished() {
+        config.spawns_due += 1;
+    }
+    // spawning on the beat saves them up and lets them all in at once when it lands
+    if settings.beat_spawns && !input.beat() {
+        return;
+    }
+    for _ in 0..std::mem::take(&mut config.spawns_due) {
+        if config.spawn_debt > 0 {
+            config.spawn_debt -= 1;
+            continue;
+        }
+        if let Some(formation) = formations.roll(wave.number, rng) {
+            let player = player
+                .get_single()
+                .map_or(Vec3::ZERO, |player| player.translation);
+            formations::spawn(&mut commands, &palette, formation, player, rng);
+            continue;
+        }
+        let position = enemy_spawn_position(&layout, rng);
+        let modifiers = elites::roll_modifiers(wave.number, rng);
+        if splitters::roll_splitter(wave.number, rng) {
+            splitters::spawn(&mut commands, &palette, position);
+        } else if snipers::roll_sniper(wave.number, rng) {
+            snipers::spawn(&mut commands, &palette, position);
+        } else if kamikazes::roll_kamikaze(wave.number, rng) {
+            kamikazes::spawn(&mut commands, &palette, position);
+        } else if hives::roll_hive(wave.number, rng) {
+            hives::spawn(&mut commands, &palette, position);
+        } else if modifiers.is_empty() {
+            spawn_enemy(&mut commands, &palette, position, EnemyKind::Chaser, 250.0);
+        } else {
+            elites::spawn_elite(
+                &mut commands,
+                &palette,
+                position,
+                EnemyKind::Chaser,
+                250.0,
+                modifiers,
+            );
+        }
+    }
+}
+
+/// Steers the enemies in a plain loop. With 5000 of them the `steering` bench steps in about 72µs,
+/// splitting the loop across the compute task pool only ever measured slower.
+fn move_enemy_system(
+    time_scale: Res<TimeScale>,
+    safe_zone: Res<SafeZone>,
+    // enemies still entering in formation, bosses, snipers and kamikazes follow their own paths
+    mut enemies: Query<
+        (
+            Entity,
+            &Enemy,
+            &mut Transform,
+            Option<&Chilled>,
+            Option<&Aggro>,
+        ),
+        (
+            Without<Dying>,
+            Without<Entering>,
+            Without<Boss>,
+            Without<Sniper>,
+            Without<Kamikaze>,
+        ),
+    >,
+    player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    targets: Query<&Transform, (With<Threat>, Without<Enemy>)>,
+    steering: Res<ScriptSteering>,
+) {
+    let player_position = match player.get_single() {
+        Ok(player) => player.translation,
+        Err(_) => return,
+    };
+
+    let _span = info_span!("enemy_steering", enemies = enemies.iter().count()).entered();
+    let step = time_scale.enemy_step();
+    for (entity, enemy, mut enemy_transform, chilled, aggro) in enemies.iter_mut() {
+        let chill = chilled.map_or(1.0, |chilled| chilled.0);
+        // a mod's behavior for the enemy takes the place of the chase
+        if let Some(velocity) = steering.0.get(&entity) {
+            enemy_transform.translation += velocity.extend(0.0) * chill * step;
+            continue;
+        }
+        let target = aggro
+            .and_then(|aggro| aggro.target)
+            .and_then(|target| targets.get(target).ok())
+            .map_or(player_position, |target| target.translation);
+        let direction = target - enemy_transform.translation;
+        let speed =
+            enemy.velocity * safe_zone.enemy_speed_factor(enemy_transform.translation) * chill;
+        // an enemy that caught up with a turret or drone stays on top of it
+        enemy_transform.translation += direction.normalize_or_zero() * speed * step;
+    }
+}
+
+fn player_shooting_system(
+    mut commands: Commands,
+    player: Query<&Transform, (With<Player>, Without<Bullet>)>,
+    enemies: Query<
+        &Transform,
+        (
+            With<Enemy>,
+            Without<Dying>,
+            Without<Bullet>,
+            Without<Player>,
+        ),
+    >,
+    time_scale: Res<TimeScale>,
+    loadout: Res<Loadout>,
+    perks: Res<Perks>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+    mut config: ResMut<TimerConfig>,
+    tunings: Res<WeaponTunings>,
+    mut supply: Query<&mut WeaponSupply, With<Player>>,
+    mut shot_fired: EventWriter<ShotFired>,
+) {
+    let player_transform = match player.get_single() {
+        Ok(player) => *player,
+        Err(_) => return,
+    };
+
+    let player_position = player_transform.translation;
+    let player_direction = player_transform.rotation * Vec3::Y;
+
+    config.bullet_timer.tick(time_scale.step_duration());
+
+    let mut supply = supply.get_single_mut().ok();
+    let ready = supply.as_ref().is_none_or(|supply| supply.can_fire());
+    if ready && config.bullet_timer.finished() {
+        if let Some(supply) = &mut supply {
+            supply.fire();
+        }
+        let weapon = loadout.weapon;
+        // the beam has no bullets, it fires on its own in the beam module
+        for angle in weapon.spread() {
+            let direction = settings.assists.aim(
+                player_position,
+                Quat::from_rotation_z(*angle) * player_direction,
+                enemies.iter().map(|enemy| enemy.translation),
+            );
+            let new_bullet = Bullet {
+                velocity: weapon.bullet_velocity(),
+                direction,
+                wrapped: false,
+                element: tunings.element(weapon),
+            };
+
+            let mut bullet = palette.spawn_sprite(
+                &mut commands,
+                RenderLayer::Bullets,
+                palette.bullet,
+                5.0,
+                Transform::from_translation(player_position),
+            );
+            bullet.insert(new_bullet);
+            if weapon.bullet_velocity() >= FAST_BULLET_VELOCITY {
+                bullet.insert(Trail::new(palette.bullet, 0.08, 3.0));
+            }
+            if weapon.is_frost() {
+                bullet.insert(FrostShot);
+            }
+            perks.modify_bullet(&mut bullet);
+            shot_fired.send(ShotFired);
+        }
+    }
+}
+
+fn move_bullets_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    settings: Res<Settings>,
+    mut bullets: Query<(Entity, &mut Bullet, &mut Transform, Option<&mut Bounce>)>,
+) {
+    let step = time_scale.step();
+    for (_, mut bullet, mut bullet_transform, bounce) in bullets.iter_mut() {
+        let distance = bullet.velocity * step;
+        let movement_delta = distance * bullet.direction;
+        bullet_transform.translation += movement_delta;
+
+        if !out_of_bounds(bullet_transform.translation) {
+            continue;
+        }
+        if let Some(mut bounce) = bounce {
+            let normal = wall_normal(bullet_transform.translation);
+            if bounce.reflect(&mut bullet.direction, normal) {
+                bullet_transform.translation = clamp_to_bounds(bullet_transform.translation);
+                continue;
+            }
+        }
+        if settings.bullet_wrap && !bullet.wrapped {
+            bullet.wrapped = true;
+            bullet_transform.translation = wrap_to_bounds(bullet_transform.translation);
+        }
+    }
+    // bounced and wrapped bullets were brought back in, the ones still out are done
+    for (entity, _, bullet_transform, _) in bullets.iter() {
+        if out_of_bounds(bullet_transform.translation) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn player_movement_system(
+    time_scale: Res<TimeScale>,
+    settings: Res<Settings>,
+    input: Res<StepInput>,
+    mut query: Query<(&Player, &mut Transform, Option<&mut Drift>)>,
+) {
+    let (player, mut transform, drift) = match query.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    let mut rotation_factor = 0.0;
+    let mut velocity = input.touch_movement().extend(0.0);
+
+    if input.pressed(KeyCode::Q) {
+        rotation_factor += 1.0;
+    }
+
+    if input.pressed(KeyCode::E) {
+        rotation_factor -= 1.0;
+    }
+
+    if input.pressed(KeyCode::Up) {
+        velocity += 1.0 * Vec3::Y;
+    }
+
+    if input.pressed(KeyCode::Down) {
+        velocity -= 1.0 * Vec3::Y;
+    }
+
+    if input.pressed(KeyCode::Left) {
+        velocity -= 1.0 * Vec3::X;
+    }
+
+    if input.pressed(KeyCode::Right) {
+        velocity += 1.0 * Vec3::X;
+    }
+
+    let max_rotation = player.rotation_speed * time_scale.step();
+    let rotation_delta = match input.touch_aim() {
+        // turns towards the touch as fast as the keys would turn it, without overshooting
+        Some(aim) => {
+            let facing = (transform.rotation * Vec3::Y).truncate();
+            let target = aim - transform.translation.truncate();
+            let angle = facing.angle_between(target);
+            let angle = if angle.is_finite() { angle } else { 0.0 };
+            Quat::from_rotation_z(angle.clamp(-max_rotation, max_rotation))
+        }
+        None => Quat::from_rotation_z(rotation_factor * max_rotation),
+    };
+    transform.rotation *= rotation_delta;
+
+    if let Some(mut drift) = drift {
+        velocity = drift.steer(velocity, time_scale.step());
+    }
+    let movement_distance = player.velocity * time_scale.step();
+    let translation_delta = velocity * movement_distance;
+    transform.translation += translation_delta;
+
+    transform.translation = confine_player(transform.translation, &settings);
+}
+
+/// Offset from `position` to the closest of the enemies within `range`, if any.
+fn nearest_enemy<'a>(
+    position: Vec3,
+    range: f32,
+    enemies: impl Iterator<Item = &'a Transform>,
+) -> Option<Vec3> {
+    enemies
+        .map(|enemy| enemy.translation - position)
+        .filter(|offset| offset.length() <= range)
+        .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+}
+
+/// Fires a shot on behalf of the player from `shooter` at `position` towards `target`, the offset
+/// to it.
+fn spawn_companion_shot(
+    commands: &mut Commands,
+    palette: &Palette,
+    shooter: Entity,
+    position: Vec3,
+    target: Vec3,
+    velocity: f32,
+    size: f32,
+) {
+    palette
+        .spawn_sprite(
+            commands,
+            RenderLayer::Bullets,
+            palette.drone,
+            size,
+            Transform::from_translation(position),
+        )
+        .insert(Bullet {
+            velocity,
+            direction: target.truncate().normalize_or_zero().extend(0.0),
+            wrapped: false,
+            element: Element::Kinetic,
+        })
+        .insert(CompanionShot(shooter));
+}
+
+/// Point in the world under `position` in the window, counted from its bottom left corner the way
+/// the cursor and touches are, as the camera sees it wherever it's moved, shaken or zoomed to.
+fn window_to_world(
+    window: &Window,
+    position: Vec2,
+    camera: &Camera,
+    transform: &GlobalTransform,
+) -> Vec2 {
+    let size = Vec2::new(window.width(), window.height());
+    // normalized device coordinates, from -1 to 1 across the window
+    let ndc = position / size * 2.0 - Vec2::ONE;
+    let to_world = transform.compute_matrix() * camera.projection_matrix.inverse();
+    to_world.project_point3(ndc.extend(-1.0)).truncate()
+}
+
+fn out_of_bounds(translation: Vec3) -> bool {
+    translation.x.abs() > BOUNDS.x / 2.0 || translation.y.abs() > BOUNDS.y / 2.0
+}
+
+/// Normal pointing back into the arena of the walls a position outside `BOUNDS` went through.
+fn wall_normal(translation: Vec3) -> Vec3 {
+    let normal = |value: f32, size: f32| {
+        if value.abs() > size / 2.0 {
+            -value.signum()
+        } else {
+            0.0
+        }
+    };
+    Vec3::new(
+        normal(translation.x, BOUNDS.x),
+        normal(translation.y, BOUNDS.y),
+        0.0,
+    )
+}
+
+fn clamp_to_bounds(translation: Vec3) -> Vec3 {
+    let extents = Vec3::from((BOUNDS / 2.0, 0.0));
+    translation.min(extents).max(-extents)
+}
+
+/// Moves a position that left `BOUNDS` in by the same distance from the opposite edge.
+fn wrap_to_bounds(translation: Vec3) -> Vec3 {
+    let wrap = |value: f32, size: f32| (value + size / 2.0).rem_euclid(size) - size / 2.0;
+    Vec3::new(
+        wrap(translation.x, BOUNDS.x),
+        wrap(translation.y, BOUNDS.y),
+        translation.z,
+    )
+}
+
+/// Keeps the player inside `BOUNDS`, wrapping or clamping depending on the settings.
+fn confine_player(translation: Vec3, settings: &Settings) -> Vec3 {
+    if settings.screen_wrap {
+        wrap_to_bounds(translation)
+    } else {
+        clamp_to_bounds(translation)
+    }
+}
+
+fn restart_system(
+    mut state: ResMut<State<GameState>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    touches: Res<Touches>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Return) || touches.iter_just_pressed().next().is_some()
+    {
+        let _ = state.set(GameState::Playing);
+    } else if keyboard_input.just_pressed(KeyCode::M) {
+        let _ = state.set(GameState::Menu);
+    }
+}
+
+/// Inserts a component unless the entity was despawned before the command is applied, where
+/// `EntityCommands::insert` panics. Systems reacting to a component being added insert through
+/// this, as another system's commands might despawn the entity first, like a bullet spent the
+/// frame after it was fired.
+struct InsertIfAlive<C>(Entity, C);
+
+impl<C: Component> Command for InsertIfAlive<C> {
+    fn write(self, world: &mut World) {
+        if let Some(mut entity) = world.get_entity_mut(self.0) {
+            entity.insert(self.1);
+        }
+    }
+}
+
+/// Spawns children for an entity the same way, unless it was despawned first.
+struct WithChildrenIfAlive<F>(Entity, F);
+
+impl<F> Command for WithChildrenIfAlive<F>
+where
+    F: FnOnce(&mut WorldChildBuilder) + Send + Sync + 'static,
+{
+    fn write(self, world: &mut World) {
+        if let Some(mut entity) = world.get_entity_mut(self.0) {
+            entity.with_children(self.1);
+        }
+    }
+}
+
+fn despawn_with<T: Component>(mut commands: Commands, entities: Query<Entity, With<T>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn teardown(mut commands: Commands, entities: Query<Entity, Without<Camera>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,339 @@
+use bevy::{
+    ecs::system::{lifetimeless::SRes, SystemParamItem},
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_asset::{PrepareAssetError, RenderAsset},
+        render_resource::{
+            std140::{AsStd140, Std140},
+            BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+            BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BlendComponent,
+            BlendFactor, BlendOperation, BlendState, Buffer, BufferBindingType,
+            BufferInitDescriptor, BufferSize, BufferUsages, RenderPipelineDescriptor, ShaderStages,
+            SpecializedMeshPipelineError,
+        },
+        renderer::RenderDevice,
+    },
+    sprite::{
+        Material2d, Material2dPipeline, Material2dPlugin, MaterialMesh2dBundle, Mesh2dHandle,
+    },
+};
+
+use crate::{
+    deaths::Dying, layers::RenderLayer, palette::Palette, settings::Settings, AddSimulationSystem,
+    Bullet, GameState, Player, TimeScale, WithChildrenIfAlive, BOUNDS,
+};
+
+/// How much the arena is darkened outside of lights.
+const AMBIENT_DIMMING: f32 = 0.35;
+const BULLET_GLOW_SIZE: f32 = 28.0;
+const PLAYER_LIGHT_SIZE: f32 = 240.0;
+const EXPLOSION_GLOW_SIZE: f32 = 120.0;
+/// Factor glows are dimmed and shrunk by with bloom turned off.
+const NO_BLOOM_FACTOR: f32 = 0.6;
+/// Seconds an explosion's glow takes to fade.
+const EXPLOSION_GLOW_DURATION: f32 = 0.4;
+/// Depths among the effects lights are drawn at, above the ambient dimming so they can cut
+/// through it.
+const LIGHT_DEPTH: f32 = 0.9;
+const AMBIENT_DEPTH: f32 = 0.8;
+
+/// Soft round light added on top of whatever is behind it.
+#[derive(Debug, Clone, TypeUuid)]
+#[uuid = "0fe8b504-5c35-4a7d-acd9-92131dfd2c87"]
+pub struct GlowMaterial {
+    pub color: Color,
+}
+
+#[derive(Clone, Default, AsStd140)]
+struct GlowMaterialUniformData {
+    color: Vec4,
+}
+
+pub struct GpuGlowMaterial {
+    _buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+impl RenderAsset for GlowMaterial {
+    type ExtractedAsset = GlowMaterial;
+    type PreparedAsset = GpuGlowMaterial;
+    type Param = (SRes<RenderDevice>, SRes<Material2dPipeline<GlowMaterial>>);
+
+    fn extract_asset(&self) -> Self::ExtractedAsset {
+        self.clone()
+    }
+
+    fn prepare_asset(
+        material: Self::ExtractedAsset,
+        (render_device, pipeline): &mut SystemParamItem<Self::Param>,
+    ) -> Result<Self::PreparedAsset, PrepareAssetError<Self::ExtractedAsset>> {
+        let value = GlowMaterialUniformData {
+            color: material.color.as_linear_rgba_f32().into(),
+        };
+        let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("glow_material_uniform_buffer"),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            contents: value.as_std140().as_bytes(),
+        });
+        let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("glow_material_bind_group"),
+            layout: &pipeline.material2d_layout,
+        });
+        Ok(GpuGlowMaterial {
+            _buffer: buffer,
+            bind_group,
+        })
+    }
+}
+
+impl Material2d for GlowMaterial {
+    fn fragment_shader(asset_server: &AssetServer) -> Option<Handle<Shader>> {
+        Some(asset_server.load("shaders/glow.wgsl"))
+    }
+
+    fn bind_group(material: &GpuGlowMaterial) -> &BindGroup {
+        &material.bind_group
+    }
+
+    fn bind_group_layout(render_device: &RenderDevice) -> BindGroupLayout {
+        render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: BufferSize::new(
+                        GlowMaterialUniformData::std140_size_static() as u64,
+                    ),
+                },
+                count: None,
+            }],
+            label: Some("glow_material_layout"),
+        })
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayout,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // lights add up rather than cover each other
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            for target in fragment.targets.iter_mut() {
+                target.blend = Some(BlendState {
+                    color: BlendComponent {
+                        src_factor: BlendFactor::SrcAlpha,
+                        dst_factor: BlendFactor::One,
+                        operation: BlendOperation::Add,
+                    },
+                    alpha: BlendComponent::OVER,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Quad mesh and shared materials every light is drawn with.
+struct Lights {
+    quad: Mesh2dHandle,
+    bullet: Handle<GlowMaterial>,
+    player: Handle<GlowMaterial>,
+}
+
+/// Glow left behind by an enemy's death, fading out along with its own material.
+#[derive(Component)]
+struct ExplosionGlow(Timer);
+
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(Material2dPlugin::<GlowMaterial>::default())
+            .add_startup_system(setup_lights)
+            .add_system(explosion_glow_system)
+            .add_system(palette_lights_system)
+            // they give bullets and the player children, which the simulation goes through
+            .add_simulation_system(bullet_glow_system)
+            .add_simulation_system(player_light_system)
+            .add_simulation_system(fade_explosion_system);
+        for state in [GameState::Playing, GameState::Tutorial] {
+            app.add_system_set(SystemSet::on_enter(state).with_system(spawn_ambient));
+        }
+    }
+}
+
+fn setup_lights(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GlowMaterial>>,
+) {
+    commands.insert_resource(Lights {
+        quad: meshes.add(Mesh::from(shape::Quad::default())).into(),
+        bullet: materials.add(GlowMaterial {
+            color: bullet_glow(&palette, &settings),
+        }),
+        player: materials.add(GlowMaterial {
+            color: Color::rgba(1.0, 1.0, 0.9, 0.3),
+        }),
+    });
+}
+
+/// Factor bloom scales the brightness and reach of glows by.
+fn bloom(settings: &Settings) -> f32 {
+    if settings.graphics.bloom {
+        1.0
+    } else {
+        NO_BLOOM_FACTOR
+    }
+}
+
+fn bullet_glow(palette: &Palette, settings: &Settings) -> Color {
+    let mut color = palette.bullet;
+    color.set_a(0.6 * bloom(settings));
+    color
+}
+
+fn glow_bundle(
+    quad: &Mesh2dHandle,
+    material: Handle<GlowMaterial>,
+    transform: Transform,
+) -> MaterialMesh2dBundle<GlowMaterial> {
+    MaterialMesh2dBundle {
+        mesh: quad.clone(),
+        material,
+        transform,
+        ..default()
+    }
+}
+
+/// Darkens the whole arena, leaving only the lights at full brightness.
+fn spawn_ambient(mut commands: Commands) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.0, 0.0, 0.0, AMBIENT_DIMMING),
+                custom_size: Some(BOUNDS),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, AMBIENT_DEPTH),
+            ..default()
+        })
+        .insert(RenderLayer::Fx);
+}
+
+fn bullet_glow_system(
+    mut commands: Commands,
+    lights: Res<Lights>,
+    bullets: Query<Entity, Added<Bullet>>,
+) {
+    for bullet in bullets.iter() {
+        let (quad, material) = (lights.quad.clone(), lights.bullet.clone());
+        commands.add(WithChildrenIfAlive(
+            bullet,
+            move |parent: &mut WorldChildBuilder| {
+                parent
+                    .spawn_bundle(glow_bundle(
+                        &quad,
+                        material,
+                        Transform::from_xyz(0.0, 0.0, LIGHT_DEPTH)
+                            .with_scale(Vec3::splat(BULLET_GLOW_SIZE)),
+                    ))
+                    .insert(RenderLayer::Fx);
+            },
+        ));
+    }
+}
+
+fn player_light_system(
+    mut commands: Commands,
+    lights: Res<Lights>,
+    player: Query<Entity, Added<Player>>,
+) {
+    for player in player.iter() {
+        let (quad, material) = (lights.quad.clone(), lights.player.clone());
+        commands.add(WithChildrenIfAlive(
+            player,
+            move |parent: &mut WorldChildBuilder| {
+                parent
+                    .spawn_bundle(glow_bundle(
+                        &quad,
+                        material,
+                        Transform::from_xyz(0.0, 0.0, LIGHT_DEPTH)
+                            .with_scale(Vec3::splat(PLAYER_LIGHT_SIZE)),
+                    ))
+                    .insert(RenderLayer::Fx);
+            },
+        ));
+    }
+}
+
+fn explosion_glow_system(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    lights: Res<Lights>,
+    mut materials: ResMut<Assets<GlowMaterial>>,
+    dying: Query<(&Sprite, &Transform), Added<Dying>>,
+) {
+    for (sprite, transform) in dying.iter() {
+        // each explosion fades on its own, so it gets its own material
+        let mut color = sprite.color;
+        color.set_a(bloom(&settings));
+        let material = materials.add(GlowMaterial { color });
+        let position = transform.translation;
+        commands
+            .spawn_bundle(glow_bundle(
+                &lights.quad,
+                material,
+                Transform::from_xyz(position.x, position.y, LIGHT_DEPTH)
+                    .with_scale(Vec3::splat(EXPLOSION_GLOW_SIZE * bloom(&settings))),
+            ))
+            .insert(RenderLayer::Fx)
+            .insert(ExplosionGlow(Timer::from_seconds(
+                EXPLOSION_GLOW_DURATION,
+                false,
+            )));
+    }
+}
+
+fn fade_explosion_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut materials: ResMut<Assets<GlowMaterial>>,
+    settings: Res<Settings>,
+    mut glows: Query<(Entity, &mut ExplosionGlow, &Handle<GlowMaterial>)>,
+) {
+    for (entity, mut glow, handle) in glows.iter_mut() {
+        if glow.0.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        if let Some(material) = materials.get_mut(handle) {
+            material
+                .color
+                .set_a(glow.0.percent_left() * bloom(&settings));
+        }
+    }
+}
+
+fn palette_lights_system(
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    lights: Res<Lights>,
+    mut materials: ResMut<Assets<GlowMaterial>>,
+) {
+    if !palette.is_changed() && !settings.is_changed() {
+        return;
+    }
+    if let Some(material) = materials.get_mut(&lights.bullet) {
+        material.color = bullet_glow(&palette, &settings);
+    }
+}
@@ -0,0 +1,715 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::{Achievement, Achievements},
+    cli::Cli,
+    despawn_with,
+    locale::Locale,
+    menu::{screen_node, spawn_button, spawn_title, MenuButton},
+    munitions::WeaponTunings,
+    mutators::Mutator,
+    save,
+    stats::LifetimeStats,
+    GameState, UiFont,
+};
+
+const LOADOUT_FILE: &str = "loadout";
+
+/// What has to happen before a ship or weapon can be selected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Requirement {
+    None,
+    Achievement(Achievement),
+    /// reach the given wave in any run
+    Wave(usize),
+}
+
+impl Requirement {
+    pub fn is_met(&self, achievements: &Achievements, lifetime: &LifetimeStats) -> bool {
+        match self {
+            Requirement::None => true,
+            Requirement::Achievement(achievement) => achievements.is_unlocked(*achievement),
+            Requirement::Wave(wave) => lifetime.best_wave >= *wave,
+        }
+    }
+
+    pub fn describe(&self, locale: &Locale) -> String {
+        match self {
+            Requirement::None => locale.get("requirement.none").to_string(),
+            Requirement::Achievement(achievement) => locale.format(
+                "requirement.achievement",
+                &[("achievement", &locale.get(achievement.name_key()))],
+            ),
+            Requirement::Wave(wave) => locale.format("requirement.wave", &[("wave", wave)]),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ship {
+    Interceptor,
+    Dart,
+    Bulwark,
+}
+
+impl Ship {
+    pub const ALL: [Ship; 3] = [Ship::Interceptor, Ship::Dart, Ship::Bulwark];
+
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            Ship::Interceptor => "ship.interceptor",
+            Ship::Dart => "ship.dart",
+            Ship::Bulwark => "ship.bulwark",
+        }
+    }
+
+    pub fn requirement(&self) -> Requirement {
+        match self {
+            Ship::Interceptor => Requirement::None,
+            Ship::Dart => Requirement::Wave(5),
+            Ship::Bulwark => Requirement::Achievement(Achievement::Centurion),
+        }
+    }
+
+    /// linear speed in meters per second
+    pub fn velocity(&self) -> f32 {
+        match self {
+            Ship::Interceptor => 500.0,
+            Ship::Dart => 650.0,
+            Ship::Bulwark => 380.0,
+        }
+    }
+
+    /// side length of the ship's sprite
+    pub fn size(&self) -> f32 {
+        match self {
+            Ship::Interceptor => 25.0,
+            Ship::Dart => 18.0,
+            Ship::Bulwark => 32.0,
+        }
+    }
+
+    pub fn max_health(&self) -> f32 {
+        match self {
+            Ship::Interceptor => 3.0,
+            Ship::Dart => 2.0,
+            Ship::Bulwark => 5.0,
+        }
+    }
+
+    pub fn passive(&self) -> Passive {
+        match self {
+            Ship::Interceptor => Passive::Overclock,
+            Ship::Dart => Passive::Afterburner,
+            Ship::Bulwark => Passive::Reserves,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Ship::Interceptor => Color::rgb(0.25, 0.25, 0.75),
+            Ship::Dart => Color::rgb(0.2, 0.55, 0.6),
+            Ship::Bulwark => Color::rgb(0.45, 0.3, 0.65),
+        }
+    }
+}
+
+/// Ability unique to a ship.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Passive {
+    /// fires 25% faster
+    Overclock,
+    /// dash recharges twice as fast
+    Afterburner,
+    /// starts every run with an extra bomb
+    Reserves,
+}
+
+impl Passive {
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            Passive::Overclock => "passive.overclock",
+            Passive::Afterburner => "passive.afterburner",
+            Passive::Reserves => "passive.reserves",
+        }
+    }
+
+    pub fn description_key(&self) -> &'static str {
+        match self {
+            Passive::Overclock => "passive.overclock.description",
+            Passive::Afterburner => "passive.afterburner.description",
+            Passive::Reserves => "passive.reserves.description",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weapon {
+    Blaster,
+    Spread,
+    Lance,
+    /// continuous hitscan beam drawing on an energy meter instead of firing bullets
+    Beam,
+    /// slow shots leaving fields that slow enemies down wherever they hit
+    Frost,
+}
+
+impl Weapon {
+    pub const ALL: [Weapon; 5] = [
+        Weapon::Blaster,
+        Weapon::Spread,
+        Weapon::Lance,
+        Weapon::Beam,
+        Weapon::Frost,
+    ];
+
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            Weapon::Blaster => "weapon.blaster",
+            Weapon::Spread => "weapon.spread",
+            Weapon::Lance => "weapon.lance",
+            Weapon::Beam => "weapon.beam",
+            Weapon::Frost => "weapon.frost",
+        }
+    }
+
+    pub fn requirement(&self) -> Requirement {
+        match self {
+            Weapon::Blaster => Requirement::None,
+            Weapon::Spread => Requirement::Wave(3),
+            Weapon::Lance => Requirement::Achievement(Achievement::Pacifist),
+            Weapon::Beam => Requirement::Wave(6),
+            Weapon::Frost => Requirement::Wave(8),
+        }
+    }
+
+    /// Time between shots, or for the beam between the ticks it damages on.
+    pub fn fire_interval(&self) -> Duration {
+        match self {
+            Weapon::Blaster => Duration::from_millis(50),
+            Weapon::Spread => Duration::from_millis(120),
+            Weapon::Lance => Duration::from_millis(30),
+            Weapon::Beam => Duration::from_millis(100),
+            Weapon::Frost => Duration::from_millis(200),
+        }
+    }
+
+    pub fn bullet_velocity(&self) -> f32 {
+        match self {
+            Weapon::Blaster => 750.0,
+            Weapon::Spread => 600.0,
+            Weapon::Lance => 1200.0,
+            Weapon::Frost => 500.0,
+            // never fires any
+            Weapon::Beam => 0.0,
+        }
+    }
+
+    /// Angles in radians, relative to the ship's facing, of the bullets fired with each shot.
+    pub fn spread(&self) -> &'static [f32] {
+        match self {
+            Weapon::Blaster | Weapon::Lance | Weapon::Frost => &[0.0],
+            Weapon::Spread => &[-0.2, 0.0, 0.2],
+            Weapon::Beam => &[],
+        }
+    }
+
+    pub fn is_beam(&self) -> bool {
+        *self == Weapon::Beam
+    }
+
+    pub fn is_frost(&self) -> bool {
+        *self == Weapon::Frost
+    }
+}
+
+/// Rules the next run is played by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum GameMode {
+    Standard,
+    /// the safe part of the arena contracts after every wave
+    ShrinkingArena,
+    /// a boss arrives with every wave and nothing else does
+    BossRush,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 3] = [
+        GameMode::Standard,
+        GameMode::ShrinkingArena,
+        GameMode::BossRush,
+    ];
+
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            GameMode::Standard => "mode.standard",
+            GameMode::ShrinkingArena => "mode.shrinking_arena",
+            GameMode::BossRush => "mode.boss_rush",
+        }
+    }
+}
+
+/// Ship, starting weapon, mode, mutators and seed the next run is played with.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Loadout {
+    pub ship: Ship,
+    pub weapon: Weapon,
+    pub mode: GameMode,
+    /// in the order they're listed in
+    pub mutators: Vec<Mutator>,
+    /// whether the run is played on the day's seed rather than a fresh one, the same for every
+    /// run that day
+    pub daily: bool,
+}
+
+impl save::Versioned for Loadout {
+    const VERSION: u32 = 1;
+}
+
+impl Loadout {
+    /// Time between shots for the selected weapon, after the ship's passive is applied.
+    pub fn fire_interval(&self) -> Duration {
+        let interval = self.weapon.fire_interval();
+        match self.ship.passive() {
+            Passive::Overclock => interval.mul_f32(0.8),
+            _ => interval,
+        }
+    }
+
+    pub fn has(&self, mutator: Mutator) -> bool {
+        self.mutators.contains(&mutator)
+    }
+
+    /// Picks the mutator if it isn't already, or drops it if it is.
+    fn toggle(&mut self, mutator: Mutator) {
+        if self.has(mutator) {
+            self.mutators.retain(|picked| *picked != mutator);
+        } else {
+            self.mutators.push(mutator);
+            self.mutators
+                .sort_by_key(|picked| Mutator::ALL.iter().position(|m| m == picked));
+        }
+    }
+}
+
+impl Default for Loadout {
+    fn default() -> Self {
+        Self {
+            ship: Ship::Interceptor,
+            weapon: Weapon::Blaster,
+            mode: GameMode::Standard,
+            mutators: Vec::new(),
+            daily: false,
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+enum LoadoutButton {
+    PreviousWeapon,
+    NextWeapon,
+    PreviousMode,
+    NextMode,
+    ToggleMutator(Mutator),
+    ToggleDaily,
+}
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct ValidateLoadout;
+
+#[derive(Component)]
+struct LoadoutScreen;
+
+#[derive(Component)]
+struct WeaponLabel;
+
+#[derive(Component)]
+struct ModeLabel;
+
+#[derive(Component)]
+struct MutatorLabel(Mutator);
+
+#[derive(Component)]
+struct DailyLabel;
+
+pub struct LoadoutPlugin;
+
+impl Plugin for LoadoutPlugin {
+    fn build(&self, app: &mut App) {
+        let mut loadout = save::load::<Loadout>(LOADOUT_FILE);
+        let cli = app.world.resource::<Cli>();
+        if let Some(mode) = cli.mode {
+            loadout.mode = mode;
+        }
+        if !cli.mutators.is_empty() {
+            loadout.mutators = cli.mutators.clone();
+        }
+        app.insert_resource(loadout)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Loadout)
+                    .with_system(validate_loadout.label(ValidateLoadout))
+                    .with_system(spawn_loadout_screen.after(ValidateLoadout)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Loadout)
+                    .with_system(loadout_button_system)
+                    .with_system(loadout_label_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Loadout)
+                    .with_system(store_loadout)
+                    .with_system(despawn_with::<LoadoutScreen>),
+            );
+    }
+}
+
+/// Steps from `current` through `options` in `direction`, skipping options that are still locked.
+fn cycle<T: Copy + PartialEq>(
+    options: &[T],
+    current: T,
+    direction: isize,
+    unlocked: impl Fn(T) -> bool,
+) -> T {
+    let len = options.len() as isize;
+    let start = options.iter().position(|o| *o == current).unwrap_or(0) as isize;
+    (1..=len)
+        .map(|step| options[(start + step * direction).rem_euclid(len) as usize])
+        .find(|option| unlocked(*option))
+        .unwrap_or(current)
+}
+
+/// Falls back to the default weapon if the saved one is no longer unlocked.
+fn validate_loadout(
+    mut loadout: ResMut<Loadout>,
+    achievements: Res<Achievements>,
+    lifetime: Res<LifetimeStats>,
+) {
+    if !loadout
+        .weapon
+        .requirement()
+        .is_met(&achievements, &lifetime)
+    {
+        loadout.weapon = Loadout::default().weapon;
+    }
+}
+
+fn store_loadout(loadout: Res<Loadout>) {
+    save::store(LOADOUT_FILE, &*loadout);
+}
+
+fn spawn_loadout_screen(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    locale: Res<Locale>,
+    loadout: Res<Loadout>,
+    tunings: Res<WeaponTunings>,
+    achievements: Res<Achievements>,
+    lifetime: Res<LifetimeStats>,
+) {
+    let text_style = TextStyle {
+        font: font.0.clone(),
+        font_size: 26.0,
+        color: Color::rgb(0.85, 0.85, 0.85),
+    };
+    let locked_style = TextStyle {
+        color: Color::GRAY,
+        font_size: 20.0,
+        ..text_style.clone()
+    };
+    let mutator_style = TextStyle {
+        font_size: 20.0,
+        ..text_style.clone()
+    };
+
+    commands
+        .spawn_bundle(screen_node())
+        .insert(LoadoutScreen)
+        .with_children(|parent| {
+            spawn_title(parent, &font, &locale, "loadout.title");
+
+            spawn_cycle_row(
+                parent,
+                &font,
+                TextSection {
+                    value: weapon_description(&locale, &tunings, &loadout),
+                    style: text_style.clone(),
+                },
+                [LoadoutButton::PreviousWeapon, LoadoutButton::NextWeapon],
+                WeaponLabel,
+            );
+            spawn_cycle_row(
+                parent,
+                &font,
+                TextSection {
+                    value: mode_description(&locale, loadout.mode),
+                    style: text_style.clone(),
+                },
+                [LoadoutButton::PreviousMode, LoadoutButton::NextMode],
+                ModeLabel,
+            );
+
+            for mutator in Mutator::ALL {
+                spawn_toggle(
+                    parent,
+                    TextSection {
+                        value: mutator_description(&locale, &loadout, mutator),
+                        style: mutator_style.clone(),
+                    },
+                    LoadoutButton::ToggleMutator(mutator),
+                    MutatorLabel(mutator),
+                );
+            }
+            spawn_toggle(
+                parent,
+                TextSection {
+                    value: daily_description(&locale, &loadout),
+                    style: mutator_style.clone(),
+                },
+                LoadoutButton::ToggleDaily,
+                DailyLabel,
+            );
+
+            for weapon in Weapon::ALL {
+                let requirement = weapon.requirement();
+                if requirement.is_met(&achievements, &lifetime) {
+                    continue;
+                }
+                parent.spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        locale.format(
+                            "loadout.locked_weapon",
+                            &[
+                                ("weapon", &locale.get(weapon.name_key())),
+                                ("requirement", &requirement.describe(&locale)),
+                            ],
+                        ),
+                        locked_style.clone(),
+                        default(),
+                    ),
+                    ..default()
+                });
+            }
+
+            spawn_button(parent, &font, &locale, "menu.start", MenuButton::StartRun);
+            spawn_button(parent, &font, &locale, "menu.back", MenuButton::Play);
+        });
+}
+
+fn weapon_description(locale: &Locale, tunings: &WeaponTunings, loadout: &Loadout) -> String {
+    let weapon = loadout.weapon;
+    if weapon.is_beam() {
+        return locale.format(
+            "loadout.weapon_beam",
+            &[
+                ("weapon", &locale.get(weapon.name_key())),
+                ("element", &locale.get(tunings.element(weapon).name_key())),
+            ],
+        );
+    }
+    locale.format(
+        "loadout.weapon",
+        &[
+            ("weapon", &locale.get(weapon.name_key())),
+            (
+                "rate",
+                &(1.0 / loadout.fire_interval().as_secs_f32()).round(),
+            ),
+            ("bullets", &weapon.spread().len()),
+            ("element", &locale.get(tunings.element(weapon).name_key())),
+        ],
+    )
+}
+
+fn mode_description(locale: &Locale, mode: GameMode) -> String {
+    locale.format("loadout.mode", &[("mode", &locale.get(mode.name_key()))])
+}
+
+fn mutator_description(locale: &Locale, loadout: &Loadout, mutator: Mutator) -> String {
+    let key = if loadout.has(mutator) {
+        "loadout.mutator_on"
+    } else {
+        "loadout.mutator_off"
+    };
+    locale.format(key, &[("mutator", &locale.get(mutator.name_key()))])
+}
+
+fn daily_description(locale: &Locale, loadout: &Loadout) -> String {
+    let key = if loadout.daily {
+        "loadout.mutator_on"
+    } else {
+        "loadout.mutator_off"
+    };
+    locale.format(key, &[("mutator", &locale.get("loadout.daily"))])
+}
+
+/// Spawns a button turning a mutator or the daily seed on and off, labelled with whether it is.
+fn spawn_toggle(
+    parent: &mut ChildBuilder,
+    text: TextSection,
+    action: LoadoutButton,
+    label: impl Component,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(480.0), Val::Px(30.0)),
+                margin: Rect::all(Val::Px(2.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::rgb(0.15, 0.15, 0.15).into(),
+            ..default()
+        })
+        .insert(action)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text {
+                        sections: vec![text],
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(label);
+        });
+}
+
+/// Spawns a line of text marked with `label` between buttons stepping backwards and forwards
+/// through the options it shows.
+fn spawn_cycle_row(
+    parent: &mut ChildBuilder,
+    font: &UiFont,
+    text: TextSection,
+    [previous, next]: [LoadoutButton; 2],
+    label: impl Component,
+) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|row| {
+            spawn_cycle_button(row, font, "<", previous);
+            row.spawn_bundle(TextBundle {
+                style: Style {
+                    size: Size::new(Val::Px(480.0), Val::Auto),
+                    margin: Rect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                text: Text {
+                    sections: vec![text],
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(label);
+            spawn_cycle_button(row, font, ">", next);
+        });
+}
+
+fn spawn_cycle_button(
+    parent: &mut ChildBuilder,
+    font: &UiFont,
+    label: &str,
+    action: LoadoutButton,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(48.0), Val::Px(48.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::rgb(0.15, 0.15, 0.15).into(),
+            ..default()
+        })
+        .insert(action)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    label,
+                    TextStyle {
+                        font: font.0.clone(),
+                        font_size: 32.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                    default(),
+                ),
+                ..default()
+            });
+        });
+}
+
+fn loadout_button_system(
+    buttons: Query<(&Interaction, &LoadoutButton), Changed<Interaction>>,
+    mut loadout: ResMut<Loadout>,
+    achievements: Res<Achievements>,
+    lifetime: Res<LifetimeStats>,
+) {
+    for (interaction, action) in buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let unlocked = |weapon: Weapon| weapon.requirement().is_met(&achievements, &lifetime);
+        match action {
+            LoadoutButton::PreviousWeapon => {
+                loadout.weapon = cycle(&Weapon::ALL, loadout.weapon, -1, unlocked)
+            }
+            LoadoutButton::NextWeapon => {
+                loadout.weapon = cycle(&Weapon::ALL, loadout.weapon, 1, unlocked)
+            }
+            LoadoutButton::PreviousMode => {
+                loadout.mode = cycle(&GameMode::ALL, loadout.mode, -1, |_| true)
+            }
+            LoadoutButton::NextMode => {
+                loadout.mode = cycle(&GameMode::ALL, loadout.mode, 1, |_| true)
+            }
+            LoadoutButton::ToggleMutator(mutator) => loadout.toggle(*mutator),
+            LoadoutButton::ToggleDaily => loadout.daily = !loadout.daily,
+        }
+    }
+}
+
+fn loadout_label_system(
+    loadout: Res<Loadout>,
+    locale: Res<Locale>,
+    tunings: Res<WeaponTunings>,
+    mut weapon_label: Query<&mut Text, (With<WeaponLabel>, Without<ModeLabel>)>,
+    mut mode_label: Query<&mut Text, (With<ModeLabel>, Without<MutatorLabel>)>,
+    mut mutator_labels: Query<(&mut Text, &MutatorLabel), Without<WeaponLabel>>,
+    mut daily_label: Query<
+        &mut Text,
+        (
+            With<DailyLabel>,
+            Without<WeaponLabel>,
+            Without<ModeLabel>,
+            Without<MutatorLabel>,
+        ),
+    >,
+) {
+    if !loadout.is_changed() && !locale.is_changed() && !tunings.is_changed() {
+        return;
+    }
+    for mut text in weapon_label.iter_mut() {
+        text.sections[0].value = weapon_description(&locale, &tunings, &loadout);
+    }
+    for mut text in mode_label.iter_mut() {
+        text.sections[0].value = mode_description(&locale, loadout.mode);
+    }
+    for (mut text, MutatorLabel(mutator)) in mutator_labels.iter_mut() {
+        text.sections[0].value = mutator_description(&locale, &loadout, *mutator);
+    }
+    for mut text in daily_label.iter_mut() {
+        text.sections[0].value = daily_description(&locale, &loadout);
+    }
+}
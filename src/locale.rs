@@ -0,0 +1,139 @@
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+use bevy::prelude::*;
+use futures_lite::future;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+/// Directory among the assets holding one `<code>.json` string table per language.
+const LOCALES_DIR: &str = "locales";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+        }
+    }
+
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let index = Language::ALL.iter().position(|l| l == self).unwrap_or(0);
+        Language::ALL[(index + 1) % Language::ALL.len()]
+    }
+}
+
+/// UI strings for the selected language, keyed by identifiers like `menu.play`.
+pub struct Locale {
+    pub language: Language,
+    strings: HashMap<String, String>,
+    /// English strings used for keys the selected language doesn't translate
+    fallback: HashMap<String, String>,
+}
+
+impl Locale {
+    /// Reads the language's strings through the asset server, from the content packs ahead of the
+    /// game's own files.
+    pub fn load(asset_server: &AssetServer, language: Language) -> Self {
+        let fallback = if language == Language::English {
+            HashMap::new()
+        } else {
+            load_strings(asset_server, Language::English)
+        };
+        Self {
+            language,
+            strings: load_strings(asset_server, language),
+            fallback,
+        }
+    }
+
+    /// The string for `key`, or the key itself if no language defines it.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.get_or(key, key)
+    }
+
+    /// The string for `key`, or `fallback` if no language defines it.
+    pub fn get_or<'a>(&'a self, key: &str, fallback: &'a str) -> &'a str {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map_or(fallback, String::as_str)
+    }
+
+    /// The string for `key` with every `{name}` placeholder replaced by the matching argument.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        args.iter()
+            .fold(self.get(key).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{}}}", name), &value.to_string())
+            })
+    }
+}
+
+fn load_strings(asset_server: &AssetServer, language: Language) -> HashMap<String, String> {
+    let path = Path::new(LOCALES_DIR)
+        .join(language.code())
+        .with_extension("json");
+    // the string tables are small and needed right away, so the read is waited on
+    let contents = match future::block_on(asset_server.asset_io().load_path(&path)) {
+        Ok(contents) => contents,
+        Err(err) => {
+            warn!("failed to read {}: {}", path.display(), err);
+            return HashMap::new();
+        }
+    };
+    serde_json::from_slice(&contents).unwrap_or_else(|err| {
+        warn!("failed to parse {}: {}", path.display(), err);
+        HashMap::new()
+    })
+}
+
+/// Text whose first section always shows the string for the given key in the current language.
+#[derive(Component)]
+pub struct Localized(pub &'static str);
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        let language = app.world.resource::<Settings>().language;
+        let locale = Locale::load(app.world.resource::<AssetServer>(), language);
+        app.insert_resource(locale)
+            .add_system(reload_locale_system)
+            .add_system(localized_text_system);
+    }
+}
+
+fn reload_locale_system(
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut locale: ResMut<Locale>,
+) {
+    if settings.is_changed() && settings.language != locale.language {
+        *locale = Locale::load(&asset_server, settings.language);
+    }
+}
+
+fn localized_text_system(locale: Res<Locale>, mut texts: Query<(&mut Text, &Localized)>) {
+    if !locale.is_changed() {
+        return;
+    }
+    for (mut text, Localized(key)) in texts.iter_mut() {
+        text.sections[0].value = locale.get(key).to_string();
+    }
+}
@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+
+use crate::{
+    elements::Element,
+    events::BashKill,
+    input_buffer::{BufferedAction, InputBuffer, StepInput},
+    knockback::Knockback,
+    layers::RenderLayer,
+    palette::Palette,
+    AddSimulationSystem, EnemyDamage, Hit, Player, Targets, TimeScale,
+};
+
+/// Seconds between bashes.
+const BASH_COOLDOWN: f32 = 1.5;
+/// Reach of the bash from the ship's center, enemies only need to overlap it with their edge.
+const BASH_RANGE: f32 = 70.0;
+/// Half the angle in radians of the arc in front of the ship the bash sweeps.
+const BASH_HALF_ARC: f32 = std::f32::consts::FRAC_PI_3;
+/// Speed enemies that survive the bash are shoved away with.
+const KNOCKBACK_SPEED: f32 = 900.0;
+const SWIPE_SEGMENTS: usize = 9;
+const SWIPE_LIFETIME: f32 = 0.15;
+
+/// Close-range bash on a cooldown, set off with C, hitting and shoving back whatever is in front
+/// of the ship.
+#[derive(Component)]
+pub struct Melee {
+    cooldown: Timer,
+}
+
+impl Default for Melee {
+    fn default() -> Self {
+        let mut cooldown = Timer::from_seconds(BASH_COOLDOWN, false);
+        cooldown.tick(cooldown.duration());
+        Self { cooldown }
+    }
+}
+
+/// Part of the arc drawn where a bash swept through, fading out quickly.
+#[derive(Component)]
+struct SwipeSegment(Timer);
+
+pub struct MeleePlugin;
+
+impl Plugin for MeleePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(bash_system)
+            .add_simulation_system(swipe_system);
+    }
+}
+
+/// Whether an enemy of the given size at `offset` from the ship overlaps the arc swept by a
+/// bash towards `facing`.
+fn in_arc(offset: Vec2, size: f32, facing: Vec2) -> bool {
+    let distance = offset.length();
+    if distance > BASH_RANGE + size / 2.0 {
+        return false;
+    }
+    // an enemy on top of the ship is hit whichever way it faces
+    distance <= size / 2.0 || facing.angle_between(offset).abs() <= BASH_HALF_ARC
+}
+
+fn bash_system(
+    time_scale: Res<TimeScale>,
+    input: Res<StepInput>,
+    mut buffer: ResMut<InputBuffer>,
+    mut player: Query<(&Transform, &mut Melee), With<Player>>,
+    mut enemies: Targets<Without<Player>>,
+    mut damage: EnemyDamage,
+    mut bash_kill: EventWriter<BashKill>,
+) {
+    let (transform, mut melee) = match player.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    melee.cooldown.tick(time_scale.step_duration());
+    let bashing = melee.cooldown.finished()
+        && (buffer.take(BufferedAction::Bash) || input.pressed(KeyCode::C));
+    if !bashing {
+        return;
+    }
+    melee.cooldown.reset();
+
+    let position = transform.translation;
+    let facing = (transform.rotation * Vec3::Y).truncate();
+    for (entity, enemy, enemy_transform, mut elite, splitter) in enemies.iter_mut() {
+        let offset = (enemy_transform.translation - position).truncate();
+        if !in_arc(offset, enemy.size, facing) {
+            continue;
+        }
+        let direction = offset.try_normalize().unwrap_or(facing).extend(0.0);
+        let hit = damage.hit(
+            entity,
+            enemy,
+            enemy_transform.translation,
+            elite.as_deref_mut(),
+            splitter,
+            direction,
+            Element::Kinetic,
+        );
+        if hit == Hit::Killed {
+            bash_kill.send(BashKill);
+            continue;
+        }
+        // shields and armor take the hit, but not the shove
+        damage
+            .commands
+            .entity(entity)
+            .insert(Knockback(direction * KNOCKBACK_SPEED));
+    }
+    spawn_swipe(&mut damage.commands, &damage.palette, position, facing);
+}
+
+fn spawn_swipe(commands: &mut Commands, palette: &Palette, position: Vec3, facing: Vec2) {
+    let mut color = palette.player.unwrap_or(Color::WHITE);
+    color.set_a(0.8);
+    let facing_angle = facing.y.atan2(facing.x);
+    for segment in 0..SWIPE_SEGMENTS {
+        let t = segment as f32 / (SWIPE_SEGMENTS - 1) as f32;
+        let angle = facing_angle - BASH_HALF_ARC + 2.0 * BASH_HALF_ARC * t;
+        let offset = Vec2::new(angle.cos(), angle.sin()) * BASH_RANGE;
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    // each segment runs along the arc, together they make up its length
+                    custom_size: Some(Vec2::new(
+                        4.0,
+                        2.0 * BASH_HALF_ARC * BASH_RANGE / (SWIPE_SEGMENTS - 1) as f32 + 2.0,
+                    )),
+                    ..default()
+                },
+                transform: Transform::from_translation((position.truncate() + offset).extend(0.05))
+                    .with_rotation(Quat::from_rotation_z(angle)),
+                ..default()
+            })
+            .insert(RenderLayer::Fx)
+            .insert(SwipeSegment(Timer::from_seconds(SWIPE_LIFETIME, false)));
+    }
+}
+
+fn swipe_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut segments: Query<(Entity, &mut SwipeSegment, &mut Sprite)>,
+) {
+    for (entity, mut segment, mut sprite) in segments.iter_mut() {
+        if segment.0.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        sprite.color.set_a(0.8 * segment.0.percent_left());
+    }
+}
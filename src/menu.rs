@@ -0,0 +1,196 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    despawn_with,
+    locale::{Locale, Localized},
+    tutorial::Onboarding,
+    GameState, UiFont,
+};
+
+const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+const PRESSED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.75);
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+
+/// What a menu button does when clicked.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuButton {
+    Play,
+    Loadout,
+    StartRun,
+    Tutorial,
+    Achievements,
+    Settings,
+    Packs,
+    Editor,
+    MainMenu,
+    Quit,
+}
+
+#[derive(Component)]
+struct MainMenuScreen;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Menu).with_system(spawn_main_menu))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Menu).with_system(despawn_with::<MainMenuScreen>),
+            )
+            .add_system(button_color_system)
+            .add_system(menu_action_system);
+    }
+}
+
+/// Root node for a full-screen menu page, laying its children out top to bottom.
+pub fn screen_node() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }
+}
+
+pub fn spawn_title(parent: &mut ChildBuilder, font: &UiFont, locale: &Locale, key: &'static str) {
+    parent
+        .spawn_bundle(TextBundle {
+            style: Style {
+                margin: Rect::all(Val::Px(30.0)),
+                ..default()
+            },
+            text: Text::with_section(
+                locale.get(key),
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 64.0,
+                    color: TEXT_COLOR,
+                },
+                default(),
+            ),
+            ..default()
+        })
+        .insert(Localized(key));
+}
+
+pub fn spawn_button(
+    parent: &mut ChildBuilder,
+    font: &UiFont,
+    locale: &Locale,
+    key: &'static str,
+    action: MenuButton,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(260.0), Val::Px(56.0)),
+                margin: Rect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: NORMAL_BUTTON.into(),
+            ..default()
+        })
+        .insert(action)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        locale.get(key),
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: 32.0,
+                            color: TEXT_COLOR,
+                        },
+                        default(),
+                    ),
+                    ..default()
+                })
+                .insert(Localized(key));
+        });
+}
+
+fn spawn_main_menu(mut commands: Commands, font: Res<UiFont>, locale: Res<Locale>) {
+    commands
+        .spawn_bundle(screen_node())
+        .insert(MainMenuScreen)
+        .with_children(|parent| {
+            spawn_title(parent, &font, &locale, "menu.title");
+            spawn_button(parent, &font, &locale, "menu.play", MenuButton::Play);
+            spawn_button(
+                parent,
+                &font,
+                &locale,
+                "menu.tutorial",
+                MenuButton::Tutorial,
+            );
+            spawn_button(
+                parent,
+                &font,
+                &locale,
+                "menu.achievements",
+                MenuButton::Achievements,
+            );
+            spawn_button(
+                parent,
+                &font,
+                &locale,
+                "menu.settings",
+                MenuButton::Settings,
+            );
+            spawn_button(parent, &font, &locale, "menu.packs", MenuButton::Packs);
+            spawn_button(parent, &font, &locale, "menu.editor", MenuButton::Editor);
+            spawn_button(parent, &font, &locale, "menu.quit", MenuButton::Quit);
+        });
+}
+
+fn button_color_system(
+    mut buttons: Query<(&Interaction, &mut UiColor), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, mut color) in buttons.iter_mut() {
+        *color = match *interaction {
+            Interaction::Clicked => PRESSED_BUTTON.into(),
+            Interaction::Hovered => HOVERED_BUTTON.into(),
+            Interaction::None => NORMAL_BUTTON.into(),
+        };
+    }
+}
+
+fn menu_action_system(
+    buttons: Query<(&Interaction, &MenuButton), (Changed<Interaction>, With<Button>)>,
+    mut state: ResMut<State<GameState>>,
+    onboarding: Res<Onboarding>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    for (interaction, action) in buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let next = match action {
+            // first-time players are walked through the tutorial before their first run
+            MenuButton::Play if !onboarding.tutorial_completed => GameState::Tutorial,
+            MenuButton::Play => GameState::ShipSelect,
+            MenuButton::Loadout => GameState::Loadout,
+            MenuButton::StartRun => GameState::Playing,
+            MenuButton::Tutorial => GameState::Tutorial,
+            MenuButton::Achievements => GameState::Achievements,
+            MenuButton::Settings => GameState::Settings,
+            MenuButton::Packs => GameState::Packs,
+            MenuButton::Editor => GameState::Editor,
+            MenuButton::MainMenu => GameState::Menu,
+            MenuButton::Quit => {
+                app_exit.send(AppExit);
+                continue;
+            }
+        };
+        // a transition queued first this frame, like pausing as the window loses focus, wins
+        let _ = state.set(next);
+    }
+}
@@ -0,0 +1,58 @@
+use bevy::{
+    math::const_vec2,
+    prelude::*,
+    window::{WindowCreated, WindowResized},
+};
+
+use crate::BOUNDS;
+
+/// Whether the game is built for a phone or tablet.
+pub const MOBILE: bool = cfg!(any(target_os = "android", target_os = "ios"));
+/// Room around the arena the screen has to fit, the HUD sits in it.
+const MARGIN: Vec2 = const_vec2!([80.0, 80.0]);
+
+pub struct MobilePlugin;
+
+impl Plugin for MobilePlugin {
+    fn build(&self, app: &mut App) {
+        if !MOBILE {
+            return;
+        }
+        app.add_system(scale_system);
+    }
+}
+
+/// Scales everything on screen so the arena fits across the screen whichever way it's held.
+///
+/// The whole game is laid out in logical pixels, and a phone's screen is only a few hundred of
+/// them wide even at its high pixel density. Overriding the scale factor makes as many logical
+/// pixels fit as the arena needs, which scales the UI along with everything else.
+fn scale_system(
+    mut windows: ResMut<Windows>,
+    mut created: EventReader<WindowCreated>,
+    mut resized: EventReader<WindowResized>,
+) {
+    let created = created.iter().count() > 0;
+    let resized = resized.iter().count() > 0;
+    if !created && !resized {
+        return;
+    }
+    let window = match windows.get_primary_mut() {
+        Some(window) => window,
+        None => return,
+    };
+    let physical = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+    // in portrait the arena's width is what has to fit, in landscape mostly its height
+    let fit = physical / (BOUNDS + MARGIN);
+    let scale_factor = fit.min_element() as f64;
+    // the new scale factor resizes the window, which comes back here
+    let unchanged = window
+        .scale_factor_override()
+        .is_some_and(|current| (current - scale_factor).abs() < 0.01);
+    if !unchanged && scale_factor > 0.0 {
+        window.set_scale_factor_override(Some(scale_factor));
+    }
+}
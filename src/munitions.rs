@@ -0,0 +1,279 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    deaths::Dying, elements::Element, events::PickupCollected, layers::RenderLayer,
+    loadout::Weapon, packs::ContentPacks, palette::Palette, rng::GameRng, AddSimulationSystem,
+    Enemy, Player, TimeScale,
+};
+
+pub const WEAPONS_FILE: &str = "weapons.ron";
+/// Chance of a killed enemy dropping ammo while the player's weapon runs on it.
+const AMMO_DROP_CHANCE: f64 = 0.2;
+const PICKUP_SIZE: f32 = 10.0;
+/// Distance past the player's hit radius from which a pickup is collected.
+const PICKUP_RADIUS: f32 = 16.0;
+/// Seconds a pickup lies around before it's gone.
+const PICKUP_LIFETIME: f32 = 10.0;
+/// Above the mines among the decals.
+const PICKUP_DEPTH: f32 = 0.7;
+
+/// What the player's weapon fires from, a hardcore setting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Munitions {
+    /// as fast as the weapon fires, for as long as the run lasts
+    #[default]
+    Unlimited,
+    /// firing heats the weapon up until it overheats and locks out until it has cooled down
+    Heat,
+    /// every shot uses up ammo, replenished by pickups killed enemies drop
+    Ammo,
+}
+
+impl Munitions {
+    pub const ALL: [Munitions; 3] = [Munitions::Unlimited, Munitions::Heat, Munitions::Ammo];
+
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            Munitions::Unlimited => "munitions.unlimited",
+            Munitions::Heat => "munitions.heat",
+            Munitions::Ammo => "munitions.ammo",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let index = Munitions::ALL.iter().position(|m| m == self).unwrap_or(0);
+        Munitions::ALL[(index + 1) % Munitions::ALL.len()]
+    }
+}
+
+/// What a weapon's hits are made of and how it heats up and how much ammo it carries, as
+/// described in `WEAPONS_FILE`.
+#[derive(Deserialize)]
+pub struct WeaponTuning {
+    weapon: Weapon,
+    element: Element,
+    /// none for weapons that aren't limited by the weapon supply setting
+    #[serde(default)]
+    supply: Option<SupplyTuning>,
+}
+
+/// How a weapon heats up and how much ammo it carries.
+#[derive(Clone, Deserialize)]
+pub struct SupplyTuning {
+    /// heat every shot adds, the weapon overheats once it adds up to 1
+    heat_per_shot: f32,
+    /// heat shed every second
+    cooling: f32,
+    /// seconds an overheated weapon can't fire for
+    lockout: f32,
+    /// shots a run starts with, and the most the player can carry
+    magazine: u32,
+    /// shots an ammo pickup gives back
+    refill: u32,
+}
+
+/// Tuning of every weapon listed, the ones listed last taking precedence.
+pub struct WeaponTunings(Vec<WeaponTuning>);
+
+impl WeaponTunings {
+    fn load(packs: &ContentPacks) -> Self {
+        Self(packs.load_all(WEAPONS_FILE))
+    }
+
+    fn get(&self, weapon: Weapon) -> Option<&WeaponTuning> {
+        self.0.iter().rev().find(|tuning| tuning.weapon == weapon)
+    }
+
+    /// What `weapon`'s hits are made of, kinetic unless it's listed.
+    pub fn element(&self, weapon: Weapon) -> Element {
+        self.get(weapon)
+            .map_or(Element::Kinetic, |tuning| tuning.element)
+    }
+
+    /// Supply the player fires `weapon` from under `munitions`, none if it's unlimited or the
+    /// weapon isn't tuned for it, like the beam with an energy meter of its own.
+    pub fn supply(&self, munitions: Munitions, weapon: Weapon) -> Option<WeaponSupply> {
+        let tuning = self.get(weapon)?.supply.as_ref()?;
+        let state = match munitions {
+            Munitions::Unlimited => return None,
+            Munitions::Heat => Supply::Heat {
+                heat: 0.0,
+                lockout: None,
+            },
+            Munitions::Ammo => Supply::Ammo {
+                rounds: tuning.magazine,
+            },
+        };
+        Some(WeaponSupply {
+            tuning: tuning.clone(),
+            state,
+        })
+    }
+}
+
+enum Supply {
+    Heat {
+        heat: f32,
+        /// counting down while the weapon is overheated
+        lockout: Option<Timer>,
+    },
+    Ammo {
+        rounds: u32,
+    },
+}
+
+/// Heat or ammo of the player's weapon, on the player while it isn't unlimited.
+#[derive(Component)]
+pub struct WeaponSupply {
+    tuning: SupplyTuning,
+    state: Supply,
+}
+
+impl WeaponSupply {
+    /// Whether the weapon is neither overheated nor out of ammo.
+    pub fn can_fire(&self) -> bool {
+        match &self.state {
+            Supply::Heat { lockout, .. } => lockout.is_none(),
+            Supply::Ammo { rounds } => *rounds > 0,
+        }
+    }
+
+    /// Heats the weapon up or uses up ammo for a shot.
+    pub fn fire(&mut self) {
+        match &mut self.state {
+            Supply::Heat { heat, lockout } => {
+                *heat += self.tuning.heat_per_shot;
+                if *heat >= 1.0 {
+                    *heat = 1.0;
+                    *lockout = Some(Timer::from_seconds(self.tuning.lockout, false));
+                }
+            }
+            Supply::Ammo { rounds } => *rounds = rounds.saturating_sub(1),
+        }
+    }
+
+    /// Fraction of the gauge that's filled, the heat built up or the ammo left.
+    pub fn gauge(&self) -> f32 {
+        match &self.state {
+            Supply::Heat { heat, .. } => *heat,
+            Supply::Ammo { rounds } => *rounds as f32 / self.tuning.magazine.max(1) as f32,
+        }
+    }
+
+    pub fn uses_ammo(&self) -> bool {
+        matches!(self.state, Supply::Ammo { .. })
+    }
+
+    pub fn label_key(&self) -> &'static str {
+        if self.uses_ammo() {
+            "hud.ammo"
+        } else {
+            "hud.heat"
+        }
+    }
+}
+
+/// Ammo lying in the arena for the player to collect.
+#[derive(Component)]
+struct AmmoPickup(Timer);
+
+pub struct MunitionsPlugin;
+
+impl Plugin for MunitionsPlugin {
+    fn build(&self, app: &mut App) {
+        let tunings = WeaponTunings::load(app.world.resource::<ContentPacks>());
+        app.insert_resource(tunings)
+            .add_system(reload_system)
+            .add_simulation_system(cooling_system)
+            .add_simulation_system(drop_system)
+            .add_simulation_system(pickup_system);
+    }
+}
+
+fn reload_system(mut commands: Commands, packs: Res<ContentPacks>) {
+    if packs.is_changed() && !packs.is_added() {
+        commands.insert_resource(WeaponTunings::load(&packs));
+    }
+}
+
+/// Cools the player's weapon down, ending its lockout once it's over.
+fn cooling_system(time_scale: Res<TimeScale>, mut player: Query<&mut WeaponSupply, With<Player>>) {
+    let mut supply = match player.get_single_mut() {
+        Ok(supply) => supply,
+        Err(_) => return,
+    };
+    let cooling = supply.tuning.cooling * time_scale.step();
+    if let Supply::Heat { heat, lockout } = &mut supply.state {
+        *heat = (*heat - cooling).max(0.0);
+        if lockout
+            .as_mut()
+            .is_some_and(|lockout| lockout.tick(time_scale.step_duration()).finished())
+        {
+            *lockout = None;
+        }
+    }
+}
+
+/// Leaves ammo behind some of the enemies killed while the player's weapon runs on it.
+fn drop_system(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    mut rng: ResMut<GameRng>,
+    player: Query<&WeaponSupply, With<Player>>,
+    killed: Query<&Transform, (With<Enemy>, Added<Dying>)>,
+) {
+    if !player.get_single().is_ok_and(WeaponSupply::uses_ammo) {
+        return;
+    }
+    for transform in killed.iter() {
+        if !rng.0.gen_bool(AMMO_DROP_CHANCE) {
+            continue;
+        }
+        let position = transform.translation.truncate().extend(PICKUP_DEPTH);
+        palette
+            .spawn_sprite(
+                &mut commands,
+                RenderLayer::Decals,
+                palette.ammo,
+                PICKUP_SIZE,
+                Transform::from_translation(position),
+            )
+            .insert(AmmoPickup(Timer::from_seconds(PICKUP_LIFETIME, false)));
+    }
+}
+
+/// Refills the player's ammo from the pickups they touch and clears away the ones left too long.
+fn pickup_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut player: Query<(&Player, &Transform, &mut WeaponSupply)>,
+    mut pickups: Query<(Entity, &mut AmmoPickup, &Transform), Without<Player>>,
+    mut pickup_collected: EventWriter<PickupCollected>,
+) {
+    let mut player = player.get_single_mut().ok();
+    for (entity, mut pickup, transform) in pickups.iter_mut() {
+        let collected = player
+            .as_mut()
+            .is_some_and(|(player, player_transform, _)| {
+                let distance = player_transform
+                    .translation
+                    .truncate()
+                    .distance(transform.translation.truncate());
+                distance <= player.hit_radius + PICKUP_RADIUS
+            });
+        if collected {
+            if let Some((_, _, supply)) = &mut player {
+                let (refill, magazine) = (supply.tuning.refill, supply.tuning.magazine);
+                if let Supply::Ammo { rounds } = &mut supply.state {
+                    *rounds = (*rounds + refill).min(magazine);
+                }
+            }
+            pickup_collected.send(PickupCollected);
+            commands.entity(entity).despawn_recursive();
+        } else if pickup.0.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
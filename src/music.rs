@@ -0,0 +1,215 @@
+use std::path::Path;
+
+use bevy::{asset::LoadState, audio::AudioSink, prelude::*};
+
+use crate::{
+    bosses::Boss, deaths::Dying, settings::Settings, waves::Wave, Enemy, GameState, Health, Player,
+};
+
+/// Directory, relative to the asset root, the soundtrack's stems are loaded from.
+const MUSIC_DIR: &str = "music";
+/// Tempo every stem is written in, in beats per minute.
+const BPM: f64 = 120.0;
+/// Rate per second a stem's volume eases towards its target.
+const FADE_EASING: f32 = 1.5;
+/// Player health at or below which the heartbeat comes in.
+const LOW_HEALTH: f32 = 2.0;
+/// Music volumes the settings screen cycles through.
+pub const VOLUMES: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+
+/// Layer of the soundtrack. All of them loop together from the start, as long as each other, and
+/// are faded in and out rather than started and stopped so they never drift apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stem {
+    /// always playing, the menus included
+    Pad,
+    Drums,
+    Bass,
+    /// comes in for bosses and the most hectic moments
+    Lead,
+    /// only while the player is one hit or two from death
+    Heartbeat,
+}
+
+impl Stem {
+    const ALL: [Stem; 5] = [
+        Stem::Pad,
+        Stem::Drums,
+        Stem::Bass,
+        Stem::Lead,
+        Stem::Heartbeat,
+    ];
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Stem::Pad => "pad.ogg",
+            Stem::Drums => "drums.ogg",
+            Stem::Bass => "bass.ogg",
+            Stem::Lead => "lead.ogg",
+            Stem::Heartbeat => "heartbeat.ogg",
+        }
+    }
+
+    /// Volume the stem should be faded to with the game as it is, from 0 to 1.
+    fn target_volume(&self, mood: &Mood) -> f32 {
+        match self {
+            Stem::Pad => 1.0,
+            _ if !mood.in_run => 0.0,
+            Stem::Drums => ramp(mood.intensity, 0.2),
+            Stem::Bass => ramp(mood.intensity, 0.45),
+            Stem::Lead if mood.boss => 1.0,
+            Stem::Lead => ramp(mood.intensity, 0.75),
+            Stem::Heartbeat if mood.low_health => 1.0,
+            Stem::Heartbeat => 0.0,
+        }
+    }
+}
+
+/// 0 up to `threshold`, rising to 1 over the next fifth of the intensity.
+fn ramp(intensity: f32, threshold: f32) -> f32 {
+    ((intensity - threshold) / 0.2).clamp(0.0, 1.0)
+}
+
+/// What the soundtrack follows.
+struct Mood {
+    /// a run is being played rather than paused or sat in the menus
+    in_run: bool,
+    intensity: f32,
+    boss: bool,
+    low_health: bool,
+}
+
+struct Track {
+    stem: Stem,
+    source: Handle<AudioSource>,
+    /// set once the stems start playing
+    sink: Option<Handle<AudioSink>>,
+    volume: f32,
+}
+
+/// The soundtrack's stems and the beat they keep.
+#[derive(Default)]
+pub struct Music {
+    tracks: Vec<Track>,
+    started: bool,
+    /// seconds since the stems started, or since the game did without any to play
+    elapsed: f64,
+}
+
+impl Music {
+    /// Beats since the soundtrack started, counted even when it's silent.
+    pub fn beat(&self) -> u64 {
+        (self.elapsed * BPM / 60.0) as u64
+    }
+
+    /// Whether any stems were found to play, the game ships without them.
+    pub fn has_stems(&self) -> bool {
+        !self.tracks.is_empty()
+    }
+}
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Music>()
+            .add_startup_system(load_stems)
+            .add_system(start_stems_system)
+            .add_system(mix_system.after(start_stems_system));
+    }
+}
+
+/// Loads whichever stems are in the music directory, the game plays without the rest.
+fn load_stems(asset_server: Res<AssetServer>, mut music: ResMut<Music>) {
+    let files: Vec<_> = match asset_server.asset_io().read_directory(Path::new(MUSIC_DIR)) {
+        Ok(files) => files.collect(),
+        Err(_) => {
+            info!(
+                "no soundtrack stems in {}, playing without music",
+                MUSIC_DIR
+            );
+            return;
+        }
+    };
+    for stem in Stem::ALL {
+        let path = Path::new(MUSIC_DIR).join(stem.file_name());
+        if files.contains(&path) {
+            music.tracks.push(Track {
+                stem,
+                source: asset_server.load(path),
+                sink: None,
+                volume: 0.0,
+            });
+        }
+    }
+}
+
+/// Starts every stem on the same frame once all of them have loaded.
+fn start_stems_system(
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    sinks: Res<Assets<AudioSink>>,
+    mut music: ResMut<Music>,
+) {
+    if music.started {
+        return;
+    }
+    music
+        .tracks
+        .retain(|track| asset_server.get_load_state(&track.source) != LoadState::Failed);
+    let loaded = music
+        .tracks
+        .iter()
+        .all(|track| asset_server.get_load_state(&track.source) == LoadState::Loaded);
+    if !loaded {
+        return;
+    }
+    for track in music.tracks.iter_mut() {
+        let sink = audio.play_with_settings(
+            track.source.clone(),
+            PlaybackSettings::LOOP.with_volume(0.0),
+        );
+        track.sink = Some(sinks.get_handle(sink));
+    }
+    music.started = true;
+}
+
+/// Fades the stems towards where the run is at and keeps the beat.
+fn mix_system(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    settings: Res<Settings>,
+    wave: Res<Wave>,
+    sinks: Res<Assets<AudioSink>>,
+    mut music: ResMut<Music>,
+    enemies: Query<(), (With<Enemy>, Without<Dying>)>,
+    bosses: Query<(), (With<Boss>, Without<Dying>)>,
+    player: Query<&Health, With<Player>>,
+) {
+    if !music.started {
+        return;
+    }
+    music.elapsed += time.delta_seconds_f64();
+
+    let in_run = state.current().is_simulated();
+    let mood = Mood {
+        in_run,
+        // the tutorial's handful of enemies shouldn't sound like a late wave
+        intensity: if *state.current() == GameState::Playing {
+            wave.intensity(enemies.iter().count())
+        } else {
+            0.0
+        },
+        boss: !bosses.is_empty(),
+        low_health: player
+            .get_single()
+            .is_ok_and(|health| health.current <= LOW_HEALTH),
+    };
+    let easing = (FADE_EASING * time.delta_seconds()).min(1.0);
+    for track in music.tracks.iter_mut() {
+        track.volume += (track.stem.target_volume(&mood) - track.volume) * easing;
+        if let Some(sink) = track.sink.as_ref().and_then(|sink| sinks.get(sink)) {
+            sink.set_volume(track.volume * settings.music_volume);
+        }
+    }
+}
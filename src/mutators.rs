@@ -0,0 +1,238 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    layers::RenderLayer, loadout::Loadout, palette::Palette, perks::Bounce, splitters::Splitter,
+    AddSimulationSystem, Bullet, Enemy, GameState, InsertIfAlive, Player, BOUNDS,
+};
+
+/// Factor enemies move faster by with `Mutator::FastEnemies`.
+const ENEMY_SPEED_FACTOR: f32 = 2.0;
+/// Factor the ship shrinks by with `Mutator::SmallShip`.
+const SHIP_SCALE: f32 = 0.5;
+/// Bounces every bullet the player fires gets with `Mutator::BouncingBullets`, on top of the ones
+/// from perks.
+const MUTATOR_BOUNCES: u32 = 2;
+/// How quickly a drifting ship comes round to the way it's steered, the higher the faster.
+const DRIFT_RESPONSE: f32 = 2.5;
+/// Side length of the tiles the fog is made of.
+const FOG_TILE: f32 = 40.0;
+/// Distance past the arena's edges the fog reaches, for no clear patch to show around it.
+const FOG_MARGIN: f32 = 200.0;
+/// Distance from the ship that's clear of fog, and past it over which the fog thickens.
+const FOG_CLEAR: f32 = 200.0;
+const FOG_FALLOFF: f32 = 140.0;
+const FOG_ALPHA: f32 = 0.95;
+/// Among the effects, above the lights so they don't cut through it.
+const FOG_DEPTH: f32 = 0.95;
+
+/// Rule picked on the loadout screen that changes how a run plays, on top of its mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Mutator {
+    /// enemies move twice as fast
+    FastEnemies,
+    /// the ship is half its size, hit radius included
+    SmallShip,
+    /// the player's bullets bounce off the walls
+    BouncingBullets,
+    /// the ship only slowly picks up speed and slows down, as if there were next to no gravity
+    Drift,
+    /// only the arena around the ship can be seen
+    Fog,
+}
+
+impl Mutator {
+    pub const ALL: [Mutator; 5] = [
+        Mutator::FastEnemies,
+        Mutator::SmallShip,
+        Mutator::BouncingBullets,
+        Mutator::Drift,
+        Mutator::Fog,
+    ];
+
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            Mutator::FastEnemies => "mutator.fast_enemies",
+            Mutator::SmallShip => "mutator.small_ship",
+            Mutator::BouncingBullets => "mutator.bouncing_bullets",
+            Mutator::Drift => "mutator.drift",
+            Mutator::Fog => "mutator.fog",
+        }
+    }
+}
+
+/// Way a ship drifting with `Mutator::Drift` is heading, which it only slowly comes round from to
+/// the way it's steered.
+#[derive(Component, Default)]
+pub struct Drift(Vec3);
+
+impl Drift {
+    /// Eases the drift toward `steering` over a step of `step` seconds, returning the way the ship
+    /// moves.
+    pub fn steer(&mut self, steering: Vec3, step: f32) -> Vec3 {
+        self.0 = self.0.lerp(steering, 1.0 - (-DRIFT_RESPONSE * step).exp());
+        self.0
+    }
+}
+
+/// Tile of the fog laid over the arena with `Mutator::Fog`.
+#[derive(Component)]
+struct FogTile;
+
+/// Every mutator's rules, each only applying to runs it was picked for. They don't depend on each
+/// other, so any of them can be combined.
+pub struct MutatorsPlugin;
+
+impl Plugin for MutatorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(FastEnemiesRule)
+            .add_plugin(SmallShipRule)
+            .add_plugin(BouncingBulletsRule)
+            .add_plugin(DriftRule)
+            .add_plugin(FogRule);
+    }
+}
+
+struct FastEnemiesRule;
+
+impl Plugin for FastEnemiesRule {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(speed_up_system);
+    }
+}
+
+fn speed_up_system(
+    loadout: Res<Loadout>,
+    mut enemies: Query<(&mut Enemy, Option<&Splitter>), Added<Enemy>>,
+) {
+    if !loadout.has(Mutator::FastEnemies) {
+        return;
+    }
+    for (mut enemy, splitter) in enemies.iter_mut() {
+        // splitters' children take after their parent, which was sped up already
+        if splitter.is_some_and(|splitter| splitter.generation > 0) {
+            continue;
+        }
+        enemy.velocity *= ENEMY_SPEED_FACTOR;
+    }
+}
+
+struct SmallShipRule;
+
+impl Plugin for SmallShipRule {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(shrink_system);
+    }
+}
+
+fn shrink_system(
+    loadout: Res<Loadout>,
+    mut player: Query<(&mut Player, &mut Transform), Added<Player>>,
+) {
+    if !loadout.has(Mutator::SmallShip) {
+        return;
+    }
+    for (mut player, mut transform) in player.iter_mut() {
+        player.hit_radius *= SHIP_SCALE;
+        transform.scale *= Vec3::new(SHIP_SCALE, SHIP_SCALE, 1.0);
+    }
+}
+
+struct BouncingBulletsRule;
+
+impl Plugin for BouncingBulletsRule {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(bounce_system);
+    }
+}
+
+fn bounce_system(
+    mut commands: Commands,
+    loadout: Res<Loadout>,
+    mut bullets: Query<(Entity, Option<&mut Bounce>), Added<Bullet>>,
+) {
+    if !loadout.has(Mutator::BouncingBullets) {
+        return;
+    }
+    for (entity, bounce) in bullets.iter_mut() {
+        match bounce {
+            Some(mut bounce) => bounce.0 += MUTATOR_BOUNCES,
+            None => commands.add(InsertIfAlive(entity, Bounce(MUTATOR_BOUNCES))),
+        }
+    }
+}
+
+struct DriftRule;
+
+impl Plugin for DriftRule {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(start_drift_system);
+    }
+}
+
+/// Sets the ship drifting, steered by the player's movement.
+fn start_drift_system(
+    mut commands: Commands,
+    loadout: Res<Loadout>,
+    player: Query<Entity, Added<Player>>,
+) {
+    if !loadout.has(Mutator::Drift) {
+        return;
+    }
+    for entity in player.iter() {
+        commands.add(InsertIfAlive(entity, Drift::default()));
+    }
+}
+
+struct FogRule;
+
+impl Plugin for FogRule {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_fog))
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(fog_system));
+    }
+}
+
+fn spawn_fog(mut commands: Commands, loadout: Res<Loadout>, palette: Res<Palette>) {
+    if !loadout.has(Mutator::Fog) {
+        return;
+    }
+    let extent = BOUNDS / 2.0 + Vec2::splat(FOG_MARGIN);
+    let tiles = (extent * 2.0 / FOG_TILE).ceil();
+    let mut color = palette.background;
+    color.set_a(FOG_ALPHA);
+    for x in 0..tiles.x as usize {
+        for y in 0..tiles.y as usize {
+            let position = -extent + (Vec2::new(x as f32, y as f32) + 0.5) * FOG_TILE;
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::splat(FOG_TILE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(position.extend(FOG_DEPTH)),
+                    ..default()
+                })
+                .insert(RenderLayer::Fx)
+                .insert(FogTile);
+        }
+    }
+}
+
+/// Clears the fog around the ship, thickening with the distance from it.
+fn fog_system(
+    player: Query<&Transform, With<Player>>,
+    mut tiles: Query<(&Transform, &mut Sprite), (With<FogTile>, Without<Player>)>,
+) {
+    let player = match player.get_single() {
+        Ok(player) => player.translation.truncate(),
+        // a ship that's gone leaves the fog as it was
+        Err(_) => return,
+    };
+    for (transform, mut sprite) in tiles.iter_mut() {
+        let distance = transform.translation.truncate().distance(player);
+        let thickness = ((distance - FOG_CLEAR) / FOG_FALLOFF).clamp(0.0, 1.0);
+        sprite.color.set_a(FOG_ALPHA * thickness);
+    }
+}
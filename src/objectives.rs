@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use crate::{
+    deaths::Dying,
+    events::{BashKill, EnemyKilled, ObjectiveGraded, ObjectiveSet, PlayerDamaged, WaveStarted},
+    loadout::{GameMode, Loadout},
+    packs::ContentPacks,
+    rng::GameRng,
+    turrets::Credits,
+    AddSimulationSystem, Enemy, Game, GameState, TimeScale,
+};
+
+/// File bonus objectives are read from, in the game's assets and in content packs.
+pub const OBJECTIVES_FILE: &str = "objectives.ron";
+
+/// What the player has to pull off during a wave to meet a bonus objective.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Goal {
+    /// take no damage
+    NoDamage,
+    /// leave no enemy standing at some point within this many seconds of the wave's start
+    Clear { seconds: f32 },
+    /// kill enemies only by bashing them, at least one
+    MeleeOnly,
+}
+
+/// Bonus a wave can be played for on top of surviving it, read from `OBJECTIVES_FILE`.
+#[derive(Clone, Deserialize)]
+pub struct BonusObjective {
+    /// looked up as `objective.<name>` in the locale
+    pub name: String,
+    pub goal: Goal,
+    /// modes the objective can be set in
+    pub modes: Vec<GameMode>,
+    /// first wave the objective can be set for
+    pub min_wave: usize,
+    /// how likely it is to be picked, relative to the other objectives available
+    pub weight: u32,
+    /// score added when the objective is met
+    pub score: usize,
+    /// credits added when the objective is met
+    pub credits: usize,
+}
+
+/// Every bonus objective that can be set.
+pub struct BonusObjectives(Vec<BonusObjective>);
+
+impl BonusObjectives {
+    fn load(packs: &ContentPacks) -> Self {
+        Self(packs.load_all(OBJECTIVES_FILE))
+    }
+
+    /// Picks one of the objectives available in the mode on the wave, none if there are none.
+    fn roll(&self, mode: GameMode, wave: usize, rng: &mut GameRng) -> Option<&BonusObjective> {
+        let available = self
+            .0
+            .iter()
+            .filter(|objective| objective.modes.contains(&mode) && objective.min_wave <= wave)
+            .collect::<Vec<_>>();
+        available
+            .choose_weighted(&mut rng.0, |objective| objective.weight)
+            .ok()
+            .copied()
+    }
+}
+
+/// Objective set for the current wave and how the player is doing on it.
+#[derive(Default)]
+struct Progress {
+    objective: Option<BonusObjective>,
+    /// seconds since the wave started
+    elapsed: f32,
+    damaged: bool,
+    kills: usize,
+    bash_kills: usize,
+    /// whether the arena was cleared in time
+    cleared: bool,
+}
+
+impl Progress {
+    fn is_met(&self, goal: Goal) -> bool {
+        match goal {
+            Goal::NoDamage => !self.damaged,
+            Goal::Clear { .. } => self.cleared,
+            Goal::MeleeOnly => self.kills > 0 && self.bash_kills == self.kills,
+        }
+    }
+}
+
+/// Sets a bonus objective for every wave of a run that has one available in its mode, announced
+/// as the wave starts and graded once it's over for extra score and credits. The wave a run ends
+/// on isn't graded.
+pub struct ObjectivesPlugin;
+
+impl Plugin for ObjectivesPlugin {
+    fn build(&self, app: &mut App) {
+        let objectives = BonusObjectives::load(app.world.resource::<ContentPacks>());
+        app.insert_resource(objectives)
+            .init_resource::<Progress>()
+            .add_system(reload_system)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_progress))
+            .add_simulation_system(objective_system);
+    }
+}
+
+fn reload_system(mut commands: Commands, packs: Res<ContentPacks>) {
+    if packs.is_changed() && !packs.is_added() {
+        commands.insert_resource(BonusObjectives::load(&packs));
+    }
+}
+
+fn reset_progress(mut progress: ResMut<Progress>) {
+    *progress = Progress::default();
+}
+
+/// Keeps track of the player's progress on the wave's objective, grading it once the next wave
+/// starts and setting the next one.
+fn objective_system(
+    state: Res<State<GameState>>,
+    time_scale: Res<TimeScale>,
+    objectives: Res<BonusObjectives>,
+    loadout: Res<Loadout>,
+    mut rng: ResMut<GameRng>,
+    mut game: ResMut<Game>,
+    mut credits: ResMut<Credits>,
+    mut progress: ResMut<Progress>,
+    enemies: Query<(), (With<Enemy>, Without<Dying>)>,
+    mut player_damaged: EventReader<PlayerDamaged>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+    mut bash_kill: EventReader<BashKill>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut objective_set: EventWriter<ObjectiveSet>,
+    mut objective_graded: EventWriter<ObjectiveGraded>,
+) {
+    if *state.current() != GameState::Playing {
+        return;
+    }
+    // on the same clock as the wave itself
+    progress.elapsed += time_scale.step();
+    progress.damaged |= player_damaged.iter().count() > 0;
+    progress.kills += enemy_killed.iter().count();
+    progress.bash_kills += bash_kill.iter().count();
+    if let Some(Goal::Clear { seconds }) =
+        progress.objective.as_ref().map(|objective| objective.goal)
+    {
+        // an arena that's empty before anything was killed wasn't cleared by the player
+        if progress.elapsed <= seconds && progress.kills > 0 && enemies.is_empty() {
+            progress.cleared = true;
+        }
+    }
+
+    for started in wave_started.iter() {
+        if let Some(objective) = progress.objective.take() {
+            let met = progress.is_met(objective.goal);
+            if met {
+                game.score += objective.score;
+                credits.0 += objective.credits;
+            }
+            objective_graded.send(ObjectiveGraded {
+                name: objective.name.clone(),
+                met,
+                score: objective.score,
+            });
+        }
+        *progress = Progress {
+            objective: objectives
+                .roll(loadout.mode, started.number, &mut rng)
+                .cloned(),
+            ..default()
+        };
+        if let Some(objective) = &progress.objective {
+            objective_set.send(ObjectiveSet {
+                name: objective.name.clone(),
+            });
+        }
+    }
+}
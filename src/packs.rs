@@ -0,0 +1,693 @@
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use bevy::{
+    asset::{create_platform_default_asset_io, AssetIo, AssetIoError, BoxedFuture, FileAssetIo},
+    prelude::*,
+    tasks::IoTaskPool,
+};
+use futures_lite::future;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha1_smol::Sha1;
+use zip::{result::ZipError, ZipArchive};
+
+use crate::{
+    arena::{ArenaLayout, ARENA_FILE},
+    bosses::{BossDefinition, BOSSES_FILE},
+    despawn_with,
+    elements::{EnemyAffinities, ENEMIES_FILE},
+    formations::{Formation, FORMATIONS_FILE},
+    locale::{Locale, Localized},
+    menu::{screen_node, spawn_button, spawn_title, MenuButton},
+    munitions::{WeaponTuning, WEAPONS_FILE},
+    objectives::{BonusObjective, OBJECTIVES_FILE},
+    save, GameState, UiFont,
+};
+
+/// Directory content packs are discovered in, every subdirectory or zip archive in it is a pack.
+const PACKS_DIR: &str = "packs";
+const PACKS_FILE: &str = "packs";
+/// File at the root of every pack describing it.
+const MANIFEST_FILE: &str = "pack.ron";
+/// Directory in a pack holding its mod scripts.
+const SCRIPTS_DIR: &str = "scripts";
+
+#[derive(Deserialize)]
+struct Manifest {
+    name: String,
+    #[serde(default)]
+    description: String,
+}
+
+/// Where a pack's files are read from.
+#[derive(Clone)]
+enum Source {
+    Directory(PathBuf),
+    Zip(PathBuf),
+}
+
+impl Source {
+    /// Contents of the file at `name` inside the pack, if it has one.
+    fn read(&self, name: &str) -> io::Result<Option<String>> {
+        match self.read_bytes(name)? {
+            Some(bytes) => String::from_utf8(bytes)
+                .map(Some)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            None => Ok(None),
+        }
+    }
+
+    /// Bytes of the file at `name` inside the pack, if it has one.
+    fn read_bytes(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        match self {
+            Source::Directory(dir) => match fs::read(dir.join(name)) {
+                Ok(contents) => Ok(Some(contents)),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(err),
+            },
+            Source::Zip(path) => {
+                let mut archive = open_zip(path)?;
+                let mut file = match archive.by_name(name) {
+                    Ok(file) => file,
+                    Err(ZipError::FileNotFound) => return Ok(None),
+                    Err(err) => return Err(zip_error(err)),
+                };
+                let mut contents = Vec::new();
+                file.read_to_end(&mut contents)?;
+                Ok(Some(contents))
+            }
+        }
+    }
+
+    /// Paths inside the pack of the files directly in `dir`, sorted.
+    fn list(&self, dir: &str) -> io::Result<Vec<String>> {
+        let mut names = match self {
+            Source::Directory(root) => match fs::read_dir(root.join(dir)) {
+                Ok(entries) => entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_file())
+                    .map(|entry| format!("{}/{}", dir, entry.file_name().to_string_lossy()))
+                    .collect(),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+                Err(err) => return Err(err),
+            },
+            Source::Zip(path) => {
+                let prefix = format!("{}/", dir);
+                open_zip(path)?
+                    .file_names()
+                    .filter(|name| {
+                        name.strip_prefix(&prefix)
+                            .is_some_and(|name| !name.is_empty() && !name.contains('/'))
+                    })
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            }
+        };
+        names.sort();
+        Ok(names)
+    }
+
+    /// Paths inside the pack of every file in it, sorted.
+    fn files(&self) -> io::Result<Vec<String>> {
+        let mut names = match self {
+            Source::Directory(root) => {
+                let mut names = Vec::new();
+                let mut dirs = vec![String::new()];
+                while let Some(dir) = dirs.pop() {
+                    for entry in fs::read_dir(root.join(&dir))? {
+                        let entry = entry?;
+                        let name = entry.file_name().to_string_lossy().into_owned();
+                        let path = if dir.is_empty() {
+                            name
+                        } else {
+                            format!("{}/{}", dir, name)
+                        };
+                        if entry.path().is_dir() {
+                            dirs.push(path);
+                        } else {
+                            names.push(path);
+                        }
+                    }
+                }
+                names
+            }
+            Source::Zip(path) => open_zip(path)?
+                .file_names()
+                .filter(|name| !name.ends_with('/'))
+                .map(String::from)
+                .collect(),
+        };
+        names.sort();
+        Ok(names)
+    }
+
+    /// Feeds the path and contents of every file in the pack to `hash`, in the order of their
+    /// paths. A zip archive is opened once for all of them.
+    fn hash_into(&self, hash: &mut Sha1) -> io::Result<()> {
+        let mut update = |name: &str, contents: &[u8]| {
+            hash.update(name.as_bytes());
+            hash.update(&[0]);
+            hash.update(&(contents.len() as u64).to_le_bytes());
+            hash.update(contents);
+        };
+        match self {
+            Source::Directory(root) => {
+                for name in self.files()? {
+                    update(&name, &fs::read(root.join(&name))?);
+                }
+            }
+            Source::Zip(path) => {
+                let mut archive = open_zip(path)?;
+                let mut names = archive
+                    .file_names()
+                    .filter(|name| !name.ends_with('/'))
+                    .map(String::from)
+                    .collect::<Vec<_>>();
+                names.sort();
+                for name in names {
+                    let mut file = archive.by_name(&name).map_err(zip_error)?;
+                    let mut contents = Vec::new();
+                    file.read_to_end(&mut contents)?;
+                    update(&name, &contents);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn open_zip(path: &Path) -> io::Result<ZipArchive<File>> {
+    ZipArchive::new(File::open(path)?).map_err(zip_error)
+}
+
+fn zip_error(err: ZipError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Content pack adding formations, bosses, enemy affinities, weapon tunings, bonus objectives,
+/// arena layouts and mod scripts to the game's own, and replacing its music, voice clips, sprites
+/// and translations, found in `PACKS_DIR` as a directory or a zip archive laid out like one:
+///
+/// - `pack.ron` naming and describing the pack
+/// - `formations.ron`, `bosses.ron`, `enemies.ron`, `weapons.ron` and `objectives.ron` in the same
+///   format as the game's own files in `assets`
+/// - `arena.ron` with obstacles, spawn zones and decorations placed in the arena, in the format
+///   the arena editor saves in
+/// - `scripts/*.rhai` mod scripts, like the ones in the `mods` directory
+/// - `music/*.ogg` soundtrack stems, `voice/*.ogg` announcer clips, `sprites/*.png` images of
+///   the player, the enemies and the bullets and `locales/*.json` string tables, named like the
+///   files they replace in `assets`
+struct Pack {
+    /// file name the pack was found under, identifying it in the saved pack choices
+    id: String,
+    name: String,
+    description: String,
+    source: Source,
+    /// why the pack can't be used, if it failed validation
+    problem: Option<String>,
+}
+
+impl Pack {
+    fn open(id: String, source: Source) -> Self {
+        let manifest = match source.read(MANIFEST_FILE) {
+            Ok(Some(contents)) => ron::from_str::<Manifest>(&contents)
+                .map_err(|err| format!("{}: {}", MANIFEST_FILE, err)),
+            Ok(None) => Err(format!("missing {}", MANIFEST_FILE)),
+            Err(err) => Err(format!("{}: {}", MANIFEST_FILE, err)),
+        };
+        let (name, description, mut problem) = match manifest {
+            Ok(manifest) => (manifest.name, manifest.description, None),
+            Err(problem) => (id.clone(), String::new(), Some(problem)),
+        };
+        // a pack with a broken data file is left out as a whole rather than merged partially
+        if problem.is_none() {
+            problem = validate::<Vec<Formation>>(&source, FORMATIONS_FILE)
+                .and(validate::<Vec<BossDefinition>>(&source, BOSSES_FILE))
+                .and(validate::<Vec<EnemyAffinities>>(&source, ENEMIES_FILE))
+                .and(validate::<Vec<WeaponTuning>>(&source, WEAPONS_FILE))
+                .and(validate::<Vec<BonusObjective>>(&source, OBJECTIVES_FILE))
+                .and(validate::<ArenaLayout>(&source, ARENA_FILE))
+                .err();
+        }
+        if let Some(problem) = &problem {
+            warn!("content pack {} is invalid: {}", id, problem);
+        }
+        Self {
+            id,
+            name,
+            description,
+            source,
+            problem,
+        }
+    }
+}
+
+/// Checks that the pack's file at `name` holds a `T`, if the pack has one.
+fn validate<T: DeserializeOwned>(source: &Source, name: &str) -> Result<(), String> {
+    match source.read(name) {
+        Ok(Some(contents)) => ron::from_str::<T>(&contents)
+            .map(|_| ())
+            .map_err(|err| format!("{}: {}", name, err)),
+        Ok(None) => Ok(()),
+        Err(err) => Err(format!("{}: {}", name, err)),
+    }
+}
+
+/// Packs the player turned off, persisted between sessions. Packs are on unless turned off, so
+/// newly installed ones take effect right away.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PackChoices {
+    disabled: BTreeSet<String>,
+}
+
+impl save::Versioned for PackChoices {
+    const VERSION: u32 = 1;
+}
+
+/// Every content pack discovered at startup. The registries built from packs are rebuilt
+/// whenever this changes.
+pub struct ContentPacks {
+    packs: Vec<Pack>,
+    choices: PackChoices,
+    /// where the enabled packs' files are read from, shared with the asset server's `PackAssetIo`
+    assets: Arc<Mutex<Vec<Source>>>,
+    /// where the game's own files are read from, on every platform
+    base: Arc<dyn AssetIo>,
+    /// `content_hash` of the packs enabled now, hashed whenever they change
+    content_hash: String,
+}
+
+impl ContentPacks {
+    fn discover(base: Arc<dyn AssetIo>) -> Self {
+        let dir = FileAssetIo::get_root_path().join(PACKS_DIR);
+        let mut packs = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter_map(|path| {
+                    let id = path.file_name()?.to_string_lossy().into_owned();
+                    if path.is_dir() {
+                        Some(Pack::open(id, Source::Directory(path)))
+                    } else if path.extension().is_some_and(|extension| extension == "zip") {
+                        Some(Pack::open(id, Source::Zip(path)))
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>(),
+            // not having any packs is fine
+            Err(_) => Vec::new(),
+        };
+        // merged in a stable order, so their mod scripts always run in the same order
+        packs.sort_by(|a, b| a.id.cmp(&b.id));
+        for pack in packs.iter().filter(|pack| pack.problem.is_none()) {
+            info!("found content pack {} ({})", pack.name, pack.id);
+        }
+        let mut packs = Self {
+            packs,
+            choices: save::load(PACKS_FILE),
+            assets: default(),
+            base,
+            content_hash: String::new(),
+        };
+        packs.share_enabled();
+        packs
+    }
+
+    /// Has the asset server read the packs enabled now, and hashes what's in them.
+    fn share_enabled(&mut self) {
+        if let Ok(mut assets) = self.assets.lock() {
+            *assets = self.enabled().map(|pack| pack.source.clone()).collect();
+        }
+        let mut hash = Sha1::new();
+        for pack in self.enabled() {
+            hash.update(pack.id.as_bytes());
+            hash.update(&[0]);
+            if let Err(err) = pack.source.hash_into(&mut hash) {
+                warn!("failed to hash the files in {}: {}", pack.id, err);
+            }
+        }
+        self.content_hash = hash.digest().to_string();
+    }
+
+    fn is_enabled(&self, pack: &Pack) -> bool {
+        pack.problem.is_none() && !self.choices.disabled.contains(&pack.id)
+    }
+
+    fn enabled(&self) -> impl Iterator<Item = &Pack> {
+        self.packs.iter().filter(|pack| self.is_enabled(pack))
+    }
+
+    /// Ids of the enabled packs, in the order they're merged in.
+    pub fn enabled_ids(&self) -> Vec<String> {
+        self.enabled().map(|pack| pack.id.clone()).collect()
+    }
+
+    /// SHA-1 of every file in the enabled packs, in hex, telling packs apart by what's in them
+    /// rather than by their ids.
+    pub fn content_hash(&self) -> &str {
+        &self.content_hash
+    }
+
+    /// Turns every pack but the ones in `ids` off for this session, failing if one of them isn't
+    /// installed or can't be used.
+    pub fn enable_only(&mut self, ids: &[String]) -> Result<(), String> {
+        if let Some(missing) = ids.iter().find(|id| {
+            !self
+                .packs
+                .iter()
+                .any(|pack| pack.id == **id && pack.problem.is_none())
+        }) {
+            return Err(format!("content pack {} isn't installed", missing));
+        }
+        self.choices.disabled = self
+            .packs
+            .iter()
+            .filter(|pack| !ids.contains(&pack.id))
+            .map(|pack| pack.id.clone())
+            .collect();
+        self.share_enabled();
+        Ok(())
+    }
+
+    /// Every `T` listed in the game's own file at `name` and the enabled packs' files of the same
+    /// name, the game's own first. The game's own is read through the platform's asset IO, the
+    /// way the asset server reads it.
+    pub fn load_all<T: DeserializeOwned>(&self, name: &str) -> Vec<T> {
+        let base = future::block_on(self.base.load_path(Path::new(name)))
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| String::from_utf8(bytes).map_err(|err| err.to_string()));
+        let mut sources = match base {
+            Ok(contents) => vec![(name.to_string(), contents)],
+            Err(err) => {
+                warn!("failed to read {}: {}", name, err);
+                Vec::new()
+            }
+        };
+        for pack in self.enabled() {
+            match pack.source.read(name) {
+                Ok(Some(contents)) => sources.push((format!("{}/{}", pack.id, name), contents)),
+                Ok(None) => {}
+                Err(err) => warn!("failed to read {} from {}: {}", name, pack.id, err),
+            }
+        }
+        let mut all = Vec::new();
+        for (origin, contents) in sources {
+            match ron::from_str::<Vec<T>>(&contents) {
+                Ok(mut items) => all.append(&mut items),
+                Err(err) => warn!("failed to parse {}: {}", origin, err),
+            }
+        }
+        all
+    }
+
+    /// The `T` in the file at `name` of every enabled pack that has one, for files the game
+    /// doesn't have one of its own.
+    pub fn load_each<T: DeserializeOwned>(&self, name: &str) -> Vec<T> {
+        self.enabled()
+            .filter_map(|pack| match pack.source.read(name) {
+                Ok(contents) => contents,
+                Err(err) => {
+                    warn!("failed to read {} from {}: {}", name, pack.id, err);
+                    None
+                }
+            })
+            .filter_map(|contents| match ron::from_str(&contents) {
+                Ok(item) => Some(item),
+                Err(err) => {
+                    warn!("failed to parse {}: {}", name, err);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Name and contents of the mod scripts in every enabled pack.
+    pub fn scripts(&self) -> Vec<(String, String)> {
+        let mut scripts = Vec::new();
+        for pack in self.enabled() {
+            let names = pack.source.list(SCRIPTS_DIR).unwrap_or_else(|err| {
+                warn!("failed to list scripts in {}: {}", pack.id, err);
+                Vec::new()
+            });
+            for name in names.into_iter().filter(|name| name.ends_with(".rhai")) {
+                match pack.source.read(&name) {
+                    Ok(Some(contents)) => scripts.push((format!("{}/{}", pack.id, name), contents)),
+                    Ok(None) => {}
+                    Err(err) => warn!("failed to read {} from {}: {}", name, pack.id, err),
+                }
+            }
+        }
+        scripts
+    }
+}
+
+/// Path of an asset inside a pack, the way zip archives name their files.
+fn pack_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Reads assets from the enabled packs ahead of the game's own `assets`, a file in a pack
+/// replacing the game's file of the same name and the pack merged in last winning. Whatever was
+/// loaded already stays as it is when packs are turned on or off, until the next start.
+struct PackAssetIo {
+    base: Arc<dyn AssetIo>,
+    sources: Arc<Mutex<Vec<Source>>>,
+}
+
+impl PackAssetIo {
+    fn sources(&self) -> Vec<Source> {
+        self.sources
+            .lock()
+            .map(|sources| sources.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl AssetIo for PackAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        Box::pin(async move {
+            let name = pack_path(path);
+            for source in self.sources().iter().rev() {
+                if let Some(contents) = source.read_bytes(&name)? {
+                    return Ok(contents);
+                }
+            }
+            self.base.load_path(path).await
+        })
+    }
+
+    fn read_directory(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        let dir = pack_path(path);
+        let mut files = BTreeSet::new();
+        for source in self.sources() {
+            match source.list(&dir) {
+                Ok(names) => files.extend(names.into_iter().map(PathBuf::from)),
+                Err(err) => warn!("failed to list {} in a content pack: {}", dir, err),
+            }
+        }
+        match self.base.read_directory(path) {
+            Ok(base) => files.extend(base),
+            // a directory only packs have is fine
+            Err(_) if !files.is_empty() => {}
+            Err(err) => return Err(err),
+        }
+        Ok(Box::new(files.into_iter()))
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        self.base.is_directory(path)
+    }
+
+    fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
+        self.base.watch_path_for_changes(path)
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        self.base.watch_for_changes()
+    }
+}
+
+/// Button turning the pack at this index on or off.
+#[derive(Component)]
+struct PackToggle(usize);
+
+/// Text showing whether the pack at this index is on.
+#[derive(Component)]
+struct PackStatus(usize);
+
+#[derive(Component)]
+struct PacksScreen;
+
+/// Registries built from the packs need `ContentPacks` while they're being built, and the asset
+/// server reads through the packs, so this plugin has to be added among the engine's plugins,
+/// ahead of `AssetPlugin`.
+pub struct PacksPlugin;
+
+impl Plugin for PacksPlugin {
+    fn build(&self, app: &mut App) {
+        let base: Arc<dyn AssetIo> = create_platform_default_asset_io(app).into();
+        let packs = ContentPacks::discover(base.clone());
+        let io = PackAssetIo {
+            base,
+            sources: packs.assets.clone(),
+        };
+        let task_pool = app.world.resource::<IoTaskPool>().0.clone();
+        app.insert_resource(AssetServer::new(io, task_pool))
+            .insert_resource(packs)
+            .add_system_set(SystemSet::on_enter(GameState::Packs).with_system(spawn_packs_screen))
+            .add_system_set(
+                SystemSet::on_update(GameState::Packs)
+                    .with_system(pack_toggle_system)
+                    .with_system(pack_status_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Packs).with_system(despawn_with::<PacksScreen>),
+            );
+    }
+}
+
+fn status(packs: &ContentPacks, pack: &Pack, locale: &Locale) -> String {
+    let key = if pack.problem.is_some() {
+        "packs.invalid"
+    } else if packs.is_enabled(pack) {
+        "settings.on"
+    } else {
+        "settings.off"
+    };
+    locale.get(key).to_string()
+}
+
+fn spawn_packs_screen(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    locale: Res<Locale>,
+    packs: Res<ContentPacks>,
+) {
+    let style = TextStyle {
+        font: font.0.clone(),
+        font_size: 24.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    let detail_style = TextStyle {
+        font_size: 18.0,
+        color: Color::GRAY,
+        ..style.clone()
+    };
+
+    commands
+        .spawn_bundle(screen_node())
+        .insert(PacksScreen)
+        .with_children(|parent| {
+            spawn_title(parent, &font, &locale, "packs.title");
+            if packs.packs.is_empty() {
+                parent
+                    .spawn_bundle(TextBundle {
+                        style: Style {
+                            margin: Rect::all(Val::Px(6.0)),
+                            ..default()
+                        },
+                        text: Text::with_section(
+                            locale.get("packs.empty"),
+                            style.clone(),
+                            default(),
+                        ),
+                        ..default()
+                    })
+                    .insert(Localized("packs.empty"));
+            }
+            for (index, pack) in packs.packs.iter().enumerate() {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(480.0), Val::Px(38.0)),
+                            margin: Rect::all(Val::Px(3.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        color: Color::rgb(0.15, 0.15, 0.15).into(),
+                        ..default()
+                    })
+                    .insert(PackToggle(index))
+                    .with_children(|button| {
+                        button
+                            .spawn_bundle(TextBundle {
+                                text: Text {
+                                    sections: vec![
+                                        TextSection {
+                                            value: pack.name.clone(),
+                                            style: style.clone(),
+                                        },
+                                        TextSection {
+                                            value: format!(": {}", status(&packs, pack, &locale)),
+                                            style: TextStyle {
+                                                color: Color::GOLD,
+                                                ..style.clone()
+                                            },
+                                        },
+                                    ],
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .insert(PackStatus(index));
+                    });
+                let detail = pack.problem.as_ref().unwrap_or(&pack.description);
+                if !detail.is_empty() {
+                    parent.spawn_bundle(TextBundle {
+                        text: Text::with_section(detail.clone(), detail_style.clone(), default()),
+                        ..default()
+                    });
+                }
+            }
+            spawn_button(parent, &font, &locale, "menu.back", MenuButton::MainMenu);
+        });
+}
+
+fn pack_toggle_system(
+    buttons: Query<(&Interaction, &PackToggle), Changed<Interaction>>,
+    mut packs: ResMut<ContentPacks>,
+) {
+    for (interaction, PackToggle(index)) in buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let packs = &mut *packs;
+        let pack = match packs.packs.get(*index) {
+            Some(pack) if pack.problem.is_none() => pack,
+            _ => continue,
+        };
+        if !packs.choices.disabled.remove(&pack.id) {
+            packs.choices.disabled.insert(pack.id.clone());
+        }
+        save::store(PACKS_FILE, &packs.choices);
+        packs.share_enabled();
+    }
+}
+
+fn pack_status_system(
+    packs: Res<ContentPacks>,
+    locale: Res<Locale>,
+    mut statuses: Query<(&mut Text, &PackStatus)>,
+) {
+    if !packs.is_changed() && !locale.is_changed() {
+        return;
+    }
+    for (mut text, PackStatus(index)) in statuses.iter_mut() {
+        if let Some(pack) = packs.packs.get(*index) {
+            text.sections[1].value = format!(": {}", status(&packs, pack, &locale));
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use std::path::Path;
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{layers::RenderLayer, settings::Settings};
+
+/// Width in pixels of the outline drawn around sprites in high-contrast mode.
+const OUTLINE_WIDTH: f32 = 3.0;
+/// Directory of the images sprites on some layers are drawn with instead of plain squares, which
+/// only content packs supply.
+const SPRITES_DIR: &str = "sprites";
+/// Layers whose sprites can be drawn with an image, with the image's file in `SPRITES_DIR`.
+const TEXTURED_LAYERS: [(RenderLayer, &str); 3] = [
+    (RenderLayer::Player, "player.png"),
+    (RenderLayer::Enemies, "enemies.png"),
+    (RenderLayer::Bullets, "bullets.png"),
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaletteMode {
+    #[default]
+    Standard,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+    HighContrast,
+}
+
+impl PaletteMode {
+    pub const ALL: [PaletteMode; 5] = [
+        PaletteMode::Standard,
+        PaletteMode::Deuteranopia,
+        PaletteMode::Protanopia,
+        PaletteMode::Tritanopia,
+        PaletteMode::HighContrast,
+    ];
+
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            PaletteMode::Standard => "palette.standard",
+            PaletteMode::Deuteranopia => "palette.deuteranopia",
+            PaletteMode::Protanopia => "palette.protanopia",
+            PaletteMode::Tritanopia => "palette.tritanopia",
+            PaletteMode::HighContrast => "palette.high_contrast",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let index = PaletteMode::ALL.iter().position(|m| m == self).unwrap_or(0);
+        PaletteMode::ALL[(index + 1) % PaletteMode::ALL.len()]
+    }
+}
+
+/// Colors and shapes every game object is spawned with, chosen by the palette setting.
+pub struct Palette {
+    pub mode: PaletteMode,
+    /// tint for the player's ship, `None` to keep the ship's own color
+    pub player: Option<Color>,
+    pub enemy: Color,
+    pub splitter: Color,
+    /// enemies keeping their distance to shoot from afar
+    pub sniper: Color,
+    /// enemies diving at the player to blow up
+    pub kamikaze: Color,
+    /// hives and the swarms they let out
+    pub hive: Color,
+    pub bullet: Color,
+    pub background: Color,
+    /// translucent overlay over parts of the arena that hurt the player
+    pub hazard: Color,
+    /// ground set alight by the player's bombs, burning only enemies
+    pub burning: Color,
+    /// slow fields left by the frost weapon
+    pub frost: Color,
+    pub asteroid: Color,
+    pub mine: Color,
+    pub laser: Color,
+    /// decal left where an enemy died
+    pub scorch: Color,
+    /// ammo dropped by killed enemies
+    pub ammo: Color,
+    /// tint and outline marking elite enemies
+    pub elite: Color,
+    /// front shield carried by shielded elites
+    pub shield: Color,
+    /// drones orbiting the player
+    pub drone: Color,
+    /// shots fired at the player by bosses
+    pub enemy_bullet: Color,
+    /// walls placed in the arena editor
+    pub obstacle: Color,
+    /// purely decorative squares placed in the arena editor
+    pub decoration: Color,
+    /// drawn behind every sprite so its silhouette stands out, if set
+    pub outline: Option<Color>,
+    /// radians enemies are turned by so they read as diamonds rather than squares
+    pub enemy_rotation: f32,
+    /// images the sprites on these layers are drawn with, tinted by their color
+    textures: Vec<(RenderLayer, Handle<Image>)>,
+}
+
+impl Palette {
+    pub fn new(mode: PaletteMode) -> Self {
+        let standard = Self {
+            mode,
+            player: None,
+            enemy: Color::rgb(0.25, 0.0, 0.0),
+            splitter: Color::rgb(0.35, 0.1, 0.3),
+            sniper: Color::rgb(0.1, 0.2, 0.35),
+            kamikaze: Color::rgb(0.45, 0.2, 0.0),
+            hive: Color::rgb(0.3, 0.3, 0.05),
+            bullet: Color::rgb(0.25, 0.25, 0.25),
+            background: ClearColor::default().0,
+            hazard: Color::rgba(0.8, 0.1, 0.1, 0.25),
+            burning: Color::rgba(1.0, 0.55, 0.1, 0.3),
+            frost: Color::rgba(0.55, 0.85, 1.0, 0.3),
+            asteroid: Color::rgb(0.45, 0.4, 0.35),
+            mine: Color::rgb(0.9, 0.75, 0.1),
+            laser: Color::rgb(1.0, 0.25, 0.25),
+            scorch: Color::rgba(0.05, 0.05, 0.05, 0.6),
+            ammo: Color::rgb(0.3, 0.85, 0.45),
+            elite: Color::rgb(1.0, 0.8, 0.2),
+            shield: Color::rgb(0.4, 0.8, 1.0),
+            drone: Color::rgb(0.4, 0.75, 0.45),
+            enemy_bullet: Color::rgb(1.0, 0.45, 0.1),
+            obstacle: Color::rgb(0.3, 0.3, 0.35),
+            decoration: Color::rgba(0.5, 0.5, 0.6, 0.15),
+            outline: None,
+            enemy_rotation: 0.0,
+            textures: Vec::new(),
+        };
+        match mode {
+            PaletteMode::Standard => standard,
+            // red and green are hard to tell apart, so enemies are orange against sky blue bullets
+            PaletteMode::Deuteranopia => Self {
+                player: Some(Color::rgb(0.0, 0.45, 0.7)),
+                enemy: Color::rgb(0.9, 0.6, 0.0),
+                splitter: Color::rgb(0.8, 0.3, 0.0),
+                sniper: Color::rgb(0.55, 0.35, 0.0),
+                kamikaze: Color::rgb(0.8, 0.8, 0.8),
+                hive: Color::rgb(0.5, 0.5, 0.2),
+                bullet: Color::rgb(0.35, 0.7, 0.9),
+                hazard: Color::rgba(0.8, 0.4, 0.0, 0.3),
+                mine: Color::rgb(0.8, 0.35, 0.8),
+                laser: Color::rgb(0.95, 0.9, 0.25),
+                ammo: Color::rgb(0.35, 0.7, 0.9),
+                elite: Color::rgb(0.95, 0.95, 0.95),
+                shield: Color::rgb(0.0, 0.45, 0.7),
+                drone: Color::rgb(0.35, 0.7, 0.9),
+                enemy_bullet: Color::rgb(0.95, 0.9, 0.25),
+                ..standard
+            },
+            // reds look dark as well as close to green, so enemies lean yellow to stay bright
+            // against sky blue bullets, and nothing is told apart by its red alone
+            PaletteMode::Protanopia => Self {
+                player: Some(Color::rgb(0.0, 0.45, 0.7)),
+                enemy: Color::rgb(0.95, 0.8, 0.1),
+                splitter: Color::rgb(0.85, 0.55, 0.0),
+                sniper: Color::rgb(0.5, 0.45, 0.1),
+                kamikaze: Color::rgb(0.85, 0.85, 0.85),
+                hive: Color::rgb(0.6, 0.6, 0.35),
+                bullet: Color::rgb(0.35, 0.7, 0.9),
+                hazard: Color::rgba(0.9, 0.75, 0.0, 0.3),
+                burning: Color::rgba(1.0, 0.85, 0.2, 0.3),
+                mine: Color::rgb(0.95, 0.95, 0.6),
+                laser: Color::rgb(1.0, 1.0, 0.4),
+                ammo: Color::rgb(0.35, 0.7, 0.9),
+                elite: Color::rgb(0.95, 0.95, 0.95),
+                shield: Color::rgb(0.0, 0.45, 0.7),
+                drone: Color::rgb(0.35, 0.7, 0.9),
+                enemy_bullet: Color::rgb(1.0, 1.0, 0.4),
+                ..standard
+            },
+            // blue and yellow are hard to tell apart, so enemies are red against teal bullets
+            PaletteMode::Tritanopia => Self {
+                player: Some(Color::rgb(0.95, 0.95, 0.95)),
+                enemy: Color::rgb(0.85, 0.15, 0.3),
+                splitter: Color::rgb(0.6, 0.2, 0.6),
+                sniper: Color::rgb(0.5, 0.1, 0.15),
+                kamikaze: Color::rgb(0.95, 0.4, 0.3),
+                hive: Color::rgb(0.45, 0.3, 0.1),
+                bullet: Color::rgb(0.0, 0.6, 0.6),
+                mine: Color::rgb(0.95, 0.6, 0.7),
+                elite: Color::rgb(0.95, 0.95, 0.95),
+                shield: Color::rgb(0.0, 0.6, 0.6),
+                drone: Color::rgb(0.0, 0.6, 0.6),
+                enemy_bullet: Color::rgb(0.95, 0.6, 0.7),
+                ..standard
+            },
+            PaletteMode::HighContrast => Self {
+                player: Some(Color::WHITE),
+                enemy: Color::YELLOW,
+                splitter: Color::LIME_GREEN,
+                sniper: Color::ORANGE_RED,
+                kamikaze: Color::PINK,
+                hive: Color::GOLD,
+                bullet: Color::CYAN,
+                background: Color::BLACK,
+                hazard: Color::rgba(1.0, 0.0, 1.0, 0.35),
+                burning: Color::rgba(1.0, 0.5, 0.0, 0.35),
+                frost: Color::rgba(0.0, 1.0, 1.0, 0.35),
+                asteroid: Color::GRAY,
+                mine: Color::FUCHSIA,
+                laser: Color::RED,
+                scorch: Color::rgba(0.5, 0.5, 0.5, 0.4),
+                ammo: Color::GREEN,
+                elite: Color::ORANGE,
+                shield: Color::WHITE,
+                drone: Color::CYAN,
+                enemy_bullet: Color::RED,
+                obstacle: Color::rgb(0.6, 0.6, 0.6),
+                decoration: Color::rgba(1.0, 1.0, 1.0, 0.1),
+                outline: Some(Color::WHITE),
+                enemy_rotation: std::f32::consts::FRAC_PI_4,
+                ..standard
+            },
+        }
+    }
+
+    /// Spawns a square sprite of the given color and side length on a layer, outlined if the
+    /// palette asks for it.
+    pub fn spawn_sprite<'w, 's, 'a>(
+        &self,
+        commands: &'a mut Commands<'w, 's>,
+        layer: RenderLayer,
+        color: Color,
+        size: f32,
+        transform: Transform,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let mut sprite = SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::splat(size)),
+                ..default()
+            },
+            transform,
+            ..default()
+        };
+        if let Some((_, texture)) = self
+            .textures
+            .iter()
+            .find(|(textured, _)| *textured == layer)
+        {
+            sprite.texture = texture.clone();
+        }
+        let mut entity = commands.spawn_bundle(sprite);
+        entity.insert(layer);
+        if let Some(outline) = self.outline {
+            entity.with_children(|parent| {
+                parent.spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: outline,
+                        custom_size: Some(Vec2::splat(size + OUTLINE_WIDTH * 2.0)),
+                        ..default()
+                    },
+                    // just behind the sprite it outlines
+                    transform: Transform::from_xyz(0.0, 0.0, -0.01),
+                    ..default()
+                });
+            });
+        }
+        entity
+    }
+}
+
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        let palette = Palette::new(app.world.resource::<Settings>().palette);
+        app.insert_resource(ClearColor(palette.background))
+            .insert_resource(palette)
+            .add_startup_system(load_textures)
+            .add_system(reload_palette_system);
+    }
+}
+
+/// Loads the images of the layers the content packs supply one for.
+fn load_textures(asset_server: Res<AssetServer>, mut palette: ResMut<Palette>) {
+    let files: Vec<_> = match asset_server
+        .asset_io()
+        .read_directory(Path::new(SPRITES_DIR))
+    {
+        Ok(files) => files.collect(),
+        // every sprite is a plain square
+        Err(_) => return,
+    };
+    for (layer, file_name) in TEXTURED_LAYERS {
+        let path = Path::new(SPRITES_DIR).join(file_name);
+        if files.contains(&path) {
+            palette.textures.push((layer, asset_server.load(path)));
+        }
+    }
+}
+
+fn reload_palette_system(
+    settings: Res<Settings>,
+    mut palette: ResMut<Palette>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    if settings.is_changed() && settings.palette != palette.mode {
+        let textures = std::mem::take(&mut palette.textures);
+        *palette = Palette {
+            textures,
+            ..Palette::new(settings.palette)
+        };
+        clear_color.0 = palette.background;
+    }
+}
@@ -0,0 +1,87 @@
+use bevy::{prelude::*, window::WindowFocused};
+
+use crate::{
+    despawn_with,
+    locale::{Locale, Localized},
+    menu::{screen_node, spawn_title},
+    mobile::MOBILE,
+    settings::Settings,
+    GameState, UiFont,
+};
+
+/// Overlay shown over a paused run.
+#[derive(Component)]
+struct PauseScreen;
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(focus_system)
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(spawn_pause_screen))
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(resume_system))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused).with_system(despawn_with::<PauseScreen>),
+            );
+    }
+}
+
+/// Pauses the run in progress, if there is one.
+///
+/// The run's state stays underneath the pause instead of being left, so nothing of it is torn
+/// down and it picks up where it was once resumed.
+pub fn pause(state: &mut State<GameState>) {
+    if state.current().is_simulated() {
+        let _ = state.push(GameState::Paused);
+    }
+}
+
+/// Pauses the run when the window loses focus, unless the player would rather it didn't.
+///
+/// Phones always pause, an app stops updating while it's in the background and would carry on
+/// the moment it comes back, with the player nowhere near ready. Losing focus is the last thing
+/// it hears before it's suspended.
+fn focus_system(
+    settings: Res<Settings>,
+    mut state: ResMut<State<GameState>>,
+    mut focused: EventReader<WindowFocused>,
+) {
+    let lost_focus = focused.iter().any(|event| !event.focused);
+    if lost_focus && (settings.pause_on_focus_loss || MOBILE) {
+        pause(&mut state);
+    }
+}
+
+fn spawn_pause_screen(mut commands: Commands, font: Res<UiFont>, locale: Res<Locale>) {
+    commands
+        .spawn_bundle(screen_node())
+        .insert(PauseScreen)
+        .with_children(|parent| {
+            spawn_title(parent, &font, &locale, "pause.title");
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        locale.get("pause.resume"),
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: 24.0,
+                            color: Color::rgb(0.85, 0.85, 0.85),
+                        },
+                        default(),
+                    ),
+                    ..default()
+                })
+                .insert(Localized("pause.resume"));
+        });
+}
+
+fn resume_system(
+    mut state: ResMut<State<GameState>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    touches: Res<Touches>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Return) || touches.iter_just_pressed().next().is_some()
+    {
+        let _ = state.pop();
+    }
+}
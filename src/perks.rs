@@ -0,0 +1,145 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use rand::seq::SliceRandom;
+
+use crate::{
+    events::{PerkGained, WaveStarted},
+    rng::GameRng,
+    AddSimulationSystem, GameState,
+};
+
+/// Every this many waves the player is granted another perk.
+const WAVES_PER_PERK: usize = 3;
+
+/// Upgrade picked up during a run, stacking when it's granted again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Perk {
+    /// bullets pass through one more enemy per stack
+    Piercing,
+    /// bullets bounce off one more wall or obstacle per stack
+    Ricochet,
+    /// another drone orbits the player, once they're all out the drones fire faster instead
+    Drone,
+}
+
+impl Perk {
+    pub const ALL: [Perk; 3] = [Perk::Piercing, Perk::Ricochet, Perk::Drone];
+
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            Perk::Piercing => "perk.piercing",
+            Perk::Ricochet => "perk.ricochet",
+            Perk::Drone => "perk.drone",
+        }
+    }
+
+    /// How likely the perk is to be the one granted, relative to the others.
+    fn weight(&self) -> u32 {
+        match self {
+            Perk::Piercing | Perk::Ricochet => 3,
+            Perk::Drone => 1,
+        }
+    }
+}
+
+/// Perks gained during the current run.
+#[derive(Default)]
+pub struct Perks(Vec<Perk>);
+
+impl Perks {
+    /// How many times the perk was gained.
+    pub fn stacks(&self, perk: Perk) -> u32 {
+        self.0.iter().filter(|gained| **gained == perk).count() as u32
+    }
+
+    /// Applies the bullet modifiers of the perks to a freshly fired bullet, whatever the weapon.
+    pub fn modify_bullet(&self, bullet: &mut EntityCommands) {
+        let pierce = self.stacks(Perk::Piercing);
+        if pierce > 0 {
+            bullet.insert(Pierce::new(pierce));
+        }
+        let bounce = self.stacks(Perk::Ricochet);
+        if bounce > 0 {
+            bullet.insert(Bounce(bounce));
+        }
+    }
+}
+
+/// Lets a bullet pass through this many enemies before it's spent.
+#[derive(Component)]
+pub struct Pierce {
+    remaining: u32,
+    /// enemies already passed through, which the bullet can't hit a second time
+    pierced: Vec<Entity>,
+}
+
+impl Pierce {
+    pub fn new(count: u32) -> Self {
+        Self {
+            remaining: count,
+            pierced: Vec::new(),
+        }
+    }
+
+    pub fn has_pierced(&self, enemy: Entity) -> bool {
+        self.pierced.contains(&enemy)
+    }
+
+    /// Records a hit on `enemy`, returning whether the bullet carries on through it.
+    pub fn pass_through(&mut self, enemy: Entity) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+        self.remaining -= 1;
+        self.pierced.push(enemy);
+        true
+    }
+}
+
+/// Lets a bullet bounce off walls and obstacles this many times before it's spent.
+#[derive(Component)]
+pub struct Bounce(pub u32);
+
+impl Bounce {
+    /// Reflects `direction` off a surface with the given normal if any bounces are left,
+    /// returning whether it did.
+    pub fn reflect(&mut self, direction: &mut Vec3, normal: Vec3) -> bool {
+        if self.0 == 0 {
+            return false;
+        }
+        self.0 -= 1;
+        let normal = normal.normalize_or_zero();
+        *direction -= 2.0 * direction.dot(normal) * normal;
+        true
+    }
+}
+
+pub struct PerksPlugin;
+
+impl Plugin for PerksPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Perks>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_perks))
+            .add_simulation_system(grant_perk_system);
+    }
+}
+
+fn reset_perks(mut perks: ResMut<Perks>) {
+    *perks = Perks::default();
+}
+
+fn grant_perk_system(
+    mut perks: ResMut<Perks>,
+    mut rng: ResMut<GameRng>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut perk_gained: EventWriter<PerkGained>,
+) {
+    for started in wave_started.iter() {
+        if started.number % WAVES_PER_PERK != 0 {
+            continue;
+        }
+        if let Ok(perk) = Perk::ALL.choose_weighted(&mut rng.0, Perk::weight) {
+            perks.0.push(*perk);
+            perk_gained.send(PerkGained(*perk));
+        }
+    }
+}
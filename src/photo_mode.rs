@@ -0,0 +1,548 @@
+use std::{
+    fs,
+    num::NonZeroU32,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    core_pipeline::node::MAIN_PASS_DRIVER,
+    input::mouse::MouseWheel,
+    prelude::*,
+    render::{
+        camera::{Camera2d, RenderTarget},
+        render_asset::RenderAssets,
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext},
+        render_resource::{
+            Buffer, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageDataLayout,
+            MapMode, TextureDimension, TextureFormat, TextureUsages,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        RenderApp, RenderStage,
+    },
+    ui::{entity::CameraUi, node::UI_PASS_DRIVER},
+};
+use image::RgbaImage;
+
+use crate::{GameState, TimeScale};
+
+/// Directory photos are saved to.
+const SCREENSHOTS_DIR: &str = "screenshots";
+const CAPTURE_NODE: &str = "photo_capture";
+/// Pixels per second the camera pans at, at normal zoom.
+const PAN_SPEED: f32 = 600.0;
+/// Factor the camera zooms by per step of the mouse wheel.
+const ZOOM_STEP: f32 = 1.1;
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 2.0;
+
+/// Color treatment applied to saved photos.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Filter {
+    None,
+    Monochrome,
+    Sepia,
+    Inverted,
+}
+
+impl Filter {
+    fn next(&self) -> Self {
+        match self {
+            Filter::None => Filter::Monochrome,
+            Filter::Monochrome => Filter::Sepia,
+            Filter::Sepia => Filter::Inverted,
+            Filter::Inverted => Filter::None,
+        }
+    }
+
+    fn apply(&self, pixel: &mut [u8]) {
+        let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(|channel| channel as f32);
+        let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+        let [r, g, b] = match self {
+            Filter::None => return,
+            Filter::Monochrome => [luma; 3],
+            Filter::Sepia => [luma * 1.07, luma * 0.74, luma * 0.43],
+            Filter::Inverted => [255.0 - r, 255.0 - g, 255.0 - b],
+        };
+        pixel[0] = r.min(255.0) as u8;
+        pixel[1] = g.min(255.0) as u8;
+        pixel[2] = b.min(255.0) as u8;
+    }
+}
+
+/// Progress of a photo from the frame it's rendered in to being saved.
+#[derive(Clone)]
+enum Capture {
+    Idle,
+    /// the arena camera renders into the image this frame instead of the window
+    Rendering(Handle<Image>),
+    /// the image is copied into a buffer the main world can read
+    Copying(Handle<Image>),
+    /// waiting for the copied pixels to come back from the render world
+    Waiting(Handle<Image>),
+}
+
+/// Camera position and zoom before photo mode moved them.
+struct SavedCamera {
+    transform: Transform,
+    zoom: f32,
+}
+
+/// Frozen view of a run that can be framed freely and saved as a PNG.
+pub struct PhotoMode {
+    active: bool,
+    filter: Filter,
+    capture: Capture,
+    /// the camera as it was when photo mode started, restored when leaving it
+    saved_camera: Option<SavedCamera>,
+    /// global time scale of the run, restored when leaving photo mode
+    saved_time_scale: f32,
+    /// pixels of the captured image as rows of RGBA, either channel order, put there by the
+    /// render world
+    pixels: Arc<Mutex<Option<Pixels>>>,
+    /// the capture in progress was asked for through `capture_frame` rather than by the player,
+    /// it's handed over through `take_capture` instead of being saved
+    requested: bool,
+    /// the image being captured is a render target of whoever asked for it through
+    /// `capture_target`, it's left in place once it's copied
+    borrowed: bool,
+    captured: Option<RgbaImage>,
+}
+
+impl Default for PhotoMode {
+    fn default() -> Self {
+        Self {
+            active: false,
+            filter: Filter::None,
+            capture: Capture::Idle,
+            saved_camera: None,
+            saved_time_scale: 1.0,
+            pixels: default(),
+            requested: false,
+            borrowed: false,
+            captured: None,
+        }
+    }
+}
+
+impl PhotoMode {
+    /// Whether the run is frozen for a photo.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Captures the next frame as it's shown, HUD and all, without entering photo mode. The
+    /// image is ready a few frames later from `take_capture`.
+    pub fn capture_frame(&mut self) {
+        self.requested = true;
+    }
+
+    /// Copies what was rendered into `image` this frame, a render target cameras were pointed at
+    /// rather than the window. The image is ready a few frames later from `take_capture`. Returns
+    /// whether the capture started, only one is taken at a time.
+    ///
+    /// It has to be called after `CaptureUpdate` in the frame, or the copy misses it.
+    pub fn capture_target(&mut self, image: Handle<Image>) -> bool {
+        if !matches!(self.capture, Capture::Idle) || self.requested {
+            return false;
+        }
+        self.requested = true;
+        self.borrowed = true;
+        self.capture = Capture::Copying(image);
+        true
+    }
+
+    /// The frame captured since `capture_frame` or `capture_target` was called, once it's ready.
+    pub fn take_capture(&mut self) -> Option<RgbaImage> {
+        self.captured.take()
+    }
+}
+
+struct Pixels {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    /// blue comes before red, as in the default swapchain format on most desktops
+    bgra: bool,
+}
+
+impl Pixels {
+    fn into_image(mut self) -> RgbaImage {
+        for pixel in self.data.chunks_exact_mut(4) {
+            if self.bgra {
+                pixel.swap(0, 2);
+            }
+            // the arena has no transparency worth keeping
+            pixel[3] = 255;
+        }
+        RgbaImage::from_raw(self.width, self.height, self.data).unwrap_or_default()
+    }
+}
+
+/// Copy of a captured image requested from the render world, along with where it goes.
+struct CaptureJob {
+    image: Handle<Image>,
+    /// buffer the image was copied into and the padded length of its rows
+    buffer: Mutex<Option<(Buffer, usize)>>,
+    pixels: Arc<Mutex<Option<Pixels>>>,
+}
+
+/// Label of the system moving a photo along, for captures asked for in the same frame to go after.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CaptureUpdate;
+
+pub struct PhotoModePlugin;
+
+impl Plugin for PhotoModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PhotoMode>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(toggle_system)
+                    .with_system(camera_system)
+                    .with_system(capture_system.label(CaptureUpdate)),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(reset));
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .add_system_to_stage(RenderStage::Extract, extract_capture)
+                .add_system_to_stage(RenderStage::Cleanup, read_capture);
+            let mut graph = render_app.world.resource_mut::<RenderGraph>();
+            graph.add_node(CAPTURE_NODE, CaptureNode);
+            graph.add_node_edge(MAIN_PASS_DRIVER, CAPTURE_NODE).unwrap();
+            // captures asked for outside photo mode have the HUD drawn over the arena
+            graph.add_node_edge(UI_PASS_DRIVER, CAPTURE_NODE).unwrap();
+        }
+    }
+}
+
+fn toggle_system(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut photo_mode: ResMut<PhotoMode>,
+    mut time_scale: ResMut<TimeScale>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+    ui_cameras: Query<Entity, With<CameraUi>>,
+) {
+    if !keys.just_pressed(KeyCode::F8) || !matches!(photo_mode.capture, Capture::Idle) {
+        return;
+    }
+    if photo_mode.active {
+        leave(
+            &mut commands,
+            &mut photo_mode,
+            &mut time_scale,
+            &mut cameras,
+        );
+        return;
+    }
+    photo_mode.active = true;
+    photo_mode.saved_time_scale = time_scale.global;
+    // waves and combos run on the global time scale, the simulation stops altogether
+    time_scale.global = 0.0;
+    if let Ok((transform, projection)) = cameras.get_single() {
+        photo_mode.saved_camera = Some(SavedCamera {
+            transform: *transform,
+            zoom: projection.scale,
+        });
+    }
+    // the HUD is hidden by taking away the camera drawing it
+    for entity in ui_cameras.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Puts the run, the camera and the HUD back the way they were before photo mode.
+fn leave(
+    commands: &mut Commands,
+    photo_mode: &mut PhotoMode,
+    time_scale: &mut TimeScale,
+    cameras: &mut Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    photo_mode.active = false;
+    time_scale.global = photo_mode.saved_time_scale;
+    if let Some(saved) = photo_mode.saved_camera.take() {
+        for (mut transform, mut projection) in cameras.iter_mut() {
+            *transform = saved.transform;
+            projection.scale = saved.zoom;
+        }
+    }
+    commands.spawn_bundle(UiCameraBundle::default());
+}
+
+fn reset(
+    mut commands: Commands,
+    mut photo_mode: ResMut<PhotoMode>,
+    mut time_scale: ResMut<TimeScale>,
+    windows: Res<Windows>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+    mut targets: Query<&mut Camera>,
+) {
+    // a photo being taken when the run ends is dropped
+    if let Capture::Rendering(_) = photo_mode.capture {
+        let window = windows.get_primary().map(|window| window.id());
+        for mut camera in targets.iter_mut() {
+            camera.target = RenderTarget::Window(window.unwrap_or_default());
+        }
+    }
+    photo_mode.capture = Capture::Idle;
+    photo_mode.requested = false;
+    photo_mode.borrowed = false;
+    if photo_mode.active {
+        leave(
+            &mut commands,
+            &mut photo_mode,
+            &mut time_scale,
+            &mut cameras,
+        );
+    }
+}
+
+fn camera_system(
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut photo_mode: ResMut<PhotoMode>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    let scroll = wheel.iter().map(|event| event.y).sum::<f32>();
+    // the view holds still while a photo is being taken
+    if !photo_mode.active || !matches!(photo_mode.capture, Capture::Idle) {
+        return;
+    }
+    if keys.just_pressed(KeyCode::F) {
+        photo_mode.filter = photo_mode.filter.next();
+        info!("photo filter: {:?}", photo_mode.filter);
+    }
+    let mut direction = Vec2::ZERO;
+    for (key, step) in [
+        (KeyCode::Left, -Vec2::X),
+        (KeyCode::A, -Vec2::X),
+        (KeyCode::Right, Vec2::X),
+        (KeyCode::D, Vec2::X),
+        (KeyCode::Up, Vec2::Y),
+        (KeyCode::W, Vec2::Y),
+        (KeyCode::Down, -Vec2::Y),
+        (KeyCode::S, -Vec2::Y),
+    ] {
+        if keys.pressed(key) {
+            direction += step;
+        }
+    }
+    for (mut transform, mut projection) in cameras.iter_mut() {
+        let pan = direction.normalize_or_zero() * PAN_SPEED * projection.scale;
+        transform.translation += (pan * time.delta_seconds()).extend(0.0);
+        if scroll != 0.0 {
+            projection.scale =
+                (projection.scale * ZOOM_STEP.powf(-scroll)).clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+    }
+}
+
+/// Starts a photo when asked to and moves it along until it's saved.
+///
+/// Every camera renders into the photo, the HUD's is only there outside photo mode.
+fn capture_system(
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    mut images: ResMut<Assets<Image>>,
+    mut photo_mode: ResMut<PhotoMode>,
+    mut cameras: Query<&mut Camera>,
+) {
+    let photo_mode = &mut *photo_mode;
+    let start = photo_mode.requested || (photo_mode.active && keys.just_pressed(KeyCode::Space));
+    photo_mode.capture = match photo_mode.capture.clone() {
+        Capture::Idle if start => {
+            let window = match windows.get_primary() {
+                Some(window) => window,
+                None => return,
+            };
+            let size = Extent3d {
+                width: window.physical_width(),
+                height: window.physical_height(),
+                depth_or_array_layers: 1,
+            };
+            // the 2d pipelines only draw into targets of the default format
+            let mut image = Image::new_fill(
+                size,
+                TextureDimension::D2,
+                &[0, 0, 0, 255],
+                TextureFormat::bevy_default(),
+            );
+            image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT;
+            let image = images.add(image);
+            for mut camera in cameras.iter_mut() {
+                camera.target = RenderTarget::Image(image.clone());
+            }
+            Capture::Rendering(image)
+        }
+        Capture::Rendering(image) => {
+            let window = windows.get_primary().map(|window| window.id());
+            for mut camera in cameras.iter_mut() {
+                camera.target = RenderTarget::Window(window.unwrap_or_default());
+            }
+            Capture::Copying(image)
+        }
+        Capture::Copying(image) => Capture::Waiting(image),
+        Capture::Waiting(image) => {
+            let pixels = match photo_mode
+                .pixels
+                .lock()
+                .ok()
+                .and_then(|mut pixels| pixels.take())
+            {
+                Some(pixels) => pixels,
+                None => return,
+            };
+            if !std::mem::take(&mut photo_mode.borrowed) {
+                images.remove(image);
+            }
+            if photo_mode.requested {
+                photo_mode.requested = false;
+                photo_mode.captured = Some(pixels.into_image());
+            } else {
+                save(pixels.into_image(), photo_mode.filter);
+            }
+            Capture::Idle
+        }
+        capture => capture,
+    };
+}
+
+/// Writes the photo to a new file in `SCREENSHOTS_DIR`.
+fn save(mut photo: RgbaImage, filter: Filter) {
+    for pixel in photo.pixels_mut() {
+        filter.apply(&mut pixel.0);
+    }
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let dir = PathBuf::from(SCREENSHOTS_DIR);
+    let path = (0..)
+        .map(|index| match index {
+            0 => dir.join(format!("madge-{}.png", seconds)),
+            index => dir.join(format!("madge-{}-{}.png", seconds, index)),
+        })
+        .find(|path| !path.exists())
+        .unwrap_or_default();
+    let result = fs::create_dir_all(&dir)
+        .map_err(image::ImageError::IoError)
+        .and_then(|_| photo.save(&path));
+    match result {
+        Ok(()) => info!("saved photo to {}", path.display()),
+        Err(err) => warn!("failed to save photo to {}: {}", path.display(), err),
+    }
+}
+
+fn extract_capture(mut commands: Commands, photo_mode: Res<PhotoMode>) {
+    if let Capture::Copying(image) = &photo_mode.capture {
+        commands.insert_resource(CaptureJob {
+            image: image.clone(),
+            buffer: Mutex::new(None),
+            pixels: photo_mode.pixels.clone(),
+        });
+    }
+}
+
+/// Copies the captured image into a buffer, once it's been asked for.
+struct CaptureNode;
+
+impl Node for CaptureNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let job = match world.get_resource::<CaptureJob>() {
+            Some(job) => job,
+            None => return Ok(()),
+        };
+        let image = match world.resource::<RenderAssets<Image>>().get(&job.image) {
+            Some(image) => image,
+            None => return Ok(()),
+        };
+        let (width, height) = (image.size.width as u32, image.size.height as u32);
+        // rows in a buffer copy have to be aligned
+        let row_length = RenderDevice::align_copy_bytes_per_row(width as usize * 4);
+        let buffer = render_context
+            .render_device
+            .create_buffer(&BufferDescriptor {
+                label: Some("photo_capture_buffer"),
+                size: (row_length * height as usize) as u64,
+                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+        render_context.command_encoder.copy_texture_to_buffer(
+            image.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(row_length as u32),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        if let Ok(mut slot) = job.buffer.lock() {
+            *slot = Some((buffer, row_length));
+        }
+        Ok(())
+    }
+}
+
+/// Reads back the buffer the capture node filled once the frame has been rendered.
+fn read_capture(
+    mut commands: Commands,
+    job: Option<Res<CaptureJob>>,
+    render_device: Res<RenderDevice>,
+    images: Res<RenderAssets<Image>>,
+) {
+    let job = match job {
+        Some(job) => job,
+        None => return,
+    };
+    commands.remove_resource::<CaptureJob>();
+    let (buffer, row_length) = match job.buffer.lock().ok().and_then(|mut slot| slot.take()) {
+        Some(buffer) => buffer,
+        None => return,
+    };
+    let image = match images.get(&job.image) {
+        Some(image) => image,
+        None => return,
+    };
+    let (width, height) = (image.size.width as u32, image.size.height as u32);
+    let slice = buffer.slice(..);
+    render_device.map_buffer(&slice, MapMode::Read);
+    let data = slice
+        .get_mapped_range()
+        .chunks(row_length)
+        .flat_map(|row| &row[..width as usize * 4])
+        .copied()
+        .collect();
+    buffer.unmap();
+    let bgra = matches!(
+        image.texture_format,
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+    );
+    let pixels = Pixels {
+        data,
+        width,
+        height,
+        bgra,
+    };
+    // the lock has to be released before `job` is
+    if let Ok(mut slot) = job.pixels.lock() {
+        *slot = Some(pixels);
+    };
+}
@@ -0,0 +1,79 @@
+use std::ops::Deref;
+
+use bevy::prelude::*;
+
+use crate::{achievements::Achievement, events::AchievementUnlocked};
+
+/// What the store the game was launched from offers beyond the game itself.
+///
+/// Every method does nothing by default, which is all a build without any store gets.
+pub trait PlatformServices: Send + Sync {
+    /// Unlocks the achievement on the store as well, called once when it's unlocked in the game.
+    fn unlock_achievement(&self, _achievement: Achievement) {}
+
+    /// Contents of the save file `name` kept in the store's cloud storage, if there is a copy.
+    fn load_cloud(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    /// Keeps a copy of the save file `name` in the store's cloud storage.
+    fn store_cloud(&self, _name: &str, _contents: &str) {}
+
+    /// Changes the window so the store's overlay can be drawn over it.
+    fn adjust_window(&self, _window: &mut WindowDescriptor) {}
+
+    /// Handles whatever the store sent since the last frame.
+    fn update(&self) {}
+}
+
+/// Services of a game not launched from any store.
+struct Standalone;
+
+impl PlatformServices for Standalone {}
+
+/// Services of the store the game was launched from.
+pub struct Platform(Box<dyn PlatformServices>);
+
+impl Platform {
+    fn detect() -> Self {
+        #[cfg(feature = "steam")]
+        if let Some(steam) = crate::steam::Steam::init() {
+            return Self(Box::new(steam));
+        }
+        Self(Box::new(Standalone))
+    }
+}
+
+impl Deref for Platform {
+    type Target = dyn PlatformServices;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+/// Sets up the platform services, it has to be added before `DefaultPlugins` to adjust the
+/// window and before any plugin loading a save file.
+pub struct PlatformPlugin;
+
+impl Plugin for PlatformPlugin {
+    fn build(&self, app: &mut App) {
+        let platform = Platform::detect();
+        let mut window = WindowDescriptor::default();
+        platform.adjust_window(&mut window);
+        app.insert_resource(window)
+            .insert_resource(platform)
+            .add_system(update_system)
+            .add_system(achievements_system);
+    }
+}
+
+fn update_system(platform: Res<Platform>) {
+    platform.update();
+}
+
+fn achievements_system(platform: Res<Platform>, mut unlocked: EventReader<AchievementUnlocked>) {
+    for AchievementUnlocked(achievement) in unlocked.iter() {
+        platform.unlock_achievement(*achievement);
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+
+use crate::{
+    arena::ArenaLayout, bosses::Boss, deaths::Dying, enemy_spawn_position, formations::Entering,
+    rng::GameRng, AddSimulationSystem, Enemy, InsertIfAlive, TimeScale, BOUNDS,
+};
+
+/// Distance beyond the arena's edges at which an enemy is considered lost and despawned, well
+/// past where any of them enter from.
+const LOST_MARGIN: f32 = 600.0;
+/// Seconds an enemy can go without being shot down before it's brought back in at a fresh spawn
+/// point, so old stragglers stuck behind cover don't pile up.
+const STRAGGLER_AGE: f32 = 45.0;
+
+/// Simulated seconds an enemy has been alive, or since it was last recycled.
+#[derive(Component, Default)]
+struct Age(f32);
+
+/// Keeps the crowd from growing without bounds, on top of the cap the spawner respects.
+pub struct PopulationPlugin;
+
+impl Plugin for PopulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(start_aging_system)
+            .add_simulation_system(despawn_lost_system)
+            .add_simulation_system(recycle_system);
+    }
+}
+
+fn start_aging_system(mut commands: Commands, enemies: Query<Entity, Added<Enemy>>) {
+    for entity in enemies.iter() {
+        commands.add(InsertIfAlive(entity, Age::default()));
+    }
+}
+
+/// Despawns enemies that ended up far outside the arena, they would never come back to fight.
+fn despawn_lost_system(
+    mut commands: Commands,
+    enemies: Query<(Entity, &Transform), (With<Enemy>, Without<Dying>, Without<Boss>)>,
+) {
+    let limit = BOUNDS / 2.0 + LOST_MARGIN;
+    for (entity, transform) in enemies.iter() {
+        let position = transform.translation.truncate().abs();
+        if position.x > limit.x || position.y > limit.y {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn recycle_system(
+    time_scale: Res<TimeScale>,
+    layout: Res<ArenaLayout>,
+    mut rng: ResMut<GameRng>,
+    // bosses are meant to stay, and enemies entering in formation are still on their way
+    mut enemies: Query<
+        (&mut Age, &mut Transform),
+        (
+            With<Enemy>,
+            Without<Dying>,
+            Without<Boss>,
+            Without<Entering>,
+        ),
+    >,
+) {
+    for (mut age, mut transform) in enemies.iter_mut() {
+        age.0 += time_scale.enemy_step();
+        if age.0 >= STRAGGLER_AGE {
+            age.0 = 0.0;
+            transform.translation = enemy_spawn_position(&layout, &mut rng.0);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use bevy::{
+    ecs::system::{lifetimeless::SRes, SystemParamItem},
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        render_asset::{PrepareAssetError, RenderAsset},
+        render_resource::{
+            std140::{AsStd140, Std140},
+            BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+            BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, Buffer,
+            BufferBindingType, BufferInitDescriptor, BufferSize, BufferUsages, ShaderStages,
+        },
+        renderer::RenderDevice,
+    },
+    sprite::{Material2d, Material2dPipeline, Material2dPlugin, MaterialMesh2dBundle},
+};
+
+use crate::{
+    events::PlayerDamaged, layers::RenderLayer, settings::Settings, GameState, Health, Player,
+};
+
+/// Health below which the vignette starts closing in, it's at full strength one hit from death.
+const LOW_HEALTH: f32 = 3.0;
+/// Seconds the aberration lingers after a hit.
+const ABERRATION_DURATION: f32 = 0.4;
+/// Depth among the effects, above the arena's lights. The UI has its own camera and stays
+/// untouched.
+const POST_PROCESSING_DEPTH: f32 = 0.95;
+
+/// Full-screen pass layering the screen effects over the arena.
+#[derive(Debug, Clone, TypeUuid)]
+#[uuid = "3d2b6c0e-8f53-4f55-9a3e-6f1f0e9b7c21"]
+pub struct PostProcessingMaterial {
+    pub vignette: f32,
+    pub aberration: f32,
+    pub crt: bool,
+    /// seconds since startup, animates the scanlines
+    pub time: f32,
+    pub resolution: Vec2,
+}
+
+#[derive(Clone, Default, AsStd140)]
+struct PostProcessingUniformData {
+    params: Vec4,
+    resolution: Vec4,
+}
+
+pub struct GpuPostProcessingMaterial {
+    _buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+impl RenderAsset for PostProcessingMaterial {
+    type ExtractedAsset = PostProcessingMaterial;
+    type PreparedAsset = GpuPostProcessingMaterial;
+    type Param = (
+        SRes<RenderDevice>,
+        SRes<Material2dPipeline<PostProcessingMaterial>>,
+    );
+
+    fn extract_asset(&self) -> Self::ExtractedAsset {
+        self.clone()
+    }
+
+    fn prepare_asset(
+        material: Self::ExtractedAsset,
+        (render_device, pipeline): &mut SystemParamItem<Self::Param>,
+    ) -> Result<Self::PreparedAsset, PrepareAssetError<Self::ExtractedAsset>> {
+        let value = PostProcessingUniformData {
+            params: Vec4::new(
+                material.vignette,
+                material.aberration,
+                if material.crt { 1.0 } else { 0.0 },
+                material.time,
+            ),
+            resolution: material.resolution.extend(0.0).extend(0.0),
+        };
+        let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("post_processing_uniform_buffer"),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            contents: value.as_std140().as_bytes(),
+        });
+        let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("post_processing_bind_group"),
+            layout: &pipeline.material2d_layout,
+        });
+        Ok(GpuPostProcessingMaterial {
+            _buffer: buffer,
+            bind_group,
+        })
+    }
+}
+
+impl Material2d for PostProcessingMaterial {
+    fn fragment_shader(asset_server: &AssetServer) -> Option<Handle<Shader>> {
+        Some(asset_server.load("shaders/post_processing.wgsl"))
+    }
+
+    fn bind_group(material: &GpuPostProcessingMaterial) -> &BindGroup {
+        &material.bind_group
+    }
+
+    fn bind_group_layout(render_device: &RenderDevice) -> BindGroupLayout {
+        render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: BufferSize::new(
+                        PostProcessingUniformData::std140_size_static() as u64,
+                    ),
+                },
+                count: None,
+            }],
+            label: Some("post_processing_layout"),
+        })
+    }
+}
+
+/// Quad covering the window the effects are drawn on.
+#[derive(Component)]
+struct PostProcessingQuad;
+
+/// Time left on the aberration flash of the last hit.
+#[derive(Default)]
+struct Aberration(Timer);
+
+pub struct PostProcessingPlugin;
+
+impl Plugin for PostProcessingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(Material2dPlugin::<PostProcessingMaterial>::default())
+            .init_resource::<Aberration>()
+            .add_system(post_processing_system);
+        for state in [GameState::Playing, GameState::Tutorial] {
+            app.add_system_set(SystemSet::on_enter(state).with_system(spawn_post_processing_quad));
+        }
+    }
+}
+
+fn spawn_post_processing_quad(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PostProcessingMaterial>>,
+) {
+    commands
+        .spawn_bundle(MaterialMesh2dBundle {
+            mesh: meshes.add(Mesh::from(shape::Quad::default())).into(),
+            material: materials.add(PostProcessingMaterial {
+                vignette: 0.0,
+                aberration: 0.0,
+                crt: false,
+                time: 0.0,
+                resolution: Vec2::ONE,
+            }),
+            transform: Transform::from_xyz(0.0, 0.0, POST_PROCESSING_DEPTH),
+            ..default()
+        })
+        .insert(RenderLayer::Fx)
+        .insert(PostProcessingQuad);
+}
+
+fn post_processing_system(
+    time: Res<Time>,
+    windows: Res<Windows>,
+    settings: Res<Settings>,
+    mut aberration: ResMut<Aberration>,
+    mut player_damaged: EventReader<PlayerDamaged>,
+    player: Query<&Health, With<Player>>,
+    mut quads: Query<(&Handle<PostProcessingMaterial>, &mut Transform), With<PostProcessingQuad>>,
+    mut materials: ResMut<Assets<PostProcessingMaterial>>,
+) {
+    if player_damaged.iter().count() > 0 {
+        aberration.0 = Timer::from_seconds(ABERRATION_DURATION, false);
+    }
+    aberration.0.tick(time.delta());
+
+    let resolution = windows.get_primary().map_or(Vec2::ONE, |window| {
+        Vec2::new(window.width(), window.height())
+    });
+    let vignette = match player.get_single() {
+        Ok(health) if settings.graphics.vignette => {
+            ((LOW_HEALTH - health.current) / (LOW_HEALTH - 1.0)).clamp(0.0, 1.0)
+        }
+        _ => 0.0,
+    };
+    // the timer has no duration until the first hit
+    let fringe = if settings.graphics.aberration && !aberration.0.duration().is_zero() {
+        aberration.0.percent_left()
+    } else {
+        0.0
+    };
+
+    for (handle, mut transform) in quads.iter_mut() {
+        transform.scale = resolution.extend(1.0);
+        if let Some(material) = materials.get_mut(handle) {
+            material.vignette = vignette;
+            material.aberration = fringe;
+            material.crt = settings.graphics.crt;
+            material.time = time.seconds_since_startup() as f32;
+            material.resolution = resolution;
+        }
+    }
+}
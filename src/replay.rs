@@ -0,0 +1,229 @@
+use std::fs;
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    cli::Cli,
+    input_buffer::TouchAxes,
+    loadout::Loadout,
+    packs::ContentPacks,
+    save,
+    scripting::Mods,
+    settings::Settings,
+    submission::{self, Submission},
+    waves::Wave,
+    Game, GameState, SimulationClock,
+};
+
+/// What the scratch directory a replay's saves are kept in is named after.
+pub const SAVE_PURPOSE: &str = "replay";
+/// Fixed steps a replay without a window takes every frame, for a run to be checked far faster
+/// than it was played.
+const HEADLESS_STEPS: u32 = 20;
+
+/// Place in a recording kept as runs of steps with the same input.
+#[derive(Default)]
+struct Cursor {
+    /// run being played, and how many of its steps were
+    run: usize,
+    played: u32,
+}
+
+impl Cursor {
+    /// Input of the next step, none once the recording ran out.
+    fn next<T: Copy>(&mut self, runs: &[(T, u32)]) -> Option<T> {
+        while let Some(&(input, steps)) = runs.get(self.run) {
+            if self.played < steps {
+                self.played += 1;
+                return Some(input);
+            }
+            self.run += 1;
+            self.played = 0;
+        }
+        None
+    }
+}
+
+/// A submitted run being played back, every step taking its input from the recording rather than
+/// from the keyboard and the touches.
+pub struct Playback {
+    submission: Submission,
+    keys: Cursor,
+    touch: Cursor,
+    /// recorded steps not played yet
+    left: u64,
+    /// whether the run went on after its recorded input ran out
+    overran: bool,
+    /// whether the run is played back to confirm its score, exiting with the result
+    verifying: bool,
+}
+
+impl Playback {
+    pub fn new(submission: Submission, verifying: bool) -> Self {
+        let left = submission
+            .inputs
+            .iter()
+            .map(|(_, steps)| *steps as u64)
+            .sum();
+        Self {
+            submission,
+            keys: default(),
+            touch: default(),
+            left,
+            overran: false,
+            verifying,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.submission.seed
+    }
+
+    /// Recorded steps not played yet.
+    pub fn steps_left(&self) -> u64 {
+        self.left
+    }
+
+    /// Keys and touch input of the next step, nothing held or touched once the recording ran
+    /// out.
+    pub fn next_step(&mut self) -> (u16, TouchAxes) {
+        let touch = self.touch.next(&self.submission.touch).unwrap_or_default();
+        match self.keys.next(&self.submission.inputs) {
+            Some(keys) => {
+                self.left -= 1;
+                (keys, touch)
+            }
+            None => {
+                self.overran = true;
+                (0, touch)
+            }
+        }
+    }
+
+    /// Whether every recorded step was played, and none past them.
+    fn played_out(&self) -> bool {
+        self.left == 0 && !self.overran
+    }
+}
+
+/// Plays back the run given with `--replay` or `--verify`, set up the way it was played: the same
+/// loadout, rules and content packs, without assists. The player's own saves are kept out of it.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let playback = match app.world.get_resource::<Playback>() {
+            Some(playback) => playback,
+            None => return,
+        };
+        let submission = &playback.submission;
+        let loadout = Loadout {
+            ship: submission.ship,
+            weapon: submission.weapon,
+            mode: submission.mode,
+            mutators: submission.mutators.clone(),
+            // the run is reseeded from the submission
+            daily: false,
+        };
+        let rules = submission.rules.clone();
+        let packs = submission.packs.clone();
+        let headless = app.world.resource::<Cli>().headless;
+
+        app.insert_resource(loadout);
+        let mut settings = app.world.resource_mut::<Settings>();
+        rules.apply(&mut settings);
+        settings.assists = default();
+        if let Err(err) = app.world.resource_mut::<ContentPacks>().enable_only(&packs) {
+            eprintln!("can't play the run back: {}", err);
+            std::process::exit(1);
+        }
+        if headless {
+            app.insert_resource(SimulationClock {
+                fixed_steps: Some(HEADLESS_STEPS),
+                ..default()
+            });
+        }
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(content_system))
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(overrun_system))
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(playback_over))
+            .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(exit_system));
+    }
+}
+
+/// Checks the run is played back with the mods and pack contents it was played with. A
+/// verification fails without them, a replay is only warned about.
+fn content_system(playback: Res<Playback>, mods: Res<Mods>, packs: Res<ContentPacks>) {
+    if submission::content_hash(&mods, &packs) == playback.submission.content_hash {
+        return;
+    }
+    if playback.verifying {
+        println!("the run was played with other mods or content packs than the ones in place");
+        finish(false);
+    }
+    warn!("the run was played with other mods or content packs, it may not play back the same");
+}
+
+/// Fails a verification as soon as the run outlasts its recorded input, rather than once it's over.
+fn overrun_system(playback: Res<Playback>) {
+    if playback.verifying && playback.overran {
+        println!("the run went on past where its recorded input ends");
+        finish(false);
+    }
+}
+
+/// Whether the run played out the way it was submitted.
+fn confirmed(playback: &Playback, game: &Game, wave: &Wave) -> bool {
+    game.score == playback.submission.score
+        && wave.number == playback.submission.wave
+        && playback.played_out()
+}
+
+/// Reports how the run played out against what was submitted.
+fn playback_over(playback: Res<Playback>, game: Res<Game>, wave: Res<Wave>) {
+    let submission = &playback.submission;
+    println!(
+        "played back: score {} on wave {}, submitted score {} on wave {}",
+        game.score, wave.number, submission.score, submission.wave
+    );
+    if !playback.played_out() {
+        println!("the run didn't end where its recorded input does");
+    }
+}
+
+/// Exits once the run is over, with the result when verifying, or without a window to watch it
+/// in. It waits for the frame after the run ended, for what's saved as it ends to be written by
+/// then.
+fn exit_system(
+    cli: Res<Cli>,
+    playback: Res<Playback>,
+    game: Res<Game>,
+    wave: Res<Wave>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if playback.verifying {
+        finish(confirmed(&playback, &game, &wave));
+    }
+    if cli.headless {
+        remove_save_dir();
+        exit.send(AppExit);
+    }
+}
+
+/// Removes the scratch directory the replay's saves were kept in, the run is played out.
+pub fn remove_save_dir() {
+    let save_dir = save::scratch_dir(SAVE_PURPOSE);
+    if let Err(err) = fs::remove_dir_all(&save_dir) {
+        warn!("failed to remove {}: {}", save_dir.display(), err);
+    }
+}
+
+/// Exits with whether the submitted score held up.
+fn finish(confirmed: bool) -> ! {
+    remove_save_dir();
+    if confirmed {
+        println!("the score is confirmed");
+        std::process::exit(0);
+    }
+    eprintln!("the score doesn't hold up, the run plays out differently than submitted");
+    std::process::exit(1);
+}
@@ -0,0 +1,149 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{ecs::event::Events, prelude::*, reflect::TypeRegistryArc, scene::DynamicScene};
+use serde::Serialize;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{
+    elements::Wounds, elites::Elite, event_log::LogHistory, events::ReportSaved, hives::Hive,
+    input_buffer::input_bit_names, loadout::Loadout, rng::RunSeed, save, settings::Settings,
+    splitters::Splitter, submission::Recording, waves::Wave, Bullet, Enemy, Game, GameState,
+    Health, Player, TIME_STEP,
+};
+
+/// Directory bug reports are saved to, among the save files.
+const REPORTS_DIR: &str = "reports";
+/// Seconds of the player's input up to the moment of the report it covers.
+const INPUT_DURATION: f32 = 30.0;
+
+/// The run a report was made in, with what it takes to play its last moments out again.
+#[derive(Serialize)]
+struct RunReport<'a> {
+    /// version of the game the run was played on, the input only plays out the same on it
+    version: &'static str,
+    seed: u64,
+    loadout: &'a Loadout,
+    wave: usize,
+    score: usize,
+    /// seconds every step of `inputs` lasts
+    step: f32,
+    /// names of what each of the bits of a step's input stands for, from the lowest
+    keys: Vec<String>,
+    /// input of every fixed step of the last `INPUT_DURATION` seconds, as runs of steps with
+    /// the same keys, in the format of the leaderboard's submissions
+    inputs: Vec<(u16, u32)>,
+}
+
+/// Saves a bug report on F10, a zip of everything about the moment that might help track a bug
+/// down, for the player to attach to an issue: a snapshot of the world as a scene, the run's
+/// event log, the settings, and the run's seed and loadout with its latest input.
+pub struct ReportPlugin;
+
+impl Plugin for ReportPlugin {
+    fn build(&self, app: &mut App) {
+        // the gameplay components a report's snapshot of the world holds
+        app.register_type::<Player>()
+            .register_type::<Health>()
+            .register_type::<Bullet>()
+            .register_type::<Enemy>()
+            .register_type::<Elite>()
+            .register_type::<Splitter>()
+            .register_type::<Hive>()
+            .register_type::<Wounds>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(report_system.exclusive_system()),
+            );
+    }
+}
+
+/// Takes the whole world in, to snapshot every entity as it is.
+fn report_system(world: &mut World) {
+    if !world
+        .resource::<Input<KeyCode>>()
+        .just_pressed(KeyCode::F10)
+    {
+        return;
+    }
+    let path = report_path();
+    match write_report(world, &path) {
+        Ok(()) => {
+            info!("saved bug report to {}", path.display());
+            world
+                .resource_mut::<Events<ReportSaved>>()
+                .send(ReportSaved {
+                    path: path.display().to_string(),
+                });
+        }
+        Err(err) => warn!("failed to save bug report to {}: {}", path.display(), err),
+    }
+}
+
+/// A new file in `REPORTS_DIR`, named after the time it's made.
+fn report_path() -> PathBuf {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let dir = save::save_dir().join(REPORTS_DIR);
+    (0..)
+        .map(|index| match index {
+            0 => dir.join(format!("madge-report-{}.zip", seconds)),
+            index => dir.join(format!("madge-report-{}-{}.zip", seconds, index)),
+        })
+        .find(|path| !path.exists())
+        .unwrap_or_default()
+}
+
+fn write_report(world: &World, path: &Path) -> io::Result<()> {
+    let to_io = |err: ron::Error| io::Error::new(io::ErrorKind::InvalidData, err);
+    let pretty = ron::ser::PrettyConfig::default;
+
+    // only the components registered for reflection make it into the scene, the engine's ones
+    // and the gameplay ones registered with the plugin
+    let registry = world.resource::<TypeRegistryArc>();
+    let scene = DynamicScene::from_world(world, registry)
+        .serialize_ron(registry)
+        .map_err(to_io)?;
+    let events = world
+        .resource::<LogHistory>()
+        .0
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect::<String>();
+    let settings =
+        ron::ser::to_string_pretty(world.resource::<Settings>(), pretty()).map_err(to_io)?;
+    let run = RunReport {
+        version: env!("CARGO_PKG_VERSION"),
+        seed: world.resource::<RunSeed>().0,
+        loadout: world.resource::<Loadout>(),
+        wave: world.resource::<Wave>().number,
+        score: world.resource::<Game>().score,
+        step: TIME_STEP,
+        keys: input_bit_names(),
+        inputs: world
+            .resource::<Recording>()
+            .tail((INPUT_DURATION / TIME_STEP) as u32),
+    };
+    let run = ron::ser::to_string_pretty(&run, pretty()).map_err(to_io)?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut zip = ZipWriter::new(File::create(path)?);
+    for (name, contents) in [
+        ("scene.ron", scene),
+        ("events.txt", events),
+        ("settings.ron", settings),
+        ("run.ron", run),
+    ] {
+        zip.start_file(name, SimpleFileOptions::default())?;
+        zip.write_all(contents.as_bytes())?;
+    }
+    zip.finish()?;
+    Ok(())
+}
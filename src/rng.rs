@@ -0,0 +1,74 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{cli::Cli, loadout::Loadout, GameState};
+
+/// Randomness deciding how a run plays out, what spawns where and which perks and bosses come.
+///
+/// Purely cosmetic randomness like the shards of a death doesn't come from here. With a seed
+/// given on the command line every run starts from it, with the daily seed picked in the loadout
+/// from the day's, otherwise from a fresh one.
+pub struct GameRng(pub StdRng);
+
+impl GameRng {
+    fn new(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
+
+/// Seed the current run's `GameRng` started from, kept so the run can be played again from it.
+pub struct RunSeed(pub u64);
+
+/// Label of the system reseeding the run as it starts, for what needs the run's seed to go after.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reseed;
+
+/// The seed given on the command line, or else the day's if the loadout asks for it, or else a
+/// fresh one.
+fn pick_seed(cli: &Cli, daily: bool) -> u64 {
+    match cli.seed {
+        Some(seed) => seed,
+        None if daily => daily_seed(),
+        None => rand::random(),
+    }
+}
+
+/// Seed of the day's runs, the same for everyone playing on the same day in UTC.
+fn daily_seed() -> u64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    since_epoch.as_secs() / (24 * 60 * 60)
+}
+
+/// Whether runs with this loadout can be played on the same seed again.
+pub fn is_repeatable(cli: &Cli, loadout: &Loadout) -> bool {
+    cli.seed.is_some() || loadout.daily
+}
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        // every run is reseeded as it starts, this is only what the menus see
+        let seed = pick_seed(app.world.resource::<Cli>(), false);
+        app.insert_resource(GameRng::new(seed))
+            .insert_resource(RunSeed(seed))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(reseed.label(Reseed)),
+            );
+    }
+}
+
+fn reseed(
+    cli: Res<Cli>,
+    loadout: Res<Loadout>,
+    mut rng: ResMut<GameRng>,
+    mut run_seed: ResMut<RunSeed>,
+) {
+    let seed = pick_seed(&cli, loadout.daily);
+    *rng = GameRng::new(seed);
+    run_seed.0 = seed;
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use gilrs::{
+    ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks},
+    GamepadId, Gilrs,
+};
+
+use crate::{
+    events::{BombDetonated, PlayerDamaged, ShotFired},
+    loadout::{Loadout, Weapon},
+    settings::Settings,
+};
+
+/// Rumble felt per point of damage taken, the strongest hit is felt at full strength.
+const DAMAGE_STRENGTH: f32 = 0.6;
+
+/// A single buzz of the controller.
+#[derive(Clone, Copy)]
+struct Rumble {
+    /// the low-frequency motor, felt as a heavy thud rather than a buzz
+    strong: f32,
+    weak: f32,
+    duration: Duration,
+}
+
+impl Rumble {
+    fn from_damage(amount: f32) -> Self {
+        Self {
+            strong: (amount * DAMAGE_STRENGTH).min(1.0),
+            weak: 0.5,
+            duration: Duration::from_millis(250),
+        }
+    }
+
+    const BOMB: Rumble = Rumble {
+        strong: 1.0,
+        weak: 1.0,
+        duration: Duration::from_millis(500),
+    };
+
+    /// Kick of a shot with the weapon, only the heavy ones have any.
+    fn recoil(weapon: Weapon) -> Option<Self> {
+        match weapon {
+            Weapon::Spread => Some(Self {
+                strong: 0.3,
+                weak: 0.2,
+                duration: Duration::from_millis(80),
+            }),
+            // fires continuously, a steady hum instead of a kick per shot
+            Weapon::Beam => Some(Self {
+                strong: 0.0,
+                weak: 0.3,
+                duration: Duration::from_millis(110),
+            }),
+            Weapon::Blaster | Weapon::Lance | Weapon::Frost => None,
+        }
+    }
+
+    fn effect(
+        &self,
+        gilrs: &mut Gilrs,
+        gamepads: &[GamepadId],
+    ) -> Result<Effect, gilrs::ff::Error> {
+        let magnitude = |strength: f32| (strength.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+        let play_for = Ticks::from_ms(self.duration.as_millis() as u32);
+        let scheduling = Replay {
+            after: Ticks::from_ms(0),
+            play_for,
+            with_delay: Ticks::from_ms(0),
+        };
+        EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: magnitude(self.strong),
+                },
+                scheduling,
+                envelope: default(),
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak {
+                    magnitude: magnitude(self.weak),
+                },
+                scheduling,
+                envelope: default(),
+            })
+            .gamepads(gamepads)
+            .repeat(Repeat::For(play_for))
+            .finish(gilrs)
+    }
+}
+
+/// Effects still playing, an effect stops as soon as it's dropped.
+#[derive(Default)]
+struct Playing(Vec<(Effect, Timer)>);
+
+pub struct RumblePlugin;
+
+impl Plugin for RumblePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_non_send_resource::<Playing>()
+            .add_system(rumble_system);
+    }
+}
+
+fn rumble_system(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    loadout: Res<Loadout>,
+    gilrs: Option<NonSendMut<Gilrs>>,
+    mut playing: NonSendMut<Playing>,
+    mut player_damaged: EventReader<PlayerDamaged>,
+    mut bomb_detonated: EventReader<BombDetonated>,
+    mut shot_fired: EventReader<ShotFired>,
+) {
+    playing
+        .0
+        .retain_mut(|(_, timer)| !timer.tick(time.delta()).finished());
+
+    let mut rumbles = player_damaged
+        .iter()
+        .map(|damaged| Rumble::from_damage(damaged.amount))
+        .collect::<Vec<_>>();
+    rumbles.extend(bomb_detonated.iter().map(|_| Rumble::BOMB));
+    // a volley counts as one shot, however many bullets are in it
+    if shot_fired.iter().count() > 0 {
+        rumbles.extend(Rumble::recoil(loadout.weapon));
+    }
+    let mut gilrs = match gilrs {
+        Some(gilrs) if settings.rumble => gilrs,
+        _ => return,
+    };
+    let gamepads = gilrs
+        .gamepads()
+        .filter(|(_, gamepad)| gamepad.is_ff_supported())
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    if gamepads.is_empty() {
+        return;
+    }
+    for rumble in rumbles {
+        let started = rumble.effect(&mut gilrs, &gamepads).and_then(|effect| {
+            effect.play()?;
+            Ok(effect)
+        });
+        match started {
+            Ok(effect) => playing.0.push((effect, Timer::new(rumble.duration, false))),
+            Err(err) => warn!("failed to rumble: {}", err),
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::platform::Platform;
+
+/// Environment variable overriding the directory save files are kept in.
+const SAVE_DIR_VAR: &str = "MADGE_SAVE_DIR";
+const DEFAULT_SAVE_DIR: &str = "saves";
+
+/// What's kept in a save file, written along with the version of its format so files left behind
+/// by older builds are brought up to date rather than discarded.
+///
+/// Changing a format in a way older files can't be read as raises its `VERSION` and teaches
+/// `migrate` to read the previous one, keeping the old layout around as its own type to read it
+/// with. Fields added with a default don't need a new version.
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// Version of the format this build writes.
+    const VERSION: u32;
+
+    /// Reads the contents of a file written at an older `version` of the format.
+    ///
+    /// Every format is still the one the unversioned files of older builds were written in, so by
+    /// default the file is read as it is.
+    fn migrate(version: u32, contents: &str) -> Result<Self, String> {
+        read_data(version, contents)
+    }
+}
+
+/// Header every versioned save file starts with, followed by its data.
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    schema_version: u32,
+    data: T,
+}
+
+/// Just the version of a save file, to know how to read the rest of it.
+#[derive(Deserialize)]
+struct Header {
+    /// missing for files from before formats had versions, which count as version 0
+    #[serde(default)]
+    schema_version: u32,
+}
+
+pub fn save_dir() -> PathBuf {
+    env::var_os(SAVE_DIR_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SAVE_DIR))
+}
+
+/// Keeps save files in `dir` from now on, instead of wherever they'd be kept otherwise.
+pub fn redirect(dir: &Path) {
+    env::set_var(SAVE_DIR_VAR, dir);
+}
+
+/// Directory the saves of a test or simulation are kept in, away from the player's and empty
+/// every time, named after what it's for.
+pub fn scratch_dir(purpose: &str) -> PathBuf {
+    env::temp_dir().join(format!("madge-{}-{}", purpose, std::process::id()))
+}
+
+/// Path of the save file `name`.
+pub fn save_path(name: &str) -> PathBuf {
+    save_dir().join(name).with_extension("ron")
+}
+
+/// Where the new contents of a save file are written before they replace the old ones, so a crash
+/// halfway through a write leaves the old file whole.
+fn temp_path(path: &Path) -> PathBuf {
+    path.with_extension("ron.tmp")
+}
+
+/// Where a save file that couldn't be read is set aside, for the next write not to overwrite it.
+fn backup_path(path: &Path) -> PathBuf {
+    path.with_extension("ron.bak")
+}
+
+/// Loads the save file `name`, falling back to the default value if it is missing or unreadable.
+pub fn load<T: Versioned + Default>(name: &str) -> T {
+    load_path(&save_path(name))
+}
+
+/// Loads the file at `path` like `load`, for files kept outside the save directory.
+pub fn load_path<T: Versioned + Default>(path: &Path) -> T {
+    match fs::read_to_string(path) {
+        Ok(contents) => parse(&contents, &path.display().to_string(), path),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => T::default(),
+        Err(err) => {
+            warn!("failed to read save file {}: {}", path.display(), err);
+            T::default()
+        }
+    }
+}
+
+/// Loads the save file `name` like `load`, preferring the copy in the platform's cloud storage.
+pub fn load_synced<T: Versioned + Default>(platform: &Platform, name: &str) -> T {
+    match platform.load_cloud(name) {
+        Some(contents) => parse(
+            &contents,
+            &format!("{} in the cloud", name),
+            &save_path(name),
+        ),
+        None => load(name),
+    }
+}
+
+/// Loads the save file `name` like `load` and removes it, if there is one, for files only kept
+/// until they've been dealt with.
+pub fn take<T: Versioned + Default>(name: &str) -> Option<T> {
+    let path = save_path(name);
+    if !path.exists() {
+        return None;
+    }
+    let value = load_path(&path);
+    remove(name);
+    Some(value)
+}
+
+/// Removes the save file `name`, if there is one.
+pub fn remove(name: &str) {
+    let path = save_path(name);
+    match fs::remove_file(&path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            warn!("failed to remove save file {}: {}", path.display(), err);
+        }
+        _ => {}
+    }
+}
+
+/// Reads the contents of a save file of any version of `T`'s format.
+pub fn read<T: Versioned>(contents: &str) -> Result<T, String> {
+    let version = ron::from_str::<Header>(contents).map_or(0, |header| header.schema_version);
+    if version > T::VERSION {
+        return Err(format!(
+            "written by a newer build in version {} of the format, this one reads up to {}",
+            version,
+            T::VERSION
+        ));
+    }
+    if version < T::VERSION {
+        info!(
+            "migrating save file from version {} to {}",
+            version,
+            T::VERSION
+        );
+        return T::migrate(version, contents);
+    }
+    read_data(version, contents)
+}
+
+/// Reads the data of a save file written at `version` as `D`, for `Versioned::migrate` to read
+/// older files with the types of their own formats.
+pub fn read_data<D: DeserializeOwned>(version: u32, contents: &str) -> Result<D, String> {
+    let data = if version == 0 {
+        ron::from_str(contents)
+    } else {
+        ron::from_str::<Envelope<D>>(contents).map(|envelope| envelope.data)
+    };
+    data.map_err(|err| err.to_string())
+}
+
+/// Reads `contents` like `read`, falling back to the default value if they can't be. The file at
+/// `path` they came from is set aside first, for a later build or a player to recover it.
+fn parse<T: Versioned + Default>(contents: &str, source: &str, path: &Path) -> T {
+    read(contents).unwrap_or_else(|err| {
+        let backup = backup_path(path);
+        warn!(
+            "can't read save file {}, starting over and keeping it as {}: {}",
+            source,
+            backup.display(),
+            err
+        );
+        if let Err(err) = write(&backup, contents) {
+            warn!("failed to keep {}: {}", backup.display(), err);
+        }
+        T::default()
+    })
+}
+
+/// Writes `value` to the save file `name`, logging instead of failing if it can't be written.
+pub fn store<T: Versioned>(name: &str, value: &T) {
+    store_path(&save_path(name), value);
+}
+
+/// Writes `value` to the file at `path` like `store`, for files kept outside the save directory.
+pub fn store_path<T: Versioned>(path: &Path, value: &T) {
+    if let Err(err) = try_store(path, value) {
+        warn!("failed to write save file {}: {}", path.display(), err);
+    }
+}
+
+/// Writes `value` to the save file `name` like `store`, and to the platform's cloud storage.
+pub fn store_synced<T: Versioned>(platform: &Platform, name: &str, value: &T) {
+    let path = save_path(name);
+    let result = serialize(value).and_then(|contents| {
+        platform.store_cloud(name, &contents);
+        write(&path, &contents)
+    });
+    if let Err(err) = result {
+        warn!("failed to write save file {}: {}", path.display(), err);
+    }
+}
+
+fn try_store<T: Versioned>(path: &Path, value: &T) -> io::Result<()> {
+    write(path, &serialize(value)?)
+}
+
+fn serialize<T: Versioned>(value: &T) -> io::Result<String> {
+    let envelope = Envelope {
+        schema_version: T::VERSION,
+        data: value,
+    };
+    ron::ser::to_string_pretty(&envelope, ron::ser::PrettyConfig::default())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Writes `contents` to a temporary file next to `path` and then moves it over the file, which
+/// either keeps the old contents or has all of the new ones whenever the game stops, even with
+/// the power cut. The temporary file is flushed to the disk before it's moved, or the move could
+/// land ahead of its contents, and the directory after, for the move itself to last.
+fn write(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temp = temp_path(path);
+    let mut file = File::create(&temp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&temp, path)?;
+    sync_dir(path)
+}
+
+/// Flushes the directory holding `path` to the disk, recording the files moved into it.
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => File::open(dir)?.sync_all(),
+        _ => Ok(()),
+    }
+}
+
+/// Elsewhere directories can't be opened to be flushed, the move is left to the file system.
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A format on its second version, the first having kept the best score under another name.
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Progress {
+        high_score: u32,
+        #[serde(default)]
+        runs: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct LegacyProgress {
+        best: u32,
+    }
+
+    impl Versioned for Progress {
+        const VERSION: u32 = 2;
+
+        fn migrate(version: u32, contents: &str) -> Result<Self, String> {
+            match version {
+                0 | 1 => read_data::<LegacyProgress>(version, contents).map(|legacy| Self {
+                    high_score: legacy.best,
+                    runs: 0,
+                }),
+                _ => read_data(version, contents),
+            }
+        }
+    }
+
+    /// Path of a file of its own in the scratch directory, with nothing left there from before.
+    fn scratch_path(name: &str) -> PathBuf {
+        let path = scratch_dir("save").join(name).with_extension("ron");
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(backup_path(&path));
+        path
+    }
+
+    #[test]
+    fn migrates_files_from_before_versions() {
+        let path = scratch_path("unversioned");
+        write(&path, "(best: 1200)").unwrap();
+        assert_eq!(
+            load_path::<Progress>(&path),
+            Progress {
+                high_score: 1200,
+                runs: 0
+            }
+        );
+        assert!(!backup_path(&path).exists());
+    }
+
+    #[test]
+    fn round_trips_the_current_version() {
+        let path = scratch_path("current");
+        let progress = Progress {
+            high_score: 4500,
+            runs: 12,
+        };
+        store_path(&path, &progress);
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("schema_version: 2"));
+        assert_eq!(load_path::<Progress>(&path), progress);
+    }
+
+    #[test]
+    fn refuses_newer_versions_and_keeps_them_aside() {
+        let path = scratch_path("newer");
+        let contents = "(schema_version: 3, data: (high_score: 9000, runs: 40, streak: 5))";
+        write(&path, contents).unwrap();
+        assert_eq!(load_path::<Progress>(&path), Progress::default());
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), contents);
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+    }
+}
@@ -0,0 +1,427 @@
+use std::{
+    collections::HashMap,
+    fs,
+    sync::{Arc, Mutex},
+};
+
+use bevy::{asset::FileAssetIo, prelude::*};
+use rhai::{
+    module_resolvers::DummyModuleResolver, Array, CallFnOptions, Dynamic, Engine, FuncArgs, Scope,
+    AST, FLOAT, INT,
+};
+use sha1_smol::Sha1;
+
+use crate::{
+    arena::ArenaLayout,
+    damage_player,
+    deaths::Dying,
+    enemy_spawn_position,
+    events::{BossArrived, DamageSource, EnemyKilled, PlayerDamaged, WaveStarted},
+    hives, kamikazes,
+    packs::ContentPacks,
+    palette::Palette,
+    rng::GameRng,
+    settings::Settings,
+    snipers, spawn_enemy, splitters, AddSimulationSystem, Enemy, EnemyKind, Game, GameState,
+    Health, Player,
+};
+
+/// Directory mod scripts are loaded from, every `.rhai` file in it is one mod.
+const MODS_DIR: &str = "mods";
+/// Operations a single hook may run before it's cut off, so a runaway loop can't hang the game.
+const MAX_OPERATIONS: u64 = 100_000;
+const ENEMY_VELOCITY: f32 = 250.0;
+/// Hook called for every enemy on every step, steering it by what it returns.
+const ENEMY_UPDATE_HOOK: &str = "on_enemy_update";
+
+/// Change to the game a script asked for, carried out once its hook returns.
+enum Request {
+    /// without a position the enemy appears where the run's seed has it, like the ones spawned
+    /// in waves
+    Spawn {
+        kind: EnemyKind,
+        position: Option<Vec2>,
+    },
+    Damage(f32),
+    Score(usize),
+}
+
+struct Script {
+    /// the script's file name, used when logging its problems
+    name: String,
+    ast: AST,
+}
+
+impl Script {
+    fn defines(&self, hook: &str) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|function| function.name == hook)
+    }
+}
+
+/// Velocities the scripts' `on_enemy_update` hooks gave enemies on this step, in units per
+/// second, steering them in place of chasing their target.
+#[derive(Default)]
+pub struct ScriptSteering(pub HashMap<Entity, Vec2>);
+
+/// Every mod script that was loaded at startup, along with the engine running them.
+///
+/// A script reacts to a run by defining any of the hooks `on_run_started()`,
+/// `on_wave_started(number)`, `on_enemy_killed(kind)`, `on_player_damaged(amount)` and
+/// `on_boss_arrived(name)`. Scripts are sandboxed: they can't import other files and can only
+/// affect the game through `spawn_enemy(kind, x, y)`, `spawn_enemy(kind)`, `damage(amount)` and
+/// `add_score(points)`, which queue requests that are carried out after the hooks return.
+///
+/// Enemies can be given behaviors of their own with `on_enemy_update(id, kind, x, y, player_x,
+/// player_y)`, called for every enemy on every step. Returning a velocity `[x, y]`, in units per
+/// second, moves the enemy that way on the step instead of chasing its target, returning nothing
+/// leaves it chasing. Enemies that follow their own paths, like bosses, snipers, kamikazes and
+/// formations still entering, aren't steered by it.
+pub struct Mods {
+    engine: Engine,
+    scripts: Vec<Script>,
+    requests: Arc<Mutex<Vec<Request>>>,
+    /// SHA-1 of every script that was read, loaded or not, in hex
+    hash: String,
+}
+
+impl Mods {
+    fn load(packs: &ContentPacks) -> Self {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let engine = engine(&requests);
+        let dir = FileAssetIo::get_root_path().join(MODS_DIR);
+        let mut paths = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == "rhai")
+                })
+                .collect::<Vec<_>>(),
+            // not having any mods is fine
+            Err(_) => Vec::new(),
+        };
+        // load in a stable order, so mods reacting to the same hook always run in the same order
+        paths.sort();
+        let mut sources = Vec::new();
+        for path in paths {
+            let name = path
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+            match fs::read_to_string(&path) {
+                Ok(contents) => sources.push((name, contents)),
+                Err(err) => warn!("failed to read {}: {}", path.display(), err),
+            }
+        }
+        // content packs' scripts run after the loose ones
+        sources.extend(packs.scripts());
+        let mut hash = Sha1::new();
+        for (name, contents) in sources.iter() {
+            hash.update(name.as_bytes());
+            hash.update(&[0]);
+            hash.update(&(contents.len() as u64).to_le_bytes());
+            hash.update(contents.as_bytes());
+        }
+        let mut scripts = Vec::new();
+        for (name, contents) in sources {
+            let ast = match engine.compile(contents) {
+                Ok(ast) => ast,
+                Err(err) => {
+                    warn!("failed to parse mod {}: {}", name, err);
+                    continue;
+                }
+            };
+            if let Err(err) = engine.run_ast(&ast) {
+                warn!("mod {} failed to start: {}", name, err);
+                continue;
+            }
+            info!("loaded mod {}", name);
+            scripts.push(Script { name, ast });
+        }
+        Self {
+            engine,
+            scripts,
+            requests,
+            hash: hash.digest().to_string(),
+        }
+    }
+
+    /// SHA-1 of every script that was read, in hex, for a run to be tied to the mods it was
+    /// played with.
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// Whether any script defines the hook.
+    fn defines(&self, hook: &str) -> bool {
+        self.scripts.iter().any(|script| script.defines(hook))
+    }
+
+    /// Calls the hook in every script defining it, with `args` built fresh for each of them, and
+    /// returns what the last of them to return anything returned.
+    fn call<A: FuncArgs>(&self, hook: &str, args: impl Fn() -> A) -> Option<Dynamic> {
+        let mut returned = None;
+        for script in self.scripts.iter().filter(|script| script.defines(hook)) {
+            // the script's top level already ran when it was loaded
+            let options = CallFnOptions::new().eval_ast(false);
+            match self.engine.call_fn_with_options::<Dynamic>(
+                options,
+                &mut Scope::new(),
+                &script.ast,
+                hook,
+                args(),
+            ) {
+                Ok(value) if !value.is_unit() => returned = Some(value),
+                Ok(_) => {}
+                Err(err) => warn!("mod {} failed in {}: {}", script.name, hook, err),
+            }
+        }
+        returned
+    }
+
+    fn take_requests(&self) -> Vec<Request> {
+        self.requests
+            .lock()
+            .map(|mut requests| std::mem::take(&mut *requests))
+            .unwrap_or_default()
+    }
+}
+
+/// Engine with the API exposed to scripts, every call queueing a request on `requests`.
+fn engine(requests: &Arc<Mutex<Vec<Request>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(4096)
+        .set_max_array_size(1024)
+        .set_max_map_size(1024)
+        .disable_symbol("eval")
+        .on_print(|text| info!("[mod] {}", text))
+        .on_debug(|text, _, position| info!("[mod] {} {}", position, text));
+
+    let queue = requests.clone();
+    let push = move |request| {
+        if let Ok(mut requests) = queue.lock() {
+            requests.push(request);
+        }
+    };
+    let spawn = push.clone();
+    engine.register_fn(
+        "spawn_enemy",
+        move |kind: &str, x: FLOAT, y: FLOAT| match enemy_kind(kind) {
+            Some(kind) => spawn(Request::Spawn {
+                kind,
+                position: Some(Vec2::new(x, y)),
+            }),
+            None => warn!("mods can't spawn enemies of kind {}", kind),
+        },
+    );
+    let spawn = push.clone();
+    engine.register_fn("spawn_enemy", move |kind: &str| match enemy_kind(kind) {
+        Some(kind) => spawn(Request::Spawn {
+            kind,
+            position: None,
+        }),
+        None => warn!("mods can't spawn enemies of kind {}", kind),
+    });
+    let damage = push.clone();
+    engine.register_fn("damage", move |amount: FLOAT| {
+        if amount > 0.0 {
+            damage(Request::Damage(amount));
+        }
+    });
+    engine.register_fn("add_score", move |points: INT| {
+        if points > 0 {
+            push(Request::Score(points as usize));
+        }
+    });
+    engine
+}
+
+/// Velocity a hook returned as `[x, y]`, none for anything else.
+fn velocity(value: Dynamic) -> Option<Vec2> {
+    let number = |value: &Dynamic| {
+        value
+            .as_float()
+            .ok()
+            .or_else(|| value.as_int().ok().map(|value| value as FLOAT))
+    };
+    match value.try_cast::<Array>()?.as_slice() {
+        [x, y] => Some(Vec2::new(number(x)?, number(y)?)),
+        _ => None,
+    }
+}
+
+/// Enemy kind a script refers to by name, only the ones that can show up in any wave.
+fn enemy_kind(name: &str) -> Option<EnemyKind> {
+    match name {
+        "chaser" => Some(EnemyKind::Chaser),
+        "splitter" => Some(EnemyKind::Splitter),
+        "sniper" => Some(EnemyKind::Sniper),
+        "kamikaze" => Some(EnemyKind::Kamikaze),
+        "hive" => Some(EnemyKind::Hive),
+        "swarmling" => Some(EnemyKind::Swarmling),
+        _ => None,
+    }
+}
+
+fn kind_name(kind: EnemyKind) -> &'static str {
+    match kind {
+        EnemyKind::Chaser => "chaser",
+        EnemyKind::Splitter => "splitter",
+        EnemyKind::Sniper => "sniper",
+        EnemyKind::Kamikaze => "kamikaze",
+        EnemyKind::Hive => "hive",
+        EnemyKind::Swarmling => "swarmling",
+        EnemyKind::Boss => "boss",
+    }
+}
+
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        let mods = Mods::load(app.world.resource::<ContentPacks>());
+        app.insert_resource(mods)
+            .init_resource::<ScriptSteering>()
+            .add_system(reload_system)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(run_started_system))
+            // ahead of the other hooks, for what the enemies' hooks ask for to be carried out on
+            // the same step
+            .add_simulation_system(enemy_hooks_system)
+            .add_simulation_system(hooks_system);
+    }
+}
+
+fn reload_system(mut commands: Commands, packs: Res<ContentPacks>) {
+    if packs.is_changed() && !packs.is_added() {
+        commands.insert_resource(Mods::load(&packs));
+    }
+}
+
+fn run_started_system(mods: Res<Mods>) {
+    mods.call("on_run_started", || ());
+}
+
+/// Asks the scripts' `on_enemy_update` hooks how every enemy moves on this step, for the enemies'
+/// steering to follow.
+fn enemy_hooks_system(
+    mods: Res<Mods>,
+    state: Res<State<GameState>>,
+    mut steering: ResMut<ScriptSteering>,
+    player: Query<&Transform, With<Player>>,
+    enemies: Query<(Entity, &Enemy, &Transform), (Without<Dying>, Without<Player>)>,
+) {
+    steering.0.clear();
+    if *state.current() != GameState::Playing || !mods.defines(ENEMY_UPDATE_HOOK) {
+        return;
+    }
+    let player = player
+        .get_single()
+        .map_or(Vec3::ZERO, |player| player.translation);
+    for (entity, enemy, transform) in enemies.iter() {
+        let position = transform.translation;
+        let returned = mods.call(ENEMY_UPDATE_HOOK, || {
+            (
+                entity.to_bits() as INT,
+                kind_name(enemy.kind).to_string(),
+                position.x,
+                position.y,
+                player.x,
+                player.y,
+            )
+        });
+        if let Some(velocity) = returned.and_then(velocity) {
+            steering.0.insert(entity, velocity);
+        }
+    }
+}
+
+/// Calls the hooks for everything that happened since the last step, then carries out what the
+/// scripts asked for. Enemies spawned without a position are placed with the run's seed, and
+/// none are spawned while the crowd is already at `max_enemies`.
+fn hooks_system(
+    mut commands: Commands,
+    mods: Res<Mods>,
+    mut game: ResMut<Game>,
+    mut state: ResMut<State<GameState>>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    layout: Res<ArenaLayout>,
+    mut rng: ResMut<GameRng>,
+    mut player: Query<(Entity, &mut Health), With<Player>>,
+    enemies: Query<(), (With<Enemy>, Without<Dying>)>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+    mut boss_arrived: EventReader<BossArrived>,
+    mut damaged: ParamSet<(EventReader<PlayerDamaged>, EventWriter<PlayerDamaged>)>,
+) {
+    // scripts only hook into runs, not the tutorial
+    if mods.scripts.is_empty() || *state.current() != GameState::Playing {
+        return;
+    }
+    for started in wave_started.iter() {
+        mods.call("on_wave_started", || (started.number as INT,));
+    }
+    for killed in enemy_killed.iter() {
+        mods.call("on_enemy_killed", || (kind_name(killed.kind).to_string(),));
+    }
+    for arrived in boss_arrived.iter() {
+        mods.call("on_boss_arrived", || (arrived.name.clone(),));
+    }
+    for damage in damaged.p0().iter() {
+        mods.call("on_player_damaged", || (damage.amount,));
+    }
+
+    let mut crowd = enemies.iter().count();
+    for request in mods.take_requests() {
+        match request {
+            Request::Spawn { kind, position } => {
+                if crowd >= settings.max_enemies {
+                    continue;
+                }
+                crowd += 1;
+                let position = match position {
+                    Some(position) => position.extend(0.0),
+                    None => enemy_spawn_position(&layout, &mut rng.0),
+                };
+                match kind {
+                    EnemyKind::Splitter => splitters::spawn(&mut commands, &palette, position),
+                    EnemyKind::Sniper => snipers::spawn(&mut commands, &palette, position),
+                    EnemyKind::Kamikaze => kamikazes::spawn(&mut commands, &palette, position),
+                    EnemyKind::Hive => hives::spawn(&mut commands, &palette, position),
+                    EnemyKind::Swarmling => {
+                        hives::spawn_swarmling(&mut commands, &palette, position)
+                    }
+                    kind => {
+                        spawn_enemy(&mut commands, &palette, position, kind, ENEMY_VELOCITY);
+                    }
+                }
+            }
+            Request::Damage(amount) => {
+                let (entity, mut health) = match player.get_single_mut() {
+                    Ok(player) => player,
+                    Err(_) => continue,
+                };
+                if damage_player(
+                    &mut commands,
+                    &mut state,
+                    &settings,
+                    entity,
+                    &mut health,
+                    amount,
+                    DamageSource::Script,
+                    &mut damaged.p1(),
+                ) {
+                    // the run is over, nothing else matters
+                    return;
+                }
+            }
+            Request::Score(points) => game.score += points,
+        }
+    }
+}
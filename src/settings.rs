@@ -0,0 +1,531 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assists::Assists,
+    cli::Cli,
+    despawn_with,
+    display::Display,
+    locale::{Language, Locale, Localized},
+    menu::{screen_node, spawn_button, spawn_title, MenuButton},
+    mobile::MOBILE,
+    munitions::Munitions,
+    music::{self, Music},
+    palette::PaletteMode,
+    platform::Platform,
+    save, GameState, UiFont,
+};
+
+const SETTINGS_FILE: &str = "settings";
+
+/// Player preferences, persisted between sessions.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub language: Language,
+    pub palette: PaletteMode,
+    pub assists: Assists,
+    /// the player leaves one edge of the arena to come back in at the opposite one
+    pub screen_wrap: bool,
+    /// bullets wrap around the arena once before disappearing
+    pub bullet_wrap: bool,
+    /// enemies are wound back along with the player while rewinding, rather than only the player
+    pub rewind_world: bool,
+    /// whether the player's weapon overheats or runs out of ammo, for a harder run
+    pub munitions: Munitions,
+    pub graphics: Graphics,
+    pub display: Display,
+    /// gamepads rumble when the player is hit, bombs or fires a heavy weapon
+    pub rumble: bool,
+    /// loudness of the soundtrack, from 0 to 1
+    pub music_volume: f32,
+    /// enemies only spawn on the soundtrack's beat, as many at once as came due since the last
+    pub beat_spawns: bool,
+    /// loudness of the announcer's voice, from 0 to 1
+    pub announcer_volume: f32,
+    /// loudness of the sound effects, from 0 to 1
+    pub effects_volume: f32,
+    /// a run pauses when the window loses focus
+    pub pause_on_focus_loss: bool,
+    /// most enemies alive at once before waves hold back new ones, only set in the settings file
+    /// for machines that can't keep up with a crowded arena
+    pub max_enemies: usize,
+    /// anonymous balance data of finished runs is sent to `telemetry_endpoint`, needs the
+    /// `telemetry` feature and is ignored without it
+    pub telemetry: bool,
+    /// `https://` URL telemetry is posted to, only set in the settings file, nothing is sent while
+    /// it's empty
+    pub telemetry_endpoint: String,
+}
+
+impl save::Versioned for Settings {
+    const VERSION: u32 = 1;
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: default(),
+            palette: default(),
+            assists: default(),
+            screen_wrap: false,
+            bullet_wrap: false,
+            rewind_world: false,
+            munitions: default(),
+            graphics: default(),
+            display: default(),
+            rumble: true,
+            music_volume: 0.75,
+            beat_spawns: false,
+            announcer_volume: 1.0,
+            effects_volume: 0.75,
+            pause_on_focus_loss: true,
+            max_enemies: 300,
+            telemetry: false,
+            telemetry_endpoint: String::new(),
+        }
+    }
+}
+
+/// Screen effects drawn by the `fancy-graphics` renderer, ignored without it.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Graphics {
+    /// brighter, wider glow around lights
+    pub bloom: bool,
+    /// red vignette closing in while the player is low on health
+    pub vignette: bool,
+    /// color fringes flashing at the edges of the screen when the player is hit
+    pub aberration: bool,
+    /// retro scanlines over the whole screen
+    pub crt: bool,
+}
+
+impl Default for Graphics {
+    fn default() -> Self {
+        Self {
+            bloom: true,
+            vignette: true,
+            aberration: true,
+            crt: false,
+        }
+    }
+}
+
+/// A setting shown on the settings screen.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+enum Setting {
+    Language,
+    Palette,
+    GameSpeed,
+    DamageReduction,
+    AimAssist,
+    ScreenWrap,
+    BulletWrap,
+    RewindWorld,
+    Munitions,
+    Rumble,
+    MusicVolume,
+    BeatSpawns,
+    AnnouncerVolume,
+    EffectsVolume,
+    PauseOnFocusLoss,
+    Telemetry,
+    Vsync,
+    FpsCap,
+    Windowing,
+    Resolution,
+    Bloom,
+    Vignette,
+    Aberration,
+    Crt,
+}
+
+impl Setting {
+    const ALL: [Setting; 24] = [
+        Setting::Language,
+        Setting::Palette,
+        Setting::GameSpeed,
+        Setting::DamageReduction,
+        Setting::AimAssist,
+        Setting::ScreenWrap,
+        Setting::BulletWrap,
+        Setting::RewindWorld,
+        Setting::Munitions,
+        Setting::Rumble,
+        Setting::MusicVolume,
+        Setting::BeatSpawns,
+        Setting::AnnouncerVolume,
+        Setting::EffectsVolume,
+        Setting::PauseOnFocusLoss,
+        Setting::Telemetry,
+        Setting::Vsync,
+        Setting::FpsCap,
+        Setting::Windowing,
+        Setting::Resolution,
+        Setting::Bloom,
+        Setting::Vignette,
+        Setting::Aberration,
+        Setting::Crt,
+    ];
+
+    /// Whether the setting does anything in this build, graphics settings need the
+    /// `fancy-graphics` renderer, telemetry its own feature, phones always pause when they lose
+    /// focus and their window is always the whole screen.
+    fn is_available(&self) -> bool {
+        let graphics = matches!(
+            self,
+            Setting::Bloom | Setting::Vignette | Setting::Aberration | Setting::Crt
+        );
+        let desktop_only = matches!(
+            self,
+            Setting::PauseOnFocusLoss | Setting::Windowing | Setting::Resolution
+        );
+        (cfg!(feature = "fancy-graphics") || !graphics)
+            && (cfg!(feature = "telemetry") || *self != Setting::Telemetry)
+            && !(MOBILE && desktop_only)
+    }
+
+    /// Key of the note shown in place of the setting's value while there's nothing for it to
+    /// change, the music volume without any stems found to play.
+    fn unavailable_key(&self, music: &Music) -> Option<&'static str> {
+        match self {
+            Setting::MusicVolume if !music.has_stems() => Some("settings.no_music"),
+            _ => None,
+        }
+    }
+
+    /// Whether the setting changes how the game looks rather than how it plays, those are
+    /// listed in a column of their own.
+    fn is_video(&self) -> bool {
+        matches!(
+            self,
+            Setting::Vsync
+                | Setting::FpsCap
+                | Setting::Windowing
+                | Setting::Resolution
+                | Setting::Bloom
+                | Setting::Vignette
+                | Setting::Aberration
+                | Setting::Crt
+        )
+    }
+
+    fn name_key(&self) -> &'static str {
+        match self {
+            Setting::Language => "settings.language",
+            Setting::Palette => "settings.palette",
+            Setting::GameSpeed => "settings.game_speed",
+            Setting::DamageReduction => "settings.damage_reduction",
+            Setting::AimAssist => "settings.aim_assist",
+            Setting::ScreenWrap => "settings.screen_wrap",
+            Setting::BulletWrap => "settings.bullet_wrap",
+            Setting::RewindWorld => "settings.rewind_world",
+            Setting::Munitions => "settings.munitions",
+            Setting::Rumble => "settings.rumble",
+            Setting::MusicVolume => "settings.music_volume",
+            Setting::BeatSpawns => "settings.beat_spawns",
+            Setting::AnnouncerVolume => "settings.announcer_volume",
+            Setting::EffectsVolume => "settings.effects_volume",
+            Setting::PauseOnFocusLoss => "settings.pause_on_focus_loss",
+            Setting::Telemetry => "settings.telemetry",
+            Setting::Vsync => "settings.vsync",
+            Setting::FpsCap => "settings.fps_cap",
+            Setting::Windowing => "settings.windowing",
+            Setting::Resolution => "settings.resolution",
+            Setting::Bloom => "settings.bloom",
+            Setting::Vignette => "settings.vignette",
+            Setting::Aberration => "settings.aberration",
+            Setting::Crt => "settings.crt",
+        }
+    }
+
+    fn value(&self, settings: &Settings, locale: &Locale, music: &Music) -> String {
+        if let Some(key) = self.unavailable_key(music) {
+            return locale.get(key).to_string();
+        }
+        match self {
+            // languages are always listed by their own name so they can be found when lost
+            Setting::Language => settings.language.native_name().to_string(),
+            Setting::Palette => locale.get(settings.palette.name_key()).to_string(),
+            Setting::GameSpeed => percent(settings.assists.game_speed),
+            Setting::DamageReduction => percent(settings.assists.damage_reduction),
+            Setting::AimAssist => percent(settings.assists.aim_assist),
+            Setting::ScreenWrap => toggle(locale, settings.screen_wrap),
+            Setting::BulletWrap => toggle(locale, settings.bullet_wrap),
+            Setting::RewindWorld => toggle(locale, settings.rewind_world),
+            Setting::Munitions => locale.get(settings.munitions.name_key()).to_string(),
+            Setting::Rumble => toggle(locale, settings.rumble),
+            Setting::MusicVolume => percent(settings.music_volume),
+            Setting::BeatSpawns => toggle(locale, settings.beat_spawns),
+            Setting::AnnouncerVolume => percent(settings.announcer_volume),
+            Setting::EffectsVolume => percent(settings.effects_volume),
+            Setting::PauseOnFocusLoss => toggle(locale, settings.pause_on_focus_loss),
+            Setting::Telemetry => toggle(locale, settings.telemetry),
+            Setting::Vsync => locale.get(settings.display.vsync.name_key()).to_string(),
+            Setting::FpsCap => match settings.display.fps_cap {
+                Some(fps) => fps.to_string(),
+                None => locale.get("settings.unlimited").to_string(),
+            },
+            Setting::Windowing => locale
+                .get(settings.display.windowing.name_key())
+                .to_string(),
+            Setting::Resolution => {
+                let (width, height) = settings.display.resolution;
+                format!("{}×{}", width, height)
+            }
+            Setting::Bloom => toggle(locale, settings.graphics.bloom),
+            Setting::Vignette => toggle(locale, settings.graphics.vignette),
+            Setting::Aberration => toggle(locale, settings.graphics.aberration),
+            Setting::Crt => toggle(locale, settings.graphics.crt),
+        }
+    }
+
+    /// Moves the setting on to its next value.
+    fn advance(&self, settings: &mut Settings) {
+        match self {
+            Setting::Language => settings.language = settings.language.next(),
+            Setting::Palette => settings.palette = settings.palette.next(),
+            Setting::GameSpeed => {
+                let speed = &mut settings.assists.game_speed;
+                *speed = next_step(&Assists::GAME_SPEEDS, *speed);
+            }
+            Setting::DamageReduction => {
+                let reduction = &mut settings.assists.damage_reduction;
+                *reduction = next_step(&Assists::DAMAGE_REDUCTIONS, *reduction);
+            }
+            Setting::AimAssist => {
+                let aim_assist = &mut settings.assists.aim_assist;
+                *aim_assist = next_step(&Assists::AIM_ASSISTS, *aim_assist);
+            }
+            Setting::ScreenWrap => settings.screen_wrap = !settings.screen_wrap,
+            Setting::BulletWrap => settings.bullet_wrap = !settings.bullet_wrap,
+            Setting::RewindWorld => settings.rewind_world = !settings.rewind_world,
+            Setting::Munitions => settings.munitions = settings.munitions.next(),
+            Setting::Rumble => settings.rumble = !settings.rumble,
+            Setting::MusicVolume => {
+                let volume = &mut settings.music_volume;
+                *volume = next_step(&music::VOLUMES, *volume);
+            }
+            Setting::BeatSpawns => settings.beat_spawns = !settings.beat_spawns,
+            Setting::AnnouncerVolume => {
+                let volume = &mut settings.announcer_volume;
+                *volume = next_step(&music::VOLUMES, *volume);
+            }
+            Setting::EffectsVolume => {
+                let volume = &mut settings.effects_volume;
+                *volume = next_step(&music::VOLUMES, *volume);
+            }
+            Setting::PauseOnFocusLoss => {
+                settings.pause_on_focus_loss = !settings.pause_on_focus_loss
+            }
+            Setting::Telemetry => settings.telemetry = !settings.telemetry,
+            Setting::Vsync => settings.display.vsync = settings.display.vsync.next(),
+            Setting::FpsCap => {
+                let fps_cap = &mut settings.display.fps_cap;
+                *fps_cap = next_step(&Display::FPS_CAPS, *fps_cap);
+            }
+            Setting::Windowing => settings.display.windowing = settings.display.windowing.next(),
+            Setting::Resolution => {
+                let resolution = &mut settings.display.resolution;
+                *resolution = next_step(&Display::RESOLUTIONS, *resolution);
+            }
+            Setting::Bloom => settings.graphics.bloom = !settings.graphics.bloom,
+            Setting::Vignette => settings.graphics.vignette = !settings.graphics.vignette,
+            Setting::Aberration => settings.graphics.aberration = !settings.graphics.aberration,
+            Setting::Crt => settings.graphics.crt = !settings.graphics.crt,
+        }
+    }
+}
+
+/// The step after `current`, wrapping around, or the first step if `current` isn't one of them.
+fn next_step<T: Copy + PartialEq>(steps: &[T], current: T) -> T {
+    let next = steps
+        .iter()
+        .position(|step| *step == current)
+        .map_or(0, |index| index + 1);
+    steps[next % steps.len()]
+}
+
+fn percent(fraction: f32) -> String {
+    format!("{:.0}%", fraction * 100.0)
+}
+
+fn toggle(locale: &Locale, enabled: bool) -> String {
+    let key = if enabled {
+        "settings.on"
+    } else {
+        "settings.off"
+    };
+    locale.get(key).to_string()
+}
+
+/// Text showing the current value of a setting.
+#[derive(Component)]
+struct SettingValue(Setting);
+
+#[derive(Component)]
+struct SettingsScreen;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        // settings kept somewhere else aren't synced, they're only meant for this machine
+        let settings = match &app.world.resource::<Cli>().config {
+            Some(path) => save::load_path::<Settings>(path),
+            None => save::load_synced::<Settings>(app.world.resource::<Platform>(), SETTINGS_FILE),
+        };
+        app.insert_resource(settings)
+            .add_system(store_settings_system)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Settings).with_system(spawn_settings_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Settings)
+                    .with_system(setting_button_system)
+                    .with_system(setting_value_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Settings).with_system(despawn_with::<SettingsScreen>),
+            );
+    }
+}
+
+fn store_settings_system(cli: Res<Cli>, platform: Res<Platform>, settings: Res<Settings>) {
+    if !settings.is_changed() || settings.is_added() {
+        return;
+    }
+    match &cli.config {
+        Some(path) => save::store_path(path, &*settings),
+        None => save::store_synced(&platform, SETTINGS_FILE, &*settings),
+    }
+}
+
+fn spawn_settings_screen(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    locale: Res<Locale>,
+    settings: Res<Settings>,
+    music: Res<Music>,
+) {
+    let style = TextStyle {
+        font: font.0.clone(),
+        font_size: 24.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    let column = || NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::FlexStart,
+            align_items: AlignItems::Center,
+            margin: Rect::all(Val::Px(4.0)),
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    };
+
+    commands
+        .spawn_bundle(screen_node())
+        .insert(SettingsScreen)
+        .with_children(|parent| {
+            spawn_title(parent, &font, &locale, "settings.title");
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::FlexStart,
+                        ..default()
+                    },
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|columns| {
+                    for video in [false, true] {
+                        columns.spawn_bundle(column()).with_children(|column| {
+                            for setting in Setting::ALL.into_iter().filter(|setting| {
+                                setting.is_available() && setting.is_video() == video
+                            }) {
+                                spawn_setting(column, setting, &style, &locale, &settings, &music);
+                            }
+                        });
+                    }
+                });
+            spawn_button(parent, &font, &locale, "menu.back", MenuButton::MainMenu);
+        });
+}
+
+fn spawn_setting(
+    parent: &mut ChildBuilder,
+    setting: Setting,
+    style: &TextStyle,
+    locale: &Locale,
+    settings: &Settings,
+    music: &Music,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(480.0), Val::Px(34.0)),
+                margin: Rect::all(Val::Px(2.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::rgb(0.15, 0.15, 0.15).into(),
+            ..default()
+        })
+        .insert(setting)
+        .with_children(|button| {
+            // the name is the localized first section, the value follows it
+            button
+                .spawn_bundle(TextBundle {
+                    text: Text {
+                        sections: vec![
+                            TextSection {
+                                value: locale.get(setting.name_key()).to_string(),
+                                style: style.clone(),
+                            },
+                            TextSection {
+                                value: format!(": {}", setting.value(settings, locale, music)),
+                                style: TextStyle {
+                                    color: Color::GOLD,
+                                    ..style.clone()
+                                },
+                            },
+                        ],
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(Localized(setting.name_key()))
+                .insert(SettingValue(setting));
+        });
+}
+
+fn setting_button_system(
+    buttons: Query<(&Interaction, &Setting), Changed<Interaction>>,
+    music: Res<Music>,
+    mut settings: ResMut<Settings>,
+) {
+    for (interaction, setting) in buttons.iter() {
+        if *interaction == Interaction::Clicked && setting.unavailable_key(&music).is_none() {
+            setting.advance(&mut settings);
+        }
+    }
+}
+
+fn setting_value_system(
+    settings: Res<Settings>,
+    locale: Res<Locale>,
+    music: Res<Music>,
+    mut values: Query<(&mut Text, &SettingValue)>,
+) {
+    if !settings.is_changed() && !locale.is_changed() {
+        return;
+    }
+    for (mut text, SettingValue(setting)) in values.iter_mut() {
+        text.sections[1].value = format!(": {}", setting.value(&settings, &locale, &music));
+    }
+}
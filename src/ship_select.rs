@@ -0,0 +1,256 @@
+use bevy::prelude::*;
+
+use crate::{
+    achievements::Achievements,
+    despawn_with,
+    loadout::{Loadout, Ship},
+    locale::Locale,
+    menu::{screen_node, spawn_button, spawn_title, MenuButton},
+    stats::LifetimeStats,
+    GameState, UiFont,
+};
+
+/// Width in pixels of a stat bar for the best ship in that stat.
+const STAT_BAR_WIDTH: f32 = 160.0;
+const CARD_COLOR: Color = Color::rgb(0.12, 0.12, 0.12);
+const TEXT_COLOR: Color = Color::rgb(0.85, 0.85, 0.85);
+
+#[derive(Component)]
+struct ShipSelectScreen;
+
+/// Clickable card previewing a ship.
+#[derive(Component)]
+struct ShipCard(Ship);
+
+/// Text on a ship's card marking whether it is the selected one.
+#[derive(Component)]
+struct SelectedMarker(Ship);
+
+pub struct ShipSelectPlugin;
+
+impl Plugin for ShipSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::ShipSelect).with_system(spawn_ship_select_screen),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::ShipSelect)
+                .with_system(ship_card_system)
+                .with_system(selected_marker_system),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::ShipSelect).with_system(despawn_with::<ShipSelectScreen>),
+        );
+    }
+}
+
+fn spawn_ship_select_screen(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    locale: Res<Locale>,
+    mut loadout: ResMut<Loadout>,
+    achievements: Res<Achievements>,
+    lifetime: Res<LifetimeStats>,
+) {
+    if !loadout.ship.requirement().is_met(&achievements, &lifetime) {
+        loadout.ship = Loadout::default().ship;
+    }
+
+    let max_velocity = Ship::ALL.iter().map(Ship::velocity).fold(0.0, f32::max);
+    let max_size = Ship::ALL.iter().map(Ship::size).fold(0.0, f32::max);
+    let max_health = Ship::ALL.iter().map(Ship::max_health).fold(0.0, f32::max);
+
+    let text = |value: String, size: f32, color: Color| TextBundle {
+        style: Style {
+            margin: Rect::all(Val::Px(3.0)),
+            ..default()
+        },
+        text: Text::with_section(
+            value,
+            TextStyle {
+                font: font.0.clone(),
+                font_size: size,
+                color,
+            },
+            default(),
+        ),
+        ..default()
+    };
+
+    commands
+        .spawn_bundle(screen_node())
+        .insert(ShipSelectScreen)
+        .with_children(|parent| {
+            spawn_title(parent, &font, &locale, "ship_select.title");
+
+            parent
+                .spawn_bundle(NodeBundle {
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for ship in Ship::ALL {
+                        let requirement = ship.requirement();
+                        let unlocked = requirement.is_met(&achievements, &lifetime);
+
+                        let mut card = row.spawn_bundle(ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(260.0), Val::Px(340.0)),
+                                margin: Rect::all(Val::Px(12.0)),
+                                padding: Rect::all(Val::Px(10.0)),
+                                flex_direction: FlexDirection::ColumnReverse,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            color: CARD_COLOR.into(),
+                            ..default()
+                        });
+                        if unlocked {
+                            card.insert(ShipCard(ship));
+                        }
+                        card.with_children(|card| {
+                            // preview drawn at twice the ship's in-game size
+                            let preview_color = if unlocked { ship.color() } else { Color::GRAY };
+                            card.spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(
+                                        Val::Px(ship.size() * 2.0),
+                                        Val::Px(ship.size() * 2.0),
+                                    ),
+                                    margin: Rect::all(Val::Px(16.0)),
+                                    ..default()
+                                },
+                                color: preview_color.into(),
+                                ..default()
+                            });
+                            card.spawn_bundle(text(
+                                locale.get(ship.name_key()).to_string(),
+                                30.0,
+                                TEXT_COLOR,
+                            ));
+
+                            for (label, value, max) in [
+                                ("ship_select.speed", ship.velocity(), max_velocity),
+                                ("ship_select.size", ship.size(), max_size),
+                                ("ship_select.health", ship.max_health(), max_health),
+                            ] {
+                                spawn_stat_bar(card, &font, locale.get(label), value / max);
+                            }
+
+                            let passive = ship.passive();
+                            card.spawn_bundle(text(
+                                locale.get(passive.name_key()).to_string(),
+                                20.0,
+                                Color::GOLD,
+                            ));
+                            card.spawn_bundle(text(
+                                locale.get(passive.description_key()).to_string(),
+                                16.0,
+                                TEXT_COLOR,
+                            ));
+                            if unlocked {
+                                card.spawn_bundle(text(
+                                    selected_text(&locale, ship, &loadout),
+                                    20.0,
+                                    Color::GREEN,
+                                ))
+                                .insert(SelectedMarker(ship));
+                            } else {
+                                card.spawn_bundle(text(
+                                    locale.format(
+                                        "locked",
+                                        &[("requirement", &requirement.describe(&locale))],
+                                    ),
+                                    18.0,
+                                    Color::GRAY,
+                                ));
+                            }
+                        });
+                    }
+                });
+
+            spawn_button(parent, &font, &locale, "menu.continue", MenuButton::Loadout);
+            spawn_button(parent, &font, &locale, "menu.back", MenuButton::MainMenu);
+        });
+}
+
+fn spawn_stat_bar(parent: &mut ChildBuilder, font: &UiFont, label: &str, fraction: f32) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                margin: Rect::all(Val::Px(2.0)),
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn_bundle(TextBundle {
+                style: Style {
+                    size: Size::new(Val::Px(60.0), Val::Auto),
+                    ..default()
+                },
+                text: Text::with_section(
+                    label,
+                    TextStyle {
+                        font: font.0.clone(),
+                        font_size: 16.0,
+                        color: TEXT_COLOR,
+                    },
+                    default(),
+                ),
+                ..default()
+            });
+            row.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(STAT_BAR_WIDTH), Val::Px(10.0)),
+                    ..default()
+                },
+                color: Color::rgb(0.25, 0.25, 0.25).into(),
+                ..default()
+            })
+            .with_children(|bar| {
+                bar.spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(fraction * 100.0), Val::Percent(100.0)),
+                        ..default()
+                    },
+                    color: Color::rgb(0.4, 0.7, 0.4).into(),
+                    ..default()
+                });
+            });
+        });
+}
+
+fn ship_card_system(
+    cards: Query<(&Interaction, &ShipCard), Changed<Interaction>>,
+    mut loadout: ResMut<Loadout>,
+) {
+    for (interaction, ShipCard(ship)) in cards.iter() {
+        if *interaction == Interaction::Clicked {
+            loadout.ship = *ship;
+        }
+    }
+}
+
+fn selected_marker_system(
+    loadout: Res<Loadout>,
+    locale: Res<Locale>,
+    mut markers: Query<(&mut Text, &SelectedMarker)>,
+) {
+    if !loadout.is_changed() && !locale.is_changed() {
+        return;
+    }
+    for (mut text, SelectedMarker(ship)) in markers.iter_mut() {
+        text.sections[0].value = selected_text(&locale, *ship, &loadout);
+    }
+}
+
+fn selected_text(locale: &Locale, ship: Ship, loadout: &Loadout) -> String {
+    if ship == loadout.ship {
+        locale.get("ship_select.selected").to_string()
+    } else {
+        String::new()
+    }
+}
@@ -0,0 +1,285 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    arena::Obstacle,
+    beam::segment_entry,
+    damage_player,
+    deaths::Dying,
+    elements::Wounds,
+    events::{DamageSource, PlayerDamaged},
+    layers::RenderLayer,
+    palette::Palette,
+    settings::Settings,
+    zones::Chilled,
+    AddSimulationSystem, Enemy, EnemyKind, GameState, Health, Player, TimeScale,
+};
+
+/// Wave from which snipers join the enemies spawned.
+const SNIPER_WAVE: usize = 4;
+/// Chance for an enemy spawned from `SNIPER_WAVE` on, that isn't a splitter, to be a sniper.
+const SNIPER_CHANCE: f64 = 0.1;
+const SNIPER_SIZE: f32 = 18.0;
+const SNIPER_VELOCITY: f32 = 140.0;
+/// Distances to the player a sniper tries to stay between, circling it while it's in between.
+const MIN_RANGE: f32 = 300.0;
+const MAX_RANGE: f32 = 500.0;
+/// Distance a shot reaches, unless a wall stops it first.
+const SHOT_RANGE: f32 = 900.0;
+const SHOT_DAMAGE: f32 = 2.0;
+/// Seconds a sniper moves around between shots before it starts aiming again.
+const REPOSITION_DURATION: f32 = 2.0;
+const AIM_DURATION: f32 = 1.0;
+/// Seconds before the shot in which the aim stops following the player, the window to dodge it.
+const LOCK_DURATION: f32 = 0.3;
+/// Seconds a sniper stands still after firing.
+const COOLDOWN_DURATION: f32 = 0.8;
+const LASER_WIDTH: f32 = 1.5;
+const TRACER_WIDTH: f32 = 5.0;
+const TRACER_LIFETIME: f32 = 0.2;
+
+/// What a sniper is doing, with the time it has left doing it.
+enum SniperState {
+    Repositioning(Timer),
+    Aiming(Timer),
+    Cooldown(Timer),
+}
+
+/// Enemy that keeps its distance from the player, shows where it's aiming with a thin laser and
+/// then fires a hitscan shot along it.
+#[derive(Component)]
+pub struct Sniper {
+    state: SniperState,
+    /// direction the laser points in and the shot goes
+    aim: Vec2,
+    /// which way it circles the player around, flipped after every shot
+    strafe: f32,
+}
+
+/// Aiming laser drawn from a sniper, a child of it.
+#[derive(Component)]
+struct AimLaser;
+
+/// Trail a sniper's shot leaves for a moment along where it went.
+#[derive(Component)]
+struct Tracer(Timer);
+
+pub struct SnipersPlugin;
+
+impl Plugin for SnipersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(sniper_system)
+            .add_simulation_system(tracer_system);
+    }
+}
+
+/// Whether the next enemy spawned on the given wave should be a sniper.
+pub fn roll_sniper(wave: usize, rng: &mut impl Rng) -> bool {
+    wave >= SNIPER_WAVE && rng.gen_bool(SNIPER_CHANCE)
+}
+
+pub fn spawn(commands: &mut Commands, palette: &Palette, position: Vec3) {
+    let mut laser_color = palette.laser;
+    laser_color.set_a(0.4);
+    palette
+        .spawn_sprite(
+            commands,
+            RenderLayer::Enemies,
+            palette.sniper,
+            SNIPER_SIZE,
+            Transform::from_translation(position)
+                .with_rotation(Quat::from_rotation_z(palette.enemy_rotation)),
+        )
+        .insert(Enemy {
+            kind: EnemyKind::Sniper,
+            velocity: SNIPER_VELOCITY,
+            size: SNIPER_SIZE,
+        })
+        .insert(Wounds::default())
+        .insert(Sniper {
+            state: SniperState::Repositioning(Timer::from_seconds(REPOSITION_DURATION, false)),
+            aim: Vec2::Y,
+            strafe: 1.0,
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: laser_color,
+                        custom_size: Some(Vec2::new(LASER_WIDTH, 1.0)),
+                        ..default()
+                    },
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert(AimLaser);
+        });
+}
+
+/// Where a shot from `start` along `direction` ends, at the first wall in its way or else at its
+/// full range.
+fn shot_end(start: Vec2, direction: Vec2, obstacles: &Query<&Obstacle>) -> Vec2 {
+    let end = start + direction * SHOT_RANGE;
+    let reached = obstacles
+        .iter()
+        .filter_map(|Obstacle(obstacle)| {
+            segment_entry(
+                start,
+                end,
+                obstacle.center(),
+                Vec2::splat(obstacle.size / 2.0),
+            )
+        })
+        .fold(1.0_f32, f32::min);
+    start + (end - start) * reached
+}
+
+/// Distance from `point` to the closest point on the segment from `start` to `end`.
+fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let delta = end - start;
+    let along = (point - start).dot(delta) / delta.length_squared().max(f32::EPSILON);
+    point.distance(start + delta * along.clamp(0.0, 1.0))
+}
+
+/// Moves snipers to keep their distance, has them aim and fire, and points their lasers.
+fn sniper_system(
+    mut commands: Commands,
+    mut state: ResMut<State<GameState>>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+    time_scale: Res<TimeScale>,
+    mut snipers: Query<
+        (
+            &Enemy,
+            &mut Sniper,
+            &mut Transform,
+            &Children,
+            Option<&Chilled>,
+        ),
+        (Without<Dying>, Without<AimLaser>),
+    >,
+    mut lasers: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<AimLaser>>,
+    mut player: Query<
+        (Entity, &Player, &Transform, &mut Health),
+        (Without<Sniper>, Without<AimLaser>),
+    >,
+    obstacles: Query<&Obstacle>,
+    mut player_damaged: EventWriter<PlayerDamaged>,
+) {
+    let (player_entity, player, player_transform, mut health) = match player.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let target = player_transform.translation.truncate();
+    let step = time_scale.enemy_step();
+    let step_duration = time_scale.enemy_step_duration();
+
+    for (enemy, mut sniper, mut transform, children, chilled) in snipers.iter_mut() {
+        let position = transform.translation.truncate();
+        let to_player = target - position;
+        let distance = to_player.length();
+        let sniper = &mut *sniper;
+        let strafe = sniper.strafe;
+        let mut fired = false;
+        match &mut sniper.state {
+            SniperState::Repositioning(timer) => {
+                let direction = if distance < MIN_RANGE {
+                    -to_player
+                } else if distance > MAX_RANGE {
+                    to_player
+                } else {
+                    to_player.perp() * strafe
+                };
+                let speed = enemy.velocity * chilled.map_or(1.0, |chilled| chilled.0);
+                transform.translation += (direction.normalize_or_zero() * speed * step).extend(0.0);
+                // only takes aim once it's in range, however long getting there takes
+                if timer.tick(step_duration).finished() && distance <= MAX_RANGE {
+                    sniper.state = SniperState::Aiming(Timer::from_seconds(AIM_DURATION, false));
+                }
+            }
+            SniperState::Aiming(timer) => {
+                timer.tick(step_duration);
+                if timer.duration().as_secs_f32() - timer.elapsed_secs() > LOCK_DURATION {
+                    sniper.aim = to_player.try_normalize().unwrap_or(sniper.aim);
+                }
+                fired = timer.finished();
+            }
+            SniperState::Cooldown(timer) => {
+                if timer.tick(step_duration).finished() {
+                    sniper.strafe = -strafe;
+                    sniper.state =
+                        SniperState::Repositioning(Timer::from_seconds(REPOSITION_DURATION, false));
+                }
+            }
+        }
+
+        let end = shot_end(position, sniper.aim, &obstacles);
+        let aiming = matches!(sniper.state, SniperState::Aiming(_));
+        let locked = match &sniper.state {
+            SniperState::Aiming(timer) => {
+                timer.duration().as_secs_f32() - timer.elapsed_secs() <= LOCK_DURATION
+            }
+            _ => false,
+        };
+        // the laser is a child, so it's laid out in the sniper's own rotated frame
+        let inverse = transform.rotation.inverse();
+        let length = position.distance(end);
+        for &child in children.iter() {
+            if let Ok((mut sprite, mut laser, mut visibility)) = lasers.get_mut(child) {
+                visibility.is_visible = aiming;
+                sprite.custom_size = Some(Vec2::new(LASER_WIDTH, length));
+                // brightens once the aim holds still, the shot is about to come
+                sprite.color.set_a(if locked { 0.9 } else { 0.4 });
+                laser.translation = inverse * (sniper.aim * length / 2.0).extend(0.01);
+                laser.rotation = inverse * Quat::from_rotation_arc(Vec3::Y, sniper.aim.extend(0.0));
+            }
+        }
+
+        if !fired {
+            continue;
+        }
+        sniper.state = SniperState::Cooldown(Timer::from_seconds(COOLDOWN_DURATION, false));
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: palette.laser,
+                    custom_size: Some(Vec2::new(TRACER_WIDTH, length)),
+                    ..default()
+                },
+                transform: Transform::from_translation(((position + end) / 2.0).extend(0.1))
+                    .with_rotation(Quat::from_rotation_arc(Vec3::Y, sniper.aim.extend(0.0))),
+                ..default()
+            })
+            .insert(RenderLayer::Fx)
+            .insert(Tracer(Timer::from_seconds(TRACER_LIFETIME, false)));
+        let hit_distance = player.hit_radius + TRACER_WIDTH / 2.0;
+        if distance_to_segment(target, position, end) <= hit_distance
+            && damage_player(
+                &mut commands,
+                &mut state,
+                &settings,
+                player_entity,
+                &mut health,
+                SHOT_DAMAGE,
+                DamageSource::Enemy(EnemyKind::Sniper),
+                &mut player_damaged,
+            )
+        {
+            return;
+        }
+    }
+}
+
+fn tracer_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut tracers: Query<(Entity, &mut Tracer, &mut Sprite)>,
+) {
+    for (entity, mut tracer, mut sprite) in tracers.iter_mut() {
+        if tracer.0.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        sprite.color.set_a(tracer.0.percent_left());
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{elements::Wounds, layers::RenderLayer, palette::Palette, Enemy, EnemyKind};
+
+/// Wave from which splitters join the enemies spawned.
+const SPLITTER_WAVE: usize = 2;
+/// Chance for an enemy spawned from `SPLITTER_WAVE` on to be a splitter.
+const SPLITTER_CHANCE: f64 = 0.2;
+const SPLITTER_SIZE: f32 = 26.0;
+const SPLITTER_VELOCITY: f32 = 170.0;
+/// Factors each generation of children shrinks and speeds up by.
+const CHILD_SIZE_FACTOR: f32 = 0.65;
+const CHILD_VELOCITY_FACTOR: f32 = 1.35;
+/// Smallest size a child can have, splitters whose children would be smaller die for good.
+const MIN_SIZE: f32 = 8.0;
+
+/// Enemy that breaks up into smaller, faster copies of itself when shot down.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Splitter {
+    /// number of splits this one descends from, 0 for the initial spawn
+    pub generation: u32,
+}
+
+impl Splitter {
+    /// Score awarded for killing the splitter, growing with each generation as the children get
+    /// harder to hit.
+    pub fn score(&self) -> usize {
+        1 + self.generation as usize
+    }
+}
+
+/// Whether the next enemy spawned on the given wave should be a splitter.
+pub fn roll_splitter(wave: usize, rng: &mut impl Rng) -> bool {
+    wave >= SPLITTER_WAVE && rng.gen_bool(SPLITTER_CHANCE)
+}
+
+fn spawn_splitter(
+    commands: &mut Commands,
+    palette: &Palette,
+    position: Vec3,
+    size: f32,
+    velocity: f32,
+    generation: u32,
+) {
+    palette
+        .spawn_sprite(
+            commands,
+            RenderLayer::Enemies,
+            palette.splitter,
+            size,
+            Transform::from_translation(position)
+                .with_rotation(Quat::from_rotation_z(palette.enemy_rotation)),
+        )
+        .insert(Enemy {
+            kind: EnemyKind::Splitter,
+            velocity,
+            size,
+        })
+        .insert(Wounds::default())
+        .insert(Splitter { generation });
+}
+
+/// Spawns the first generation of a splitter.
+pub fn spawn(commands: &mut Commands, palette: &Palette, position: Vec3) {
+    spawn_splitter(
+        commands,
+        palette,
+        position,
+        SPLITTER_SIZE,
+        SPLITTER_VELOCITY,
+        0,
+    );
+}
+
+/// Breaks a killed splitter up into 2 or 3 children fanning out around where it died, unless
+/// they would be too small.
+pub fn split(
+    commands: &mut Commands,
+    palette: &Palette,
+    position: Vec3,
+    enemy: &Enemy,
+    splitter: &Splitter,
+    rng: &mut impl Rng,
+) {
+    let size = enemy.size * CHILD_SIZE_FACTOR;
+    if size < MIN_SIZE {
+        return;
+    }
+    let children = rng.gen_range(2..=3);
+    let start = rng.gen::<f32>() * std::f32::consts::TAU;
+    for i in 0..children {
+        let angle = start + i as f32 / children as f32 * std::f32::consts::TAU;
+        let offset = Quat::from_rotation_z(angle) * Vec3::X * enemy.size / 2.0;
+        spawn_splitter(
+            commands,
+            palette,
+            position + offset,
+            size,
+            enemy.velocity * CHILD_VELOCITY_FACTOR,
+            splitter.generation + 1,
+        );
+    }
+}
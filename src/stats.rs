@@ -0,0 +1,367 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attract::Attract,
+    bot::BotController,
+    combo::Combo,
+    despawn_with,
+    events::{
+        BulletHit, DamageSource, EnemyKilled, PickupCollected, PlayerDamaged, ShotFired,
+        WaveStarted,
+    },
+    loadout::Loadout,
+    locale::Locale,
+    menu::screen_node,
+    platform::Platform,
+    save,
+    settings::Settings,
+    AddSimulationSystem, EnemyKind, Game, GameState, TimeScale, UiFont,
+};
+
+const LIFETIME_STATS_FILE: &str = "lifetime_stats";
+/// Save file keeping the run in progress, counted into the lifetime stats on the next start if
+/// the game stopped before the run ended.
+const RUN_CHECKPOINT_FILE: &str = "run_checkpoint";
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StatsUpdate;
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct RecordRun;
+
+/// Statistics for the run currently being played, reset whenever a new run starts.
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunStats {
+    pub shots_fired: usize,
+    pub hits: usize,
+    pub kills: BTreeMap<EnemyKind, usize>,
+    /// kills that were elites, also counted in `kills`
+    pub elite_kills: usize,
+    pub damage_taken: f32,
+    pub pickups: usize,
+    /// what hurt the player last, which is what ended the run once it's over
+    pub last_damage: Option<DamageSource>,
+    pub peak_combo: usize,
+    pub wave_reached: usize,
+    /// simulated seconds spent in the current run, slowed down along with the game
+    pub time_survived: f32,
+    /// whether any assist is enabled or the bot is playing, which keeps the run out of
+    /// records and achievements
+    pub assisted: bool,
+}
+
+impl RunStats {
+    pub fn total_kills(&self) -> usize {
+        self.kills.values().sum()
+    }
+
+    /// Fraction of fired bullets that hit an enemy.
+    pub fn accuracy(&self) -> f32 {
+        accuracy(self.hits, self.shots_fired)
+    }
+}
+
+/// The run in progress as of its last checkpoint.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RunCheckpoint {
+    stats: RunStats,
+    score: usize,
+}
+
+impl save::Versioned for RunCheckpoint {
+    const VERSION: u32 = 1;
+}
+
+/// Aggregates over every finished run, persisted between sessions.
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifetimeStats {
+    pub runs: usize,
+    pub shots_fired: usize,
+    pub hits: usize,
+    pub kills: BTreeMap<EnemyKind, usize>,
+    pub damage_taken: f32,
+    pub pickups: usize,
+    pub best_score: usize,
+    pub best_combo: usize,
+    pub best_wave: usize,
+    pub longest_run: f32,
+    pub time_played: f32,
+}
+
+impl save::Versioned for LifetimeStats {
+    const VERSION: u32 = 1;
+}
+
+impl LifetimeStats {
+    pub fn total_kills(&self) -> usize {
+        self.kills.values().sum()
+    }
+
+    pub fn accuracy(&self) -> f32 {
+        accuracy(self.hits, self.shots_fired)
+    }
+
+    fn record(&mut self, run: &RunStats, score: usize) {
+        self.runs += 1;
+        self.shots_fired += run.shots_fired;
+        self.hits += run.hits;
+        for (kind, kills) in &run.kills {
+            *self.kills.entry(*kind).or_default() += kills;
+        }
+        self.damage_taken += run.damage_taken;
+        self.pickups += run.pickups;
+        self.time_played += run.time_survived;
+        if run.assisted {
+            return;
+        }
+        self.best_score = self.best_score.max(score);
+        self.best_combo = self.best_combo.max(run.peak_combo);
+        self.best_wave = self.best_wave.max(run.wave_reached);
+        self.longest_run = self.longest_run.max(run.time_survived);
+    }
+}
+
+fn accuracy(hits: usize, shots: usize) -> f32 {
+    if shots == 0 {
+        0.0
+    } else {
+        hits as f32 / shots as f32
+    }
+}
+
+#[derive(Component)]
+struct SummaryScreen;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        let platform = app.world.resource::<Platform>();
+        let mut lifetime = save::load_synced::<LifetimeStats>(platform, LIFETIME_STATS_FILE);
+        // the game stopped during the last run, it still counts as far as it got
+        if let Some(checkpoint) = save::take::<RunCheckpoint>(RUN_CHECKPOINT_FILE) {
+            info!("counting the stats of a run that didn't end");
+            lifetime.record(&checkpoint.stats, checkpoint.score);
+            save::store_synced(platform, LIFETIME_STATS_FILE, &lifetime);
+        }
+        app.init_resource::<RunStats>()
+            .insert_resource(lifetime)
+            .add_system(run_stats_system.label(StatsUpdate))
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_run_stats))
+            .add_simulation_system(run_timer_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(checkpoint_system.after(StatsUpdate)),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver)
+                    .with_system(record_run.label(RecordRun))
+                    .with_system(spawn_summary.after(RecordRun)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::GameOver).with_system(despawn_with::<SummaryScreen>),
+            );
+    }
+}
+
+fn reset_run_stats(mut stats: ResMut<RunStats>, settings: Res<Settings>, bot: Res<BotController>) {
+    *stats = RunStats {
+        assisted: settings.assists.is_active() || bot.enabled,
+        ..default()
+    };
+}
+
+fn run_timer_system(
+    state: Res<State<GameState>>,
+    time_scale: Res<TimeScale>,
+    mut stats: ResMut<RunStats>,
+) {
+    if *state.current() != GameState::Playing {
+        return;
+    }
+    stats.time_survived += time_scale.step();
+}
+
+fn run_stats_system(
+    mut stats: ResMut<RunStats>,
+    combo: Res<Combo>,
+    mut shot_fired: EventReader<ShotFired>,
+    mut bullet_hit: EventReader<BulletHit>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+    mut player_damaged: EventReader<PlayerDamaged>,
+    mut pickup_collected: EventReader<PickupCollected>,
+    mut wave_started: EventReader<WaveStarted>,
+) {
+    stats.shots_fired += shot_fired.iter().count();
+    stats.hits += bullet_hit.iter().count();
+    for killed in enemy_killed.iter() {
+        *stats.kills.entry(killed.kind).or_default() += 1;
+        if !killed.modifiers.is_empty() {
+            stats.elite_kills += 1;
+        }
+    }
+    for damaged in player_damaged.iter() {
+        stats.damage_taken += damaged.amount;
+        stats.last_damage = Some(damaged.source);
+    }
+    stats.pickups += pickup_collected.iter().count();
+    for started in wave_started.iter() {
+        stats.wave_reached = stats.wave_reached.max(started.number);
+    }
+    stats.peak_combo = stats.peak_combo.max(combo.count);
+}
+
+fn record_run(
+    platform: Res<Platform>,
+    stats: Res<RunStats>,
+    game: Res<Game>,
+    mut lifetime: ResMut<LifetimeStats>,
+) {
+    lifetime.record(&stats, game.score);
+    save::store_synced(&platform, LIFETIME_STATS_FILE, &*lifetime);
+    save::remove(RUN_CHECKPOINT_FILE);
+}
+
+/// Saves the run so far whenever a wave starts or a boss goes down, for a crash not to lose it.
+fn checkpoint_system(
+    stats: Res<RunStats>,
+    game: Res<Game>,
+    attract: Res<Attract>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+) {
+    let boss_killed = enemy_killed
+        .iter()
+        .any(|killed| killed.kind == EnemyKind::Boss);
+    // demo runs never end in a game over, there's nothing to recover from them
+    if (wave_started.iter().count() == 0 && !boss_killed) || attract.is_running() {
+        return;
+    }
+    save::store(
+        RUN_CHECKPOINT_FILE,
+        &RunCheckpoint {
+            stats: stats.clone(),
+            score: game.score,
+        },
+    );
+}
+
+fn spawn_summary(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    locale: Res<Locale>,
+    game: Res<Game>,
+    loadout: Res<Loadout>,
+    stats: Res<RunStats>,
+    lifetime: Res<LifetimeStats>,
+) {
+    let heading = TextStyle {
+        font: font.0.clone(),
+        font_size: 48.0,
+        color: Color::rgb(0.25, 0.25, 0.75),
+    };
+    let body = TextStyle {
+        font: font.0.clone(),
+        font_size: 24.0,
+        color: Color::rgb(0.85, 0.85, 0.85),
+    };
+
+    let percent = |fraction: f32| format!("{:.0}", fraction * 100.0);
+
+    let mut run_lines = vec![
+        locale.format("summary.score", &[("score", &game.score)]),
+        locale.format("summary.wave_reached", &[("wave", &stats.wave_reached)]),
+        locale.format(
+            "summary.time_survived",
+            &[("time", &format_duration(stats.time_survived))],
+        ),
+        locale.format("summary.shots_fired", &[("shots", &stats.shots_fired)]),
+        locale.format(
+            "summary.accuracy",
+            &[("accuracy", &percent(stats.accuracy()))],
+        ),
+        locale.format("summary.kills", &[("kills", &stats.total_kills())]),
+    ];
+    run_lines.extend(stats.kills.iter().map(|(kind, kills)| {
+        locale.format(
+            "summary.kills_by_kind",
+            &[("enemy", &locale.get(kind.name_key())), ("kills", kills)],
+        )
+    }));
+    if stats.elite_kills > 0 {
+        run_lines.push(locale.format("summary.elite_kills", &[("kills", &stats.elite_kills)]));
+    }
+    run_lines.push(locale.format("summary.damage_taken", &[("damage", &stats.damage_taken)]));
+    run_lines.push(locale.format("summary.pickups", &[("pickups", &stats.pickups)]));
+    run_lines.push(locale.format("summary.peak_combo", &[("combo", &stats.peak_combo)]));
+    if !loadout.mutators.is_empty() {
+        let mutators = loadout
+            .mutators
+            .iter()
+            .map(|mutator| locale.get(mutator.name_key()).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        run_lines.push(locale.format("summary.mutators", &[("mutators", &mutators)]));
+    }
+    if stats.assisted {
+        run_lines.push(locale.get("summary.assisted").to_string());
+    }
+
+    let lifetime_lines = [
+        locale.format("summary.runs", &[("runs", &lifetime.runs)]),
+        locale.format("summary.best_score", &[("score", &lifetime.best_score)]),
+        locale.format("summary.total_kills", &[("kills", &lifetime.total_kills())]),
+        locale.format(
+            "summary.accuracy",
+            &[("accuracy", &percent(lifetime.accuracy()))],
+        ),
+        locale.format("summary.best_combo", &[("combo", &lifetime.best_combo)]),
+        locale.format("summary.best_wave", &[("wave", &lifetime.best_wave)]),
+        locale.format(
+            "summary.longest_run",
+            &[("time", &format_duration(lifetime.longest_run))],
+        ),
+        locale.format(
+            "summary.time_played",
+            &[("time", &format_duration(lifetime.time_played))],
+        ),
+    ];
+
+    commands
+        .spawn_bundle(screen_node())
+        .insert(SummaryScreen)
+        .with_children(|parent| {
+            let mut line = |value: &str, style: &TextStyle| {
+                parent.spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    text: Text::with_section(value, style.clone(), default()),
+                    ..default()
+                });
+            };
+
+            line(locale.get("summary.game_over"), &heading);
+            for value in &run_lines {
+                line(value, &body);
+            }
+            line(locale.get("summary.lifetime"), &heading);
+            for value in &lifetime_lines {
+                line(value, &body);
+            }
+            line(locale.get("summary.export_clip"), &body);
+            line(locale.get("summary.restart"), &body);
+        });
+}
+
+fn format_duration(seconds: f32) -> String {
+    let seconds = seconds as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
@@ -0,0 +1,84 @@
+use std::io::{Read, Write};
+
+use bevy::{prelude::*, window::PresentMode};
+use steamworks::Client;
+
+use crate::{achievements::Achievement, platform::PlatformServices};
+
+/// Services of Steam, available when the game was launched from it.
+pub struct Steam {
+    client: Client,
+}
+
+impl Steam {
+    /// Connects to the running Steam client, if there is one and it's running the game.
+    pub fn init() -> Option<Self> {
+        match Client::init() {
+            Ok(client) => {
+                info!("running under Steam");
+                Some(Self { client })
+            }
+            Err(err) => {
+                info!("not running under Steam: {}", err);
+                None
+            }
+        }
+    }
+}
+
+/// Id the achievement is registered under on Steam.
+fn achievement_id(achievement: Achievement) -> &'static str {
+    match achievement {
+        Achievement::Centurion => "CENTURION",
+        Achievement::Pacifist => "PACIFIST",
+        Achievement::Untouchable => "UNTOUCHABLE",
+    }
+}
+
+impl PlatformServices for Steam {
+    fn unlock_achievement(&self, achievement: Achievement) {
+        let stats = self.client.user_stats();
+        let id = achievement_id(achievement);
+        if stats
+            .achievement(id)
+            .set()
+            .and_then(|_| stats.store_stats())
+            .is_err()
+        {
+            warn!("failed to unlock achievement {} on Steam", id);
+        }
+    }
+
+    fn load_cloud(&self, name: &str) -> Option<String> {
+        let file = self.client.remote_storage().file(name);
+        if !file.exists() {
+            return None;
+        }
+        let mut contents = String::new();
+        match file.read().read_to_string(&mut contents) {
+            Ok(_) => Some(contents),
+            Err(err) => {
+                warn!("failed to read {} from Steam Cloud: {}", name, err);
+                None
+            }
+        }
+    }
+
+    fn store_cloud(&self, name: &str, contents: &str) {
+        let mut writer = self.client.remote_storage().file(name).write();
+        if let Err(err) = writer.write_all(contents.as_bytes()) {
+            warn!("failed to write {} to Steam Cloud: {}", name, err);
+        }
+    }
+
+    fn adjust_window(&self, window: &mut WindowDescriptor) {
+        // the overlay hooks into presenting frames, which it can't do reliably without vsync or
+        // through a see-through window
+        window.present_mode = PresentMode::Fifo;
+        window.transparent = false;
+    }
+
+    fn update(&self) {
+        self.client.run_callbacks();
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use rand::Rng;
+
+use crate::{
+    cli::Cli, deaths::Dying, elements::Element, layers::RenderLayer, palette::Palette,
+    rng::GameRng, spawn_enemy, Bullet, Enemy, EnemyKind, GameState, Health, Player, BOUNDS,
+    FAST_BULLET_VELOCITY,
+};
+
+/// Seconds at the start of a stress run that aren't measured, while the crowd is spawned in.
+const WARMUP: f32 = 2.0;
+const CSV_HEADER: &str = concat!(
+    "timestamp,enemies,bullets,frames,",
+    "frame_p50_ms,frame_p90_ms,frame_p99_ms,frame_max_ms,",
+    "update_p50_ms,update_p90_ms,update_p99_ms,update_max_ms",
+);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+enum StressStage {
+    /// before anything else in a frame
+    Start,
+    /// after the frame's work, before the frame limiter can sleep in `CoreStage::Last`
+    End,
+}
+
+/// A stress run in progress, keeping the arena crowded and timing every frame.
+struct StressRun {
+    enemies: usize,
+    bullets: usize,
+    /// seconds measured so far and how many to measure
+    elapsed: f32,
+    duration: f32,
+    update_started: Option<Instant>,
+    /// milliseconds from one frame to the next, what the player would see
+    frame_times: Vec<f64>,
+    /// milliseconds the frame's work took, without waiting for vsync or the next tick headless
+    update_times: Vec<f64>,
+    output: PathBuf,
+}
+
+/// Hidden benchmark mode started with `--stress`: a run with the arena kept full of enemies and
+/// bullets whose frame times are appended to a CSV file before the game exits.
+pub struct StressPlugin;
+
+impl Plugin for StressPlugin {
+    fn build(&self, app: &mut App) {
+        let cli = app.world.resource::<Cli>();
+        if !cli.stress {
+            return;
+        }
+        let run = StressRun {
+            enemies: cli.stress_enemies,
+            bullets: cli.stress_bullets,
+            elapsed: 0.0,
+            duration: cli.stress_duration,
+            update_started: None,
+            frame_times: Vec::new(),
+            update_times: Vec::new(),
+            output: cli.stress_output.clone(),
+        };
+        app.insert_resource(run)
+            .add_stage_before(
+                CoreStage::First,
+                StressStage::Start,
+                SystemStage::single_threaded(),
+            )
+            .add_stage_before(
+                CoreStage::Last,
+                StressStage::End,
+                SystemStage::single_threaded(),
+            )
+            .add_system_to_stage(StressStage::Start, start_update_system)
+            .add_system_to_stage(StressStage::End, measure_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(populate_system)
+                    .with_system(invincible_system),
+            );
+    }
+}
+
+/// Tops the arena up to the enemies and bullets asked for, as many as were shot down or flew out.
+fn populate_system(
+    mut commands: Commands,
+    run: Res<StressRun>,
+    palette: Res<Palette>,
+    mut rng: ResMut<GameRng>,
+    enemies: Query<(), (With<Enemy>, Without<Dying>)>,
+    bullets: Query<(), With<Bullet>>,
+) {
+    let rng = &mut rng.0;
+    let extents = BOUNDS / 2.0;
+    let mut position = || {
+        Vec3::new(
+            rng.gen_range(-extents.x..extents.x),
+            rng.gen_range(-extents.y..extents.y),
+            0.0,
+        )
+    };
+    for _ in enemies.iter().count()..run.enemies {
+        spawn_enemy(
+            &mut commands,
+            &palette,
+            position(),
+            EnemyKind::Chaser,
+            250.0,
+        );
+    }
+    for _ in bullets.iter().count()..run.bullets {
+        let position = position();
+        palette
+            .spawn_sprite(
+                &mut commands,
+                RenderLayer::Bullets,
+                palette.bullet,
+                5.0,
+                Transform::from_translation(position),
+            )
+            .insert(Bullet {
+                velocity: FAST_BULLET_VELOCITY,
+                // away from the center so they cross most of the arena before leaving it
+                direction: position.normalize_or_zero(),
+                wrapped: false,
+                element: Element::Kinetic,
+            });
+    }
+}
+
+/// Keeps the player alive, a stress run ends when it's done measuring rather than at game over.
+fn invincible_system(mut player: Query<&mut Health, Added<Player>>) {
+    for mut health in player.iter_mut() {
+        health.current = f32::MAX;
+    }
+}
+
+fn start_update_system(mut run: ResMut<StressRun>) {
+    run.update_started = Some(Instant::now());
+}
+
+fn measure_system(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    mut run: ResMut<StressRun>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if *state.current() != GameState::Playing {
+        return;
+    }
+    run.elapsed += time.delta_seconds();
+    if run.elapsed < WARMUP {
+        return;
+    }
+    let frame_time = time.delta_seconds_f64() * 1000.0;
+    if let Some(started) = run.update_started {
+        run.update_times
+            .push(started.elapsed().as_secs_f64() * 1000.0);
+        run.frame_times.push(frame_time);
+    }
+    if run.elapsed < WARMUP + run.duration {
+        return;
+    }
+    let output = run.output.clone();
+    match append_results(&output, &mut run) {
+        Ok(row) => info!(
+            "stress run finished, appended to {}: {}",
+            output.display(),
+            row
+        ),
+        Err(err) => error!(
+            "failed to write stress results to {}: {}",
+            output.display(),
+            err
+        ),
+    }
+    exit.send(AppExit);
+}
+
+/// Value at `fraction` of the way through the sorted `values`.
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    sorted[((sorted.len() - 1) as f64 * fraction).round() as usize]
+}
+
+/// p50, p90, p99 and the maximum of `values`, in that order.
+fn percentiles(values: &mut [f64]) -> [f64; 4] {
+    values.sort_by(f64::total_cmp);
+    [
+        percentile(values, 0.5),
+        percentile(values, 0.9),
+        percentile(values, 0.99),
+        values.last().copied().unwrap_or(0.0),
+    ]
+}
+
+/// Appends a row with the run's results to the CSV file at `path`, starting the file with a
+/// header if it's new, and returns the row.
+fn append_results(path: &Path, run: &mut StressRun) -> io::Result<String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let mut columns = vec![
+        timestamp.to_string(),
+        run.enemies.to_string(),
+        run.bullets.to_string(),
+        run.frame_times.len().to_string(),
+    ];
+    let times = percentiles(&mut run.frame_times)
+        .into_iter()
+        .chain(percentiles(&mut run.update_times));
+    columns.extend(times.map(|ms| format!("{:.3}", ms)));
+    let row = columns.join(",");
+
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if is_new {
+        writeln!(file, "{}", CSV_HEADER)?;
+    }
+    writeln!(file, "{}", row)?;
+    Ok(row)
+}
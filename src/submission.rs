@@ -0,0 +1,489 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha1_smol::Sha1;
+
+use crate::{
+    input_buffer::{StepInput, TouchAxes},
+    loadout::{GameMode, Loadout, Ship, Weapon},
+    munitions::Munitions,
+    mutators::Mutator,
+    packs::ContentPacks,
+    replay::Playback,
+    rng::RunSeed,
+    save,
+    scripting::Mods,
+    settings::Settings,
+    stats::RunStats,
+    waves::Wave,
+    AddSimulationSystem, Game, GameState,
+};
+
+/// Save file the last finished run's submission is written to, ready to send to the leaderboard.
+const SUBMISSION_FILE: &str = "submission";
+/// Key submissions are signed with. Release builds are given their own at compile time, but one
+/// shipped with the game can be dug out of it, so a signature only shows the run wasn't edited by
+/// hand. It's playing the inputs back that confirms the score.
+const SIGNING_KEY: &str = match option_env!("MADGE_SIGNING_KEY") {
+    Some(key) => key,
+    None => "madge-development",
+};
+/// Input of every fixed step of the current run, as runs of steps with the same input.
+#[derive(Default)]
+pub struct Recording {
+    keys: Vec<(u16, u32)>,
+    touch: Vec<(TouchAxes, u32)>,
+}
+
+/// Where the submission of the run that just ended was written, if it was submitted at all.
+#[derive(Default)]
+pub struct LastSubmission(pub Option<PathBuf>);
+
+impl Recording {
+    /// Input of the last `steps` steps recorded, in the same runs as the whole recording.
+    pub fn tail(&self, steps: u32) -> Vec<(u16, u32)> {
+        let mut tail = Vec::new();
+        let mut remaining = steps;
+        for &(keys, run) in self.keys.iter().rev() {
+            if remaining == 0 {
+                break;
+            }
+            tail.push((keys, run.min(remaining)));
+            remaining = remaining.saturating_sub(run);
+        }
+        tail.reverse();
+        tail
+    }
+}
+
+/// Settings that change how a run plays out rather than how it looks or sounds, recorded with it
+/// for its replay to play by the same ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rules {
+    pub screen_wrap: bool,
+    pub bullet_wrap: bool,
+    pub rewind_world: bool,
+    pub munitions: Munitions,
+    pub beat_spawns: bool,
+    pub max_enemies: usize,
+}
+
+impl Rules {
+    fn of(settings: &Settings) -> Self {
+        Self {
+            screen_wrap: settings.screen_wrap,
+            bullet_wrap: settings.bullet_wrap,
+            rewind_world: settings.rewind_world,
+            munitions: settings.munitions,
+            beat_spawns: settings.beat_spawns,
+            max_enemies: settings.max_enemies,
+        }
+    }
+
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.screen_wrap = self.screen_wrap;
+        settings.bullet_wrap = self.bullet_wrap;
+        settings.rewind_world = self.rewind_world;
+        settings.munitions = self.munitions;
+        settings.beat_spawns = self.beat_spawns;
+        settings.max_enemies = self.max_enemies;
+    }
+}
+
+/// A finished run as it's sent to the leaderboard, with what it takes to check the score.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Submission {
+    /// version of the game the run was played on, a replay only plays out the same on it
+    pub version: String,
+    pub seed: u64,
+    pub mode: GameMode,
+    pub ship: Ship,
+    pub weapon: Weapon,
+    /// mutators the run was played with, tagging its entry
+    pub mutators: Vec<Mutator>,
+    pub rules: Rules,
+    /// ids of the content packs that were enabled, their content and scripts play a part too
+    pub packs: Vec<String>,
+    /// SHA-1 of the mod scripts that were loaded and of the enabled packs' files, in hex, for a
+    /// modded run not to pass for one without mods
+    pub content_hash: String,
+    pub score: usize,
+    pub wave: usize,
+    /// SHA-1 of `inputs` and `touch`, in hex
+    pub input_hash: String,
+    /// input of every fixed step of the run, as runs of steps with the same input, in the bits of
+    /// `StepInput`
+    pub inputs: Vec<(u16, u32)>,
+    /// the touch controls' stick and aim on every fixed step the same way, empty for runs that
+    /// never touched them
+    pub touch: Vec<(TouchAxes, u32)>,
+    /// HMAC-SHA1 over everything above but the inputs themselves, in hex
+    pub signature: String,
+}
+
+impl save::Versioned for Submission {
+    const VERSION: u32 = 1;
+}
+
+impl Submission {
+    fn new(
+        seed: u64,
+        loadout: &Loadout,
+        rules: Rules,
+        packs: Vec<String>,
+        content_hash: String,
+        score: usize,
+        wave: usize,
+        inputs: Vec<(u16, u32)>,
+        touch: Vec<(TouchAxes, u32)>,
+    ) -> Self {
+        let mut submission = Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            seed,
+            mode: loadout.mode,
+            ship: loadout.ship,
+            weapon: loadout.weapon,
+            mutators: loadout.mutators.clone(),
+            rules,
+            packs,
+            content_hash,
+            score,
+            wave,
+            input_hash: input_hash(&inputs, &touch),
+            inputs,
+            touch,
+            signature: String::new(),
+        };
+        submission.signature = submission.expected_signature();
+        submission
+    }
+
+    fn expected_signature(&self) -> String {
+        let message = format!(
+            "{}|{}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{}|{}|{}|{}",
+            self.version,
+            self.seed,
+            self.mode,
+            self.ship,
+            self.weapon,
+            self.mutators,
+            self.rules,
+            self.packs,
+            self.content_hash,
+            self.score,
+            self.wave,
+            self.input_hash
+        );
+        hmac(SIGNING_KEY.as_bytes(), message.as_bytes())
+    }
+}
+
+/// SHA-1 of the mod scripts and of the enabled packs' files, in hex.
+pub fn content_hash(mods: &Mods, packs: &ContentPacks) -> String {
+    let mut hash = Sha1::new();
+    hash.update(mods.hash().as_bytes());
+    hash.update(packs.content_hash().as_bytes());
+    hash.digest().to_string()
+}
+
+fn input_hash(inputs: &[(u16, u32)], touch: &[(TouchAxes, u32)]) -> String {
+    let mut hash = Sha1::new();
+    for (keys, steps) in inputs {
+        hash.update(&keys.to_le_bytes());
+        hash.update(&steps.to_le_bytes());
+    }
+    // the touch input's runs are counted, for where the keys' end and the touch's start not to
+    // be shifted along
+    hash.update(&(touch.len() as u32).to_le_bytes());
+    for (axes, steps) in touch {
+        hash.update(&axes.to_bytes());
+        hash.update(&steps.to_le_bytes());
+    }
+    hash.digest().to_string()
+}
+
+/// HMAC of `message` with SHA-1, in hex.
+fn hmac(key: &[u8], message: &[u8]) -> String {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..20].copy_from_slice(&Sha1::from(key).digest().bytes());
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha1::new();
+    inner.update(&block.map(|byte| byte ^ 0x36));
+    inner.update(message);
+    let mut outer = Sha1::new();
+    outer.update(&block.map(|byte| byte ^ 0x5c));
+    outer.update(&inner.digest().bytes());
+    outer.digest().to_string()
+}
+
+pub struct SubmissionPlugin;
+
+impl Plugin for SubmissionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recording>()
+            .init_resource::<LastSubmission>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(start_recording_system),
+            )
+            .add_simulation_system(record_system)
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(submission_system),
+            );
+    }
+}
+
+fn start_recording_system(
+    mut recording: ResMut<Recording>,
+    mut last_submission: ResMut<LastSubmission>,
+) {
+    *recording = Recording::default();
+    last_submission.0 = None;
+}
+
+fn record_system(
+    state: Res<State<GameState>>,
+    input: Res<StepInput>,
+    mut recording: ResMut<Recording>,
+) {
+    // the tutorial doesn't score
+    if *state.current() != GameState::Playing {
+        return;
+    }
+    push_step(&mut recording.keys, input.bits());
+    push_step(&mut recording.touch, input.touch());
+}
+
+/// Adds a step's input to the runs of steps with the same input.
+fn push_step<T: PartialEq>(runs: &mut Vec<(T, u32)>, input: T) {
+    match runs.last_mut() {
+        Some((last, steps)) if *last == input => *steps += 1,
+        _ => runs.push((input, 1)),
+    }
+}
+
+/// Signs the run that just ended and writes it out for the leaderboard. Assisted runs are kept
+/// out of it, the same as out of the records, and so are replays of runs already submitted.
+fn submission_system(
+    seed: Res<RunSeed>,
+    loadout: Res<Loadout>,
+    settings: Res<Settings>,
+    packs: Res<ContentPacks>,
+    mods: Res<Mods>,
+    game: Res<Game>,
+    wave: Res<Wave>,
+    stats: Res<RunStats>,
+    playback: Option<Res<Playback>>,
+    mut recording: ResMut<Recording>,
+    mut last_submission: ResMut<LastSubmission>,
+) {
+    let Recording { keys, mut touch } = std::mem::take(&mut *recording);
+    if touch.iter().all(|(axes, _)| *axes == TouchAxes::default()) {
+        touch.clear();
+    }
+    if stats.assisted || playback.is_some() {
+        return;
+    }
+    let submission = Submission::new(
+        seed.0,
+        &loadout,
+        Rules::of(&settings),
+        packs.enabled_ids(),
+        content_hash(&mods, &packs),
+        game.score,
+        wave.number,
+        keys,
+        touch,
+    );
+    save::store(SUBMISSION_FILE, &submission);
+    last_submission.0 = Some(save::save_path(SUBMISSION_FILE));
+}
+
+/// Reads the submission at `path`.
+pub fn read(path: &Path) -> Result<Submission, String> {
+    fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|contents| save::read::<Submission>(&contents))
+        .map_err(|err| format!("can't read submission {}: {}", path.display(), err))
+}
+
+/// Checks the submission at `path` for tampering, printing what was found, and returns it if it
+/// held up, to be played back next.
+///
+/// Only the signature and the hash of the inputs are checked here, which anyone with the signing
+/// key can forge. The score is confirmed by playing the inputs back, see `replay`.
+pub fn verify(path: &Path) -> Option<Submission> {
+    let submission = match read(path) {
+        Ok(submission) => submission,
+        Err(err) => {
+            eprintln!("{}", err);
+            return None;
+        }
+    };
+    if input_hash(&submission.inputs, &submission.touch) != submission.input_hash {
+        eprintln!("the inputs don't match their hash, they were changed after the run");
+        return None;
+    }
+    if submission.expected_signature() != submission.signature {
+        eprintln!("the signature doesn't match, the submission was changed after the run");
+        return None;
+    }
+    if submission.version != env!("CARGO_PKG_VERSION") {
+        eprintln!(
+            "the run was played on version {}, it only plays back the same on that version",
+            submission.version
+        );
+        return None;
+    }
+    let steps: u64 = submission
+        .inputs
+        .iter()
+        .map(|(_, steps)| *steps as u64)
+        .sum();
+    println!(
+        "signature holds: score {} on wave {}, seed {}, {} steps of input",
+        submission.score, submission.wave, submission.seed, steps
+    );
+    Some(submission)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submission(inputs: Vec<(u16, u32)>) -> Submission {
+        Submission::new(
+            7,
+            &Loadout::default(),
+            Rules::of(&Settings::default()),
+            Vec::new(),
+            String::new(),
+            1200,
+            4,
+            inputs,
+            Vec::new(),
+        )
+    }
+
+    /// Writes `submission` to a file of its own in the scratch directory and verifies it.
+    fn verify_stored(name: &str, submission: &Submission) -> Option<Submission> {
+        let path = save::scratch_dir("submission")
+            .join(name)
+            .with_extension("ron");
+        save::store_path(&path, submission);
+        let verified = verify(&path);
+        let _ = fs::remove_file(&path);
+        verified
+    }
+
+    #[test]
+    fn hmac_matches_rfc_2202() {
+        let cases: [(&[u8], &[u8], &str); 7] = [
+            (
+                &[0x0b; 20],
+                b"Hi There",
+                "b617318655057264e28bc0b6fb378c8ef146be00",
+            ),
+            (
+                b"Jefe",
+                b"what do ya want for nothing?",
+                "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+            ),
+            (
+                &[0xaa; 20],
+                &[0xdd; 50],
+                "125d7342b9ac11cd91a39af48aa17b4f63f175d3",
+            ),
+            (
+                &[
+                    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22,
+                    23, 24, 25,
+                ],
+                &[0xcd; 50],
+                "4c9007f4026250c6bc8414f9bf50c86c2d7235da",
+            ),
+            (
+                &[0x0c; 20],
+                b"Test With Truncation",
+                "4c1a03424b55e07fe7f27be1d58bb9324a9a5a04",
+            ),
+            (
+                &[0xaa; 80],
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                "aa4ae5e15272d00e95705637ce8a3b55ed402112",
+            ),
+            (
+                &[0xaa; 80],
+                b"Test Using Larger Than Block-Size Key and Larger Than One Block-Size Data",
+                "e8e99d0f45237d786d6bbaa7965c7808bbff1a91",
+            ),
+        ];
+        for (key, message, expected) in cases {
+            assert_eq!(hmac(key, message), expected);
+        }
+    }
+
+    #[test]
+    fn verify_accepts_an_untouched_submission() {
+        let verified = verify_stored("untouched", &submission(vec![(0, 30), (1, 12)])).unwrap();
+        assert_eq!(verified.score, 1200);
+        assert_eq!(verified.inputs, vec![(0, 30), (1, 12)]);
+    }
+
+    #[test]
+    fn verify_rejects_a_tampered_signature() {
+        let mut tampered = submission(vec![(0, 30)]);
+        tampered.signature = hmac(b"another key", b"");
+        assert!(verify_stored("tampered-signature", &tampered).is_none());
+    }
+
+    #[test]
+    fn verify_rejects_tampered_inputs() {
+        let mut tampered = submission(vec![(0, 30), (1, 12)]);
+        tampered.inputs[1] = (2, 12);
+        assert!(verify_stored("tampered-inputs", &tampered).is_none());
+
+        // rehashing the changed inputs still leaves the signature over the old hash
+        tampered.input_hash = input_hash(&tampered.inputs, &tampered.touch);
+        assert!(verify_stored("rehashed-inputs", &tampered).is_none());
+    }
+
+    #[test]
+    fn verify_rejects_a_tampered_score() {
+        let mut tampered = submission(vec![(0, 30)]);
+        tampered.score += 1;
+        assert!(verify_stored("tampered-score", &tampered).is_none());
+    }
+
+    #[test]
+    fn tail_splits_the_run_the_window_starts_in() {
+        let recording = Recording {
+            keys: vec![(1, 10), (2, 5), (3, 3)],
+            touch: Vec::new(),
+        };
+        assert_eq!(recording.tail(0), vec![]);
+        assert_eq!(recording.tail(2), vec![(3, 2)]);
+        assert_eq!(recording.tail(3), vec![(3, 3)]);
+        assert_eq!(recording.tail(4), vec![(2, 1), (3, 3)]);
+        assert_eq!(recording.tail(8), vec![(2, 5), (3, 3)]);
+        assert_eq!(recording.tail(9), vec![(1, 1), (2, 5), (3, 3)]);
+    }
+
+    #[test]
+    fn tail_longer_than_the_recording_is_all_of_it() {
+        let recording = Recording {
+            keys: vec![(1, 10), (2, 5)],
+            touch: Vec::new(),
+        };
+        assert_eq!(recording.tail(15), vec![(1, 10), (2, 5)]);
+        assert_eq!(recording.tail(100), vec![(1, 10), (2, 5)]);
+        assert_eq!(Recording::default().tail(5), vec![]);
+    }
+}
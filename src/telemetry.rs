@@ -0,0 +1,183 @@
+use std::{
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::DamageSource,
+    loadout::{GameMode, Loadout, Ship, Weapon},
+    save,
+    settings::Settings,
+    stats::RunStats,
+    GameState,
+};
+
+/// Save file the reports not sent yet are kept in between sessions.
+const QUEUE_FILE: &str = "telemetry_queue";
+/// Reports sent together, few enough that a handful of runs go out at once and many enough that
+/// it's not a request for every run.
+const BATCH_SIZE: usize = 5;
+/// Most reports kept waiting while the endpoint can't be reached, the oldest are dropped past it.
+const MAX_QUEUED: usize = 50;
+/// Time given to connecting to the endpoint.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time given to a whole request, from connecting to the endpoint to its answer.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What's sent about a finished run. Nothing in it tells players apart: no names, ids, addresses
+/// or times, only how the run went.
+#[derive(Debug, Serialize, Deserialize)]
+struct Report {
+    version: String,
+    mode: GameMode,
+    ship: Ship,
+    weapon: Weapon,
+    wave_reached: usize,
+    time_survived: f32,
+    shots_fired: usize,
+    hits: usize,
+    /// what hurt the player last, missing for runs that ended without anything hurting them
+    cause_of_death: Option<DamageSource>,
+    assisted: bool,
+}
+
+impl Report {
+    fn new(loadout: &Loadout, stats: &RunStats) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            mode: loadout.mode,
+            ship: loadout.ship,
+            weapon: loadout.weapon,
+            wave_reached: stats.wave_reached,
+            time_survived: stats.time_survived,
+            shots_fired: stats.shots_fired,
+            hits: stats.hits,
+            cause_of_death: stats.last_damage,
+            assisted: stats.assisted,
+        }
+    }
+}
+
+/// Reports of finished runs waiting for a batch to fill up.
+#[derive(Default, Serialize, Deserialize)]
+struct Queue(Vec<Report>);
+
+impl save::Versioned for Queue {
+    const VERSION: u32 = 1;
+}
+
+/// Posts `body` as JSON to `url`, failing unless the endpoint answers with a success within
+/// `TIMEOUT`.
+fn post(url: &str, body: &str) -> Result<(), String> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(CONNECT_TIMEOUT)
+        .timeout(TIMEOUT)
+        .build();
+    agent
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_string(body)
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+/// Batch being posted on a thread of its own, with how many of the queued reports it holds.
+#[derive(Default)]
+struct Sending(Option<(usize, JoinHandle<Result<(), String>>)>);
+
+/// Sends anonymous balance data of finished runs, while the player has agreed to it in the
+/// settings.
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Sending>()
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(report_system))
+            .add_system(sent_system);
+    }
+}
+
+/// Queues a report of the run that just ended and sends the queue off once a batch is full.
+///
+/// Nothing is kept about runs played while telemetry is off or has nowhere to go. Reports stay
+/// queued until the endpoint takes them, a batch that fails to send goes again with the next
+/// one, up to `MAX_QUEUED` of them.
+fn report_system(
+    settings: Res<Settings>,
+    loadout: Res<Loadout>,
+    stats: Res<RunStats>,
+    mut sending: ResMut<Sending>,
+) {
+    if !settings.telemetry || settings.telemetry_endpoint.is_empty() {
+        return;
+    }
+    // reports are only ever sent encrypted
+    if !settings.telemetry_endpoint.starts_with("https://") {
+        warn!(
+            "can't send telemetry: {} isn't an https:// URL",
+            settings.telemetry_endpoint
+        );
+        return;
+    }
+    let endpoint = settings.telemetry_endpoint.clone();
+    let mut queue = save::load::<Queue>(QUEUE_FILE);
+    queue.0.push(Report::new(&loadout, &stats));
+    // the batch going out is the front of the queue until it's taken out, it stays in place
+    if sending.0.is_none() {
+        let dropped = queue.0.len().saturating_sub(MAX_QUEUED);
+        queue.0.drain(..dropped);
+    }
+    save::store(QUEUE_FILE, &queue);
+    // the batch still going out is sent along with the next one if it fails
+    if queue.0.len() < BATCH_SIZE || sending.0.is_some() {
+        return;
+    }
+    let body = match serde_json::to_string(&queue.0) {
+        Ok(body) => body,
+        Err(err) => {
+            warn!("can't send telemetry: {}", err);
+            return;
+        }
+    };
+    // blocking on the endpoint shouldn't hold up the game, nor a thread of the task pools
+    let post = thread::spawn(move || {
+        post(&endpoint, &body).map_err(|err| format!("{}: {}", endpoint, err))
+    });
+    sending.0 = Some((queue.0.len(), post));
+}
+
+/// Takes the batch that was sent out of the queue once the endpoint took it.
+fn sent_system(mut sending: ResMut<Sending>) {
+    if !sending
+        .0
+        .as_ref()
+        .is_some_and(|(_, post)| post.is_finished())
+    {
+        return;
+    }
+    let (runs, post) = match sending.0.take() {
+        Some(sent) => sent,
+        None => return,
+    };
+    match post.join() {
+        Ok(Ok(())) => {
+            // runs that ended while it was being sent are still queued after it
+            let mut queue = save::load::<Queue>(QUEUE_FILE);
+            queue.0.drain(..runs.min(queue.0.len()));
+            if queue.0.is_empty() {
+                save::remove(QUEUE_FILE);
+            } else {
+                save::store(QUEUE_FILE, &queue);
+            }
+            info!("sent telemetry of {} runs", runs);
+        }
+        Ok(Err(err)) => warn!(
+            "failed to send telemetry, it's kept for the next try: {}",
+            err
+        ),
+        Err(_) => warn!("sending telemetry panicked, it's kept for the next try"),
+    }
+}
@@ -0,0 +1,225 @@
+use bevy::{
+    input::{touch::Touch, InputSystem},
+    prelude::*,
+    render::camera::Camera2d,
+};
+
+use crate::{despawn_with, mobile::MOBILE, window_to_world, GameState};
+
+/// Distance from where the stick was put down at which it's pushed all the way.
+const JOYSTICK_RADIUS: f32 = 70.0;
+const JOYSTICK_KNOB_SIZE: f32 = 50.0;
+/// Factor buttons grow by once the game is played with touches.
+const BUTTON_SCALE: f32 = 1.4;
+
+/// What the touches on the screen ask the ship to do, read by the simulation alongside the
+/// keyboard.
+///
+/// The left half of the screen is a virtual joystick moving the ship, appearing wherever it's
+/// touched. Touching the right half turns the ship towards the touch for as long as it's held.
+#[derive(Default)]
+pub struct TouchControls {
+    /// how far the joystick is pushed in each direction, at most one long
+    pub movement: Vec2,
+    /// point in the arena the ship should turn towards
+    pub aim: Option<Vec2>,
+    /// touch acting as the joystick and where it went down, in window coordinates
+    stick: Option<(u64, Vec2)>,
+    /// touch aiming
+    trigger: Option<u64>,
+    /// set once the screen was touched at all, from then on the UI is made for fingers
+    in_use: bool,
+}
+
+/// Base of the virtual joystick, shown while it's held.
+#[derive(Component)]
+struct JoystickBase;
+
+/// Knob of the virtual joystick, following the finger within the base.
+#[derive(Component)]
+struct JoystickKnob;
+
+/// Button already made large enough to hit with a finger.
+#[derive(Component)]
+struct Enlarged;
+
+/// Label of the system reading the touches, for what takes the touch controls in to go after.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TouchInput;
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchControls>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                touch_input_system.label(TouchInput).after(InputSystem),
+            )
+            .add_system(button_size_system);
+        // the tutorial is played with the same controls as a run
+        for state in [GameState::Playing, GameState::Tutorial] {
+            app.add_system_set(SystemSet::on_enter(state.clone()).with_system(spawn_joystick))
+                .add_system_set(SystemSet::on_update(state.clone()).with_system(joystick_system))
+                .add_system_set(
+                    SystemSet::on_exit(state)
+                        .with_system(despawn_with::<JoystickBase>)
+                        .with_system(reset),
+                );
+        }
+    }
+}
+
+/// Position of a touch with the origin at the bottom left, like the cursor's and the UI's.
+fn touch_position(touch: &Touch, window: &Window) -> Vec2 {
+    let position = touch.position();
+    // touches on phones already come that way, elsewhere they start at the top
+    if MOBILE {
+        position
+    } else {
+        Vec2::new(position.x, window.height() - position.y)
+    }
+}
+
+fn touch_input_system(
+    windows: Res<Windows>,
+    touches: Res<Touches>,
+    camera: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut controls: ResMut<TouchControls>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let controls = &mut *controls;
+    for touch in touches.iter_just_pressed() {
+        controls.in_use = true;
+        let position = touch_position(touch, window);
+        if position.x < window.width() / 2.0 {
+            if controls.stick.is_none() {
+                controls.stick = Some((touch.id(), position));
+            }
+        } else if controls.trigger.is_none() {
+            controls.trigger = Some(touch.id());
+        }
+    }
+    // a touch that's lifted and one that's cancelled both let go
+    let released = |id| touches.get_pressed(id).is_none();
+    if controls.stick.is_some_and(|(id, _)| released(id)) {
+        controls.stick = None;
+    }
+    if controls.trigger.is_some_and(released) {
+        controls.trigger = None;
+    }
+
+    controls.movement = match controls.stick {
+        Some((id, origin)) => touches.get_pressed(id).map_or(Vec2::ZERO, |touch| {
+            let offset = (touch_position(touch, window) - origin) / JOYSTICK_RADIUS;
+            offset.clamp_length_max(1.0)
+        }),
+        None => Vec2::ZERO,
+    };
+    let camera = camera.get_single().ok();
+    controls.aim = controls
+        .trigger
+        .and_then(|id| touches.get_pressed(id))
+        .zip(camera)
+        .map(|(touch, (camera, transform))| {
+            window_to_world(window, touch_position(touch, window), camera, transform)
+        });
+}
+
+fn reset(mut controls: ResMut<TouchControls>) {
+    // fingers still down when the run ended have to be put down again to count
+    *controls = TouchControls {
+        in_use: controls.in_use,
+        ..default()
+    };
+}
+
+/// Makes every button larger once the game is played with touches, the screens are built for a
+/// mouse.
+fn button_size_system(
+    mut commands: Commands,
+    controls: Res<TouchControls>,
+    mut buttons: Query<(Entity, &mut Style), (With<Button>, Without<Enlarged>)>,
+) {
+    if !controls.in_use {
+        return;
+    }
+    for (entity, mut style) in buttons.iter_mut() {
+        let scale = |value: Val| match value {
+            Val::Px(pixels) => Val::Px(pixels * BUTTON_SCALE),
+            value => value,
+        };
+        style.size = Size::new(scale(style.size.width), scale(style.size.height));
+        style.margin = Rect {
+            left: scale(style.margin.left),
+            right: scale(style.margin.right),
+            top: scale(style.margin.top),
+            bottom: scale(style.margin.bottom),
+        };
+        commands.entity(entity).insert(Enlarged);
+    }
+}
+
+fn spawn_joystick(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(
+                    Val::Px(JOYSTICK_RADIUS * 2.0),
+                    Val::Px(JOYSTICK_RADIUS * 2.0),
+                ),
+                ..default()
+            },
+            color: Color::rgba(1.0, 1.0, 1.0, 0.1).into(),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(JoystickBase)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        size: Size::new(Val::Px(JOYSTICK_KNOB_SIZE), Val::Px(JOYSTICK_KNOB_SIZE)),
+                        ..default()
+                    },
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.3).into(),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert(JoystickKnob);
+        });
+}
+
+fn joystick_system(
+    controls: Res<TouchControls>,
+    mut base: Query<(&mut Style, &mut Visibility), (With<JoystickBase>, Without<JoystickKnob>)>,
+    mut knob: Query<(&mut Style, &mut Visibility), With<JoystickKnob>>,
+) {
+    let origin = controls.stick.map(|(_, origin)| origin);
+    if let Ok((mut style, mut visibility)) = base.get_single_mut() {
+        visibility.is_visible = origin.is_some();
+        if let Some(origin) = origin {
+            style.position = Rect {
+                left: Val::Px(origin.x - JOYSTICK_RADIUS),
+                bottom: Val::Px(origin.y - JOYSTICK_RADIUS),
+                ..default()
+            };
+        }
+    }
+    if let Ok((mut style, mut visibility)) = knob.get_single_mut() {
+        visibility.is_visible = origin.is_some();
+        // positioned within the base
+        let corner = Vec2::splat(JOYSTICK_RADIUS - JOYSTICK_KNOB_SIZE / 2.0)
+            + controls.movement * JOYSTICK_RADIUS;
+        style.position = Rect {
+            left: Val::Px(corner.x),
+            bottom: Val::Px(corner.y),
+            ..default()
+        };
+    }
+}
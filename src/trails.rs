@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use crate::{
+    abilities::Dash, culling::Culled, layers::RenderLayer, AddSimulationSystem, Player, TimeScale,
+};
+
+/// Longest step between two recorded positions that still gets a segment, anything further is
+/// a wrap around the screen or a teleport and would streak across the arena.
+const MAX_SEGMENT_LENGTH: f32 = 100.0;
+
+/// Leaves a fading ribbon of segments along the path of the entity it's on.
+#[derive(Component)]
+pub struct Trail {
+    pub color: Color,
+    /// seconds each segment lasts, and with that how far the trail reaches back
+    pub length: f32,
+    pub width: f32,
+    /// whether new segments are laid, the ones already laid fade out either way
+    pub emitting: bool,
+    /// position recorded on the previous step
+    last: Option<Vec3>,
+}
+
+impl Trail {
+    pub fn new(color: Color, length: f32, width: f32) -> Self {
+        Self {
+            color,
+            length,
+            width,
+            emitting: true,
+            last: None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct TrailSegment {
+    color: Color,
+    lifetime: Timer,
+}
+
+pub struct TrailsPlugin;
+
+impl Plugin for TrailsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(dash_trail_system)
+            .add_simulation_system(trail_system)
+            .add_simulation_system(segment_system);
+    }
+}
+
+/// The player only leaves a trail while dashing.
+fn dash_trail_system(mut player: Query<(&Dash, &mut Trail), With<Player>>) {
+    for (dash, mut trail) in player.iter_mut() {
+        trail.emitting = dash.is_dashing();
+    }
+}
+
+fn trail_system(
+    mut commands: Commands,
+    culled: Res<Culled>,
+    mut trails: Query<(Entity, &mut Trail, &Transform, Option<&RenderLayer>)>,
+) {
+    for (entity, mut trail, transform, layer) in trails.iter_mut() {
+        let position = transform.translation;
+        if let Some(last) = trail.last {
+            let step = position - last;
+            let length = step.length();
+            // nobody would see the segments laid out of view
+            let seen = !culled.contains(entity);
+            if seen && trail.emitting && length > 0.0 && length <= MAX_SEGMENT_LENGTH {
+                let center = (position + last) / 2.0;
+                commands
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            color: trail.color,
+                            // overlap the neighbouring segments slightly to avoid gaps
+                            custom_size: Some(Vec2::new(trail.width, length + trail.width / 2.0)),
+                            ..default()
+                        },
+                        // behind whatever leaves the trail, on its layer
+                        transform: Transform::from_xyz(center.x, center.y, position.z - 0.1)
+                            .with_rotation(Quat::from_rotation_z((-step.x).atan2(step.y))),
+                        ..default()
+                    })
+                    .insert(layer.copied().unwrap_or(RenderLayer::Fx))
+                    .insert(TrailSegment {
+                        color: trail.color,
+                        lifetime: Timer::from_seconds(trail.length, false),
+                    });
+            }
+        }
+        trail.last = Some(position);
+    }
+}
+
+fn segment_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut segments: Query<(Entity, &mut TrailSegment, &mut Sprite)>,
+) {
+    let _span = info_span!("trail_segments", segments = segments.iter().count()).entered();
+    for (entity, mut segment, mut sprite) in segments.iter_mut() {
+        if segment.lifetime.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        sprite
+            .color
+            .set_a(segment.color.a() * segment.lifetime.percent_left());
+    }
+}
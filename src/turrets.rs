@@ -0,0 +1,227 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    aggro::{Taunt, Threat},
+    deaths::Dying,
+    events::EnemyKilled,
+    input_buffer::StepInput,
+    layers::RenderLayer,
+    nearest_enemy,
+    palette::Palette,
+    photo_mode::PhotoMode,
+    spawn_companion_shot, AddSimulationSystem, Enemy, GameState, Player, TimeScale,
+};
+
+/// Credits a turret costs to deploy.
+pub const TURRET_COST: usize = 15;
+/// Most turrets standing at once.
+const MAX_TURRETS: usize = 3;
+const TURRET_SIZE: f32 = 14.0;
+/// Distance in front of the ship turrets are deployed at.
+const PLACEMENT_DISTANCE: f32 = 60.0;
+const TURRET_RANGE: f32 = 300.0;
+const FIRE_INTERVAL: f32 = 0.4;
+const SHOT_VELOCITY: f32 = 650.0;
+const SHOT_SIZE: f32 = 4.0;
+/// Seconds a turret stands before it breaks down.
+const TURRET_LIFETIME: f32 = 15.0;
+/// Seconds before breaking down over which a turret blinks as a warning.
+const EXPIRY_WARNING: f32 = 3.0;
+/// How strongly a turret draws enemies, about as much as the player when they're equally close.
+const TURRET_THREAT: f32 = 0.8;
+/// Seconds after it's deployed that a turret taunts enemies into going after it.
+const TAUNT_DURATION: f32 = 3.0;
+/// Dots marking the turret's range around the placement preview.
+const RANGE_DOTS: usize = 32;
+
+/// Currency earned by killing enemies during a run, spent on deploying turrets.
+#[derive(Default)]
+pub struct Credits(pub usize);
+
+/// Stationary gun deployed by the player, firing at enemies in range until it breaks down.
+#[derive(Component)]
+struct Turret {
+    fire: Timer,
+    lifetime: Timer,
+}
+
+/// Outline shown while T is held of where a turret would be deployed.
+#[derive(Component)]
+struct PlacementPreview;
+
+pub struct TurretsPlugin;
+
+impl Plugin for TurretsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Credits>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_credits)
+                    .with_system(spawn_preview),
+            )
+            .add_simulation_system(earn_credits_system)
+            .add_simulation_system(placement_system)
+            .add_simulation_system(turret_fire_system)
+            .add_simulation_system(turret_lifetime_system);
+    }
+}
+
+fn reset_credits(mut credits: ResMut<Credits>) {
+    *credits = Credits::default();
+}
+
+fn earn_credits_system(mut credits: ResMut<Credits>, mut enemy_killed: EventReader<EnemyKilled>) {
+    for killed in enemy_killed.iter() {
+        // elites pay out a credit more for every modifier they carried
+        credits.0 += 1 + killed.modifiers.len();
+    }
+}
+
+fn spawn_preview(mut commands: Commands) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(TURRET_SIZE)),
+                ..default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(RenderLayer::Player)
+        .insert(PlacementPreview)
+        .with_children(|parent| {
+            for dot in 0..RANGE_DOTS {
+                let angle = dot as f32 / RANGE_DOTS as f32 * TAU;
+                parent.spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(1.0, 1.0, 1.0, 0.3),
+                        custom_size: Some(Vec2::splat(3.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(
+                        angle.cos() * TURRET_RANGE,
+                        angle.sin() * TURRET_RANGE,
+                        0.0,
+                    ),
+                    ..default()
+                });
+            }
+        });
+}
+
+/// Shows the preview while T is held and deploys a turret where it is on release, as long as
+/// the player can afford one and isn't at the cap.
+fn placement_system(
+    mut commands: Commands,
+    input: Res<StepInput>,
+    palette: Res<Palette>,
+    mut credits: ResMut<Credits>,
+    player: Query<&Transform, (With<Player>, Without<PlacementPreview>)>,
+    turrets: Query<(), With<Turret>>,
+    mut preview: Query<
+        (&mut Transform, &mut Sprite, &mut Visibility, &Children),
+        With<PlacementPreview>,
+    >,
+    mut dots: Query<&mut Visibility, Without<PlacementPreview>>,
+    photo_mode: Res<PhotoMode>,
+) {
+    let (mut preview_transform, mut sprite, mut visibility, children) =
+        match preview.get_single_mut() {
+            Ok(preview) => preview,
+            Err(_) => return,
+        };
+    // nothing gets deployed in a frozen run
+    let player = player.get_single().ok().filter(|_| !photo_mode.is_active());
+    let placing = input.pressed(KeyCode::T) && player.is_some();
+    // the dots are children, but their visibility doesn't follow the preview's
+    visibility.is_visible = placing;
+    for &dot in children.iter() {
+        if let Ok(mut dot) = dots.get_mut(dot) {
+            dot.is_visible = placing;
+        }
+    }
+
+    let player = match player {
+        Some(player) => player,
+        None => return,
+    };
+    let position =
+        player.translation + player.rotation * Vec3::Y * PLACEMENT_DISTANCE + Vec3::Z * 0.1;
+    preview_transform.translation = position;
+    let placeable = credits.0 >= TURRET_COST && turrets.iter().count() < MAX_TURRETS;
+    sprite.color = if placeable {
+        Color::rgba(0.3, 1.0, 0.3, 0.5)
+    } else {
+        Color::rgba(1.0, 0.3, 0.3, 0.5)
+    };
+
+    if !input.just_released(KeyCode::T) || !placeable {
+        return;
+    }
+    credits.0 -= TURRET_COST;
+    palette
+        .spawn_sprite(
+            &mut commands,
+            RenderLayer::Player,
+            palette.drone,
+            TURRET_SIZE,
+            Transform::from_translation(position),
+        )
+        .insert(Turret {
+            fire: Timer::from_seconds(FIRE_INTERVAL, false),
+            lifetime: Timer::from_seconds(TURRET_LIFETIME, false),
+        })
+        .insert(Threat {
+            weight: TURRET_THREAT,
+        })
+        .insert(Taunt(Timer::from_seconds(TAUNT_DURATION, false)));
+}
+
+fn turret_fire_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    palette: Res<Palette>,
+    mut turrets: Query<(Entity, &mut Turret, &mut Transform)>,
+    enemies: Query<&Transform, (With<Enemy>, Without<Dying>, Without<Turret>)>,
+) {
+    for (entity, mut turret, mut transform) in turrets.iter_mut() {
+        if !turret.fire.tick(time_scale.step_duration()).finished() {
+            continue;
+        }
+        let position = transform.translation;
+        let offset = match nearest_enemy(position, TURRET_RANGE, enemies.iter()) {
+            Some(offset) => offset,
+            None => continue,
+        };
+        turret.fire.reset();
+        // turn to face whatever it's shooting at
+        transform.rotation = Quat::from_rotation_z((-offset.x).atan2(offset.y));
+        spawn_companion_shot(
+            &mut commands,
+            &palette,
+            entity,
+            position,
+            offset,
+            SHOT_VELOCITY,
+            SHOT_SIZE,
+        );
+    }
+}
+
+fn turret_lifetime_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    mut turrets: Query<(Entity, &mut Turret, &mut Visibility)>,
+) {
+    for (entity, mut turret, mut visibility) in turrets.iter_mut() {
+        let lifetime = &mut turret.lifetime;
+        if lifetime.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let left = lifetime.duration().as_secs_f32() - lifetime.elapsed_secs();
+        visibility.is_visible = left > EXPIRY_WARNING || (left * 4.0).fract() < 0.5;
+    }
+}
@@ -0,0 +1,430 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    abilities::{Bombs, Dash},
+    deaths::Dying,
+    despawn_with,
+    events::{BombDetonated, PlayerDamaged, ShotFired, WaveStarted},
+    input_buffer::StepInput,
+    locale::Locale,
+    palette::Palette,
+    save, spawn_enemy,
+    stats::LifetimeStats,
+    AddSimulationSystem, Enemy, EnemyKind, GameState, Health, Player, UiFont, TIME_STEP,
+};
+
+const ONBOARDING_FILE: &str = "onboarding";
+/// Seconds the player has to spend moving or rotating to clear those steps.
+const PRACTICE_DURATION: f32 = 1.0;
+const SHOTS_REQUIRED: usize = 10;
+const DASHES_REQUIRED: usize = 2;
+/// Number of finished runs during which contextual hints are still shown.
+const HINT_RUNS: usize = 3;
+/// Seconds a contextual hint stays on screen.
+const HINT_DURATION: f32 = 4.0;
+/// Distance within which enemies count as crowding the player.
+const CROWD_RADIUS: f32 = 150.0;
+
+/// Onboarding progress, persisted between sessions.
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Onboarding {
+    pub tutorial_completed: bool,
+}
+
+impl save::Versioned for Onboarding {
+    const VERSION: u32 = 1;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TutorialStep {
+    Move,
+    Rotate,
+    Shoot,
+    Dash,
+    Bomb,
+    Done,
+}
+
+impl TutorialStep {
+    fn prompt_key(&self) -> &'static str {
+        match self {
+            TutorialStep::Move => "tutorial.move",
+            TutorialStep::Rotate => "tutorial.rotate",
+            TutorialStep::Shoot => "tutorial.shoot",
+            TutorialStep::Dash => "tutorial.dash",
+            TutorialStep::Bomb => "tutorial.bomb",
+            TutorialStep::Done => "tutorial.done",
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            TutorialStep::Move => TutorialStep::Rotate,
+            TutorialStep::Rotate => TutorialStep::Shoot,
+            TutorialStep::Shoot => TutorialStep::Dash,
+            TutorialStep::Dash => TutorialStep::Bomb,
+            TutorialStep::Bomb | TutorialStep::Done => TutorialStep::Done,
+        }
+    }
+}
+
+struct Tutorial {
+    step: TutorialStep,
+    /// seconds or actions completed towards the current step
+    progress: f32,
+    was_dashing: bool,
+}
+
+impl Default for Tutorial {
+    fn default() -> Self {
+        Self {
+            step: TutorialStep::Move,
+            progress: 0.0,
+            was_dashing: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Hint {
+    Bash,
+    Bomb,
+    Dash,
+    Waves,
+}
+
+impl Hint {
+    fn text_key(&self) -> &'static str {
+        match self {
+            Hint::Bash => "hint.bash",
+            Hint::Bomb => "hint.bomb",
+            Hint::Dash => "hint.dash",
+            Hint::Waves => "hint.waves",
+        }
+    }
+}
+
+/// Hints already shown during the current run.
+#[derive(Default)]
+struct ShownHints(Vec<Hint>);
+
+#[derive(Component)]
+struct TutorialPrompt;
+
+/// Button leaving the tutorial, for players without a keyboard to press Enter on.
+#[derive(Component)]
+struct LeaveButton;
+
+/// Label of the leave button, saying whether it skips the tutorial or starts the first run.
+#[derive(Component)]
+struct LeaveLabel;
+
+#[derive(Component)]
+struct HintText(Timer);
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(save::load::<Onboarding>(ONBOARDING_FILE))
+            .init_resource::<Tutorial>()
+            .init_resource::<ShownHints>()
+            .add_system_set(SystemSet::on_enter(GameState::Tutorial).with_system(start_tutorial))
+            .add_system_set(
+                SystemSet::on_update(GameState::Tutorial)
+                    .with_system(leave_system)
+                    .with_system(tutorial_prompt_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Tutorial)
+                    .with_system(despawn_with::<TutorialPrompt>)
+                    .with_system(despawn_with::<LeaveButton>),
+            )
+            .add_simulation_system(tutorial_system)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_hints))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(hint_system)
+                    .with_system(hint_fade_system),
+            );
+    }
+}
+
+fn start_tutorial(mut commands: Commands, font: Res<UiFont>, mut tutorial: ResMut<Tutorial>) {
+    *tutorial = Tutorial::default();
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(40.0),
+                    left: Val::Px(40.0),
+                    ..default()
+                },
+                ..default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 32.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+                default(),
+            ),
+            ..default()
+        })
+        .insert(TutorialPrompt);
+    commands
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(40.0),
+                    right: Val::Px(40.0),
+                    ..default()
+                },
+                size: Size::new(Val::Px(160.0), Val::Px(56.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::rgb(0.15, 0.15, 0.15).into(),
+            ..default()
+        })
+        .insert(LeaveButton)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: 32.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                        default(),
+                    ),
+                    ..default()
+                })
+                .insert(LeaveLabel);
+        });
+}
+
+/// Advances the tutorial's steps with what the player did on each step of the simulation, from
+/// the keyboard or the touch controls.
+fn tutorial_system(
+    mut commands: Commands,
+    input: Res<StepInput>,
+    palette: Res<Palette>,
+    state: Res<State<GameState>>,
+    mut tutorial: ResMut<Tutorial>,
+    mut player: Query<(&Dash, &mut Bombs), With<Player>>,
+    mut shot_fired: EventReader<ShotFired>,
+    mut bomb_detonated: EventReader<BombDetonated>,
+) {
+    if *state.current() != GameState::Tutorial {
+        return;
+    }
+    let shots = shot_fired.iter().count();
+    let bombed = bomb_detonated.iter().count() > 0;
+    let dashing = player.get_single().is_ok_and(|(dash, _)| dash.is_dashing());
+    let dash_started = dashing && !tutorial.was_dashing;
+    tutorial.was_dashing = dashing;
+
+    let done = match tutorial.step {
+        TutorialStep::Move => {
+            if input.any_pressed([KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right])
+                || input.touch_movement() != Vec2::ZERO
+            {
+                tutorial.progress += TIME_STEP;
+            }
+            tutorial.progress >= PRACTICE_DURATION
+        }
+        TutorialStep::Rotate => {
+            // touching the right half of the screen turns the ship towards the touch
+            if input.any_pressed([KeyCode::Q, KeyCode::E]) || input.touch_aim().is_some() {
+                tutorial.progress += TIME_STEP;
+            }
+            tutorial.progress >= PRACTICE_DURATION
+        }
+        TutorialStep::Shoot => {
+            tutorial.progress += shots as f32;
+            tutorial.progress >= SHOTS_REQUIRED as f32
+        }
+        TutorialStep::Dash => {
+            if dash_started {
+                tutorial.progress += 1.0;
+            }
+            tutorial.progress >= DASHES_REQUIRED as f32
+        }
+        TutorialStep::Bomb => bombed,
+        TutorialStep::Done => false,
+    };
+
+    if done {
+        tutorial.step = tutorial.step.next();
+        tutorial.progress = 0.0;
+        if tutorial.step == TutorialStep::Bomb {
+            if let Ok((_, mut bombs)) = player.get_single_mut() {
+                bombs.0 = bombs.0.max(1);
+            }
+            // a ring of stationary targets to clear with the bomb
+            for i in 0..8 {
+                let angle = i as f32 / 8.0 * std::f32::consts::TAU;
+                let (x, y) = angle.sin_cos();
+                spawn_enemy(
+                    &mut commands,
+                    &palette,
+                    Vec3::new(x * 250.0, y * 250.0, 0.0),
+                    EnemyKind::Chaser,
+                    0.0,
+                );
+            }
+        }
+    }
+}
+
+/// Enter or the leave button finishes the tutorial from the last step and skips it from any other.
+fn leave_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<LeaveButton>)>,
+    mut onboarding: ResMut<Onboarding>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let clicked = buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Clicked);
+    if keyboard_input.just_pressed(KeyCode::Return) || clicked {
+        onboarding.tutorial_completed = true;
+        save::store(ONBOARDING_FILE, &*onboarding);
+        let _ = state.set(GameState::ShipSelect);
+    }
+}
+
+fn tutorial_prompt_system(
+    tutorial: Res<Tutorial>,
+    locale: Res<Locale>,
+    mut prompts: Query<&mut Text, (With<TutorialPrompt>, Without<LeaveLabel>)>,
+    mut labels: Query<&mut Text, With<LeaveLabel>>,
+) {
+    if !tutorial.is_changed() && !locale.is_changed() {
+        return;
+    }
+    let progress = match tutorial.step {
+        TutorialStep::Shoot => format!(" ({}/{})", tutorial.progress, SHOTS_REQUIRED),
+        TutorialStep::Dash => format!(" ({}/{})", tutorial.progress, DASHES_REQUIRED),
+        _ => String::new(),
+    };
+    let skip = if tutorial.step == TutorialStep::Done {
+        String::new()
+    } else {
+        format!("\n{}", locale.get("tutorial.skip"))
+    };
+    let prompt = locale.get(tutorial.step.prompt_key());
+    for mut text in prompts.iter_mut() {
+        text.sections[0].value = format!("{}{}{}", prompt, progress, skip);
+    }
+    let label = if tutorial.step == TutorialStep::Done {
+        locale.get("tutorial.start_button")
+    } else {
+        locale.get("tutorial.skip_button")
+    };
+    for mut text in labels.iter_mut() {
+        text.sections[0].value = label.to_string();
+    }
+}
+
+fn reset_hints(mut hints: ResMut<ShownHints>) {
+    hints.0.clear();
+}
+
+fn hint_system(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    locale: Res<Locale>,
+    lifetime: Res<LifetimeStats>,
+    mut shown: ResMut<ShownHints>,
+    player: Query<(&Transform, &Health, &Bombs), With<Player>>,
+    enemies: Query<&Transform, (With<Enemy>, Without<Dying>)>,
+    hints: Query<&HintText>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut player_damaged: EventReader<PlayerDamaged>,
+) {
+    if lifetime.runs >= HINT_RUNS {
+        return;
+    }
+    let (player_transform, health, bombs) = match player.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    let mut hint = None;
+    if wave_started.iter().any(|started| started.number == 2) {
+        hint = Some(Hint::Waves);
+    }
+    if player_damaged.iter().count() > 0 && health.current <= 1.0 {
+        hint = Some(Hint::Dash);
+    }
+    let crowd = enemies
+        .iter()
+        .filter(|enemy| enemy.translation.distance(player_transform.translation) < CROWD_RADIUS)
+        .count();
+    if crowd > 0 {
+        hint = Some(Hint::Bash);
+    }
+    if crowd >= 3 && bombs.0 > 0 {
+        hint = Some(Hint::Bomb);
+    }
+
+    let hint = match hint {
+        Some(hint) if !shown.0.contains(&hint) && hints.is_empty() => hint,
+        _ => return,
+    };
+    shown.0.push(hint);
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(40.0),
+                    left: Val::Px(40.0),
+                    ..default()
+                },
+                ..default()
+            },
+            text: Text::with_section(
+                locale.get(hint.text_key()),
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 26.0,
+                    color: Color::rgb(0.9, 0.9, 0.5),
+                },
+                default(),
+            ),
+            ..default()
+        })
+        .insert(HintText(Timer::from_seconds(HINT_DURATION, false)));
+}
+
+fn hint_fade_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut hints: Query<(Entity, &mut HintText, &mut Text)>,
+) {
+    for (entity, mut hint, mut text) in hints.iter_mut() {
+        hint.0.tick(time.delta());
+        if hint.0.finished() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            // fade out over the last second
+            let remaining = hint.0.duration().as_secs_f32() - hint.0.elapsed_secs();
+            let alpha = remaining.min(1.0);
+            for section in text.sections.iter_mut() {
+                section.style.color.set_a(alpha);
+            }
+        }
+    }
+}
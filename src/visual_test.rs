@@ -0,0 +1,309 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    cli::Cli,
+    deaths::Dying,
+    palette::{Palette, PaletteMode},
+    photo_mode::PhotoMode,
+    save,
+    settings::Settings,
+    spawn_enemy, AddSimulationSystem, Enemy, EnemyKind, GameState, Health, Player, TimeScale,
+};
+
+/// Seed every scene is played from, for enemies to spawn in the same places each time.
+pub const SEED: u64 = 0x6d61_6467_6521;
+/// What the scratch directory a visual test's saves are kept in is named after.
+pub const SAVE_PURPOSE: &str = "visual-test";
+/// Fixed steps a scene is simulated for before it's captured.
+const SCENE_STEPS: u32 = 180;
+/// Step at which the enemies in a scene are killed, shortly before the capture so their shards
+/// are still flying.
+const KILL_STEP: u32 = 168;
+/// Enemies set around the player at the start of a scene, and how far from it.
+const RING_ENEMIES: usize = 12;
+const RING_RADIUS: f32 = 260.0;
+/// Slow enough for them not to reach the player before they're killed.
+const RING_VELOCITY: f32 = 60.0;
+/// Frames waited after the simulation stops, for the HUD to catch up with it.
+const SETTLE_FRAMES: u32 = 3;
+/// Difference in any channel below which pixels count as the same, for what renderers and
+/// drivers round differently.
+const CHANNEL_TOLERANCE: u8 = 16;
+
+/// Scene the visual test renders, a run with each palette.
+#[derive(Clone, Copy)]
+struct Scene(PaletteMode);
+
+impl Scene {
+    const ALL: [Scene; 5] = [
+        Scene(PaletteMode::Standard),
+        Scene(PaletteMode::Deuteranopia),
+        Scene(PaletteMode::Protanopia),
+        Scene(PaletteMode::Tritanopia),
+        Scene(PaletteMode::HighContrast),
+    ];
+
+    /// Name of the scene's golden image, without its extension.
+    fn name(&self) -> String {
+        let palette = self.0.name_key().trim_start_matches("palette.");
+        format!("arena_{}", palette)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    /// the scene's run is being simulated
+    Running,
+    /// the simulation is stopped, counting down the frames until the capture
+    Settling(u32),
+    /// waiting for the captured frame
+    Capturing,
+    /// counting down the frames until the next scene's run starts, for its palette to be loaded
+    Restarting(u32),
+    /// waiting for the next scene's run to start
+    Starting,
+}
+
+/// A visual test in progress, going through `Scene::ALL` one run at a time.
+struct VisualTest {
+    golden: PathBuf,
+    update: bool,
+    tolerance: f32,
+    scene: usize,
+    phase: Phase,
+    /// fixed steps the current scene has been simulated for
+    steps: u32,
+    /// health the player started the scene with, kept topped up to it
+    health: f32,
+    failures: Vec<String>,
+}
+
+/// Hidden test mode started with `--visual-test`: renders a few runs with a fixed seed for a fixed
+/// number of steps and compares what ends up on screen against golden images, catching changes to
+/// the HUD's layout, the palettes and the particles.
+///
+/// Only the simulation is fixed, whatever runs on the frame's time might come out differently
+/// from one capture to the next, which the tolerance covers. Headless runs have nothing to
+/// capture. The saves read and written are kept apart from the player's by `run`.
+pub struct VisualTestPlugin;
+
+impl Plugin for VisualTestPlugin {
+    fn build(&self, app: &mut App) {
+        let cli = app.world.resource::<Cli>();
+        let golden = match &cli.visual_test {
+            Some(golden) => golden.clone(),
+            None => return,
+        };
+        let test = VisualTest {
+            golden,
+            update: cli.update_golden,
+            tolerance: cli.visual_tolerance,
+            scene: 0,
+            phase: Phase::Running,
+            steps: 0,
+            health: 0.0,
+            failures: Vec::new(),
+        };
+        app.world.resource_mut::<Settings>().palette = Scene::ALL[0].0;
+        app.insert_resource(test)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(start_scene))
+            // ahead of the rest of the simulation in every step, stopping it before the step it
+            // shouldn't take rather than partway through it
+            .add_simulation_system_first(step_system)
+            .add_simulation_system(scene_system)
+            .add_system(capture_system);
+    }
+}
+
+/// Sets the scene's enemies around the player as its run starts.
+fn start_scene(mut commands: Commands, palette: Res<Palette>, mut test: ResMut<VisualTest>) {
+    test.steps = 0;
+    test.phase = Phase::Running;
+    for index in 0..RING_ENEMIES {
+        let angle = index as f32 / RING_ENEMIES as f32 * std::f32::consts::TAU;
+        let position = Quat::from_rotation_z(angle) * Vec3::X * RING_RADIUS;
+        spawn_enemy(
+            &mut commands,
+            &palette,
+            position,
+            EnemyKind::Chaser,
+            RING_VELOCITY,
+        );
+    }
+}
+
+/// Counts the scene's steps and stops the simulation once it has taken all of them.
+fn step_system(mut test: ResMut<VisualTest>, mut time_scale: ResMut<TimeScale>) {
+    if test.phase != Phase::Running {
+        return;
+    }
+    if test.steps == SCENE_STEPS {
+        time_scale.global = 0.0;
+        test.phase = Phase::Settling(SETTLE_FRAMES);
+        return;
+    }
+    test.steps += 1;
+}
+
+/// Keeps the player alive through the scene and kills the enemies right before its capture.
+fn scene_system(
+    mut commands: Commands,
+    mut test: ResMut<VisualTest>,
+    mut player: Query<&mut Health, With<Player>>,
+    enemies: Query<Entity, (With<Enemy>, Without<Dying>)>,
+) {
+    for mut health in player.iter_mut() {
+        // the player only exists from the first step on
+        if test.steps == 1 {
+            test.health = health.current;
+        }
+        health.current = health.current.max(test.health);
+    }
+    if test.phase == Phase::Running && test.steps == KILL_STEP {
+        for entity in enemies.iter() {
+            commands.entity(entity).insert(Dying::default());
+        }
+    }
+}
+
+/// Captures each scene once it's settled, judges it and moves on to the next one, exiting with
+/// the result after the last.
+fn capture_system(
+    mut test: ResMut<VisualTest>,
+    mut state: ResMut<State<GameState>>,
+    mut settings: ResMut<Settings>,
+    mut photo_mode: ResMut<PhotoMode>,
+) {
+    match test.phase {
+        Phase::Running | Phase::Starting => {}
+        Phase::Settling(0) => {
+            photo_mode.capture_frame();
+            test.phase = Phase::Capturing;
+        }
+        Phase::Settling(frames) => test.phase = Phase::Settling(frames - 1),
+        Phase::Capturing => {
+            let capture = match photo_mode.take_capture() {
+                Some(capture) => capture,
+                None => return,
+            };
+            let scene = Scene::ALL[test.scene];
+            if let Err(failure) = judge(&test, scene, &capture) {
+                eprintln!("{}: {}", scene.name(), failure);
+                test.failures.push(scene.name());
+            }
+            test.scene += 1;
+            match Scene::ALL.get(test.scene) {
+                Some(next) => {
+                    settings.palette = next.0;
+                    test.phase = Phase::Restarting(2);
+                }
+                None => finish(&test),
+            }
+        }
+        Phase::Restarting(0) => {
+            // a change of state already queued this frame goes first, the restart waits for it
+            if state.restart().is_ok() {
+                test.phase = Phase::Starting;
+            }
+        }
+        Phase::Restarting(frames) => test.phase = Phase::Restarting(frames - 1),
+    }
+}
+
+/// Compares a scene's capture against its golden image, or replaces the golden image with it.
+fn judge(test: &VisualTest, scene: Scene, capture: &RgbaImage) -> Result<(), String> {
+    let path = test.golden.join(scene.name()).with_extension("png");
+    if test.update {
+        fs::create_dir_all(&test.golden)
+            .map_err(image::ImageError::IoError)
+            .and_then(|_| capture.save(&path))
+            .map_err(|err| format!("failed to write {}: {}", path.display(), err))?;
+        println!("{}: wrote {}", scene.name(), path.display());
+        return Ok(());
+    }
+    let golden = image::open(&path)
+        .map_err(|err| {
+            format!(
+                "can't read {}, write it with --update-golden: {}",
+                path.display(),
+                err
+            )
+        })?
+        .to_rgba8();
+    if golden.dimensions() != capture.dimensions() {
+        return Err(format!(
+            "captured at {:?} but the golden image is {:?}",
+            capture.dimensions(),
+            golden.dimensions()
+        ));
+    }
+    let (diff, differing) = difference(&golden, capture);
+    let fraction = differing as f32 / (capture.width() * capture.height()).max(1) as f32;
+    if fraction <= test.tolerance {
+        println!(
+            "{}: matches, {:.2}% of pixels differ",
+            scene.name(),
+            fraction * 100.0
+        );
+        return Ok(());
+    }
+    // what came out is kept next to the golden image to see what changed
+    let actual = path.with_extension("actual.png");
+    let diff_path = path.with_extension("diff.png");
+    for (image, path) in [(capture, &actual), (&diff, &diff_path)] {
+        if let Err(err) = image.save(path) {
+            warn!("failed to write {}: {}", path.display(), err);
+        }
+    }
+    Err(format!(
+        "{:.2}% of pixels differ, more than the {:.2}% allowed, see {} and {}",
+        fraction * 100.0,
+        test.tolerance * 100.0,
+        actual.display(),
+        diff_path.display()
+    ))
+}
+
+/// Image of where `actual` differs from `golden`, in red over a faded copy of it, and how many
+/// pixels differ.
+fn difference(golden: &RgbaImage, actual: &RgbaImage) -> (RgbaImage, usize) {
+    let mut differing = 0;
+    let diff = RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let (expected, pixel) = (golden.get_pixel(x, y), actual.get_pixel(x, y));
+        let differs = expected
+            .0
+            .iter()
+            .zip(pixel.0)
+            .any(|(expected, channel)| expected.abs_diff(channel) > CHANNEL_TOLERANCE);
+        if differs {
+            differing += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, _] = pixel.0.map(|channel| channel / 4);
+            Rgba([r, g, b, 255])
+        }
+    });
+    (diff, differing)
+}
+
+fn finish(test: &VisualTest) -> ! {
+    // nothing the scenes saved is needed anymore
+    let save_dir = save::scratch_dir(SAVE_PURPOSE);
+    if let Err(err) = fs::remove_dir_all(&save_dir) {
+        warn!("failed to remove {}: {}", save_dir.display(), err);
+    }
+    if test.failures.is_empty() {
+        println!("all {} scenes match", Scene::ALL.len());
+        std::process::exit(0);
+    }
+    eprintln!(
+        "{} of {} scenes changed: {}",
+        test.failures.len(),
+        Scene::ALL.len(),
+        test.failures.join(", ")
+    );
+    std::process::exit(1);
+}
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{events::WaveStarted, AddSimulationSystem, GameState, TimeScale};
+
+/// Seconds each wave lasts before the next, denser one begins.
+const WAVE_DURATION: f32 = 20.0;
+const BASE_SPAWN_INTERVAL: f32 = 0.5;
+const MIN_SPAWN_INTERVAL: f32 = 0.15;
+/// Factor the spawn interval shrinks by with every wave.
+const SPAWN_INTERVAL_DECAY: f32 = 0.9;
+/// Wave at which a run reaches its full intensity from the wave alone.
+const INTENSE_WAVE: f32 = 10.0;
+/// Enemies on screen at which a run reaches its full intensity from the crowd alone.
+const INTENSE_CROWD: f32 = 40.0;
+
+/// The wave the current run is on.
+pub struct Wave {
+    pub number: usize,
+    timer: Timer,
+}
+
+impl Default for Wave {
+    fn default() -> Self {
+        Self {
+            number: 1,
+            timer: Timer::from_seconds(WAVE_DURATION, true),
+        }
+    }
+}
+
+impl Wave {
+    /// Time between enemy spawns during this wave.
+    pub fn spawn_interval(&self) -> Duration {
+        let interval = BASE_SPAWN_INTERVAL * SPAWN_INTERVAL_DECAY.powi(self.number as i32 - 1);
+        Duration::from_secs_f32(interval.max(MIN_SPAWN_INTERVAL))
+    }
+
+    /// How hectic the run is with `enemies` on screen during this wave, from 0 to 1.
+    pub fn intensity(&self, enemies: usize) -> f32 {
+        (0.5 * self.number as f32 / INTENSE_WAVE + 0.5 * enemies as f32 / INTENSE_CROWD).min(1.0)
+    }
+}
+
+pub struct WavesPlugin;
+
+impl Plugin for WavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Wave>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(start_first_wave))
+            .add_simulation_system(wave_system);
+    }
+}
+
+fn start_first_wave(mut wave: ResMut<Wave>, mut wave_started: EventWriter<WaveStarted>) {
+    *wave = Wave::default();
+    wave_started.send(WaveStarted {
+        number: wave.number,
+    });
+}
+
+fn wave_system(
+    state: Res<State<GameState>>,
+    time_scale: Res<TimeScale>,
+    mut wave: ResMut<Wave>,
+    mut wave_started: EventWriter<WaveStarted>,
+) {
+    // the tutorial has no waves
+    if *state.current() != GameState::Playing {
+        return;
+    }
+    if wave.timer.tick(time_scale.step_duration()).just_finished() {
+        wave.number += 1;
+        wave_started.send(WaveStarted {
+            number: wave.number,
+        });
+    }
+}
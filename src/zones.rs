@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+
+use crate::{
+    damage_player,
+    elements::Element,
+    events::{BombDetonated, DamageSource, PlayerDamaged},
+    layers::RenderLayer,
+    palette::Palette,
+    settings::Settings,
+    AddSimulationSystem, EnemyDamage, GameState, Health, Player, Targets, TimeScale,
+};
+
+/// Depth on the floor, above scorch marks and beneath the arena's decorations.
+const ZONE_DEPTH: f32 = 0.1;
+const BOMB_FIRE_RADIUS: f32 = 120.0;
+const BOMB_FIRE_DURATION: f32 = 4.0;
+const MINE_FIRE_RADIUS: f32 = 70.0;
+const MINE_FIRE_DURATION: f32 = 3.0;
+const FROST_RADIUS: f32 = 50.0;
+const FROST_DURATION: f32 = 2.0;
+/// Fraction of their speed enemies keep inside a slow field.
+const FROST_SLOW: f32 = 0.4;
+/// Seconds between the hits burning ground deals to whatever stands in it.
+const BURN_INTERVAL: f32 = 0.75;
+/// Seconds at the end of a zone's lifetime over which it fades out.
+const FADE_DURATION: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ZoneKind {
+    /// hits everything inside it every so often
+    Burning,
+    /// slows enemies down while they're inside it
+    Frost,
+}
+
+/// Area left on the ground that keeps affecting whatever is inside it until it runs out.
+///
+/// Zones don't stack: standing in several burning ones still only burns once every interval, and
+/// overlapping slow fields slow no more than one of them does.
+#[derive(Component)]
+pub struct Zone {
+    kind: ZoneKind,
+    radius: f32,
+    /// hurts the player as well as enemies, for zones left by hazards rather than the player
+    hostile: bool,
+    lifetime: Timer,
+}
+
+impl Zone {
+    fn contains(&self, center: Vec3, position: Vec3) -> bool {
+        center.truncate().distance(position.truncate()) <= self.radius
+    }
+}
+
+/// Time until whatever the zone's on can be burnt again.
+#[derive(Component)]
+struct Burnt(Timer);
+
+/// Fraction of their speed an enemy in a slow field keeps.
+#[derive(Component)]
+pub struct Chilled(pub f32);
+
+/// Marks a bullet fired by the frost weapon, leaving a slow field wherever it hits.
+#[derive(Component)]
+pub struct FrostShot;
+
+pub struct ZonesPlugin;
+
+impl Plugin for ZonesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(bomb_fire_system)
+            .add_simulation_system(zone_lifetime_system)
+            .add_simulation_system(burn_system)
+            .add_simulation_system(chill_system);
+    }
+}
+
+/// Leaves a zone of the given kind centered on `position`.
+pub fn spawn(
+    commands: &mut Commands,
+    palette: &Palette,
+    kind: ZoneKind,
+    position: Vec3,
+    hostile: bool,
+) {
+    let (color, radius, duration) = match kind {
+        ZoneKind::Burning if hostile => (palette.hazard, MINE_FIRE_RADIUS, MINE_FIRE_DURATION),
+        ZoneKind::Burning => (palette.burning, BOMB_FIRE_RADIUS, BOMB_FIRE_DURATION),
+        ZoneKind::Frost => (palette.frost, FROST_RADIUS, FROST_DURATION),
+    };
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::splat(radius * 2.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(position.x, position.y, ZONE_DEPTH)
+                .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+            ..default()
+        })
+        .insert(RenderLayer::Decals)
+        .insert(Zone {
+            kind,
+            radius,
+            hostile,
+            lifetime: Timer::from_seconds(duration, false),
+        });
+}
+
+/// Bombs leave the ground around the player burning.
+fn bomb_fire_system(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    palette: Res<Palette>,
+    player: Query<&Transform, With<Player>>,
+    mut bomb_detonated: EventReader<BombDetonated>,
+) {
+    // the tutorial's bombs don't set the floor on fire
+    if bomb_detonated.iter().count() == 0 || *state.current() != GameState::Playing {
+        return;
+    }
+    if let Ok(transform) = player.get_single() {
+        spawn(
+            &mut commands,
+            &palette,
+            ZoneKind::Burning,
+            transform.translation,
+            false,
+        );
+    }
+}
+
+fn zone_lifetime_system(
+    mut commands: Commands,
+    time_scale: Res<TimeScale>,
+    palette: Res<Palette>,
+    mut zones: Query<(Entity, &mut Zone, &mut Sprite)>,
+) {
+    for (entity, mut zone, mut sprite) in zones.iter_mut() {
+        if zone.lifetime.tick(time_scale.step_duration()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let base = match zone.kind {
+            ZoneKind::Burning if zone.hostile => palette.hazard,
+            ZoneKind::Burning => palette.burning,
+            ZoneKind::Frost => palette.frost,
+        };
+        let remaining = zone.lifetime.duration().as_secs_f32() - zone.lifetime.elapsed_secs();
+        let fade = (remaining / FADE_DURATION).min(1.0);
+        // fire flickers, frost holds still
+        let flicker = match zone.kind {
+            ZoneKind::Burning => 0.85 + 0.15 * (zone.lifetime.elapsed_secs() * 14.0).sin(),
+            ZoneKind::Frost => 1.0,
+        };
+        sprite.color.set_a(base.a() * fade * flicker);
+    }
+}
+
+/// Burns enemies, and the player in hostile fire, once every interval while they're in any
+/// burning zone.
+fn burn_system(
+    mut state: ResMut<State<GameState>>,
+    time_scale: Res<TimeScale>,
+    settings: Res<Settings>,
+    zones: Query<(&Zone, &Transform)>,
+    mut enemies: Targets<(Without<Zone>, Without<Player>)>,
+    mut burnt: Query<(Entity, &mut Burnt)>,
+    mut player: Query<(Entity, &Transform, &mut Health), With<Player>>,
+    mut damage: EnemyDamage,
+    mut player_damaged: EventWriter<PlayerDamaged>,
+) {
+    let delta = time_scale.step_duration();
+    for (entity, mut burn) in burnt.iter_mut() {
+        if burn.0.tick(delta).finished() {
+            damage.commands.entity(entity).remove::<Burnt>();
+        }
+    }
+    let burning = |position: Vec3, hurts: fn(&Zone) -> bool| {
+        zones.iter().any(|(zone, transform)| {
+            zone.kind == ZoneKind::Burning
+                && hurts(zone)
+                && zone.contains(transform.translation, position)
+        })
+    };
+    // burnt just now or too recently to burn again
+    let cooling = |entity: Entity, burnt: &Query<(Entity, &mut Burnt)>| {
+        burnt.get(entity).is_ok_and(|(_, burn)| !burn.0.finished())
+    };
+
+    for (entity, enemy, transform, mut elite, splitter) in enemies.iter_mut() {
+        if cooling(entity, &burnt) || !burning(transform.translation, |_| true) {
+            continue;
+        }
+        damage
+            .commands
+            .entity(entity)
+            .insert(Burnt(Timer::from_seconds(BURN_INTERVAL, false)));
+        // fire comes from every side, so it goes around shields
+        damage.hit(
+            entity,
+            enemy,
+            transform.translation,
+            elite.as_deref_mut(),
+            splitter,
+            Vec3::ZERO,
+            Element::Fire,
+        );
+    }
+
+    let (entity, transform, mut health) = match player.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    if cooling(entity, &burnt) || !burning(transform.translation, |zone| zone.hostile) {
+        return;
+    }
+    damage
+        .commands
+        .entity(entity)
+        .insert(Burnt(Timer::from_seconds(BURN_INTERVAL, false)));
+    damage_player(
+        &mut damage.commands,
+        &mut state,
+        &settings,
+        entity,
+        &mut health,
+        1.0,
+        DamageSource::Burning,
+        &mut player_damaged,
+    );
+}
+
+/// Slows enemies in slow fields down, and gives them their speed back once they're out.
+fn chill_system(
+    mut commands: Commands,
+    zones: Query<(&Zone, &Transform)>,
+    enemies: Targets<Without<Zone>>,
+    chilled: Query<&Chilled>,
+) {
+    for (entity, _, transform, _, _) in enemies.iter() {
+        let in_frost = zones.iter().any(|(zone, zone_transform)| {
+            zone.kind == ZoneKind::Frost
+                && zone.contains(zone_transform.translation, transform.translation)
+        });
+        match (in_frost, chilled.get(entity).is_ok()) {
+            (true, false) => {
+                commands.entity(entity).insert(Chilled(FROST_SLOW));
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Chilled>();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,24 @@
+//! Renders the scenes of the hidden `--visual-test` mode and compares them against the golden
+//! images in `tests/golden`. It opens a window and draws with the GPU, so it's only run when asked
+//! for, with `cargo test --test visual -- --ignored`. The golden images are written with
+//! `cargo run -- --visual-test tests/golden --update-golden`, and committed along with whatever
+//! changed how the scenes look.
+
+use std::process::Command;
+
+#[test]
+#[ignore = "needs a GPU and a display"]
+fn scenes_match_golden_images() {
+    let golden = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+    let status = Command::new(env!("CARGO_BIN_EXE_madge"))
+        .arg("--visual-test")
+        .arg(golden)
+        .status()
+        .expect("failed to start the game");
+    assert!(
+        status.success(),
+        "scenes differ from the golden images in {}, see the .actual.png and .diff.png files \
+         written next to them",
+        golden
+    );
+}